@@ -1,7 +1,8 @@
 // Web Worker script for running WASM game state in background thread
 import init, { 
     init_game, 
-    tick,
+    tick_export,
+    state_export_ptr,
     add_promiser, 
     get_promiser_count,
     make_promiser_think,
@@ -18,12 +19,15 @@ console.log('🎮 Worker: Starting WASM game worker...');
 
 // Initialize WASM module
 let wasmInitialized = false;
+let wasmMemory = null;
 let gameRunning = false;
 let updateInterval = null;
+const stateDecoder = new TextDecoder();
 
 async function initWasm() {
     if (!wasmInitialized) {
-        await init();
+        const wasm = await init();
+        wasmMemory = wasm.memory;
         wasmInitialized = true;
         console.log('🎮 Worker: WASM game module initialized in worker thread');
     }
@@ -36,9 +40,11 @@ function startGameLoop(worldWidthTiles, worldHeightTiles) {
     init_game(worldWidthTiles, worldHeightTiles);
     gameRunning = true;
     
-    // Simple game loop - just call tick() every 16ms (≈60fps)
+    // Simple game loop - just call tick_export() every 16ms (≈60fps) and read
+    // the state JSON straight out of WASM memory
     updateInterval = setInterval(() => {
-        const stateData = tick();
+        const length = tick_export();
+        const stateData = stateDecoder.decode(new Uint8Array(wasmMemory.buffer, state_export_ptr(), length));
         
         // Send compact state to main thread for rendering
         self.postMessage({
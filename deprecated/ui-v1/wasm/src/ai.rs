@@ -0,0 +1,337 @@
+//! Behavior trees: what promisers and creatures decide to do each tick.
+//! Every entity type has a tree of nodes, built from composites (sequence,
+//! selector, parallel), conditions and a few reusable behaviors (idle,
+//! wander, seek a tile, flee, speak). The tree is evaluated from the root
+//! every tick, so whatever persists between ticks lives on the agent: its
+//! activity and how long it's been at it.
+//!
+//! A new behavior is a new node, and a new kind of entity composes its tree
+//! from the existing ones instead of growing another match on states.
+use crate::combat::ATTACK_REACH;
+use crate::genetics::Traits;
+use crate::shelter::SHELTER_RANGE;
+use crate::threat::PERCEPTION_RADIUS;
+use crate::warmth::WARMTH_RANGE;
+use crate::{memory, random, steering, Promiser, TileMap, TILE_SIZE_PIXELS};
+
+// Activities, exported to JS as the promiser `state`
+pub const IDLE: u32 = 0;
+pub const THINKING: u32 = 1;
+pub const SPEAKING: u32 = 2;
+pub const WHISPERING: u32 = 3;
+pub const RUNNING: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+/// An entity as its behavior tree sees it
+pub trait Agent {
+    /// Current activity (IDLE, THINKING, ...) and seconds spent at it
+    fn activity(&self) -> (u32, f64);
+    /// Switch to another activity, restarting its timer
+    fn set_activity(&mut self, activity: u32);
+    /// Position in pixels
+    fn position(&self) -> (f64, f64);
+    /// Head for a point `dx`, `dy` pixels away
+    fn steer(&mut self, dx: f64, dy: f64, dt: f64);
+    /// Pick a new heading at random
+    fn wander(&mut self, map: &TileMap);
+
+    /// Offset in pixels to the nearest thing worth running from
+    fn threat(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Offset in pixels to somewhere safe from the threat
+    fn refuge(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Offset in pixels to whoever the agent follows
+    fn leader(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Seconds the current line lasts (0 = the speaking node's default)
+    fn speech_duration(&self) -> f64 {
+        0.0
+    }
+
+    /// Done speaking: clear the line
+    fn stop_speaking(&mut self) {}
+}
+
+pub enum Node<A: 'static> {
+    /// Children in order until one doesn't succeed
+    Sequence(&'static [Node<A>]),
+    /// Children in order until one doesn't fail
+    Selector(&'static [Node<A>]),
+    /// Every child; running if any is, else succeeds if any did
+    Parallel(&'static [Node<A>]),
+    /// Succeeds if the agent passes the test
+    If(fn(&A) -> bool),
+    /// Succeeds with this probability (per tick)
+    Chance(fn(&A) -> f64),
+    /// Succeeds if the agent is at this activity
+    Doing(u32),
+    /// Switches to an activity and succeeds
+    Start(u32),
+    /// Runs something on the agent and succeeds
+    Do(fn(&mut A)),
+    /// Running until the activity has lasted between `min` and `max` seconds
+    For { min: f64, max: f64 },
+    /// Back to idle, then succeeds
+    Idle,
+    /// Running while the current line lasts (`min` to `max` seconds unless
+    /// the agent says otherwise), then clears it and succeeds
+    Speak { min: f64, max: f64 },
+    /// Picks a new heading with this probability (per tick); always succeeds
+    Wander(fn(&A) -> f64),
+    /// Succeeds on a tile that passes the test, runs while heading for one
+    /// at most `range` tiles away, fails if there's none
+    SeekTile { tile: fn(&TileMap, i32, i32) -> bool, range: i32 },
+    /// Runs while heading away from a threat closer than `radius` pixels
+    Flee { radius: f64 },
+    /// Runs while heading for a leader farther than `within` pixels
+    Follow { within: f64 },
+    /// Runs while heading for a refuge farther than `within` pixels,
+    /// succeeds there, fails if there's none
+    Refuge { within: f64 },
+}
+
+impl<A: Agent> Node<A> {
+    pub fn tick(&self, agent: &mut A, map: &TileMap, dt: f64) -> Status {
+        match *self {
+            Node::Sequence(children) => {
+                for child in children {
+                    match child.tick(agent, map, dt) {
+                        Status::Success => {}
+                        status => return status,
+                    }
+                }
+                Status::Success
+            }
+            Node::Selector(children) => {
+                for child in children {
+                    match child.tick(agent, map, dt) {
+                        Status::Failure => {}
+                        status => return status,
+                    }
+                }
+                Status::Failure
+            }
+            Node::Parallel(children) => {
+                let statuses: Vec<Status> = children.iter().map(|child| child.tick(agent, map, dt)).collect();
+                if statuses.contains(&Status::Running) {
+                    Status::Running
+                } else if statuses.contains(&Status::Success) {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Node::If(test) => status(test(agent)),
+            Node::Chance(chance) => status(random() < chance(agent)),
+            Node::Doing(activity) => status(agent.activity().0 == activity),
+            Node::Start(activity) => {
+                agent.set_activity(activity);
+                Status::Success
+            }
+            Node::Do(action) => {
+                action(agent);
+                Status::Success
+            }
+            Node::For { min, max } => {
+                // Re-rolled every tick, so the length is spread over min..max
+                let (_, timer) = agent.activity();
+                if timer > min + random() * (max - min) { Status::Success } else { Status::Running }
+            }
+            Node::Idle => {
+                if agent.activity().0 != IDLE {
+                    agent.set_activity(IDLE);
+                }
+                Status::Success
+            }
+            Node::Speak { min, max } => {
+                let (_, timer) = agent.activity();
+                let duration = agent.speech_duration();
+                let done = if duration > 0.0 { timer > duration } else { timer > min + random() * (max - min) };
+                if !done {
+                    return Status::Running;
+                }
+                agent.stop_speaking();
+                Status::Success
+            }
+            Node::Wander(chance) => {
+                if random() < chance(agent) {
+                    agent.wander(map);
+                }
+                Status::Success
+            }
+            Node::SeekTile { tile, range } => {
+                let (x, y) = agent.position();
+                let (tx, ty) = ((x / TILE_SIZE_PIXELS).floor() as i32, (y / TILE_SIZE_PIXELS).floor() as i32);
+                // Nearest column first, and this row before the ones around it
+                let found = (0..=2 * range)
+                    .map(|i| if i % 2 == 1 { (i + 1) / 2 } else { -i / 2 })
+                    .flat_map(|dx| [0, -1, 1].map(|dy| (dx, dy)))
+                    .find(|&(dx, dy)| tile(map, tx + dx, ty + dy));
+                match found {
+                    Some((0, _)) => Status::Success,
+                    Some((dx, dy)) => {
+                        let target_x = (tx + dx) as f64 * TILE_SIZE_PIXELS + TILE_SIZE_PIXELS / 2.0;
+                        let target_y = (ty + dy) as f64 * TILE_SIZE_PIXELS + TILE_SIZE_PIXELS / 2.0;
+                        agent.steer(target_x - x, target_y - y, dt);
+                        Status::Running
+                    }
+                    None => Status::Failure,
+                }
+            }
+            Node::Flee { radius } => match agent.threat() {
+                Some((dx, dy)) if dx.hypot(dy) < radius => {
+                    agent.steer(-dx, -dy, dt);
+                    Status::Running
+                }
+                _ => Status::Failure,
+            },
+            Node::Follow { within } => match agent.leader() {
+                Some((dx, dy)) if dx.hypot(dy) > within => {
+                    agent.steer(dx, dy, dt);
+                    Status::Running
+                }
+                _ => Status::Failure,
+            },
+            Node::Refuge { within } => match agent.refuge() {
+                Some((dx, dy)) if dx.hypot(dy) > within => {
+                    agent.steer(dx, dy, dt);
+                    Status::Running
+                }
+                Some(_) => Status::Success,
+                None => Status::Failure,
+            },
+        }
+    }
+}
+
+fn status(success: bool) -> Status {
+    if success { Status::Success } else { Status::Failure }
+}
+
+// Promisers speak, whisper, think and run for a while, then go back to idle,
+// where they sometimes start thinking. Threatened ones break off whatever
+// they're doing and run, for a refuge if they know one or else away, and
+// brave ones charge hostile creatures instead. In the
+// rain, those without a task head for shelter and stay there, and in the
+// cold they gather by a fire if there's one near; otherwise all but thinkers
+// wander now and then.
+const PROMISER: Node<Promiser> = Node::Parallel(&[
+    Node::Selector(&[
+        Node::Sequence(&[Node::If(|p| (p.threat.is_some() || p.foe.is_some()) && !p.controlled), Node::Start(RUNNING)]),
+        Node::Sequence(&[Node::Doing(SPEAKING), Node::Speak { min: 3.0, max: 5.0 }, Node::Idle]),
+        Node::Sequence(&[Node::Doing(WHISPERING), Node::Speak { min: 1.0, max: 2.0 }, Node::Idle]),
+        Node::Sequence(&[Node::Doing(THINKING), Node::For { min: 2.0, max: 5.0 }, Node::Idle]),
+        Node::Sequence(&[
+            Node::Doing(RUNNING),
+            Node::For { min: 2.0, max: 5.0 },
+            Node::Do(|p| {
+                // Slow down after running
+                p.vx *= 0.6;
+                p.vy *= 0.8;
+            }),
+            Node::Idle,
+        ]),
+        // The player decides what Pixel does
+        Node::Sequence(&[
+            Node::Doing(IDLE),
+            Node::If(|p| !p.controlled),
+            Node::Chance(|p| 0.002 * p.life_stage().thoughtfulness() * Traits::factor(p.traits.introspection)),
+            Node::Start(THINKING),
+        ]),
+        Node::Idle, // Also resets unknown activities
+    ]),
+    Node::Selector(&[
+        Node::Sequence(&[
+            Node::If(|p| p.threat.is_some() && !p.controlled),
+            Node::Selector(&[Node::Refuge { within: TILE_SIZE_PIXELS }, Node::Flee { radius: PERCEPTION_RADIUS }]),
+        ]),
+        // Close in on a foe and stand there trading blows
+        Node::Sequence(&[
+            Node::If(|p| p.foe.is_some() && !p.controlled),
+            Node::Selector(&[Node::Follow { within: ATTACK_REACH / 2.0 }, Node::Do(|p| p.vx *= 0.9)]),
+        ]),
+        Node::Sequence(&[
+            Node::If(|p| p.raining && !p.controlled && p.task.is_none()),
+            Node::SeekTile { tile: TileMap::is_sheltered, range: SHELTER_RANGE },
+        ]),
+        Node::Sequence(&[
+            Node::If(|p| p.chilled && !p.controlled && p.task.is_none()),
+            Node::SeekTile { tile: TileMap::is_warm, range: WARMTH_RANGE },
+        ]),
+        Node::Sequence(&[
+            Node::If(|p| !p.controlled && p.state != THINKING),
+            Node::Wander(|p| 0.01 * Traits::factor(p.traits.restlessness)),
+        ]),
+    ]),
+]);
+
+impl Promiser {
+    /// Run the promiser's behavior tree for one tick
+    pub(crate) fn think(&mut self, tile_map: &TileMap, dt: f64) {
+        self.state_timer += dt;
+        PROMISER.tick(self, tile_map, dt);
+    }
+}
+
+impl Agent for Promiser {
+    fn activity(&self) -> (u32, f64) {
+        (self.state, self.state_timer)
+    }
+
+    fn set_activity(&mut self, activity: u32) {
+        self.state = activity;
+        self.state_timer = 0.0;
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    fn steer(&mut self, dx: f64, _dy: f64, dt: f64) {
+        self.vx += steering::seek_vx(dx, dt);
+    }
+
+    // A random horizontal impulse, leaning toward places this promiser hasn't
+    // been yet
+    fn wander(&mut self, map: &TileMap) {
+        let cells_wide = map.width.div_ceil(memory::CELL_TILES) as i32;
+        let bias = self.visited.explore_bias(self.dimension, self.x, self.y, cells_wide, map.wrap_x);
+        self.vx += (random() - 0.5) * 2.0 + bias * 0.6 * Traits::factor(self.traits.curiosity);
+    }
+
+    fn threat(&self) -> Option<(f64, f64)> {
+        self.threat
+    }
+
+    fn refuge(&self) -> Option<(f64, f64)> {
+        self.refuge
+    }
+
+    // A promiser only goes after a foe
+    fn leader(&self) -> Option<(f64, f64)> {
+        self.foe
+    }
+
+    fn speech_duration(&self) -> f64 {
+        self.speech_duration
+    }
+
+    fn stop_speaking(&mut self) {
+        self.thought.clear();
+        self.emote.clear();
+        self.target_id = 0;
+    }
+}
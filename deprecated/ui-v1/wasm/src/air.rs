@@ -0,0 +1,133 @@
+//! Air quality: a coarse oxygen field over the tile map, one level (0.0
+//! stale to 1.0 fresh) per cell of AIR_CELL_TILES × AIR_CELL_TILES tiles.
+//! Lit foliage freshens the air and breathing promisers use it up; air mixes
+//! between neighboring cells through the open tiles along their shared edge,
+//! and the open sky above the top of the map keeps the surface fresh. Air in
+//! cells cut off from the sky (see regions.rs) slowly goes stale, so sealed
+//! caves run out of oxygen, and promisers move slower in thin air.
+//!
+//! There is no fire yet; it would burn oxygen like a crowd of breathers.
+use crate::{Promiser, TileMap, TileType, TILE_SIZE_PIXELS};
+
+pub const AIR_CELL_TILES: usize = 4;
+const AIR_STEP_SECONDS: f32 = 1.0; // One foliage step (every 60 ticks)
+
+const PHOTOSYNTHESIS: f32 = 0.05; // Open tiles' worth of fresh air per second per fully lit foliage tile
+const BREATH: f32 = 0.02; // Open tiles' worth of air a promiser uses per second
+const EXCHANGE: f32 = 0.1; // Mixing per second per open tile along a cell edge
+const STALE_LEVEL: f32 = 0.3; // Enclosed air drifts toward this
+const STALING_RATE: f32 = 0.002; // Per second
+const THIN_AIR: f32 = 0.5; // Below this, promisers slow down
+const MIN_BREATH_SPEED: f64 = 0.5; // Speed multiplier with no oxygen at all
+
+impl TileMap {
+    fn air_cells(&self) -> (usize, usize) {
+        (self.width.div_ceil(AIR_CELL_TILES), self.height.div_ceil(AIR_CELL_TILES))
+    }
+
+    /// Oxygen level at a pixel position, if this map's air is simulated
+    pub fn oxygen_at_pixel(&self, px: f64, py: f64) -> Option<f32> {
+        let (cols, rows) = self.air_cells();
+        let cx = (px / TILE_SIZE_PIXELS).floor() as i64 / AIR_CELL_TILES as i64;
+        let cy = (py / TILE_SIZE_PIXELS).floor() as i64 / AIR_CELL_TILES as i64;
+        if cx < 0 || cy < 0 || cx as usize >= cols || cy as usize >= rows {
+            return None;
+        }
+        self.oxygen.get(cy as usize * cols + cx as usize).copied()
+    }
+
+    /// Speed multiplier for breathing the air at a pixel position
+    pub(crate) fn breath_speed(&self, px: f64, py: f64) -> f64 {
+        let oxygen = self.oxygen_at_pixel(px, py).unwrap_or(1.0) as f64;
+        if oxygen >= THIN_AIR as f64 {
+            return 1.0;
+        }
+        MIN_BREATH_SPEED + (1.0 - MIN_BREATH_SPEED) * oxygen / THIN_AIR as f64
+    }
+
+    /// Advance the oxygen field by one foliage step, with promisers breathing
+    /// at the given pixel positions
+    pub fn step_air(&mut self, breathers: &[(f64, f64)]) {
+        let dt = AIR_STEP_SECONDS;
+        let (cols, rows) = self.air_cells();
+        if self.oxygen.len() != cols * rows {
+            self.oxygen = vec![1.0; cols * rows];
+        }
+        let open = |map: &TileMap, x: usize, y: usize| !Promiser::is_solid_tile(map.tiles[y * map.width + x].tile_type);
+
+        // Open tiles (the cell's volume), freshening from lit foliage and
+        // whether any of it is open to the sky, per cell
+        let mut volume = vec![0.0f32; cols * rows];
+        let mut change = vec![0.0f32; cols * rows];
+        let mut enclosed = vec![true; cols * rows];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = (y / AIR_CELL_TILES) * cols + x / AIR_CELL_TILES;
+                let tile = self.tiles[y * self.width + x].tile_type;
+                if open(self, x, y) {
+                    volume[c] += 1.0;
+                    enclosed[c] &= self.is_enclosed(y * self.width + x);
+                } else if tile == TileType::Foliage {
+                    let light = self.light_at(x as i32, y as i32).unwrap_or(1.0).min(1.0);
+                    change[c] += PHOTOSYNTHESIS * light * dt;
+                }
+            }
+        }
+        for &(px, py) in breathers {
+            let (cx, cy) = ((px / TILE_SIZE_PIXELS).floor() as i64 / AIR_CELL_TILES as i64, (py / TILE_SIZE_PIXELS).floor() as i64 / AIR_CELL_TILES as i64);
+            if cx >= 0 && cy >= 0 && (cx as usize) < cols && (cy as usize) < rows {
+                change[cy as usize * cols + cx as usize] -= BREATH * dt;
+            }
+        }
+
+        // Mixing across each cell's right and top edges, and with the sky
+        let old = self.oxygen.clone();
+        let mix = |a: usize, level_b: f32, b: Option<usize>, openings: f32, change: &mut [f32]| {
+            if openings == 0.0 {
+                return;
+            }
+            let smallest = b.map_or(volume[a], |b| volume[a].min(volume[b]));
+            // Never move more than a quarter of the smaller cell per step
+            let flow = (EXCHANGE * openings * dt).min(smallest / 4.0) * (level_b - old[a]);
+            change[a] += flow;
+            if let Some(b) = b {
+                change[b] -= flow;
+            }
+        };
+        for cy in 0..rows {
+            for cx in 0..cols {
+                let a = cy * cols + cx;
+                let (x0, y0) = (cx * AIR_CELL_TILES, cy * AIR_CELL_TILES);
+                let x_range = x0..(x0 + AIR_CELL_TILES).min(self.width);
+                let y_range = y0..(y0 + AIR_CELL_TILES).min(self.height);
+                // Right edge (around the seam in wrapping worlds)
+                let right_x = (x0 + AIR_CELL_TILES).min(self.width);
+                let right = if right_x < self.width { Some(right_x) } else if self.wrap_x && cols > 1 { Some(0) } else { None };
+                if let Some(rx) = right {
+                    let openings = y_range.clone().filter(|&y| open(self, right_x - 1, y) && open(self, rx, y)).count() as f32;
+                    let b = cy * cols + rx / AIR_CELL_TILES;
+                    mix(a, old[b], Some(b), openings, &mut change);
+                }
+                // Top edge, or the sky above the top row
+                let top_y = (y0 + AIR_CELL_TILES).min(self.height);
+                if top_y < self.height {
+                    let openings = x_range.filter(|&x| open(self, x, top_y - 1) && open(self, x, top_y)).count() as f32;
+                    let b = (cy + 1) * cols + cx;
+                    mix(a, old[b], Some(b), openings, &mut change);
+                } else {
+                    let openings = x_range.filter(|&x| open(self, x, top_y - 1)).count() as f32;
+                    mix(a, 1.0, None, openings, &mut change);
+                }
+            }
+        }
+
+        for c in 0..cols * rows {
+            if volume[c] == 0.0 {
+                continue; // Solid through and through; nothing to breathe
+            }
+            let level = old[c] + change[c] / volume[c];
+            let staling = if enclosed[c] { STALING_RATE * dt } else { 0.0 };
+            self.oxygen[c] = (level + (STALE_LEVEL - level) * staling).clamp(0.0, 1.0);
+        }
+    }
+}
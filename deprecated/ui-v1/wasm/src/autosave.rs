@@ -0,0 +1,46 @@
+//! Autosave: every so many ticks the game saves itself (the same bytes as
+//! save_game, see persistence.rs) and raises an Autosaved event, so the page
+//! can fetch the save with latest_autosave and keep it somewhere that
+//! outlives a crashed tab. Only the latest save is kept here; restoring it
+//! rewinds what a save covers (see save_game): the overworld's tiles and
+//! fertility, promisers, loose items and Pixel's inventory. Promisers come
+//! back idle, with what they carried set down. Everything else carries on
+//! from the present, so an item fed to a pet or a furnace since the save is
+//! back on the ground as well as in the pet or furnace.
+use crate::events::SimEvent;
+use crate::GameState;
+
+impl GameState {
+    /// Save every `ticks` ticks from now on; 0 turns autosaving off
+    pub(crate) fn set_autosave_interval(&mut self, ticks: u64) {
+        self.autosave_ticks = ticks;
+        console_log!(Info, Save, "Autosave every {} ticks", ticks);
+    }
+
+    /// Save if it's time to, after the tick count moves on
+    pub(crate) fn autosave_if_due(&mut self) {
+        if self.autosave_ticks == 0 || !self.tick_count.is_multiple_of(self.autosave_ticks) {
+            return;
+        }
+        let bytes = self.save_game();
+        self.events.push(SimEvent::Autosaved { tick: self.tick_count, bytes: bytes.len() });
+        self.autosave = Some((self.tick_count, bytes));
+    }
+
+    /// The latest autosave, as save_game bytes, if there's been one
+    pub(crate) fn latest_save(&self) -> Option<&[u8]> {
+        self.autosave.as_ref().map(|(_, bytes)| bytes.as_slice())
+    }
+
+    /// Load the latest autosave back; false if there's none yet
+    pub(crate) fn rewind_to_autosave(&mut self) -> bool {
+        let Some((tick, bytes)) = self.autosave.take() else { return false };
+        let restored = self.load_game(&bytes);
+        if restored {
+            console_log!(Info, Save, "Restored the autosave from tick {}", tick);
+        }
+        // Kept, to restore again
+        self.autosave = Some((tick, bytes));
+        restored
+    }
+}
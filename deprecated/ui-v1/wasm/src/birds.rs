@@ -0,0 +1,171 @@
+//! Birds in flight and at rest. Flying birds ignore gravity (see
+//! creatures.rs), look ahead along their heading and turn before they hit
+//! anything solid, and get blown along by the wind. After a while on the
+//! wing a bird looks for a treetop nearby, flies to it and perches there
+//! until it's rested or a promiser comes too close and flushes it. Pets only
+//! land while their owner is near.
+//!
+//! The wind is one horizontal speed for the whole world: it wanders between
+//! calm and gusts, gustier while it rains (see shelter.rs).
+use crate::creatures::CreatureKind;
+use crate::ecs::Body;
+use crate::pets::PET_DISTANCE;
+use crate::{dimension, random, GameState, TileMap, TileType, TILE_SIZE_PIXELS};
+
+const LOOKAHEAD: f64 = 1.5 * TILE_SIZE_PIXELS; // How far ahead a flying bird looks for obstacles
+const TURNS: [f64; 6] = [0.8, -0.8, 1.6, -1.6, 2.4, -2.4]; // Radians, tried in order to fly around one
+const FLIGHT_SECONDS: (f64, f64) = (15.0, 45.0); // How long a bird flies before looking to land, min and max
+const PERCH_RANGE: i32 = 8; // Tiles a bird looks for a treetop
+const LANDING_SECONDS: f64 = 10.0; // A bird that can't reach its treetop by then flies on
+const PERCH_SECONDS: (f64, f64) = (5.0, 20.0); // How long a bird rests, min and max
+const FLUSH_RADIUS: f64 = 3.0 * TILE_SIZE_PIXELS; // Perched birds take off from promisers closer than this
+const TAKEOFF_CLIMB: f64 = 0.7; // Share of its speed a bird climbs at taking off
+const WIND_DRIFT: f64 = 0.5; // Share of the wind speed a flying bird is carried along at
+const MAX_WIND: f64 = 60.0; // Pixels per second, either way
+const GUSTINESS: f64 = 40.0; // Most the wind changes per second (doubled in the rain)
+const CALMING: f64 = 0.05; // Per second, how fast the wind settles back toward calm
+
+/// Where a bird is between flights
+#[derive(Clone, Copy, Debug)]
+pub enum Roost {
+    /// On the wing, looking to land once `seconds` run out
+    Flying { seconds: f64 },
+    /// Heading for the open tile (x, y) on top of a tree, giving up once
+    /// `seconds` run out
+    Landing { x: i32, y: i32, seconds: f64 },
+    /// Resting on a tree for `seconds` more
+    Perched { seconds: f64 },
+}
+
+impl Roost {
+    fn flying() -> Roost {
+        Roost::Flying { seconds: between(FLIGHT_SECONDS) }
+    }
+}
+
+fn between((min, max): (f64, f64)) -> f64 {
+    min + random() * (max - min)
+}
+
+// Treetops: open tiles right on top of foliage or wood
+fn is_treetop(map: &TileMap, x: i32, y: i32) -> bool {
+    map.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Air)
+        && map.get_tile(x, y - 1).is_some_and(|tile| matches!(tile.tile_type, TileType::Foliage | TileType::Wood))
+}
+
+// Nearest treetop within PERCH_RANGE of tile (x, y)
+fn find_treetop(map: &TileMap, x: i32, y: i32) -> Option<(i32, i32)> {
+    let mut found = None;
+    for dy in -PERCH_RANGE..=PERCH_RANGE {
+        for dx in -PERCH_RANGE..=PERCH_RANGE {
+            let distance = dx * dx + dy * dy;
+            if found.is_none_or(|(_, best)| distance < best) && is_treetop(map, x + dx, y + dy) {
+                found = Some(((x + dx, y + dy), distance));
+            }
+        }
+    }
+    found.map(|(tile, _)| tile)
+}
+
+impl GameState {
+    /// Set the wind speed (pixels per second, positive blows right); it
+    /// keeps changing from there
+    pub(crate) fn set_wind_speed(&mut self, speed: f64) {
+        self.wind = speed.clamp(-MAX_WIND, MAX_WIND);
+    }
+
+    /// Let the wind wander, gustier while it rains in the overworld; it holds
+    /// still with weather off
+    pub(crate) fn update_wind(&mut self, dt: f64) {
+        if !self.config.rules.weather_enabled {
+            return;
+        }
+        let gustiness = if self.tile_map.is_raining() { 2.0 * GUSTINESS } else { GUSTINESS };
+        let wind = self.wind + (random() - 0.5) * 2.0 * gustiness * dt - self.wind * CALMING * dt;
+        self.wind = wind.clamp(-MAX_WIND, MAX_WIND);
+    }
+
+    /// Land, perch, take off, steer around obstacles and drift with the
+    /// wind, for every bird before the creatures move
+    pub(crate) fn update_birds(&mut self, dt: f64) {
+        let birds: Vec<u32> = self.ecs.creatures.iter()
+            .filter(|&(_, &kind)| kind == CreatureKind::Bird)
+            .map(|(id, _)| id)
+            .collect();
+        let speed = CreatureKind::Bird.speed();
+        for id in birds {
+            let Some(&body) = self.ecs.bodies.get(id) else { continue };
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, body.dimension) else { continue };
+            let (tile_x, tile_y) = ((body.x / TILE_SIZE_PIXELS).floor() as i32, (body.y / TILE_SIZE_PIXELS).floor() as i32);
+            let distance_to = |p: &crate::Promiser| map.offset_x(body.x, p.x).hypot(p.y - body.y);
+            let owner = self.pet_owner(id);
+            let owner_near = owner.is_none_or(|owner| {
+                self.promisers.get(&owner).is_some_and(|p| p.dimension == body.dimension && distance_to(p) <= PET_DISTANCE)
+            });
+
+            let mut body = body;
+            let roost = match self.ecs.roosts.get(id).copied().unwrap_or_else(Roost::flying) {
+                Roost::Perched { seconds } => {
+                    let flushed = owner.is_none()
+                        && self.promisers.values().any(|p| p.dimension == body.dimension && distance_to(p) < FLUSH_RADIUS);
+                    if seconds > dt && !flushed && owner_near && is_treetop(map, tile_x, tile_y) {
+                        Roost::Perched { seconds: seconds - dt }
+                    } else {
+                        body.vx = if random() < 0.5 { -speed } else { speed };
+                        body.vy = speed * TAKEOFF_CLIMB;
+                        Roost::flying()
+                    }
+                }
+                Roost::Landing { x, y, .. } if (x, y) == (tile_x, tile_y) && is_treetop(map, x, y) => {
+                    // Settle right on top of the tree
+                    (body.vx, body.vy) = (0.0, 0.0);
+                    body.y = y as f64 * TILE_SIZE_PIXELS + CreatureKind::Bird.size() + 0.5;
+                    Roost::Perched { seconds: between(PERCH_SECONDS) }
+                }
+                Roost::Landing { x, y, seconds } if seconds > dt && owner_near && is_treetop(map, x, y) => {
+                    let target_x = (x as f64 + 0.5) * TILE_SIZE_PIXELS;
+                    let target_y = y as f64 * TILE_SIZE_PIXELS + CreatureKind::Bird.size() + 0.5;
+                    let (dx, dy) = (map.offset_x(body.x, target_x), target_y - body.y);
+                    let distance = dx.hypot(dy).max(1.0);
+                    (body.vx, body.vy) = (speed * dx / distance, speed * dy / distance);
+                    Roost::Landing { x, y, seconds: seconds - dt }
+                }
+                Roost::Landing { .. } => Roost::flying(),
+                Roost::Flying { seconds } if seconds > dt => Roost::Flying { seconds: seconds - dt },
+                Roost::Flying { .. } => match find_treetop(map, tile_x, tile_y).filter(|_| owner_near) {
+                    Some((x, y)) => Roost::Landing { x, y, seconds: LANDING_SECONDS },
+                    None => Roost::flying(),
+                },
+            };
+            self.ecs.roosts.insert(id, roost);
+
+            if !matches!(roost, Roost::Perched { .. }) {
+                avoid_obstacles(map, &mut body);
+                let drifted = body.x + self.wind * WIND_DRIFT * dt;
+                if !CreatureKind::Bird.collision().blocked_at(map, drifted, body.y) {
+                    body.x = drifted;
+                }
+            }
+            *self.ecs.bodies.get_mut(id).unwrap() = body;
+        }
+    }
+}
+
+// Turn a flying bird whose heading runs into something solid within
+// LOOKAHEAD onto the first clear heading, or back the way it came
+fn avoid_obstacles(map: &TileMap, body: &mut Body) {
+    let speed = body.vx.hypot(body.vy);
+    if speed == 0.0 {
+        return;
+    }
+    let collision = CreatureKind::Bird.collision();
+    let clear = |vx: f64, vy: f64| !collision.blocked_at(map, body.x + vx / speed * LOOKAHEAD, body.y + vy / speed * LOOKAHEAD);
+    if clear(body.vx, body.vy) {
+        return;
+    }
+    let heading = body.vy.atan2(body.vx);
+    let turned = TURNS.iter()
+        .map(|turn| ((heading + turn).cos() * speed, (heading + turn).sin() * speed))
+        .find(|&(vx, vy)| clear(vx, vy));
+    (body.vx, body.vy) = turned.unwrap_or((-body.vx, -body.vy));
+}
@@ -0,0 +1,138 @@
+//! Blueprints: a schematic placed as ghost tiles that promisers build one tile
+//! at a time, each from a matching item they fetch first.
+use serde::Serialize;
+
+use crate::events::SimEvent;
+use crate::items::ItemKind;
+use crate::schematic::Schematic;
+use crate::tasks::Task;
+use crate::{GameState, Tile, TileType, TILE_SIZE_PIXELS};
+
+pub const BUILD_SECONDS: f64 = 2.0; // Time to build one tile
+pub const BUILD_REACH: f64 = 48.0; // How close a builder has to be to the tile (pixels)
+
+/// A tile waiting to be built
+#[derive(Clone, Debug, Serialize)]
+pub struct Ghost {
+    pub x: i32,
+    pub y: i32,
+    pub tile_type: TileType,
+    #[serde(skip)]
+    pub claimed_by: Option<u32>, // Promiser building this one
+}
+
+impl Ghost {
+    /// Item used up building this tile
+    pub fn material(&self) -> ItemKind {
+        ItemKind::from_tile(self.tile_type).unwrap_or(ItemKind::Dirt)
+    }
+
+    /// Pixel position of the tile center
+    pub fn center(&self) -> (f64, f64) {
+        ((self.x as f64 + 0.5) * TILE_SIZE_PIXELS, (self.y as f64 + 0.5) * TILE_SIZE_PIXELS)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Blueprint {
+    pub id: u32,
+    pub dimension: u32,
+    pub ghosts: Vec<Ghost>, // Tiles still to build
+    pub total: usize,
+}
+
+impl Blueprint {
+    /// Ghosts for every cell of the schematic that can be built from an item,
+    /// with its bottom-left corner at tile (x, y). Air, water and transparent
+    /// cells are left alone.
+    pub fn new(id: u32, dimension: u32, schematic: &Schematic, x: i32, y: i32) -> Blueprint {
+        let mut ghosts = Vec::new();
+        // Bottom rows first, so walls go up from the ground
+        for sy in 0..schematic.height {
+            for sx in 0..schematic.width {
+                if let Some(tile_type) = schematic.get(sx, sy).filter(|&t| ItemKind::from_tile(t).is_some()) {
+                    ghosts.push(Ghost { x: x + sx as i32, y: y + sy as i32, tile_type, claimed_by: None });
+                }
+            }
+        }
+        let total = ghosts.len();
+        Blueprint { id, dimension, ghosts, total }
+    }
+
+    pub fn ghost(&self, x: i32, y: i32) -> Option<&Ghost> {
+        self.ghosts.iter().find(|ghost| ghost.x == x && ghost.y == y)
+    }
+}
+
+impl GameState {
+    /// Give idle promisers an unclaimed ghost to build, along with the nearest
+    /// unreserved item it needs
+    pub(crate) fn assign_construction(&mut self) {
+        // Free ghosts whose builder died or gave up
+        for blueprint in &mut self.blueprints {
+            for ghost in &mut blueprint.ghosts {
+                let still_building = ghost.claimed_by
+                    .and_then(|id| self.promisers.get(&id))
+                    .is_some_and(|promiser| promiser.task.is_some_and(|task| task.builds(blueprint.id, ghost.x, ghost.y)));
+                if !still_building {
+                    ghost.claimed_by = None;
+                }
+            }
+        }
+
+        let open: Vec<(u32, u32, i32, i32, ItemKind)> = self.blueprints.iter()
+            .flat_map(|blueprint| blueprint.ghosts.iter()
+                .filter(|ghost| ghost.claimed_by.is_none())
+                .map(|ghost| (blueprint.id, blueprint.dimension, ghost.x, ghost.y, ghost.material())))
+            .collect();
+
+        for (blueprint, dim, x, y, kind) in open {
+            let Some(map) = crate::dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else {
+                continue;
+            };
+            let (ghost_x, ghost_y) = ((x as f64 + 0.5) * TILE_SIZE_PIXELS, (y as f64 + 0.5) * TILE_SIZE_PIXELS);
+            let Some((item, item_x, item_y)) = self.ecs.items()
+                .filter(|item| item.kind == kind && item.dimension == dim && item.reserved_by.is_none())
+                .map(|item| (item.id, item.x, item.y, map.offset_x(ghost_x, item.x).hypot(item.y - ghost_y)))
+                .min_by(|a, b| a.3.total_cmp(&b.3).then(a.0.cmp(&b.0)))
+                .map(|(id, x, y, _)| (id, x, y))
+            else {
+                continue; // Nothing to build it from yet
+            };
+            let Some(worker) = self.idle_worker(dim, item_x, item_y) else {
+                break;
+            };
+
+            self.promisers.get_mut(&worker).unwrap().task = Some(Task::Fetch { blueprint, x, y, item, elapsed: 0.0 });
+            self.ecs.reservations.insert(item, worker);
+            if let Some(ghost) = self.blueprints.iter_mut()
+                .find(|b| b.id == blueprint)
+                .and_then(|b| b.ghosts.iter_mut().find(|g| g.x == x && g.y == y))
+            {
+                ghost.claimed_by = Some(worker);
+            }
+        }
+    }
+
+    /// Turn a ghost into a real tile with the material the builder carries
+    pub(crate) fn finish_ghost(&mut self, builder: u32, blueprint_id: u32, x: i32, y: i32) {
+        let Some(index) = self.blueprints.iter().position(|b| b.id == blueprint_id) else { return };
+        let blueprint = &mut self.blueprints[index];
+        let Some(ghost_index) = blueprint.ghosts.iter().position(|g| g.x == x && g.y == y) else { return };
+        let ghost = blueprint.ghosts.remove(ghost_index);
+        let (built, total, dim) = (blueprint.total - blueprint.ghosts.len(), blueprint.total, blueprint.dimension);
+
+        if let Some(promiser) = self.promisers.get_mut(&builder) {
+            promiser.use_carried(ghost.material());
+        }
+        if let Some(map) = self.dimension_map_mut(dim) {
+            map.set_tile(x, y, Tile { tile_type: ghost.tile_type, water_amount: 0, variant: 0 });
+        }
+
+        self.events.push(SimEvent::BlueprintProgress { id: blueprint_id, built, total });
+        if built == total {
+            self.blueprints.remove(index);
+            self.events.push(SimEvent::BlueprintComplete { id: blueprint_id });
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! World borders: what lies past each edge of a tile map (see
+//! SimConfig::borders). A wall holds everything in: promisers and creatures
+//! bounce off or stop at it and light reflects off it. The void lets things
+//! go: promisers and creatures past it are lost, water drains into it and
+//! light escapes. Wrapping edges connect left to right (a toroidal world);
+//! only the sides can wrap, so a wrapping top or bottom is a wall.
+use crate::config::{Borders, Edge};
+use crate::{LightRay, TileMap, TILE_SIZE_PIXELS};
+
+const WALL_REFLECTANCE: f64 = 0.9; // Light kept by a ray bouncing off a wall, as off solid tiles
+
+impl TileMap {
+    /// Take on the given edge behavior
+    pub fn set_borders(&mut self, borders: Borders) {
+        self.wrap_x = borders.left == Edge::Wrap || borders.right == Edge::Wrap;
+        self.open_left = borders.left == Edge::Void && !self.wrap_x;
+        self.open_right = borders.right == Edge::Void && !self.wrap_x;
+        self.open_top = borders.top == Edge::Void;
+        self.open_bottom = borders.bottom == Edge::Void;
+    }
+
+    fn pixel_size(&self) -> (f64, f64) {
+        (self.width as f64 * TILE_SIZE_PIXELS, self.height as f64 * TILE_SIZE_PIXELS)
+    }
+
+    /// Whether a pixel position is more than `margin` past a void edge, and
+    /// so gone for good
+    pub fn in_void(&self, px: f64, py: f64, margin: f64) -> bool {
+        let (width, height) = self.pixel_size();
+        (self.open_left && px < -margin)
+            || (self.open_right && px > width + margin)
+            || (self.open_bottom && py < -margin)
+            || (self.open_top && py > height + margin)
+    }
+
+    /// A pixel position brought back inside: wrapped around, or pushed back
+    /// onto any wall it went through. Positions past the void are left as is.
+    pub fn within_walls(&self, px: f64, py: f64) -> (f64, f64) {
+        let (width, height) = self.pixel_size();
+        let x = if self.wrap_x {
+            px.rem_euclid(width)
+        } else {
+            let x = if self.open_left { px } else { px.max(0.0) };
+            if self.open_right { x } else { x.min(width) }
+        };
+        let y = if self.open_bottom { py } else { py.max(0.0) };
+        let y = if self.open_top { y } else { y.min(height) };
+        (x, y)
+    }
+
+    /// Bounce a light ray that has left the map back off the walls it went
+    /// through, a little dimmer. Returns false if it escaped into the void or
+    /// is too dim to go on.
+    pub(crate) fn reflect_off_walls(&self, ray: &mut LightRay) -> bool {
+        let (width, height) = self.pixel_size();
+        let mut reflected = false;
+        if !self.wrap_x && (ray.x < 0.0 || ray.x >= width) {
+            if (ray.x < 0.0 && self.open_left) || (ray.x >= width && self.open_right) {
+                return false;
+            }
+            ray.vx = -ray.vx;
+            ray.x = ray.x.clamp(0.0, width - 0.01);
+            reflected = true;
+        }
+        if ray.y < 0.0 || ray.y >= height {
+            if (ray.y < 0.0 && self.open_bottom) || (ray.y >= height && self.open_top) {
+                return false;
+            }
+            ray.vy = -ray.vy;
+            ray.y = ray.y.clamp(0.0, height - 0.01);
+            reflected = true;
+        }
+        if reflected {
+            ray.intensity *= WALL_REFLECTANCE;
+        }
+        ray.intensity >= 0.1
+    }
+}
+
+impl Borders {
+    /// Edges from the older config flags: walls all around, with wrapping
+    /// sides and an open bottom if asked for
+    pub fn from_flags(wrap_x: bool, open_bottom: bool) -> Borders {
+        let side = if wrap_x { Edge::Wrap } else { Edge::Wall };
+        Borders { left: side, right: side, top: Edge::Wall, bottom: if open_bottom { Edge::Void } else { Edge::Wall } }
+    }
+}
@@ -0,0 +1,63 @@
+//! Flat per-tile buffers that live in WASM memory, so renderers can read the
+//! tile map through typed array views instead of deserializing it every frame.
+//!
+//! JS side: `new Uint8Array(memory.buffer, tile_types_ptr(), tile_buffer_len())`
+//! (likewise for variants, shadow and fertility, Uint16Array for water and Float32Array
+//! for brightness). The pointers change when the world is resized or memory grows,
+//! so views must be recreated whenever `tile_buffer_len()` or `memory.buffer`
+//! changes.
+//!
+//! The lightmap is the brightness again as RGBA bytes, `4 * tile_buffer_len()`
+//! long and row by row from y=0 (the bottom), ready to upload as a width×height
+//! texture and multiply over the scene. Moonlight is tinted in it.
+use crate::light::{self, AmbientLight};
+use crate::sun::Sun;
+use crate::TileMap;
+
+#[derive(Default)]
+pub struct TileBuffers {
+    pub types: Vec<u8>,       // TileType discriminant per tile
+    pub variants: Vec<u8>,    // Tile shape and grass bit per tile (see shape.rs)
+    pub water: Vec<u16>,      // Water amount per tile
+    pub brightness: Vec<f32>, // Light energy per tile (see light.rs), 0.0 to 1.0
+    pub shadow: Vec<u8>,      // 1 where the tile is shaded from the sun, or the moon at night (see sun.rs)
+    pub lightmap: Vec<u8>,    // RGBA per tile, brightness scaled to 0-255
+    pub fertility: Vec<u8>,   // Soil fertility per tile, for overlays (see fertility.rs)
+}
+
+impl TileBuffers {
+    /// Refresh the buffers in place from the tile map, the sky and ambient
+    /// light; `seconds` is simulation time, which drives the water shimmer
+    pub fn sync(&mut self, map: &TileMap, sun: &Sun, ambient: &AmbientLight, seconds: f64) {
+        let len = map.tiles.len();
+        if self.types.len() != len {
+            // Only reallocate when the world changes size
+            self.types = vec![0; len];
+            self.variants = vec![0; len];
+            self.water = vec![0; len];
+            self.brightness = vec![0.0; len];
+            self.shadow = vec![0; len];
+            self.lightmap = vec![255; len * 4];
+            self.fertility = vec![0; len];
+        }
+
+        let moon_up = sun.light_direction().is_none();
+        map.cast_shadows(sun.light_direction().or(sun.moon_direction()), &mut self.shadow);
+        let moon_rgb = ambient.moon_rgb();
+
+        for (i, tile) in map.tiles.iter().enumerate() {
+            self.types[i] = tile.tile_type as u8;
+            self.variants[i] = tile.variant;
+            self.water[i] = tile.water_amount;
+            self.fertility[i] = map.fertility(i);
+            let (x, y) = ((i % map.width) as i32, (i / map.width) as i32);
+            let rays = map.brightness(i) * light::shimmer(map, x, y, seconds);
+            let moon = if moon_up && self.shadow[i] == 0 { ambient.moon } else { 0.0 };
+            self.brightness[i] = (rays + moon).clamp(ambient.floor, 1.0);
+            for (channel, tint) in moon_rgb.iter().enumerate() {
+                let level = (rays + moon * tint).clamp(ambient.floor, 1.0);
+                self.lightmap[i * 4 + channel] = (level * 255.0).round() as u8; // Alpha stays opaque
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! The chronicle: a running history of notable happenings (deaths, ...) for the
+//! JS side to show. Unlike the event queue it isn't drained when read.
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+// Oldest entries are forgotten past this
+const MAX_ENTRIES: usize = 512;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChronicleEntry {
+    pub tick: u64,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct Chronicle {
+    entries: VecDeque<ChronicleEntry>,
+}
+
+impl Chronicle {
+    pub fn record(&mut self, tick: u64, text: String) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ChronicleEntry { tick, text });
+    }
+
+    /// All entries, oldest first, as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.entries).unwrap_or_else(|_| "[]".to_string())
+    }
+}
@@ -0,0 +1,47 @@
+//! Collision layers and masks. Everything that moves sits on some layers and
+//! has a mask of the layers it collides with, so a new kind of body picks its
+//! interactions by choosing a filter instead of adding special cases to every
+//! update loop: a ghost leaves TERRAIN out of its mask, a water strider adds
+//! WATER_SURFACE, and bodies without TRIGGERS walk over portals.
+use serde::{Deserialize, Serialize};
+
+use crate::{TileMap, TileType, MAX_WATER_AMOUNT, TILE_SIZE_PIXELS};
+
+pub const TERRAIN: u8 = 1 << 0; // Solid tiles
+pub const ENTITIES: u8 = 1 << 1; // Promisers and creatures
+pub const WATER_SURFACE: u8 = 1 << 2; // Water, for bodies that float or walk on it
+pub const TRIGGERS: u8 = 1 << 3; // Tiles that act on whatever enters them, such as portals
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionFilter {
+    pub layer: u8, // Layers this body is on
+    pub mask: u8,  // Layers this body collides with
+}
+
+impl CollisionFilter {
+    pub const PROMISER: CollisionFilter = CollisionFilter { layer: ENTITIES, mask: TERRAIN | TRIGGERS };
+    /// Rays (raycasts, line of sight) stop at terrain and entities
+    pub const RAY: CollisionFilter = CollisionFilter { layer: 0, mask: TERRAIN | ENTITIES };
+
+    pub fn hits(self, layers: u8) -> bool {
+        self.mask & layers != 0
+    }
+
+    /// Whether a body with this filter is blocked at a pixel position: inside
+    /// a solid tile's filled part, or below the surface of water
+    pub fn blocked_at(self, map: &TileMap, px: f64, py: f64) -> bool {
+        if self.hits(TERRAIN) && map.is_solid_at_pixel(px, py) {
+            return true;
+        }
+        self.hits(WATER_SURFACE) && map.get_tile_at_pixel(px, py).is_some_and(|tile| {
+            let depth = tile.water_amount as f64 / MAX_WATER_AMOUNT as f64;
+            tile.tile_type == TileType::Water && (py / TILE_SIZE_PIXELS).rem_euclid(1.0) < depth
+        })
+    }
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        CollisionFilter::PROMISER
+    }
+}
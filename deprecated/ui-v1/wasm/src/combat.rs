@@ -0,0 +1,151 @@
+//! Combat: hostile creatures (see threat.rs) attack promisers within reach,
+//! and promisers brave enough stand their ground and hit back instead of
+//! running. Every hit takes health (see vitals.rs) and knocks whoever took
+//! it back. Creatures keep their own health here; one beaten down to nothing
+//! is gone and leaves its drops behind. What a promiser carried it drops
+//! when it dies, however it died.
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::items::ItemKind;
+use crate::vitals::MAX_HEALTH;
+use crate::{dimension, GameState, Promiser, TILE_SIZE_PIXELS};
+
+pub const ATTACK_REACH: f64 = TILE_SIZE_PIXELS; // Between centers, for either side to hit
+const ATTACK_COOLDOWN: f64 = 1.0; // Seconds between two attacks by the same one
+const PUNCH_DAMAGE: f64 = 5.0; // A promiser hitting bare-handed
+const TOOL_DAMAGE: f64 = 10.0; // A promiser hitting with a tool in hand
+const COURAGE: f64 = 0.3; // Bravery times health fraction it takes to stand and fight
+const KNOCKBACK: f64 = 4.0; // Promiser velocity a bite gives, away from the biter
+const KNOCKBACK_LIFT: f64 = 3.0;
+const CREATURE_KNOCKBACK: f64 = 80.0; // Pixels per second a hit gives a creature
+const CREATURE_KNOCKBACK_LIFT: f64 = 60.0;
+
+impl CreatureKind {
+    /// Health a creature starts with
+    pub fn max_health(self) -> f64 {
+        match self {
+            CreatureKind::Wolf => 30.0,
+            CreatureKind::Fish | CreatureKind::Bird | CreatureKind::Rabbit | CreatureKind::Mole => 10.0,
+        }
+    }
+
+    /// Damage one attack does to a promiser, 0.0 for creatures that don't
+    /// attack
+    pub fn attack_damage(self) -> f64 {
+        match self {
+            CreatureKind::Wolf => 10.0,
+            CreatureKind::Fish | CreatureKind::Bird | CreatureKind::Rabbit | CreatureKind::Mole => 0.0,
+        }
+    }
+
+    /// What a defeated one leaves behind
+    pub fn drops(self) -> Option<(ItemKind, u32)> {
+        match self {
+            CreatureKind::Wolf => Some((ItemKind::Meat, 2)),
+            CreatureKind::Fish => Some((ItemKind::Fish, 1)),
+            CreatureKind::Bird | CreatureKind::Rabbit | CreatureKind::Mole => None,
+        }
+    }
+
+    // Cause of death for promisers it kills
+    fn cause(self) -> &'static str {
+        match self {
+            CreatureKind::Fish => "fish",
+            CreatureKind::Bird => "bird",
+            CreatureKind::Rabbit => "rabbit",
+            CreatureKind::Mole => "mole",
+            CreatureKind::Wolf => "wolf",
+        }
+    }
+}
+
+impl Promiser {
+    /// Whether the promiser stands and fights hostile creatures instead of
+    /// running: brave, and not too hurt
+    pub(crate) fn fights_back(&self) -> bool {
+        self.traits.bravery * self.health / MAX_HEALTH >= COURAGE
+    }
+}
+
+impl GameState {
+    /// Creature `id`'s health
+    pub(crate) fn creature_health(&self, id: u32) -> Option<f64> {
+        let kind = *self.ecs.creatures.get(id)?;
+        Some(self.ecs.health.get(id).copied().unwrap_or(kind.max_health()))
+    }
+
+    /// Let hostile creatures and the promisers fighting them trade blows
+    pub(crate) fn update_combat(&mut self, dt: f64) {
+        if !self.config.rules.entities_take_damage {
+            return;
+        }
+        let hostiles: Vec<(u32, CreatureKind)> = self.ecs.creatures.iter()
+            .filter(|&(_, &kind)| kind.attack_damage() > 0.0)
+            .map(|(id, &kind)| (id, kind))
+            .collect();
+        for promiser in self.promisers.values_mut() {
+            promiser.attack_cooldown = (promiser.attack_cooldown - dt).max(0.0);
+        }
+
+        let mut defeated = Vec::new();
+        for (id, kind) in hostiles {
+            let Some(&body) = self.ecs.bodies.get(id) else { continue };
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, body.dimension) else { continue };
+            let cooldown = self.ecs.cooldowns.get(id).copied().unwrap_or(0.0) - dt;
+            self.ecs.cooldowns.insert(id, cooldown.max(0.0));
+
+            // Promisers within reach, nearest first
+            let mut in_reach: Vec<(f64, u32, f64)> = self.promisers.values()
+                .filter(|p| p.dimension == body.dimension)
+                .map(|p| (map.offset_x(body.x, p.x), p.y - body.y, p.id))
+                .filter(|&(dx, dy, _)| dx.hypot(dy) <= ATTACK_REACH)
+                .map(|(dx, dy, pid)| (dx.hypot(dy), pid, dx))
+                .collect();
+            in_reach.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+            if let Some(&(_, target, dx)) = in_reach.first().filter(|_| cooldown <= 0.0) {
+                let damage = kind.attack_damage();
+                let promiser = self.promisers.get_mut(&target).unwrap();
+                promiser.damage(damage, kind.cause());
+                promiser.vx += KNOCKBACK * dx.signum();
+                promiser.vy += KNOCKBACK_LIFT;
+                self.ecs.cooldowns.insert(id, ATTACK_COOLDOWN);
+                self.events.push(SimEvent::Attacked { promiser: target, creature: id, damage, attacker: "creature".to_string() });
+            }
+
+            // Brave ones within reach hit back
+            for &(_, pid, dx) in &in_reach {
+                let promiser = self.promisers.get_mut(&pid).unwrap();
+                if promiser.attack_cooldown > 0.0 || promiser.controlled || !promiser.fights_back() {
+                    continue;
+                }
+                promiser.attack_cooldown = ATTACK_COOLDOWN;
+                let damage = if promiser.tool.is_some() { TOOL_DAMAGE } else { PUNCH_DAMAGE };
+                let health = self.creature_health(id).unwrap() - damage;
+                self.ecs.health.insert(id, health);
+                let body = self.ecs.bodies.get_mut(id).unwrap();
+                body.vx = -CREATURE_KNOCKBACK * dx.signum();
+                body.vy = CREATURE_KNOCKBACK_LIFT;
+                self.events.push(SimEvent::Attacked { promiser: pid, creature: id, damage, attacker: "promiser".to_string() });
+                if health <= 0.0 {
+                    defeated.push((id, kind));
+                    break;
+                }
+            }
+        }
+
+        for (id, kind) in defeated {
+            self.defeat(id, kind, "fought");
+        }
+    }
+
+    /// Take out creature `id`, beaten down to nothing, leaving its drops
+    pub(crate) fn defeat(&mut self, id: u32, kind: CreatureKind, cause: &str) {
+        let Some(&body) = self.ecs.bodies.get(id) else { return };
+        self.ecs.despawn(id);
+        if let Some((item, count)) = kind.drops() {
+            self.add_item(item, count, body.x, body.y, body.dimension);
+        }
+        self.events.push(SimEvent::CreatureDied { id, kind, cause: cause.to_string() });
+    }
+}
@@ -0,0 +1,150 @@
+//! Simulation configuration passed from JS as JSON (e.g. to `init_game`).
+//! Every field is optional; missing fields fall back to defaults.
+use serde::Deserialize;
+
+use crate::light::LightBudget;
+
+/// Named world generation presets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorldPreset {
+    Flat,
+    Islands,
+    Caverns,
+    Waterworld,
+    #[default]
+    Classic,
+}
+
+impl WorldPreset {
+    /// Preset from its lowercase name, e.g. "caverns"
+    pub fn from_name(name: &str) -> Option<WorldPreset> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Light ray quality tiers, trading smoother lighting for CPU time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LightQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl LightQuality {
+    /// (rays per tile, fraction of the budget spawned per second, ray speed)
+    fn settings(self) -> (f64, f64, f64) {
+        match self {
+            LightQuality::Low => (8.0, 0.05, 80.0),
+            LightQuality::Medium => (20.0, 0.1, 100.0),
+            LightQuality::High => (40.0, 0.2, 120.0),
+        }
+    }
+}
+
+/// How hard hostile waves hit (see waves.rs); peaceful has no hostile
+/// creatures at all
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// Toggles between a pure sandbox and a survival simulation; all on (and
+/// water finite) by default
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    pub infinite_water: bool,       // Water isn't used up: no evaporating, seeping away into dirt or foliage, overflowing, draining into the void or scooping out
+    pub plants_need_light: bool,    // Foliage and grass only grow where light reaches
+    pub entities_take_damage: bool, // Promisers and creatures get hurt; old age still comes when off
+    pub weather_enabled: bool,      // The wind changes on its own and the director brings rain
+    pub fire_spreads: bool,         // Reserved: there is no fire yet (see air.rs); accepted and ignored until there is
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules { infinite_water: false, plants_need_light: true, entities_take_damage: true, weather_enabled: true, fire_spreads: true }
+    }
+}
+
+/// What lies past an edge of the world (see borders.rs)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    #[default]
+    Wall,
+    Void,
+    Wrap, // Sides only
+}
+
+/// Edge behavior per side, e.g. {"left": "wrap", "right": "wrap", "bottom": "void"}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Borders {
+    pub left: Edge,
+    pub right: Edge,
+    pub top: Edge,
+    pub bottom: Edge,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    pub seed: Option<u64>,     // World seed (random when omitted)
+    pub preset: WorldPreset,   // Which worldgen preset to use
+    pub wrap_x: bool,          // Toroidal world: the left and right edges connect
+    pub open_bottom: bool,     // Entities and water falling past y=0 are removed
+    pub borders: Option<Borders>, // Per-edge behavior; overrides wrap_x and open_bottom when given
+    pub director: f64,         // Director intensity, 0.0 (off) to 1.0 (see director.rs)
+    pub light_quality: LightQuality,
+    pub max_light_rays: Option<usize>, // Ray budget; by default it scales with the world's area
+    pub light_spawn_rate: Option<f64>, // Fraction of the ray budget spawned per second
+    pub ray_speed: Option<f64>,        // Pixels per second
+    pub day_seconds: Option<f64>,      // Length of a day (see sun.rs); 0 keeps the sun still
+    pub ambient_light: Option<f64>,    // Brightness floor, 0.0 to 1.0; 0 allows pitch black
+    pub moon_light: Option<f64>,       // Moonlight brightness at night, 0.0 to 1.0
+    pub moon_color: Option<u32>,       // RGB tint of moonlight
+    pub day_temperature: Option<f64>,  // Air temperature at noon, °C (see thermal.rs)
+    pub night_temperature: Option<f64>, // Air temperature at night, °C; below 0 freezes water
+    pub ambient_spawning: Option<bool>, // Creatures and items appearing on their own (see creatures.rs); on by default
+    pub item_lifetime: Option<f64>,    // Seconds before a loose item despawns (see despawn.rs); 0 keeps them forever
+    pub max_items: Option<usize>,      // Most loose items at once; the oldest go first
+    pub max_creatures: Option<usize>,  // Most creatures at once; the oldest go first
+    pub support_span: Option<u32>,     // Dirt and snow this many tiles out from grounded terrain collapse (see structure.rs); off when omitted
+    pub difficulty: Difficulty,        // Hostile waves each night (see waves.rs); "peaceful" turns hostiles off
+    pub rules: Rules,                  // Sandbox toggles, e.g. {"infinite_water": true, "entities_take_damage": false}
+    pub autosave_ticks: u64,           // Ticks between autosaves (see autosave.rs); off when omitted
+    pub warmup_ticks: u32,             // Ticks the land runs before the first frame (see warmup.rs); none by default
+}
+
+impl SimConfig {
+    /// Parse a config from JSON, falling back to defaults on malformed input
+    pub fn from_json(json: &str) -> SimConfig {
+        serde_json::from_str(json).unwrap_or_else(|err| {
+            console_log!(Error, Core, "Invalid sim config ({}), using defaults", err);
+            SimConfig::default()
+        })
+    }
+
+    /// Edge behavior for tile maps
+    pub fn borders(&self) -> Borders {
+        self.borders.unwrap_or_else(|| Borders::from_flags(self.wrap_x, self.open_bottom))
+    }
+
+    /// Light ray budget for a world with this many tiles
+    pub fn light_budget(&self, tiles: usize) -> LightBudget {
+        let (rays_per_tile, spawn_rate, ray_speed) = self.light_quality.settings();
+        LightBudget {
+            max_rays: self.max_light_rays.unwrap_or((tiles as f64 * rays_per_tile) as usize),
+            spawn_rate: self.light_spawn_rate.unwrap_or(spawn_rate).max(0.0),
+            ray_speed: self.ray_speed.unwrap_or(ray_speed).max(1.0),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Crafting: a fixed table of recipes turning stored items into other items,
+//! run against what's lying in a stockpile.
+use serde::Serialize;
+
+use crate::items::ItemKind;
+use crate::GameState;
+
+#[derive(Debug, Serialize)]
+pub struct Recipe {
+    pub name: &'static str,
+    pub inputs: &'static [(ItemKind, u32)],
+    pub output: (ItemKind, u32),
+}
+
+pub const RECIPES: &[Recipe] = &[
+    Recipe { name: "platform", inputs: &[(ItemKind::Wood, 1)], output: (ItemKind::Platform, 2) },
+    Recipe { name: "ladder", inputs: &[(ItemKind::Wood, 2)], output: (ItemKind::Ladder, 1) },
+    Recipe { name: "bricks", inputs: &[(ItemKind::Stone, 2)], output: (ItemKind::Bricks, 1) },
+    Recipe { name: "furnace", inputs: &[(ItemKind::Stone, 4)], output: (ItemKind::Furnace, 1) },
+    Recipe { name: "spikes", inputs: &[(ItemKind::Wood, 1), (ItemKind::Ingot, 1)], output: (ItemKind::Spikes, 2) },
+    Recipe { name: "pitcover", inputs: &[(ItemKind::Wood, 1), (ItemKind::Plant, 2)], output: (ItemKind::PitCover, 1) },
+    Recipe { name: "reinforcedwall", inputs: &[(ItemKind::Bricks, 2), (ItemKind::Ingot, 1)], output: (ItemKind::ReinforcedWall, 1) },
+    Recipe { name: "shovel", inputs: &[(ItemKind::Wood, 1), (ItemKind::Stone, 1)], output: (ItemKind::Shovel, 1) },
+    Recipe { name: "pick", inputs: &[(ItemKind::Wood, 1), (ItemKind::Stone, 2)], output: (ItemKind::Pick, 1) },
+    Recipe { name: "bucket", inputs: &[(ItemKind::Ore, 2)], output: (ItemKind::Bucket, 1) },
+];
+
+pub fn find(name: &str) -> Option<&'static Recipe> {
+    RECIPES.iter().find(|recipe| recipe.name == name)
+}
+
+impl GameState {
+    /// Total count of a kind of item stored in a stockpile
+    pub(crate) fn stockpile_count(&self, stockpile_id: u32, kind: ItemKind) -> u32 {
+        let Some(stockpile) = self.stockpiles.iter().find(|s| s.id == stockpile_id) else {
+            return 0;
+        };
+        self.ecs.items()
+            .filter(|item| item.kind == kind && stockpile.contains(item.dimension, item.x, item.y))
+            .map(|item| item.count)
+            .sum()
+    }
+
+    /// Remove `count` items of a kind from a stockpile, oldest stacks first.
+    /// The caller checks there are enough.
+    pub(crate) fn take_from_stockpile(&mut self, stockpile_id: u32, kind: ItemKind, mut count: u32) {
+        let Some(stockpile) = self.stockpiles.iter().find(|s| s.id == stockpile_id) else {
+            return;
+        };
+        let mut stacks: Vec<u32> = self.ecs.items()
+            .filter(|item| item.kind == kind && stockpile.contains(item.dimension, item.x, item.y))
+            .map(|item| item.id)
+            .collect();
+        stacks.sort_unstable();
+
+        for id in stacks {
+            if count == 0 {
+                break;
+            }
+            count -= self.ecs.take_item(id, count);
+        }
+    }
+}
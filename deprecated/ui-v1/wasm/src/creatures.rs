@@ -0,0 +1,424 @@
+//! Creatures: the animals sharing the world with the promisers. Fish swim in
+//! water, birds fly through open air and rabbits hop along the ground, grazing
+//! on grass as they go and bolting from promisers that come too close. What
+//! each kind does is its behavior tree (see ai.rs); this module moves it.
+//! Tamed ones (see pets.rs) follow their owner instead of fleeing, fish
+//! school together (see fish.rs), birds perch in trees (see birds.rs) and
+//! moles dig tunnels (see moles.rs).
+//!
+//! Besides the promisers every world starts with, creatures and items appear
+//! on their own: every AMBIENT_SPAWN_TICKS, each spawn rule tries a few random
+//! overworld tiles and spawns there if the tile suits it (depth of water,
+//! light, what's below) and its population is under the rule's cap.
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{Agent, Node, IDLE};
+use crate::birds::Roost;
+use crate::collision::{CollisionFilter, ENTITIES, TERRAIN, WATER_SURFACE};
+use crate::ecs::{Body, Ecs};
+use crate::items::ItemKind;
+use crate::items::ItemStack;
+use crate::light::MIN_FOLIAGE_LIGHT;
+use crate::pets::PET_DISTANCE;
+use crate::{dimension, random, GameState, Promiser, TileMap, TileType, GRAVITY, MAX_WATER_AMOUNT, OVERWORLD, TILE_SIZE_PIXELS};
+
+pub const AMBIENT_SPAWN_TICKS: u64 = 300; // Ticks between spawn attempts (≈ 5s at 60fps)
+const SPAWN_TRIES: usize = 8; // Random tiles each rule looks at per attempt
+const TURN_CHANCE: f64 = 0.02; // Per tick, chance to pick a new heading
+const HOP_CHANCE: f64 = 0.01; // Per tick, for a rabbit on the ground
+const HOP_SPEED: f64 = 120.0; // Pixels per second, upward
+const GRAZE_CHANCE: f64 = 0.005; // Per tick, for a rabbit standing on grass
+const GRASS_RANGE: i32 = 8; // Tiles a rabbit looks for grass
+const FLEE_RADIUS: f64 = 4.0 * TILE_SIZE_PIXELS; // Rabbits run from promisers closer than this
+pub(crate) const HUNT_RADIUS: f64 = 8.0 * TILE_SIZE_PIXELS; // Wolves stalk promisers closer than this
+const SKY_CLEARANCE: i32 = 6; // Tiles of air under a bird spawned in open sky
+
+// Fish and birds cruise about, turning now and then
+const CRUISE: Node<Creature> = Node::Wander(|_| TURN_CHANCE);
+// Birds hold their course while landing or perched (see birds.rs)
+const BIRD: Node<Creature> = Node::Selector(&[Node::If(|creature| creature.roosting), CRUISE]);
+// Rabbits run from promisers, head for grass and graze it, and otherwise wander
+const RABBIT: Node<Creature> = Node::Selector(&[
+    Node::Flee { radius: FLEE_RADIUS },
+    Node::Sequence(&[
+        Node::SeekTile { tile: |map, x, y| map.is_grassy(x, y - 1), range: GRASS_RANGE },
+        Node::If(|creature| creature.grounded),
+        Node::Chance(|_| GRAZE_CHANCE),
+        Node::Do(Creature::graze),
+    ]),
+    Node::Wander(|_| TURN_CHANCE),
+]);
+// Pets keep up with their owner, and otherwise go about as usual
+// Moles hold still while digging, and otherwise meander along their tunnels
+const MOLE: Node<Creature> = Node::Selector(&[Node::If(|creature| creature.burrowing), Node::Wander(|_| TURN_CHANCE)]);
+// Wolves stalk the nearest promiser in range, and otherwise prowl about
+const WOLF: Node<Creature> = Node::Selector(&[Node::Follow { within: TILE_SIZE_PIXELS / 2.0 }, Node::Wander(|_| TURN_CHANCE)]);
+const PET_CRUISE: Node<Creature> = Node::Selector(&[Node::Follow { within: PET_DISTANCE }, CRUISE]);
+const PET_BIRD: Node<Creature> = Node::Selector(&[Node::Follow { within: PET_DISTANCE }, BIRD]);
+const PET_RABBIT: Node<Creature> = Node::Selector(&[Node::Follow { within: PET_DISTANCE }, RABBIT]);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CreatureKind {
+    Fish,
+    Bird,
+    Rabbit,
+    Mole,
+    Wolf, // Hostile (see threat.rs)
+}
+
+impl CreatureKind {
+    /// Radius in pixels
+    pub fn size(self) -> f64 {
+        match self {
+            CreatureKind::Fish => 5.0,
+            CreatureKind::Bird => 4.0,
+            CreatureKind::Rabbit => 6.0,
+            CreatureKind::Mole => 5.0,
+            CreatureKind::Wolf => 7.0,
+        }
+    }
+
+    /// Cruising speed in pixels per second
+    pub(crate) fn speed(self) -> f64 {
+        match self {
+            CreatureKind::Fish => 30.0,
+            CreatureKind::Bird => 80.0,
+            CreatureKind::Rabbit => 40.0,
+            CreatureKind::Mole => 15.0,
+            CreatureKind::Wolf => 50.0,
+        }
+    }
+
+    /// Whether it keeps to the ground, under gravity
+    pub fn walks(self) -> bool {
+        matches!(self, CreatureKind::Rabbit | CreatureKind::Wolf)
+    }
+
+    /// What the creature bumps into (see collision.rs): birds don't dive into
+    /// water, the others swim or sink
+    pub fn collision(self) -> CollisionFilter {
+        match self {
+            CreatureKind::Fish | CreatureKind::Rabbit | CreatureKind::Mole | CreatureKind::Wolf => CollisionFilter { layer: ENTITIES, mask: TERRAIN },
+            CreatureKind::Bird => CollisionFilter { layer: ENTITIES, mask: TERRAIN | WATER_SURFACE },
+        }
+    }
+
+    // Whether the creature can be at a pixel position: fish in water, birds in
+    // open air, rabbits and wolves anywhere they aren't buried, moles in
+    // tunnels and against cave walls
+    fn can_be_at(self, map: &TileMap, x: f64, y: f64) -> bool {
+        let Some(tile) = map.get_tile_at_pixel(x, y) else { return false };
+        let open = !self.collision().blocked_at(map, x, y);
+        match self {
+            CreatureKind::Fish => open && tile.tile_type == TileType::Water,
+            CreatureKind::Bird | CreatureKind::Rabbit | CreatureKind::Wolf => open,
+            CreatureKind::Mole => {
+                let (tile_x, tile_y) = ((x / TILE_SIZE_PIXELS).floor() as i32, (y / TILE_SIZE_PIXELS).floor() as i32);
+                open && map.is_burrow(tile_x, tile_y)
+            }
+        }
+    }
+}
+
+impl CreatureKind {
+    fn behavior(self, pet: bool) -> &'static Node<Creature> {
+        match (self, pet) {
+            (CreatureKind::Fish, false) => &CRUISE,
+            (CreatureKind::Fish, true) => &PET_CRUISE,
+            (CreatureKind::Bird, false) => &BIRD,
+            (CreatureKind::Bird, true) => &PET_BIRD,
+            (CreatureKind::Rabbit, false) => &RABBIT,
+            (CreatureKind::Rabbit, true) => &PET_RABBIT,
+            (CreatureKind::Mole, _) => &MOLE,
+            (CreatureKind::Wolf, _) => &WOLF,
+        }
+    }
+
+    fn pick_heading(self, body: &mut Body) {
+        let speed = self.speed();
+        match self {
+            CreatureKind::Fish | CreatureKind::Bird => {
+                let angle = random() * std::f64::consts::TAU;
+                // Mostly level, like fish and birds cruising
+                (body.vx, body.vy) = (speed * angle.cos(), speed * angle.sin() * 0.3);
+            }
+            CreatureKind::Rabbit | CreatureKind::Wolf => body.vx = if random() < 0.5 { -speed } else { speed },
+            CreatureKind::Mole => (body.vx, body.vy) = self.burrow_heading(),
+        }
+    }
+}
+
+/// A creature as its behavior tree sees it, copied out of the ECS for one tick
+struct Creature {
+    kind: CreatureKind,
+    body: Body,
+    grounded: bool,
+    threat: Option<(f64, f64)>, // Offset to the nearest promiser
+    owner: Option<(f64, f64)>, // Offset to its owner, for pets in the owner's dimension
+    pet: bool,
+    roosting: bool, // Landing or perched
+    burrowing: bool, // Digging into the tile ahead (see moles.rs)
+    grazed: Option<(i32, i32)>, // Tile grazed this tick
+}
+
+impl Creature {
+    fn graze(&mut self) {
+        let tile_x = (self.body.x / TILE_SIZE_PIXELS).floor() as i32;
+        let below_y = ((self.body.y - self.kind.size() - 1.0) / TILE_SIZE_PIXELS).floor() as i32;
+        self.grazed = Some((tile_x, below_y));
+    }
+
+    /// Let the creature's behavior tree pick a heading, then move for `dt`
+    /// seconds, turning back from anywhere it can't go
+    fn update(&mut self, map: &TileMap, dt: f64) {
+        let kind = self.kind;
+        self.grounded = kind.collision().blocked_at(map, self.body.x, self.body.y - kind.size() - 1.0);
+        kind.behavior(self.pet).tick(self, map, dt);
+        let hunting = self.leader().is_some();
+
+        let (body, grounded) = (&mut self.body, self.grounded);
+        if kind.walks() || !kind.can_be_at(map, body.x, body.y) {
+            // Rabbits and wolves, and the others when out of their element, fall
+            body.vy = if grounded { body.vy.max(0.0) } else { body.vy - GRAVITY * dt };
+        }
+        if kind == CreatureKind::Rabbit && grounded && random() < HOP_CHANCE {
+            body.vy = HOP_SPEED;
+        }
+
+        // Out of its element, anything that isn't solid will do
+        let in_element = kind.can_be_at(map, body.x, body.y);
+        let passable = |x, y| if in_element { kind.can_be_at(map, x, y) } else { !kind.collision().blocked_at(map, x, y) };
+        let (new_x, new_y) = (body.x + body.vx * dt, body.y + body.vy * dt);
+        if passable(new_x, body.y) {
+            body.x = new_x;
+        } else if kind.attack_damage() > 0.0 && map.is_reinforced_at_pixel(new_x + kind.size() * body.vx.signum(), body.y) {
+            // Hostile creatures turn from reinforced walls, unless there's
+            // prey behind one; then they stay and chew at it (see defenses.rs)
+            if !hunting {
+                body.vx = -body.vx;
+            }
+        } else {
+            if kind == CreatureKind::Wolf && grounded {
+                // Wolves leap up what's in their way instead of turning
+                body.vy = HOP_SPEED;
+            } else {
+                body.vx = -body.vx;
+            }
+        }
+        if passable(body.x, new_y) {
+            body.y = new_y;
+        } else {
+            body.vy = if kind.walks() { 0.0 } else { -body.vy };
+        }
+        (body.x, body.y) = map.within_walls(body.x, body.y);
+    }
+}
+
+impl Agent for Creature {
+    // Creatures have no activities (yet); they're always idle
+    fn activity(&self) -> (u32, f64) {
+        (IDLE, 0.0)
+    }
+
+    fn set_activity(&mut self, _activity: u32) {}
+
+    fn position(&self) -> (f64, f64) {
+        (self.body.x, self.body.y)
+    }
+
+    fn steer(&mut self, dx: f64, dy: f64, _dt: f64) {
+        let speed = self.kind.speed();
+        match self.kind {
+            CreatureKind::Fish | CreatureKind::Bird | CreatureKind::Mole => {
+                let distance = dx.hypot(dy).max(1.0);
+                (self.body.vx, self.body.vy) = (speed * dx / distance, speed * dy / distance);
+            }
+            CreatureKind::Rabbit | CreatureKind::Wolf => self.body.vx = speed * dx.signum(),
+        }
+    }
+
+    fn wander(&mut self, _map: &TileMap) {
+        self.kind.pick_heading(&mut self.body);
+    }
+
+    fn threat(&self) -> Option<(f64, f64)> {
+        self.threat
+    }
+
+    // A pet follows its owner, a wolf the promiser it's stalking
+    fn leader(&self) -> Option<(f64, f64)> {
+        match self.kind {
+            CreatureKind::Wolf => self.threat.filter(|&(dx, dy)| dx.hypot(dy) < HUNT_RADIUS),
+            _ => self.owner,
+        }
+    }
+}
+
+/// A creature as exported to JS
+#[derive(Serialize)]
+struct CreatureView {
+    id: u32,
+    kind: CreatureKind,
+    #[serde(flatten)]
+    body: Body,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<u32>, // Pets only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    carrying: Option<ItemStack>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    perched: bool, // Birds resting on a tree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<f64>, // Creatures that have been hurt (see combat.rs)
+}
+
+/// What an ambient spawn rule puts into the world
+#[derive(Clone, Copy)]
+enum Spawn {
+    Creature(CreatureKind),
+    Item(ItemKind),
+}
+
+struct SpawnRule {
+    spawn: Spawn,
+    cap: usize,                                // Most of these alive (or lying around) at once
+    habitat: fn(&TileMap, i32, i32) -> bool, // Whether tile (x, y) suits it
+}
+
+const SPAWN_RULES: [SpawnRule; 6] = [
+    // Fish in deep water: a full water tile under more water
+    SpawnRule { spawn: Spawn::Creature(CreatureKind::Fish), cap: 30, habitat: |map, x, y| {
+        let deep = |y| map.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Water && tile.water_amount >= MAX_WATER_AMOUNT * 3 / 4);
+        deep(y) && deep(y + 1)
+    } },
+    // Birds in lit air over forests: foliage a few tiles below
+    SpawnRule { spawn: Spawn::Creature(CreatureKind::Bird), cap: 20, habitat: |map, x, y| {
+        is_lit_air(map, x, y) && (1..=4).any(|dy| map.get_tile(x, y - dy).is_some_and(|tile| tile.tile_type == TileType::Foliage))
+    } },
+    // Birds high in open sky: lit air with nothing above and a few tiles of
+    // air below
+    SpawnRule { spawn: Spawn::Creature(CreatureKind::Bird), cap: 20, habitat: |map, x, y| {
+        is_lit_air(map, x, y) && !map.is_sheltered(x, y)
+            && (1..=SKY_CLEARANCE).all(|dy| map.get_tile(x, y - dy).is_some_and(|tile| tile.tile_type == TileType::Air))
+    } },
+    // Rabbits on lit grass
+    SpawnRule { spawn: Spawn::Creature(CreatureKind::Rabbit), cap: 15, habitat: |map, x, y| {
+        is_lit_air(map, x, y) && map.is_grassy(x, y - 1)
+    } },
+    // Moles deep in dirt, where they dig themselves out
+    SpawnRule { spawn: Spawn::Creature(CreatureKind::Mole), cap: 6, habitat: |map, x, y| {
+        let dirt = |x, y| map.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Dirt);
+        dirt(x, y) && dirt(x, y + 1) && dirt(x, y - 1)
+    } },
+    // Lit foliage sheds plant matter onto the ground beneath it
+    SpawnRule { spawn: Spawn::Item(ItemKind::Plant), cap: 20, habitat: |map, x, y| {
+        is_lit_air(map, x, y) && map.get_tile(x, y + 1).is_some_and(|tile| tile.tile_type == TileType::Foliage)
+            && map.get_tile(x, y - 1).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type))
+    } },
+];
+
+fn is_lit_air(map: &TileMap, x: i32, y: i32) -> bool {
+    map.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Air)
+        && map.light_at(x, y).is_none_or(|light| light >= MIN_FOLIAGE_LIGHT)
+}
+
+impl GameState {
+    pub(crate) fn add_creature(&mut self, kind: CreatureKind, x: f64, y: f64, dimension: u32) -> u32 {
+        let id = self.ecs.spawn();
+        let mut body = Body { x, y, vx: 0.0, vy: 0.0, dimension };
+        kind.pick_heading(&mut body);
+        self.ecs.bodies.insert(id, body);
+        self.ecs.creatures.insert(id, kind);
+        id
+    }
+
+    /// Move every creature in the tile map of its dimension
+    pub(crate) fn update_creatures(&mut self, dt: f64) {
+        let mut grazed = Vec::new();
+        let mut lost = Vec::new();
+        let Ecs { bodies, creatures, pets, roosts, burrows, .. } = &mut self.ecs;
+        for (id, &kind) in creatures.iter() {
+            let Some(body) = bodies.get_mut(id) else { continue };
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, body.dimension) else {
+                continue;
+            };
+            let owner = pets.get(id).filter(|pet| pet.tamed(kind)).map(|pet| pet.owner);
+            let threat = self.promisers.values()
+                .filter(|p| p.dimension == body.dimension && owner.is_none())
+                .map(|p| (map.offset_x(body.x, p.x), p.y - body.y))
+                .min_by(|a, b| a.0.hypot(a.1).total_cmp(&b.0.hypot(b.1)));
+            let leader = owner.and_then(|owner| self.promisers.get(&owner))
+                .filter(|p| p.dimension == body.dimension)
+                .map(|p| (map.offset_x(body.x, p.x), p.y - body.y));
+            let mut creature = Creature { kind, body: *body, grounded: false, threat, owner: leader, pet: owner.is_some(), roosting: roosts.get(id).is_some_and(|roost| !matches!(roost, Roost::Flying { .. })),
+                burrowing: burrows.get(id).is_some(), grazed: None };
+            creature.update(map, dt);
+            *body = creature.body;
+            if let Some((x, y)) = creature.grazed {
+                grazed.push((body.dimension, x, y));
+            }
+            if map.in_void(body.x, body.y, kind.size()) {
+                lost.push(id);
+            }
+        }
+        for id in lost {
+            self.ecs.despawn(id);
+        }
+        for (dim, x, y) in grazed {
+            if let Some(map) = dimension::dimension_map_mut(&mut self.tile_map, &mut self.dimensions, dim) {
+                map.graze(x, y);
+            }
+        }
+    }
+
+    /// Try every ambient spawn rule once
+    pub(crate) fn spawn_ambient(&mut self) {
+        if !self.config.ambient_spawning.unwrap_or(true) {
+            return;
+        }
+        for rule in &SPAWN_RULES {
+            let population = match rule.spawn {
+                Spawn::Creature(kind) => self.ecs.creatures.iter().filter(|&(_, &k)| k == kind).count(),
+                Spawn::Item(kind) => self.ecs.stacks.iter().filter(|&(_, stack)| stack.kind == kind).count(),
+            };
+            let full = match rule.spawn {
+                Spawn::Creature(_) => self.ecs.creatures.len() >= self.max_creatures(),
+                Spawn::Item(_) => self.ecs.stacks.len() >= self.max_items(),
+            };
+            if population >= rule.cap || full {
+                continue;
+            }
+            let map = &self.tile_map;
+            let spot = (0..SPAWN_TRIES)
+                .map(|_| ((random() * map.width as f64) as i32, (random() * map.height as f64) as i32))
+                .find(|&(x, y)| (rule.habitat)(map, x, y));
+            let Some((x, y)) = spot else { continue };
+            let (px, py) = ((x as f64 + 0.5) * TILE_SIZE_PIXELS, (y as f64 + 0.5) * TILE_SIZE_PIXELS);
+            match rule.spawn {
+                Spawn::Creature(kind) => {
+                    self.add_creature(kind, px, py, OVERWORLD);
+                }
+                Spawn::Item(kind) => {
+                    self.add_item(kind, 1, px, py, OVERWORLD);
+                }
+            }
+        }
+    }
+
+    /// Every creature as JSON, by id
+    pub(crate) fn creatures_json(&self) -> String {
+        let mut creatures: Vec<CreatureView> = self.ecs.creatures.iter()
+            .filter_map(|(id, &kind)| Some(CreatureView {
+                id,
+                kind,
+                body: *self.ecs.bodies.get(id)?,
+                owner: self.pet_owner(id),
+                carrying: self.ecs.pets.get(id).and_then(|pet| pet.carrying),
+                perched: matches!(self.ecs.roosts.get(id), Some(Roost::Perched { .. })),
+                health: self.ecs.health.get(id).copied(),
+            }))
+            .collect();
+        creatures.sort_by_key(|creature| creature.id);
+        serde_json::to_string(&creatures).unwrap_or_else(|_| "[]".to_string())
+    }
+}
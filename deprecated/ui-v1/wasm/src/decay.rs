@@ -0,0 +1,65 @@
+//! Decay: dead foliage and the bodies of promisers rot into Decay tiles,
+//! whose `water_amount` is the matter left in them. Each foliage step a
+//! Decay tile passes some of its matter on to the dirt around and below it as
+//! fertility (see fertility.rs), and disappears once it's used up.
+use crate::{Tile, TileMap, TileType};
+
+pub const FOLIAGE_MATTER: u16 = 128; // Matter in a dead foliage tile
+pub const CORPSE_MATTER: u16 = 512; // Matter in a promiser's body
+const ROT_RATE: u16 = 16; // Matter a Decay tile gives up per step
+
+impl TileMap {
+    /// Leave `matter` to rot at tile (x, y), dropped straight down onto the
+    /// first surface below (or added to decay already lying there). Returns
+    /// whether there was room for it.
+    pub fn add_decay(&mut self, x: i32, y: i32, matter: u16) -> bool {
+        let mut y = y;
+        while self.get_tile(x, y - 1).is_some_and(|below| below.tile_type == TileType::Air) {
+            y -= 1;
+        }
+        let Some(tile) = self.get_tile(x, y).cloned() else { return false };
+        let matter = match tile.tile_type {
+            TileType::Air => matter,
+            TileType::Decay => tile.water_amount.saturating_add(matter),
+            _ => return false,
+        };
+        self.set_tile(x, y, Tile { tile_type: TileType::Decay, water_amount: matter, variant: 0 });
+        true
+    }
+
+    /// Rot every Decay tile a little further, enriching the dirt next to and
+    /// below it
+    pub fn step_decay(&mut self) {
+        self.ensure_fertility();
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut changes: Vec<(i32, i32, Tile)> = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let tile = &self.tiles[(y * w + x) as usize];
+                if tile.tile_type != TileType::Decay {
+                    continue;
+                }
+                let rotted = tile.water_amount.min(ROT_RATE);
+                let soil: Vec<usize> = [(0, -1), (-1, 0), (1, 0), (-1, -1), (1, -1)]
+                    .iter()
+                    .filter_map(|&(dx, dy)| self.index(x + dx, y + dy))
+                    .filter(|&j| self.tiles[j].tile_type == TileType::Dirt)
+                    .collect();
+                for &j in &soil {
+                    let share = (rotted as usize / soil.len()).min(u8::MAX as usize) as u8;
+                    self.fertility[j] = self.fertility[j].saturating_add(share);
+                }
+                let left = tile.water_amount - rotted;
+                let tile = if left == 0 {
+                    Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 }
+                } else {
+                    Tile { water_amount: left, ..tile.clone() }
+                };
+                changes.push((x, y, tile));
+            }
+        }
+        for (x, y, tile) in changes {
+            self.set_tile(x, y, tile);
+        }
+    }
+}
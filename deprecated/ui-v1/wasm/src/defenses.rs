@@ -0,0 +1,69 @@
+//! Defenses promisers can build against hostile creatures (see threat.rs),
+//! placed like any other building tile (see crafting.rs). Spikes wound the
+//! hostile creatures that walk through them; promisers know where they are
+//! and pass unhurt. Pit covers hold promisers but give way under hostile
+//! creatures, dropping them into whatever's below. Reinforced walls are hard
+//! to dig out, and hostile creatures turn from them (see creatures.rs),
+//! unless there's prey close behind one; then they stay and chew at it, very
+//! slowly.
+use crate::creatures::{CreatureKind, HUNT_RADIUS};
+use crate::events::SimEvent;
+use crate::{dimension, GameState, Tile, TileMap, TileType, TILE_SIZE_PIXELS};
+
+const SPIKE_DAMAGE: f64 = 20.0; // Health per second a hostile creature on spikes loses
+const GNAW_SPEED: f32 = 0.25; // Of bare-handed digging (see tools.rs): 32 seconds a reinforced wall tile
+
+impl TileMap {
+    /// Whether the pixel position is in a reinforced wall
+    pub fn is_reinforced_at_pixel(&self, x: f64, y: f64) -> bool {
+        self.get_tile_at_pixel(x, y).is_some_and(|tile| tile.tile_type == TileType::ReinforcedWall)
+    }
+}
+
+impl GameState {
+    /// Spring pit covers under hostile creatures, wound those on spikes and
+    /// let those hunting behind a reinforced wall chew at it, after the
+    /// creatures move
+    pub(crate) fn update_defenses(&mut self, dt: f64) {
+        let hostiles: Vec<(u32, CreatureKind)> = self.ecs.creatures.iter()
+            .filter(|&(_, &kind)| kind.attack_damage() > 0.0)
+            .map(|(id, &kind)| (id, kind))
+            .collect();
+
+        let mut defeated = Vec::new();
+        for (id, kind) in hostiles {
+            let Some(&body) = self.ecs.bodies.get(id) else { continue };
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, body.dimension) else { continue };
+            let hunting = self.promisers.values()
+                .any(|p| p.dimension == body.dimension && map.offset_x(body.x, p.x).hypot(p.y - body.y) < HUNT_RADIUS);
+            let Some(map) = dimension::dimension_map_mut(&mut self.tile_map, &mut self.dimensions, body.dimension) else { continue };
+            let tile_at = |x: f64, y: f64| ((x / TILE_SIZE_PIXELS).floor() as i32, (y / TILE_SIZE_PIXELS).floor() as i32);
+
+            let below = tile_at(body.x, body.y - kind.size() - 1.0);
+            if map.get_tile(below.0, below.1).is_some_and(|tile| tile.tile_type == TileType::PitCover) {
+                map.set_tile(below.0, below.1, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 });
+                self.events.push(SimEvent::TrapSprung { x: below.0, y: below.1, creature: id });
+            }
+
+            let ahead = tile_at(body.x + (kind.size() + 1.0) * body.vx.signum(), body.y);
+            if hunting && body.vx != 0.0 && map.get_tile(ahead.0, ahead.1).is_some_and(|tile| tile.tile_type == TileType::ReinforcedWall) {
+                // Chewed to pieces; nothing's left of it to pick up
+                let _ = map.mine(ahead.0, ahead.1, dt as f32 * GNAW_SPEED);
+            }
+
+            let here = tile_at(body.x, body.y);
+            let spiked = map.get_tile(here.0, here.1).is_some_and(|tile| tile.tile_type == TileType::Spikes);
+            if spiked && self.config.rules.entities_take_damage {
+                let health = self.creature_health(id).unwrap() - SPIKE_DAMAGE * dt;
+                self.ecs.health.insert(id, health);
+                if health <= 0.0 {
+                    defeated.push((id, kind));
+                }
+            }
+        }
+
+        for (id, kind) in defeated {
+            self.defeat(id, kind, "trapped");
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Incremental promiser export: only promisers that moved or changed since the
+//! last call are sent, plus tombstones for ids that were removed.
+use crate::determinism::HashMap;
+use crate::Promiser;
+
+// Movement smaller than this (in pixels) isn't worth re-sending
+const POSITION_THRESHOLD: f64 = 0.5;
+
+// What the consumer was last told about a promiser
+struct Sent {
+    x: f64,
+    y: f64,
+    state: u32,
+    thought: String,
+    dimension: u32,
+    glow: (f64, u32),
+}
+
+impl Sent {
+    fn of(promiser: &Promiser) -> Sent {
+        Sent {
+            x: promiser.x,
+            y: promiser.y,
+            state: promiser.state,
+            thought: promiser.thought.clone(),
+            dimension: promiser.dimension,
+            glow: (promiser.glow, promiser.glow_color),
+        }
+    }
+
+    fn differs_from(&self, promiser: &Promiser) -> bool {
+        (self.x - promiser.x).abs() > POSITION_THRESHOLD
+            || (self.y - promiser.y).abs() > POSITION_THRESHOLD
+            || self.state != promiser.state
+            || self.dimension != promiser.dimension
+            || self.thought != promiser.thought
+            || self.glow != (promiser.glow, promiser.glow_color)
+    }
+}
+
+#[derive(Default)]
+pub struct PromiserDelta {
+    sent: HashMap<u32, Sent>,
+}
+
+impl PromiserDelta {
+    /// JSON `{"tick", "changed": [promiser...], "removed": [id...]}` relative
+    /// to the previous call. The first call sends every promiser.
+    pub fn collect(&mut self, promisers: &HashMap<u32, Promiser>, tick: u64, include_meta: bool) -> String {
+        let mut changed = Vec::new();
+        for promiser in promisers.values() {
+            let stale = self.sent.get(&promiser.id).is_none_or(|sent| sent.differs_from(promiser));
+            if stale {
+                changed.push(promiser.to_json(include_meta));
+                self.sent.insert(promiser.id, Sent::of(promiser));
+            }
+        }
+
+        let mut removed = Vec::new();
+        self.sent.retain(|id, _| {
+            let alive = promisers.contains_key(id);
+            if !alive {
+                removed.push(id.to_string());
+            }
+            alive
+        });
+
+        format!("{{\"tick\":{},\"changed\":[{}],\"removed\":[{}]}}", tick, changed.join(","), removed.join(","))
+    }
+
+    /// Forget what was sent so the next collect() is a full snapshot
+    pub fn reset(&mut self) {
+        self.sent.clear();
+    }
+}
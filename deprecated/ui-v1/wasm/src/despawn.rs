@@ -0,0 +1,99 @@
+//! Despawning, so long sessions don't pile up entities: loose items time out
+//! (except in stockpiles or while someone is on the way to fetch them),
+//! creatures wander off once no promiser is near, and items and creatures
+//! are each kept under a cap, oldest first out. Promisers and pets (see
+//! pets.rs) are never despawned.
+use crate::creatures::CreatureKind;
+use crate::items::{Item, ItemKind};
+use crate::{dimension, GameState, TILE_SIZE_PIXELS};
+
+pub const DESPAWN_CHECK_TICKS: u64 = 60; // Ticks between despawn passes (≈ 1s at 60fps)
+const DEFAULT_ITEM_LIFETIME: f64 = 600.0; // Seconds a loose item lies around
+const DEFAULT_MAX_ITEMS: usize = 500;
+const DEFAULT_MAX_CREATURES: usize = 200;
+const CREATURE_RANGE: f64 = 48.0 * TILE_SIZE_PIXELS; // Creatures farther than this from every promiser despawn
+
+impl GameState {
+    pub(crate) fn max_items(&self) -> usize {
+        self.config.max_items.unwrap_or(DEFAULT_MAX_ITEMS)
+    }
+
+    pub(crate) fn max_creatures(&self) -> usize {
+        self.config.max_creatures.unwrap_or(DEFAULT_MAX_CREATURES)
+    }
+
+    /// Age loose items by `seconds` and remove whatever has timed out,
+    /// strayed or is over its cap
+    pub(crate) fn despawn(&mut self, seconds: f64) {
+        let lifetime = self.config.item_lifetime.unwrap_or(DEFAULT_ITEM_LIFETIME);
+        let mut expired = Vec::new();
+        let items: Vec<Item> = self.ecs.items().collect();
+        for item in items {
+            let age = self.ecs.ages.get_mut(item.id).unwrap();
+            *age += seconds;
+            let kept = item.reserved_by.is_some() || self.stockpiles.iter().any(|s| s.contains(item.dimension, item.x, item.y));
+            if lifetime > 0.0 && *age > lifetime && !kept {
+                expired.push(item.id);
+            }
+        }
+        for id in expired {
+            self.ecs.despawn(id);
+        }
+
+        let (tile_map, dimensions, promisers) = (&self.tile_map, &self.dimensions, &self.promisers);
+        self.ecs.despawn_where(|ecs, id| {
+            let (Some(body), Some(&kind)) = (ecs.bodies.get(id), ecs.creatures.get(id)) else { return false };
+            if ecs.pets.get(id).is_some_and(|pet| pet.tamed(kind)) {
+                return false;
+            }
+            let Some(map) = dimension::dimension_map(tile_map, dimensions, body.dimension) else { return true };
+            !promisers.values()
+                .filter(|p| p.dimension == body.dimension)
+                .any(|p| map.offset_x(body.x, p.x).hypot(p.y - body.y) <= CREATURE_RANGE)
+        });
+
+        let max_items = self.max_items();
+        if self.ecs.stacks.len() > max_items {
+            let mut ids: Vec<u32> = self.ecs.stacks.iter().map(|(id, _)| id).collect();
+            ids.sort_unstable();
+            for &id in &ids[..ids.len() - max_items] {
+                self.ecs.despawn(id);
+            }
+        }
+        let max_creatures = self.max_creatures();
+        if self.ecs.creatures.len() > max_creatures {
+            let mut ids: Vec<u32> = self.ecs.creatures.iter()
+                .map(|(id, _)| id)
+                .filter(|&id| self.pet_owner(id).is_none())
+                .collect();
+            ids.sort_unstable();
+            let excess = (self.ecs.creatures.len() - max_creatures).min(ids.len());
+            for &id in &ids[..excess] {
+                self.ecs.despawn(id);
+            }
+        }
+    }
+
+    /// Remove every entity matching `kind` (see remove_all) and return how
+    /// many went
+    pub(crate) fn remove_entities(&mut self, kind: &str) -> u32 {
+        let before = self.ecs.stacks.len() + self.ecs.creatures.len() + self.promisers.len();
+        match kind {
+            "items" => {
+                self.ecs.despawn_where(|ecs, id| ecs.stacks.get(id).is_some());
+            }
+            "creatures" => {
+                self.ecs.despawn_where(|ecs, id| ecs.creatures.get(id).is_some());
+            }
+            "promisers" => self.promisers.clear(),
+            _ => {
+                if let Ok(kind) = serde_json::from_value::<CreatureKind>(serde_json::Value::String(kind.to_string())) {
+                    self.ecs.despawn_where(|ecs, id| ecs.creatures.get(id) == Some(&kind));
+                } else if let Some(kind) = ItemKind::from_name(kind) {
+                    self.ecs.despawn_where(|ecs, id| ecs.stacks.get(id).is_some_and(|stack| stack.kind == kind));
+                }
+            }
+        }
+        (before - self.ecs.stacks.len() - self.ecs.creatures.len() - self.promisers.len()) as u32
+    }
+}
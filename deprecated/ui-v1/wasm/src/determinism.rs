@@ -0,0 +1,56 @@
+//! Determinism: the same seed and the same inputs give the same run, on every
+//! browser. What that rests on:
+//!
+//! - Randomness. Everything in the simulation draws from one seeded PRNG
+//!   (random() below, the same xorshift64* as worldgen) instead of
+//!   Math.random, seeded from SimConfig::seed when the game is created. Only
+//!   a game created without a seed asks JS for one, once.
+//! - Numbers. The simulation is f64 throughout; f32 only stores some values
+//!   (tile temperatures, crack progress, light intensities). WASM floats are
+//!   IEEE 754 with round-to-nearest for both, and +, -, *, / and sqrt are
+//!   exact to the last bit, so they agree across engines.
+//! - Transcendentals. The simulation uses sin and cos (wander, spray and
+//!   flight angles, the sun), exp (light fading), atan2 (bird headings) and
+//!   hypot (distances, all over). They compile to Rust's own libm inside the
+//!   module, never to the JS engine's Math, so they give the same bits in
+//!   every browser. powi (grass spreading, light reflectance) is repeated
+//!   multiplication, inside the module too. Native builds (cargo test) use
+//!   the system libm and may differ from WASM in the last bit.
+//! - Iteration order. std's HashMap seeds its hasher per process (from
+//!   allocation addresses on wasm32-unknown-unknown), so iterating one visits
+//!   entries in a different order from run to run, and with it the order
+//!   random numbers are drawn in. The simulation's maps and sets are the
+//!   aliases below instead, which always hash the same way.
+//!
+//! New code should keep to these: no Math.* or Date from JS inside the tick,
+//! random() rather than a PRNG of its own, and these HashMap/HashSet rather
+//! than std's.
+use std::cell::RefCell;
+use std::hash::{BuildHasherDefault, DefaultHasher};
+
+use crate::worldgen::WorldRng;
+
+/// std's HashMap with a fixed hasher key (construct with ::default())
+pub type HashMap<K, V> = std::collections::HashMap<K, V, BuildHasherDefault<DefaultHasher>>;
+pub type HashSet<T> = std::collections::HashSet<T, BuildHasherDefault<DefaultHasher>>;
+
+const SIM_STREAM: u64 = 0x5157_1A7E; // Keeps the sim's numbers apart from worldgen's for the same seed
+
+thread_local! {
+    static SIM_RNG: RefCell<WorldRng> = RefCell::new(WorldRng::new(SIM_STREAM));
+}
+
+/// Restart the simulation's random numbers from `seed`
+pub fn seed(seed: u64) {
+    SIM_RNG.with(|rng| *rng.borrow_mut() = WorldRng::new(seed ^ SIM_STREAM));
+}
+
+/// A seed from JS for games created without one
+pub fn fresh_seed() -> u64 {
+    (crate::js_random() * u32::MAX as f64) as u64
+}
+
+/// Uniform value in [0, 1) from the simulation's seeded PRNG
+pub fn random() -> f64 {
+    SIM_RNG.with(|rng| rng.borrow_mut().next_f64())
+}
@@ -0,0 +1,55 @@
+//! Dimensions: extra named tile maps (e.g. a cavern layer) owned by the game
+//! state alongside the overworld, connected through portal tiles.
+use crate::TileMap;
+
+/// Dimension id of the overworld (GameState::tile_map)
+pub const OVERWORLD: u32 = 0;
+pub const OVERWORLD_NAME: &str = "overworld";
+
+/// Which simulations run in a dimension
+#[derive(Clone, Copy, Debug)]
+pub struct DimensionSettings {
+    pub simulate_water: bool,
+    pub simulate_foliage: bool,
+}
+
+impl Default for DimensionSettings {
+    fn default() -> Self {
+        DimensionSettings { simulate_water: true, simulate_foliage: true }
+    }
+}
+
+pub struct Dimension {
+    pub name: String,
+    pub tile_map: TileMap,
+    pub settings: DimensionSettings,
+}
+
+/// One end of a portal: stepping onto (x, y) in `from` moves you to (to_x, to_y) in `to`
+#[derive(Clone, Copy, Debug)]
+pub struct PortalLink {
+    pub from: u32,
+    pub x: i32,
+    pub y: i32,
+    pub to: u32,
+    pub to_x: i32,
+    pub to_y: i32,
+}
+
+/// Tile map of a dimension id (OVERWORLD or 1 + index into `dimensions`)
+pub fn dimension_map<'a>(overworld: &'a TileMap, dimensions: &'a [Dimension], id: u32) -> Option<&'a TileMap> {
+    if id == OVERWORLD {
+        Some(overworld)
+    } else {
+        dimensions.get(id as usize - 1).map(|d| &d.tile_map)
+    }
+}
+
+/// Mutable tile map of a dimension id
+pub fn dimension_map_mut<'a>(overworld: &'a mut TileMap, dimensions: &'a mut [Dimension], id: u32) -> Option<&'a mut TileMap> {
+    if id == OVERWORLD {
+        Some(overworld)
+    } else {
+        dimensions.get_mut(id as usize - 1).map(|d| &mut d.tile_map)
+    }
+}
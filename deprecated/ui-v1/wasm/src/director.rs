@@ -0,0 +1,108 @@
+//! The director: watches how eventful the world is, and when it has been quiet
+//! for too long, stirs things up with rain (and the objective of getting
+//! everyone through it, see objectives.rs), a wandering stranger or a rumor.
+//! Every intervention is written to the chronicle.
+use crate::events::SimEvent;
+use crate::objectives::{Goal, ObjectiveSpec};
+use crate::{random, GameState, Promiser, OVERWORLD, TILE_SIZE_PIXELS};
+
+pub const CHECK_INTERVAL: u64 = 60; // Ticks between looks at the event rate
+const QUIET_EVENTS: u64 = 2; // Fewer new events than this in a check counts as quiet
+const BORED_SECONDS: f64 = 60.0; // Quiet time before stepping in, at full intensity
+const MIN_INTENSITY: f64 = 0.05; // At 0.05, waits 20 times as long
+
+const RUMORS: &[&str] = &[
+    "They say there's ore deep below us",
+    "Someone saw lights down by the water",
+    "A storm is coming, I can feel it",
+    "The elders remember a tunnel nobody can find",
+    "I heard a stranger is on the way",
+];
+
+/// Pacing state; off while intensity is 0
+#[derive(Default)]
+pub struct Director {
+    pub intensity: f64, // 0.0 (off) to 1.0 (steps in after a minute of quiet)
+    last_event_count: u64,
+    quiet_seconds: f64,
+}
+
+impl Director {
+    pub fn new(intensity: f64) -> Director {
+        Director { intensity: intensity.clamp(0.0, 1.0), ..Director::default() }
+    }
+}
+
+impl GameState {
+    /// Called every CHECK_INTERVAL ticks
+    pub(crate) fn run_director(&mut self) {
+        let director = &mut self.director;
+        if director.intensity <= 0.0 {
+            return;
+        }
+        let total = self.events.total_pushed();
+        let recent = total - director.last_event_count;
+        director.last_event_count = total;
+        director.quiet_seconds = if recent < QUIET_EVENTS {
+            director.quiet_seconds + CHECK_INTERVAL as f64 / 60.0
+        } else {
+            0.0
+        };
+        if director.quiet_seconds < BORED_SECONDS / director.intensity.clamp(MIN_INTENSITY, 1.0) {
+            return;
+        }
+        director.quiet_seconds = 0.0;
+
+        let roll = random();
+        let intervention = if roll < 0.4 {
+            self.director_rain()
+        } else if roll < 0.7 {
+            self.director_stranger()
+        } else {
+            self.director_rumor()
+        };
+        if let Some((kind, text)) = intervention {
+            self.chronicle.record(self.tick_count, text);
+            self.events.push(SimEvent::DirectorIntervened { kind: kind.to_string() });
+        }
+    }
+
+    fn director_rain(&mut self) -> Option<(&'static str, String)> {
+        if !self.config.rules.weather_enabled {
+            return None;
+        }
+        let width = self.tile_map.width as i32;
+        let drops = (width as f64 * (1.0 + 2.0 * self.director.intensity)) as u32;
+        self.tile_map.rain(0, width, drops);
+        self.set_objective(ObjectiveSpec { label: "Keep everyone alive through the storm".to_string(), goal: Goal::Storm, time_limit: None });
+        Some(("rain", "Rain began to fall".to_string()))
+    }
+
+    // A stranger drops in somewhere along the top of the overworld
+    fn director_stranger(&mut self) -> Option<(&'static str, String)> {
+        let id = self.next_id;
+        let x = random() * self.world_width;
+        let y = self.world_height - 2.0 * TILE_SIZE_PIXELS;
+        let mut stranger = Promiser::with_pixel(id, x, y, false);
+        let (x, y) = stranger.find_open_spot(x, y, &self.tile_map)?;
+        stranger.x = x;
+        stranger.y = y;
+        stranger.dimension = OVERWORLD;
+        stranger.name = "Stranger".to_string();
+        stranger.color = 0xFF808080;
+        self.spatial.insert(&stranger);
+        self.promisers.insert(id, stranger);
+        self.next_id += 1;
+        Some(("stranger", "A stranger wandered in".to_string()))
+    }
+
+    fn director_rumor(&mut self) -> Option<(&'static str, String)> {
+        let mut ids: Vec<u32> = self.promisers.keys().copied().collect();
+        ids.sort_unstable();
+        let &id = ids.get((random() * ids.len() as f64) as usize)?;
+        let rumor = RUMORS[(random() * RUMORS.len() as f64) as usize % RUMORS.len()];
+        let who = crate::display_name(&self.promisers[&id]);
+        self.make_promiser_speak(id, rumor.to_string(), None, None);
+        Some(("rumor", format!("{} spread a rumor: \"{}\"", who, rumor)))
+    }
+}
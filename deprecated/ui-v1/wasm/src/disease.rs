@@ -0,0 +1,128 @@
+//! Illness: promisers catch it from standing in water or from sick neighbors,
+//! lose health while it lasts, then either recover (and are immune for a
+//! while) or die. Sick promisers walk to the quarantine zone if one is set,
+//! and don't infect anyone while inside it.
+use serde::{Deserialize, Serialize};
+
+use crate::events::SimEvent;
+use crate::lifecycle::LifeStage;
+use crate::tasks::Task;
+use crate::{random, GameState, Promiser, TileMap, TileType, TILE_SIZE_PIXELS};
+
+const WATER_INFECTION_RATE: f64 = 0.002; // Chance per second of catching it while in water
+const CONTAGION_RADIUS: f64 = 48.0; // Pixels
+const CONTAGION_CHANCE: f64 = 0.05; // Per check, per sick neighbor in range
+pub const SPREAD_INTERVAL: u64 = 30; // Ticks between contagion checks
+const ILLNESS_MIN_SECONDS: f64 = 20.0; // An illness lasts 20-40 seconds
+const ILLNESS_RANGE_SECONDS: f64 = 20.0;
+const ILLNESS_DAMAGE: f64 = 1.5; // Health lost per second while sick (adults)
+const IMMUNITY_SECONDS: f64 = 60.0; // Can't catch it again for this long after recovering
+
+/// A rectangle of tiles where sick promisers are kept apart
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuarantineZone {
+    pub dimension: u32,
+    pub x: i32, // Bottom-left tile
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl QuarantineZone {
+    pub fn contains(&self, dimension: u32, px: f64, py: f64) -> bool {
+        let tx = (px / TILE_SIZE_PIXELS).floor() as i32;
+        let ty = (py / TILE_SIZE_PIXELS).floor() as i32;
+        dimension == self.dimension
+            && (self.x..self.x + self.width).contains(&tx)
+            && (self.y..self.y + self.height).contains(&ty)
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.x as f64 + self.width as f64 / 2.0) * TILE_SIZE_PIXELS,
+            (self.y as f64 + 0.5) * TILE_SIZE_PIXELS,
+        )
+    }
+}
+
+impl Promiser {
+    pub(crate) fn is_sick(&self) -> bool {
+        self.illness > 0.0
+    }
+
+    fn can_catch_illness(&self) -> bool {
+        !self.is_sick() && self.immunity == 0.0
+    }
+
+    pub(crate) fn fall_ill(&mut self) {
+        self.illness = ILLNESS_MIN_SECONDS + random() * ILLNESS_RANGE_SECONDS;
+    }
+
+    /// Run the course of an illness, and maybe catch one from the water the
+    /// promiser is standing in. Returns the event for falling ill or
+    /// recovering, if either happened.
+    pub(crate) fn update_illness(&mut self, tile_map: &TileMap, dt: f64) -> Option<SimEvent> {
+        self.immunity = (self.immunity - dt).max(0.0);
+
+        if !self.is_sick() {
+            let in_water = tile_map.get_tile_at_pixel(self.x, self.y)
+                .is_some_and(|tile| tile.tile_type == TileType::Water);
+            if in_water && self.can_catch_illness() && random() < WATER_INFECTION_RATE * dt {
+                self.fall_ill();
+                return Some(SimEvent::FellIll { id: self.id, source: "water".to_string() });
+            }
+            return None;
+        }
+
+        // The young and old take it harder
+        let frailty = match self.life_stage() {
+            LifeStage::Child => 1.5,
+            LifeStage::Adult => 1.0,
+            LifeStage::Elder => 2.0,
+        };
+        self.damage(ILLNESS_DAMAGE * frailty * dt, "illness");
+        self.illness = (self.illness - dt).max(0.0);
+        if self.illness == 0.0 && !self.is_dead() {
+            self.immunity = IMMUNITY_SECONDS;
+            return Some(SimEvent::Recovered { id: self.id });
+        }
+        None
+    }
+}
+
+impl GameState {
+    /// Sick promisers outside quarantine may infect others close by
+    pub(crate) fn spread_illness(&mut self) {
+        let carriers: Vec<(u32, u32, f64, f64)> = self.promisers.values()
+            .filter(|p| p.is_sick())
+            .filter(|p| !self.quarantine.as_ref().is_some_and(|zone| zone.contains(p.dimension, p.x, p.y)))
+            .map(|p| (p.id, p.dimension, p.x, p.y))
+            .collect();
+
+        for (carrier, dim, x, y) in carriers {
+            for id in self.spatial.query_radius(&self.promisers, dim, x, y, CONTAGION_RADIUS) {
+                let Some(promiser) = self.promisers.get_mut(&id) else { continue };
+                if id != carrier && promiser.can_catch_illness() && random() < CONTAGION_CHANCE {
+                    promiser.fall_ill();
+                    self.events.push(SimEvent::FellIll { id, source: "contagion".to_string() });
+                }
+            }
+        }
+    }
+
+    /// Send sick promisers in the zone's dimension to quarantine, dropping
+    /// whatever job they had
+    pub(crate) fn assign_quarantine(&mut self) {
+        let Some(dim) = self.quarantine.as_ref().map(|zone| zone.dimension) else {
+            return;
+        };
+        let patients: Vec<u32> = self.promisers.values()
+            .filter(|p| p.is_sick() && p.dimension == dim && !matches!(p.task, Some(Task::Quarantine { .. })))
+            .map(|p| p.id)
+            .collect();
+        for id in patients {
+            self.abandon_task(id);
+            self.promisers.get_mut(&id).unwrap().task = Some(Task::Quarantine { elapsed: 0.0 });
+        }
+    }
+}
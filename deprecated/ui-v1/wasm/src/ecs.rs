@@ -0,0 +1,167 @@
+//! A minimal entity-component store: entities are bare ids, and each kind of
+//! data lives in its own component store keyed by id (a sparse set, so
+//! systems iterate densely packed components). A new entity kind is a new
+//! combination of components, and systems such as movement or export run
+//! over every entity that has the components they need.
+//!
+//! Creatures and loose items live here; promisers still have their own map.
+use serde::Serialize;
+
+use crate::birds::Roost;
+use crate::creatures::CreatureKind;
+use crate::determinism::HashMap;
+use crate::items::{Item, ItemKind, ItemStack};
+use crate::pets::Pet;
+
+/// Where an entity is and how it's moving (pixels, pixels per second)
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Body {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub dimension: u32,
+}
+
+/// Components of one type, packed densely with an index from entity id
+pub struct ComponentStore<T> {
+    ids: Vec<u32>,
+    dense: Vec<T>,
+    index: HashMap<u32, usize>,
+}
+
+impl<T> Default for ComponentStore<T> {
+    fn default() -> Self {
+        ComponentStore { ids: Vec::new(), dense: Vec::new(), index: HashMap::default() }
+    }
+}
+
+impl<T> ComponentStore<T> {
+    pub fn insert(&mut self, id: u32, component: T) {
+        match self.index.get(&id) {
+            Some(&i) => self.dense[i] = component,
+            None => {
+                self.index.insert(id, self.dense.len());
+                self.ids.push(id);
+                self.dense.push(component);
+            }
+        }
+    }
+
+    /// Remove an entity's component, moving the last one into its slot
+    pub fn remove(&mut self, id: u32) -> Option<T> {
+        let i = self.index.remove(&id)?;
+        self.ids.swap_remove(i);
+        let component = self.dense.swap_remove(i);
+        if let Some(&moved) = self.ids.get(i) {
+            self.index.insert(moved, i);
+        }
+        Some(component)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&T> {
+        self.index.get(&id).map(|&i| &self.dense[i])
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut T> {
+        self.index.get(&id).map(|&i| &mut self.dense[i])
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
+        self.ids.iter().copied().zip(self.dense.iter())
+    }
+}
+
+/// Every entity and its components
+#[derive(Default)]
+pub struct Ecs {
+    next_id: u32,
+    pub bodies: ComponentStore<Body>,
+    pub creatures: ComponentStore<CreatureKind>,
+    pub pets: ComponentStore<Pet>, // Creatures being tamed or tamed (see pets.rs)
+    pub stranded: ComponentStore<f64>, // Seconds a fish has been out of water (see fish.rs)
+    pub roosts: ComponentStore<Roost>, // Birds flying, landing or perched (see birds.rs)
+    pub burrows: ComponentStore<(f64, f64)>, // Heading of a mole digging ahead (see moles.rs)
+    pub health: ComponentStore<f64>, // Creatures that have been hurt (see combat.rs)
+    pub cooldowns: ComponentStore<f64>, // Seconds until a hostile creature can attack again
+    pub stacks: ComponentStore<ItemStack>, // Item stacks lying in the world (see items.rs)
+    pub reservations: ComponentStore<u32>, // Promiser on its way to pick an item up
+    pub ages: ComponentStore<f64>, // Seconds since an item was put down (see despawn.rs)
+}
+
+impl Ecs {
+    /// A fresh entity id (ids start at 1 and are never reused)
+    pub fn spawn(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Remove an entity and all its components
+    pub fn despawn(&mut self, id: u32) {
+        self.bodies.remove(id);
+        self.creatures.remove(id);
+        self.pets.remove(id);
+        self.stranded.remove(id);
+        self.roosts.remove(id);
+        self.burrows.remove(id);
+        self.health.remove(id);
+        self.cooldowns.remove(id);
+        self.stacks.remove(id);
+        self.reservations.remove(id);
+        self.ages.remove(id);
+    }
+
+    /// Put an item stack down at a pixel position and return its id
+    pub fn spawn_item(&mut self, kind: ItemKind, count: u32, x: f64, y: f64, dimension: u32) -> u32 {
+        let id = self.spawn();
+        self.bodies.insert(id, Body { x, y, vx: 0.0, vy: 0.0, dimension });
+        self.stacks.insert(id, ItemStack { kind, count });
+        self.ages.insert(id, 0.0);
+        id
+    }
+
+    /// A loose item stack with where it lies, if `id` is one
+    pub fn item(&self, id: u32) -> Option<Item> {
+        let stack = self.stacks.get(id)?;
+        let body = self.bodies.get(id)?;
+        Some(Item {
+            id,
+            kind: stack.kind,
+            count: stack.count,
+            x: body.x,
+            y: body.y,
+            dimension: body.dimension,
+            reserved_by: self.reservations.get(id).copied(),
+        })
+    }
+
+    /// Every loose item stack
+    pub fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        self.stacks.iter().filter_map(|(id, _)| self.item(id))
+    }
+
+    /// Take up to `count` from an item stack, despawning it once it's empty;
+    /// returns how many were taken
+    pub fn take_item(&mut self, id: u32, count: u32) -> u32 {
+        let Some(stack) = self.stacks.get_mut(id) else { return 0 };
+        let taken = stack.count.min(count);
+        stack.count -= taken;
+        if stack.count == 0 {
+            self.despawn(id);
+        }
+        taken
+    }
+
+    /// Despawn every entity the predicate picks; returns how many
+    pub fn despawn_where(&mut self, mut pick: impl FnMut(&Ecs, u32) -> bool) -> usize {
+        let ids: Vec<u32> = self.bodies.iter().map(|(id, _)| id).filter(|&id| pick(self, id)).collect();
+        for &id in &ids {
+            self.despawn(id);
+        }
+        ids.len()
+    }
+}
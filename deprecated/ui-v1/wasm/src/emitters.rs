@@ -0,0 +1,199 @@
+//! Light emitters: everything that spawns light rays. The sky is one (a
+//! segment of the top edge lit along the sun's direction); torches, glowing
+//! tiles such as lava, and promisers carrying a light are others, each with
+//! its own rate and color.
+//!
+//! Emitters are added from JSON, e.g.
+//! `{"kind": "point", "x": 320, "y": 96, "rate": 40, "color": 16750848, "range": 160}`
+//! or `{"kind": "promiser", "id": 0, "rate": 20}`.
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{random, GameState, LightRay, OVERWORLD, TILE_SIZE_PIXELS};
+
+pub const SKY_EMITTER: u32 = 1; // Id of the sky emitter every world starts with
+const RAY_START_EPSILON: f64 = 2.0; // Distance to start ray from boundary
+pub const MAX_BURST_RAYS: u32 = 2000; // Most rays a single light burst spawns
+
+fn default_rate() -> f64 {
+    20.0
+}
+
+fn default_color() -> u32 {
+    0xFFFFFF
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+/// Where an emitter's rays come from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EmitterSource {
+    /// The top edge from `x` across `width` pixels (the whole world when
+    /// omitted), shining along the sun's direction while it's up
+    Sky {
+        #[serde(default)]
+        x: Option<f64>,
+        #[serde(default)]
+        width: Option<f64>,
+    },
+    /// A fixed point shining in every direction, like a torch (pixels)
+    Point { x: f64, y: f64 },
+    /// A glowing tile, such as lava, shining from its center
+    Tile { x: i32, y: i32 },
+    /// A promiser carrying a light
+    Promiser { id: u32 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Emitter {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(flatten)]
+    pub source: EmitterSource,
+    /// Rays per second; the sky ignores this and follows the light budget
+    #[serde(default = "default_rate")]
+    pub rate: f64,
+    #[serde(default = "default_color")]
+    pub color: u32, // RGB
+    #[serde(default = "default_intensity")]
+    pub intensity: f64,
+    /// How far (pixels) rays travel before fading out; unlimited when omitted
+    #[serde(default)]
+    pub range: Option<f64>,
+    #[serde(skip)]
+    pending: f64, // Fractional rays carried over to the next spawn
+}
+
+impl Emitter {
+    /// The sky over the whole world
+    pub fn sky() -> Emitter {
+        Emitter {
+            id: SKY_EMITTER,
+            source: EmitterSource::Sky { x: None, width: None },
+            rate: 0.0,
+            color: default_color(),
+            intensity: default_intensity(),
+            range: None,
+            pending: 0.0,
+        }
+    }
+
+    /// A glowing tile, such as a burning furnace
+    pub fn tile(id: u32, x: i32, y: i32, rate: f64, color: u32, range: f64) -> Emitter {
+        Emitter {
+            id,
+            source: EmitterSource::Tile { x, y },
+            rate,
+            color,
+            intensity: default_intensity(),
+            range: Some(range),
+            pending: 0.0,
+        }
+    }
+}
+
+impl GameState {
+    /// Spawn rays from every emitter for the `seconds` since the last call,
+    /// keeping to the light budget
+    pub(crate) fn generate_light_rays(&mut self, seconds: f64) {
+        let budget = self.light_budget();
+        let mut emitters = std::mem::take(&mut self.emitters);
+        for emitter in &mut emitters {
+            let rate = match emitter.source {
+                EmitterSource::Sky { .. } => budget.max_rays as f64 * budget.spawn_rate,
+                _ => emitter.rate.max(0.0),
+            };
+            emitter.pending += rate * seconds;
+            let count = emitter.pending.floor() as usize;
+            emitter.pending -= count as f64;
+
+            let room = budget.max_rays.saturating_sub(self.light_rays.len());
+            for _ in 0..count.min(room) {
+                let Some((x, y, dir_x, dir_y)) = self.emitter_ray_start(&emitter.source) else { continue };
+                // Skip rays that would start inside solid tiles
+                if !self.is_valid_spawn_position(x, y) {
+                    continue;
+                }
+                let mut ray = LightRay::new(x, y, dir_x, dir_y, budget.ray_speed);
+                ray.color = emitter.color;
+                ray.intensity = emitter.intensity.clamp(0.0, 1.0);
+                ray.ttl = emitter.range.map_or(f64::INFINITY, |range| range / budget.ray_speed);
+                self.light_rays.push(ray);
+            }
+        }
+        self.emitters = emitters;
+    }
+
+    /// A ring of `count` rays flying out from pixel (x, y) all at once. Bursts
+    /// may go over the light budget, since they're short-lived effects.
+    /// Returns how many rays were spawned (none inside solid tiles).
+    pub(crate) fn light_burst(&mut self, x: f64, y: f64, count: u32, intensity: f64) -> u32 {
+        if !self.is_valid_spawn_position(x, y) {
+            return 0;
+        }
+        let count = count.min(MAX_BURST_RAYS);
+        let speed = self.light_budget().ray_speed;
+        let offset = random(); // Turn each ring a little so bursts don't line up
+        for i in 0..count {
+            let angle = (i as f64 + offset) / count as f64 * 2.0 * PI;
+            let mut ray = LightRay::new(x, y, angle.cos(), angle.sin(), speed);
+            ray.intensity = intensity.clamp(0.0, 1.0);
+            self.light_rays.push(ray);
+        }
+        count
+    }
+
+    // Start position and direction for one ray, or None if the emitter can't
+    // shine right now
+    fn emitter_ray_start(&self, source: &EmitterSource) -> Option<(f64, f64, f64, f64)> {
+        let radial = || {
+            let angle = random() * 2.0 * PI;
+            (angle.cos(), angle.sin())
+        };
+        match *source {
+            EmitterSource::Sky { x, width } => {
+                let (dir_x, dir_y) = self.sun.light_direction()?;
+                let x0 = x.unwrap_or(0.0);
+                let span = width.unwrap_or(self.world_width);
+                // Rays for the whole span start on the line above the top
+                // edge; with a slanted sun, the ones that would miss it come
+                // in through the side edge the light falls on instead (in
+                // wrapping worlds they come around the seam by themselves)
+                let slant = if self.tile_map.wrap_x { 0.0 } else { self.world_height * dir_x / -dir_y };
+                let top_x = x0 + random() * (span + slant.abs()) - slant.max(0.0);
+                let (start_x, start_y) = if self.tile_map.wrap_x || (0.0..self.world_width).contains(&top_x) {
+                    (top_x.rem_euclid(self.world_width), self.world_height)
+                } else if dir_x != 0.0 {
+                    let edge_x = if top_x < 0.0 { 0.0 } else { self.world_width };
+                    let t = (edge_x - top_x) / dir_x;
+                    (edge_x, self.world_height + dir_y * t)
+                } else {
+                    return None;
+                };
+                Some((start_x + dir_x.signum() * RAY_START_EPSILON, start_y + dir_y.signum() * RAY_START_EPSILON, dir_x, dir_y))
+            }
+            EmitterSource::Point { x, y } => {
+                let (dir_x, dir_y) = radial();
+                Some((x, y, dir_x, dir_y))
+            }
+            EmitterSource::Tile { x, y } => {
+                let (dir_x, dir_y) = radial();
+                let center_x = (x as f64 + 0.5) * TILE_SIZE_PIXELS;
+                let center_y = (y as f64 + 0.5) * TILE_SIZE_PIXELS;
+                // Start at the tile's edge so the tile itself doesn't block them
+                let reach = TILE_SIZE_PIXELS * 0.75;
+                Some((center_x + dir_x * reach, center_y + dir_y * reach, dir_x, dir_y))
+            }
+            EmitterSource::Promiser { id } => {
+                // Rays only exist in the overworld
+                let promiser = self.promisers.get(&id).filter(|p| p.dimension == OVERWORLD)?;
+                let (dir_x, dir_y) = radial();
+                Some((promiser.x, promiser.y, dir_x, dir_y))
+            }
+        }
+    }
+}
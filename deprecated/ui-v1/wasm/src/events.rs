@@ -0,0 +1,112 @@
+//! Simulation events queued for the JS side, which drains them as JSON.
+use serde::Serialize;
+
+use crate::creatures::CreatureKind;
+use crate::items::ItemKind;
+
+// Oldest events are dropped past this so an undrained queue can't grow forever
+const MAX_PENDING_EVENTS: usize = 1024;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimEvent {
+    /// A promiser fell out of the bottom of an open-bottom world
+    FellIntoVoid { id: u32, x: f64 },
+    /// Water drained out of the bottom of an open-bottom world
+    WaterFellIntoVoid { amount: u32 },
+    /// A child was born to two promisers
+    Born { id: u32, parents: Vec<u32> },
+    /// A promiser caught an illness ("water" or "contagion")
+    FellIll { id: u32, source: String },
+    /// A promiser got over an illness
+    Recovered { id: u32 },
+    /// A promiser ran out of health ("drowning", ...)
+    Died { id: u32, x: f64, y: f64, cause: String },
+    /// A promiser stepped through a portal
+    ChangedDimension { id: u32, from: String, to: String },
+    /// A promiser that stopped making progress was nudged ("nudge") or moved
+    /// to the nearest free spot ("teleport"), or one buried in solid tiles was
+    /// pushed out ("push_out")
+    Unstuck { id: u32, x: f64, y: f64, method: String },
+    /// Who heard a line of speech (listeners in range with no wall in between)
+    HeardSpeech { speaker: u32, listeners: Vec<u32>, text: String, whisper: bool },
+    /// A hauler set items down in a stockpile
+    ItemStored { promiser: u32, stockpile: u32, kind: ItemKind, count: u32 },
+    /// A blueprint tile was built
+    BlueprintProgress { id: u32, built: usize, total: usize },
+    /// The last tile of a blueprint was built
+    BlueprintComplete { id: u32 },
+    /// The director stepped in on a quiet world ("rain", "stranger", "rumor")
+    DirectorIntervened { kind: String },
+    /// A sequence asks the front end to move the camera
+    Camera { sequence: u32, x: f64, y: f64, zoom: Option<f64> },
+    /// A sequence ran its last action
+    SequenceComplete { id: u32 },
+    /// A recipe was made from items in a stockpile
+    Crafted { recipe: String, stockpile: u32 },
+    /// Unsupported overworld tiles fell (see structure.rs)
+    Collapsed { tiles: u32 },
+    /// A promiser struggled free of quicksand (see quicksand.rs)
+    EscapedQuicksand { id: u32, x: f64, y: f64 },
+    /// A furnace finished cooking an item, dropped on top of it (tile
+    /// position, see furnace.rs)
+    Cooked { x: i32, y: i32, kind: ItemKind },
+    /// A creature was fed enough to be tamed (see pets.rs)
+    Tamed { creature: u32, owner: u32 },
+    /// A creature died, and why ("stranded": a fish out of water, see
+    /// fish.rs; "fought": beaten by promisers, see combat.rs; "trapped":
+    /// killed by spikes, see defenses.rs)
+    CreatureDied { id: u32, kind: CreatureKind, cause: String },
+    /// A hostile creature and a promiser traded a blow; `attacker` is
+    /// "creature" or "promiser" (see combat.rs)
+    Attacked { promiser: u32, creature: u32, damage: f64, attacker: String },
+    /// A mole dug into dirt next to water, letting it into its tunnels (see
+    /// moles.rs)
+    Breached { x: i32, y: i32 },
+    /// A pit cover at (x, y) gave way under a hostile creature (see
+    /// defenses.rs)
+    TrapSprung { x: i32, y: i32, creature: u32 },
+    /// A wave of `size` hostile creatures came at nightfall on `day` (see
+    /// waves.rs)
+    Wave { day: u32, size: u32 },
+    /// An objective's goal was met (see objectives.rs)
+    ObjectiveCompleted { id: u32, label: String },
+    /// An objective failed: "death" (someone it was keeping alive died) or
+    /// "time" (its time limit ran out)
+    ObjectiveFailed { id: u32, label: String, reason: String },
+    /// The game saved itself at `tick`, `bytes` long; fetch it with
+    /// latest_autosave (see autosave.rs)
+    Autosaved { tick: u64, bytes: usize },
+}
+
+#[derive(Default)]
+pub struct EventQueue {
+    pending: Vec<SimEvent>,
+    pushed: u64, // Events ever raised, drained or not
+}
+
+impl EventQueue {
+    pub fn push(&mut self, event: SimEvent) {
+        if self.pending.len() >= MAX_PENDING_EVENTS {
+            self.pending.remove(0);
+        }
+        self.pending.push(event);
+        self.pushed += 1;
+    }
+
+    /// Drop all pending events without sending them
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn total_pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    /// Take all pending events as a JSON array
+    pub fn drain_json(&mut self) -> String {
+        let json = serde_json::to_string(&self.pending).unwrap_or_else(|_| "[]".to_string());
+        self.pending.clear();
+        json
+    }
+}
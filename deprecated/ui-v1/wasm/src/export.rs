@@ -0,0 +1,201 @@
+//! The state export (get_state_data) written straight into a byte buffer.
+//! GameState keeps one StateExport between frames, so once the buffer and
+//! its scratch space have grown to fit, exporting a tick allocates nothing:
+//! export_state refills it in place and renderers read it out of WASM memory
+//! (`new Uint8Array(memory.buffer, state_export_ptr(), export_state())`,
+//! then a TextDecoder), the same way as the tile buffers (see buffers.rs).
+//! tick_export and update_game_export step the simulation and export in one
+//! call, as public/wasm-worker.js does every frame. get_state_data goes
+//! through the same buffer and only allocates the String it returns.
+//!
+//! The same state also goes out as MessagePack (get_state_msgpack), through
+//! borrowed views with the JSON's layout serialized straight to bytes (see
+//! msgpack.rs).
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{msgpack, GameState, ItemKind, Promiser, SpeechEntry, TileMap};
+
+#[derive(Default)]
+pub struct StateExport {
+    pub buffer: Vec<u8>, // The last export, UTF-8 JSON
+    links: Vec<(u32, u32)>, // Scratch space for sorting leader links
+    cracks: Vec<(usize, f32)>, // Scratch space for sorting cracks
+}
+
+impl GameState {
+    /// Replace the contents of `export`'s buffer with the state JSON
+    pub(crate) fn write_state(&self, export: &mut StateExport) {
+        export.buffer.clear();
+        // Writing into a Vec never fails
+        let _ = self.write_state_json(export);
+    }
+
+    /// The state as MessagePack, laid out like the JSON (unrounded numbers)
+    pub(crate) fn state_msgpack(&self) -> Result<Vec<u8>, msgpack::Error> {
+        let mut leaders: Vec<(u32, u32)> = self.promisers.values().filter_map(|p| p.leader.map(|leader| (p.id, leader))).collect();
+        leaders.sort_unstable();
+        let mut cracks: Vec<(usize, f32)> = self.tile_map.cracks.iter().map(|(&i, &progress)| (i, progress)).collect();
+        cracks.sort_unstable_by_key(|&(i, _)| i);
+        let width = self.tile_map.width.max(1);
+
+        msgpack::to_vec(&StateView {
+            tick: self.tick_count,
+            promisers: self.promisers.values().map(|promiser| PromiserView::new(promiser, self.export_meta)).collect(),
+            tile_map: &self.tile_map,
+            light_rays: self.light_rays.iter().filter(|_| self.export_light_rays)
+                .map(|ray| RayView { x: ray.x, y: ray.y, vx: ray.vx, vy: ray.vy, intensity: ray.intensity, color: ray.color })
+                .collect(),
+            leaders,
+            sun: SunView { time: self.sun.time, day: self.sun.day, angle: self.sun.angle(), up: self.sun.angle().is_some() },
+            cracks: cracks.into_iter().map(|(i, progress)| (i % width, i / width, progress)).collect(),
+        })
+    }
+
+    fn write_state_json(&self, export: &mut StateExport) -> io::Result<()> {
+        let StateExport { buffer: out, links, cracks } = export;
+        write!(out, "{{\"tick\":{},\"promisers\":[", self.tick_count)?;
+        for (n, promiser) in self.promisers.values().enumerate() {
+            if n > 0 {
+                out.push(b',');
+            }
+            promiser.write_json(out, self.export_meta)?;
+        }
+
+        out.extend_from_slice(b"],\"tile_map\":");
+        serde_json::to_writer(&mut *out, &self.tile_map)?;
+
+        // Renderers using the lightmap can turn the rays off
+        out.extend_from_slice(b",\"light_rays\":[");
+        for (n, ray) in self.light_rays.iter().filter(|_| self.export_light_rays).enumerate() {
+            if n > 0 {
+                out.push(b',');
+            }
+            write!(
+                out,
+                "{{\"x\":{:.2},\"y\":{:.2},\"vx\":{:.2},\"vy\":{:.2},\"intensity\":{:.2},\"color\":{}}}",
+                ray.x, ray.y, ray.vx, ray.vy, ray.intensity, ray.color
+            )?;
+        }
+
+        // [follower, leader] pairs (see leadership.rs)
+        out.extend_from_slice(b"],\"leaders\":[");
+        links.clear();
+        links.extend(self.promisers.values().filter_map(|p| p.leader.map(|leader| (p.id, leader))));
+        links.sort_unstable();
+        for (n, (follower, leader)) in links.iter().enumerate() {
+            write!(out, "{}[{},{}]", if n > 0 { "," } else { "" }, follower, leader)?;
+        }
+
+        out.extend_from_slice(b"],\"sun\":");
+        self.sun.write_json(out)?;
+
+        // [x, y, progress] for partly dug tiles (see mining.rs)
+        out.extend_from_slice(b",\"cracks\":");
+        self.tile_map.write_cracks(out, cracks)?;
+        out.push(b'}');
+        Ok(())
+    }
+}
+
+// What get_state_msgpack sends; mirrors write_state_json
+#[derive(Serialize)]
+struct StateView<'a> {
+    tick: u64,
+    promisers: Vec<PromiserView<'a>>,
+    tile_map: &'a TileMap,
+    light_rays: Vec<RayView>,
+    leaders: Vec<(u32, u32)>, // [follower, leader]
+    sun: SunView,
+    cracks: Vec<(usize, usize, f32)>, // [x, y, progress]
+}
+
+// A promiser as Promiser::write_json lays it out
+#[derive(Serialize)]
+struct PromiserView<'a> {
+    id: u32,
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    size: f64,
+    color: u32,
+    state: u32,
+    thought: &'a str,
+    target_id: u32,
+    is_pixel: bool,
+    dimension: u32,
+    name: &'a str,
+    emote: &'a str,
+    speech: Vec<&'a SpeechEntry>,
+    health: f64,
+    air: f64,
+    age: f64,
+    stage: &'static str,
+    sick: bool,
+    group: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    glow: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    glow_color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool: Option<ItemKind>,
+    #[serde(skip_serializing_if = "<[u32]>::is_empty")]
+    pets: &'a [u32],
+}
+
+impl<'a> PromiserView<'a> {
+    fn new(promiser: &'a Promiser, include_meta: bool) -> PromiserView<'a> {
+        let glowing = promiser.glow > 0.0;
+        PromiserView {
+            id: promiser.id,
+            x: promiser.x,
+            y: promiser.y,
+            vx: promiser.vx,
+            vy: promiser.vy,
+            size: promiser.size,
+            color: promiser.color,
+            state: promiser.state,
+            thought: &promiser.thought,
+            target_id: promiser.target_id,
+            is_pixel: promiser.is_pixel,
+            dimension: promiser.dimension,
+            name: &promiser.name,
+            emote: &promiser.emote,
+            speech: promiser.speech_history.iter().collect(),
+            health: promiser.health,
+            air: promiser.air,
+            age: promiser.years(),
+            stage: promiser.life_stage().name(),
+            sick: promiser.is_sick(),
+            group: promiser.group,
+            // Already validated JSON, so this only fails if it was never set
+            meta: (include_meta && !promiser.meta.is_empty()).then(|| serde_json::from_str(&promiser.meta).ok()).flatten(),
+            glow: glowing.then_some(promiser.glow),
+            glow_color: glowing.then_some(promiser.glow_color),
+            tool: promiser.tool,
+            pets: &promiser.pets,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RayView {
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    intensity: f64,
+    color: u32,
+}
+
+#[derive(Serialize)]
+struct SunView {
+    time: f64,
+    day: u32,
+    angle: Option<f64>,
+    up: bool,
+}
@@ -0,0 +1,59 @@
+//! Soil fertility, one byte per tile (only dirt uses it). Untouched dirt
+//! starts at a natural level. Each foliage that grows takes some out of the
+//! dirt under it, and dirt too poor to feed a plant grows nothing, so
+//! farmland wears out and has to lie fallow. Moist dirt slowly recovers to
+//! the natural level, and rotting matter (see decay.rs) enriches it beyond
+//! that: enriched dirt grows foliage with less moisture and grass sooner.
+use crate::{TileMap, TileType, MIN_FOLIAGE_MOISTURE};
+
+pub const NATURAL_FERTILITY: u8 = 128; // Untouched dirt, and what moisture restores
+pub const GROWTH_COST: u8 = 32; // Taken from the dirt each time foliage grows on it
+const RECOVERY_MOISTURE: u16 = MIN_FOLIAGE_MOISTURE / 2; // Dirt this moist recovers
+const RECOVERY_RATE: u8 = 1; // Per foliage step, up to the natural level
+
+impl TileMap {
+    /// Fertility of a tile from 0 (exhausted) to 255
+    pub fn fertility_at(&self, x: i32, y: i32) -> u8 {
+        self.index(x, y).map_or(0, |i| self.fertility(i))
+    }
+
+    pub(crate) fn fertility(&self, i: usize) -> u8 {
+        self.fertility.get(i).copied().unwrap_or(NATURAL_FERTILITY)
+    }
+
+    /// How far the tile at index `i` is enriched beyond the natural level,
+    /// from 0.0 to 1.0
+    pub(crate) fn enrichment(&self, i: usize) -> f64 {
+        let extra = self.fertility(i).saturating_sub(NATURAL_FERTILITY);
+        extra as f64 / (u8::MAX - NATURAL_FERTILITY) as f64
+    }
+
+    /// Moisture the dirt at index `i` needs to grow foliage: less the more
+    /// enriched it is, down to half
+    pub(crate) fn foliage_moisture_needed(&self, i: usize) -> u16 {
+        let discount = MIN_FOLIAGE_MOISTURE as f64 / 2.0 * self.enrichment(i);
+        MIN_FOLIAGE_MOISTURE - discount as u16
+    }
+
+    /// Take the cost of growing one plant out of the tile at index `i`
+    pub(crate) fn consume_fertility(&mut self, i: usize) {
+        self.ensure_fertility();
+        self.fertility[i] = self.fertility[i].saturating_sub(GROWTH_COST);
+    }
+
+    /// Let moist dirt recover toward the natural fertility
+    pub fn step_fertility(&mut self) {
+        self.ensure_fertility();
+        for (tile, fertility) in self.tiles.iter().zip(self.fertility.iter_mut()) {
+            if tile.tile_type == TileType::Dirt && tile.water_amount >= RECOVERY_MOISTURE && *fertility < NATURAL_FERTILITY {
+                *fertility = (*fertility + RECOVERY_RATE).min(NATURAL_FERTILITY);
+            }
+        }
+    }
+
+    pub(crate) fn ensure_fertility(&mut self) {
+        if self.fertility.len() != self.tiles.len() {
+            self.fertility = vec![NATURAL_FERTILITY; self.tiles.len()];
+        }
+    }
+}
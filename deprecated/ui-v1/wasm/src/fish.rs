@@ -0,0 +1,155 @@
+//! Fish: they swim only through water (see creatures.rs), so each stays in
+//! the body of connected water it hatched in. Fish school like boids, keeping
+//! apart from their nearest neighbors while matching the heading of and
+//! closing in on the rest of the fish near them in the same body of water.
+//! A fish stranded out of water, because its pool drained or it jumped out,
+//! dies after a few seconds and leaves a fish item behind. Promisers within
+//! reach can catch fish as food items, which pets also eat (see pets.rs).
+use std::collections::VecDeque;
+
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::items::ItemKind;
+use crate::{dimension, GameState, TileMap, TileType, TILE_SIZE_PIXELS};
+
+const SCHOOL_RADIUS: f64 = 3.0 * TILE_SIZE_PIXELS; // Fish this close school together
+const SEPARATION_RADIUS: f64 = 12.0; // Pixels; closer than this they move apart
+const SEPARATION: f64 = 4.0; // Per second, weights of the three boid rules
+const ALIGNMENT: f64 = 1.0;
+const COHESION: f64 = 0.5;
+const STRANDED_SECONDS: f64 = 4.0; // How long a fish lasts out of water
+const CATCH_REACH: f64 = 48.0; // How close a promiser has to be to catch a fish (pixels)
+
+impl TileMap {
+    /// Label each tile with its body of connected water (1, 2, ...; 0 for
+    /// anything but water)
+    pub fn water_bodies(&self) -> Vec<u32> {
+        let mut labels = vec![0; self.tiles.len()];
+        let mut next = 0;
+        let mut queue = VecDeque::new();
+        for start in 0..self.tiles.len() {
+            if labels[start] != 0 || self.tiles[start].tile_type != TileType::Water {
+                continue;
+            }
+            next += 1;
+            labels[start] = next;
+            queue.push_back(start);
+            while let Some(i) = queue.pop_front() {
+                let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let Some(j) = self.index(x + dx, y + dy) else { continue };
+                    if labels[j] == 0 && self.tiles[j].tile_type == TileType::Water {
+                        labels[j] = next;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        labels
+    }
+}
+
+impl GameState {
+    /// Steer fish along with their school, and let the ones stranded out of
+    /// water for too long die
+    pub(crate) fn update_fish(&mut self, dt: f64) {
+        let mut dead = Vec::new();
+        let dims: Vec<u32> = {
+            let mut dims: Vec<u32> = self.ecs.creatures.iter()
+                .filter(|&(_, &kind)| kind == CreatureKind::Fish)
+                .filter_map(|(id, _)| self.ecs.bodies.get(id).map(|body| body.dimension))
+                .collect();
+            dims.sort_unstable();
+            dims.dedup();
+            dims
+        };
+        for dim in dims {
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else { continue };
+            let bodies = map.water_bodies();
+            let water_body = |x: f64, y: f64| {
+                let i = map.index((x / TILE_SIZE_PIXELS).floor() as i32, (y / TILE_SIZE_PIXELS).floor() as i32)?;
+                Some(bodies[i]).filter(|&body| body != 0)
+            };
+            // (id, water body, body) of every fish in this map
+            let fish: Vec<_> = self.ecs.creatures.iter()
+                .filter(|&(_, &kind)| kind == CreatureKind::Fish)
+                .filter_map(|(id, _)| Some((id, *self.ecs.bodies.get(id).filter(|body| body.dimension == dim)?)))
+                .map(|(id, body)| (id, water_body(body.x, body.y), body))
+                .collect();
+
+            for &(id, pool, body) in &fish {
+                let Some(pool) = pool else {
+                    let stranded = self.ecs.stranded.get(id).copied().unwrap_or(0.0) + dt;
+                    self.ecs.stranded.insert(id, stranded);
+                    if stranded > STRANDED_SECONDS && self.config.rules.entities_take_damage {
+                        dead.push((id, body));
+                    }
+                    continue;
+                };
+                self.ecs.stranded.remove(id);
+
+                let (mut count, mut sum_vx, mut sum_vy, mut sum_dx, mut sum_dy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+                let (mut push_x, mut push_y) = (0.0, 0.0);
+                for &(other, other_pool, other_body) in &fish {
+                    if other == id || other_pool != Some(pool) {
+                        continue;
+                    }
+                    let (dx, dy) = (map.offset_x(body.x, other_body.x), other_body.y - body.y);
+                    let distance = dx.hypot(dy);
+                    if distance > SCHOOL_RADIUS {
+                        continue;
+                    }
+                    count += 1.0;
+                    (sum_vx, sum_vy) = (sum_vx + other_body.vx, sum_vy + other_body.vy);
+                    (sum_dx, sum_dy) = (sum_dx + dx, sum_dy + dy);
+                    if distance < SEPARATION_RADIUS {
+                        let away = (SEPARATION_RADIUS - distance) / distance.max(1.0);
+                        (push_x, push_y) = (push_x - dx * away, push_y - dy * away);
+                    }
+                }
+                if count == 0.0 {
+                    continue;
+                }
+                let weight = |rate: f64| (rate * dt).min(1.0);
+                let body = self.ecs.bodies.get_mut(id).unwrap();
+                body.vx += (sum_vx / count - body.vx) * weight(ALIGNMENT) + sum_dx / count * weight(COHESION) + push_x * weight(SEPARATION);
+                body.vy += (sum_vy / count - body.vy) * weight(ALIGNMENT) + sum_dy / count * weight(COHESION) + push_y * weight(SEPARATION);
+                let (speed, max) = (body.vx.hypot(body.vy), CreatureKind::Fish.speed());
+                if speed > max {
+                    (body.vx, body.vy) = (body.vx * max / speed, body.vy * max / speed);
+                }
+            }
+        }
+
+        for (id, body) in dead {
+            self.ecs.despawn(id);
+            self.add_item(ItemKind::Fish, 1, body.x, body.y, body.dimension);
+            self.events.push(SimEvent::CreatureDied { id, kind: CreatureKind::Fish, cause: "stranded".to_string() });
+        }
+    }
+
+    /// Have a promiser within reach catch a fish, into its hands (Pixel's
+    /// inventory). Returns false if it's out of reach or its hands are full.
+    pub(crate) fn catch(&mut self, promiser_id: u32, creature_id: u32) -> bool {
+        let (Some(promiser), Some(CreatureKind::Fish), Some(body)) = (
+            self.promisers.get(&promiser_id),
+            self.ecs.creatures.get(creature_id),
+            self.ecs.bodies.get(creature_id),
+        ) else {
+            return false;
+        };
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else { return false };
+        if body.dimension != promiser.dimension || map.offset_x(promiser.x, body.x).hypot(body.y - promiser.y) > CATCH_REACH {
+            return false;
+        }
+        if promiser.is_pixel {
+            self.player.add(ItemKind::Fish, 1);
+        } else if promiser.can_carry(ItemKind::Fish) {
+            self.promisers.get_mut(&promiser_id).unwrap().pick_up(ItemKind::Fish, 1);
+        } else {
+            return false;
+        }
+        self.ecs.despawn(creature_id);
+        true
+    }
+}
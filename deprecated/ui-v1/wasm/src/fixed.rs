@@ -0,0 +1,225 @@
+//! Fixed-point movement for lockstep play (the `fixed-point` feature). With
+//! the feature on, each promiser keeps its position and velocity as Q32.16
+//! numbers (32 integer bits, 16 fraction bits, in an i64), and the movement
+//! step (integration, collision offsets, bounces, friction and the speed
+//! limits) runs in integer arithmetic on those. The f64 fields mirror them
+//! for everything else; a system that sets one (steering, a teleport, ...)
+//! is taken up onto the grid at the next movement step.
+//!
+//! Forces worked out in f64 before the step (gravity, mud, quicksand) are
+//! rounded onto the grid as they're taken up, so peers agree on them as long
+//! as their f64 math does, which on WASM it does (see determinism.rs).
+//! Creatures, items and light rays stay f64.
+//!
+//! Without the feature, Num is f64 and movement is plain float math.
+//! state_checksum hashes positions and velocities as Q32.16 either way, for
+//! peers to compare.
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub};
+
+use crate::{GameState, Promiser};
+
+const FRAC_BITS: u32 = 16;
+const ONE: i64 = 1 << FRAC_BITS;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    /// Nearest Q32.16 value, saturating at the ends of the range
+    pub fn from_f64(value: f64) -> Fixed {
+        Fixed((value * ONE as f64).round() as i64) // `as` saturates and maps NaN to 0
+    }
+}
+
+// Only movement uses these, with the feature on
+#[cfg_attr(not(feature = "fixed-point"), allow(dead_code))]
+impl Fixed {
+    /// Exact, for anything within 2^37 of zero
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / ONE as f64
+    }
+
+    pub fn rem_euclid(self, other: Fixed) -> Fixed {
+        Fixed(self.0.rem_euclid(other.0))
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        let product = (self.0 as i128 * other.0 as i128) >> FRAC_BITS;
+        Fixed(product.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Fixed) {
+        *self = *self + other;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, other: Fixed) {
+        *self = *self * other;
+    }
+}
+
+/// What movement math runs in
+#[cfg(feature = "fixed-point")]
+pub type Num = Fixed;
+#[cfg(not(feature = "fixed-point"))]
+pub type Num = f64;
+
+#[cfg(feature = "fixed-point")]
+pub fn num(value: f64) -> Num {
+    Fixed::from_f64(value)
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub fn num(value: f64) -> Num {
+    value
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn real(value: Num) -> f64 {
+    value.to_f64()
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub fn real(value: Num) -> f64 {
+    value
+}
+
+/// A promiser's position and velocity, in Num
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Motion {
+    pub x: Num,
+    pub y: Num,
+    pub vx: Num,
+    pub vy: Num,
+}
+
+impl Promiser {
+    /// Position and velocity to move with: with the feature on, the stored
+    /// fixed-point ones, taking up whichever f64 field something else has
+    /// set since the last step
+    pub(crate) fn motion(&mut self) -> Motion {
+        #[cfg(feature = "fixed-point")]
+        {
+            let motion = &mut self.motion;
+            for (value, field) in [(&mut motion.x, self.x), (&mut motion.y, self.y), (&mut motion.vx, self.vx), (&mut motion.vy, self.vy)] {
+                if real(*value) != field {
+                    *value = num(field);
+                }
+            }
+            self.motion
+        }
+        #[cfg(not(feature = "fixed-point"))]
+        Motion { x: self.x, y: self.y, vx: self.vx, vy: self.vy }
+    }
+
+    /// Keep a moved position and velocity, mirrored into the f64 fields
+    pub(crate) fn set_motion(&mut self, motion: Motion) {
+        #[cfg(feature = "fixed-point")]
+        {
+            self.motion = motion;
+        }
+        (self.x, self.y) = (real(motion.x), real(motion.y));
+        (self.vx, self.vy) = (real(motion.vx), real(motion.vy));
+    }
+}
+
+// 64-bit FNV-1a, the same on every toolchain and platform
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+impl GameState {
+    /// Hash of the tick, every promiser's position and velocity (as Q32.16)
+    /// and the overworld's tiles, folded to 32 bits. Peers in lockstep
+    /// compare it to catch a desync.
+    pub(crate) fn checksum(&self) -> u32 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(&self.tick_count.to_le_bytes());
+        let mut ids: Vec<u32> = self.promisers.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let promiser = &self.promisers[&id];
+            hasher.write(&id.to_le_bytes());
+            for value in [promiser.x, promiser.y, promiser.vx, promiser.vy] {
+                hasher.write(&Fixed::from_f64(value).0.to_le_bytes());
+            }
+        }
+        for tile in &self.tile_map.tiles {
+            hasher.write(&[tile.tile_type as u8, tile.variant]);
+            hasher.write(&tile.water_amount.to_le_bytes());
+        }
+        let hash = hasher.0;
+        (hash ^ (hash >> 32)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logging::{self, LogLevel};
+    use crate::{GameState, SimConfig};
+
+    fn run(seed: u64) -> GameState {
+        logging::set_level(LogLevel::Off);
+        let mut state = GameState::with_config(64.0, 48.0, SimConfig { seed: Some(seed), ..Default::default() });
+        state.spawn_promisers(8, "{}".to_string());
+        for _ in 0..600 {
+            state.tick();
+        }
+        state
+    }
+
+    #[test]
+    fn same_seed_same_checksum() {
+        let checksum = run(7).checksum();
+        assert_eq!(run(7).checksum(), checksum);
+        assert_ne!(run(8).checksum(), checksum);
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn promisers_move_on_the_grid() {
+        let state = run(7);
+        for promiser in state.promisers.values() {
+            for value in [promiser.x, promiser.y, promiser.vx, promiser.vy] {
+                assert_eq!(super::Fixed::from_f64(value).to_f64(), value);
+            }
+        }
+    }
+}
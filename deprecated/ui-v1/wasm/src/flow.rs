@@ -0,0 +1,116 @@
+//! Flow fields: for a target tile, how many steps every tile is from it,
+//! walking the way promisers do (see nav.rs), found by searching backwards
+//! from the target. Every promiser heading for the same tile (a stockpile,
+//! the quarantine zone) shares one field instead of finding its own path, and
+//! just steps to whichever neighboring tile is closer.
+//!
+//! Fields are cached per dimension and target and recomputed when the map's
+//! navigation grid changes; ones nobody has used for FLOW_FIELD_TICKS are
+//! dropped.
+use std::collections::VecDeque;
+
+use crate::determinism::HashMap;
+use crate::nav::NEIGHBORS;
+use crate::{dimension, steering, GameState, TileMap, TILE_SIZE_PIXELS};
+
+pub const UNREACHABLE: u16 = u16::MAX;
+const FLOW_FIELD_TICKS: u64 = 120; // Ticks an unused field is kept (≈ 2s at 60fps)
+
+/// Steps from every tile to the target (row-major like the tiles)
+pub struct FlowField {
+    pub distance: Vec<u16>,
+    revision: u64, // Navigation grid revision it was computed from
+    last_used: u64, // Tick
+}
+
+impl FlowField {
+    fn compute(map: &TileMap, (target_x, target_y): (i32, i32)) -> FlowField {
+        let mut distance = vec![UNREACHABLE; map.width * map.height];
+        let mut queue = VecDeque::new();
+        if let Some(i) = map.index(target_x, target_y) {
+            distance[i] = 0;
+            queue.push_back((target_x, target_y));
+        }
+        // Backwards: a tile is one step further than any tile it can move to
+        while let Some((x, y)) = queue.pop_front() {
+            let d = distance[map.index(x, y).unwrap()];
+            for (dx, dy) in NEIGHBORS {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let Some(i) = map.index(from_x, from_y) else { continue };
+                if distance[i] == UNREACHABLE && map.can_move(from_x, from_y, (dx, dy)) {
+                    distance[i] = d + 1;
+                    let from_x = if map.wrap_x { from_x.rem_euclid(map.width as i32) } else { from_x };
+                    queue.push_back((from_x, from_y));
+                }
+            }
+        }
+        FlowField { distance, revision: map.nav.revision, last_used: 0 }
+    }
+
+    pub fn distance_at(&self, map: &TileMap, x: i32, y: i32) -> u16 {
+        map.index(x, y).map_or(UNREACHABLE, |i| self.distance[i])
+    }
+
+    /// The step (dx, dy in tiles) from tile (x, y) toward the target, or None
+    /// at the target or where it can't be reached
+    pub fn step(&self, map: &TileMap, x: i32, y: i32) -> Option<(i32, i32)> {
+        let here = self.distance_at(map, x, y);
+        NEIGHBORS.into_iter()
+            .filter(|&step| map.can_move(x, y, step))
+            .map(|(dx, dy)| ((dx, dy), self.distance_at(map, x + dx, y + dy)))
+            .filter(|&(_, d)| d < here)
+            .min_by_key(|&(_, d)| d)
+            .map(|(step, _)| step)
+    }
+}
+
+/// Flow fields by dimension and target tile
+#[derive(Default)]
+pub struct FlowFields {
+    fields: HashMap<(u32, i32, i32), FlowField>,
+}
+
+impl FlowFields {
+    /// The field toward a target tile, computed if there's none or the map
+    /// has changed since
+    pub fn get(&mut self, map: &mut TileMap, dimension: u32, target: (i32, i32), tick: u64) -> &FlowField {
+        map.ensure_nav();
+        let key = (dimension, target.0, target.1);
+        if self.fields.get(&key).is_none_or(|field| field.revision != map.nav.revision) {
+            self.fields.retain(|_, field| tick.saturating_sub(field.last_used) < FLOW_FIELD_TICKS);
+            self.fields.insert(key, FlowField::compute(map, target));
+        }
+        let field = self.fields.get_mut(&key).unwrap();
+        field.last_used = tick;
+        field
+    }
+}
+
+fn tile_of(px: f64, py: f64) -> (i32, i32) {
+    ((px / TILE_SIZE_PIXELS).floor() as i32, (py / TILE_SIZE_PIXELS).floor() as i32)
+}
+
+impl GameState {
+    /// Steer a promiser toward a pixel position along the flow field for it,
+    /// or straight at it where the field doesn't help (unreachable, or
+    /// already in the target tile)
+    pub(crate) fn steer_along_flow(&mut self, id: u32, target_x: f64, target_y: f64, dt: f64) {
+        let Some(promiser) = self.promisers.get_mut(&id) else { return };
+        let Some(map) = dimension::dimension_map_mut(&mut self.tile_map, &mut self.dimensions, promiser.dimension) else {
+            return;
+        };
+        let (x, y) = tile_of(promiser.x, promiser.y);
+        let field = self.flow_fields.get(map, promiser.dimension, tile_of(target_x, target_y), self.tick_count);
+        match field.step(map, x, y) {
+            Some((dx, dy)) => {
+                if dx != 0 {
+                    promiser.vx += steering::seek_vx(dx as f64, dt);
+                }
+                if dy != 0 && map.is_climbable(x, y) {
+                    promiser.climb_dir = dy as f64;
+                }
+            }
+            None => promiser.vx += steering::seek_vx(map.offset_x(promiser.x, target_x), dt),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Fluids: the tile types the water solver (see simulation.rs) moves, each
+//! with its own parameters. A fluid tile's water amount is how much of the
+//! fluid it holds; it flows into air and into tiles of the same fluid, never
+//! into another one. Viscosity holds back that share of every move each
+//! step, so a thick fluid creeps where water pours, and stops spreading once
+//! the moves get too small to make. Only water soaks into dirt and foliage.
+use serde::{Deserialize, Serialize};
+
+use crate::TileType;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct FluidParams {
+    pub viscosity: f32, // 0.0 flows freely, 1.0 doesn't flow at all
+    #[serde(default)]
+    pub seeps: bool, // Soaks into dirt and foliage
+}
+
+impl FluidParams {
+    /// How much of a move of `amount` this fluid makes in one step
+    pub fn flow(&self, amount: u16) -> u16 {
+        (amount as f32 * (1.0 - self.viscosity.clamp(0.0, 1.0))) as u16
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fluids {
+    params: Vec<(TileType, FluidParams)>,
+}
+
+impl Default for Fluids {
+    fn default() -> Self {
+        Fluids { params: vec![(TileType::Water, FluidParams { viscosity: 0.0, seeps: true })] }
+    }
+}
+
+impl Fluids {
+    /// Parameters of a fluid tile type, None if it isn't one
+    pub fn get(&self, tile_type: TileType) -> Option<&FluidParams> {
+        self.params.iter().find(|(fluid, _)| *fluid == tile_type).map(|(_, params)| params)
+    }
+
+    /// Change a fluid's parameters. Returns false if the tile type isn't a fluid.
+    pub fn set(&mut self, tile_type: TileType, params: FluidParams) -> bool {
+        let Some(entry) = self.params.iter_mut().find(|(fluid, _)| *fluid == tile_type) else { return false };
+        entry.1 = params;
+        true
+    }
+
+    /// {"Water": {"viscosity": 0.0, "seeps": true}, ...}
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self.params.iter()
+            .map(|(fluid, params)| format!("\"{}\":{}", fluid.name(), serde_json::to_string(params).unwrap_or_else(|_| "null".to_string())))
+            .collect();
+        format!("{{{}}}", entries.join(","))
+    }
+}
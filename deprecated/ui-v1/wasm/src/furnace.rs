@@ -0,0 +1,196 @@
+//! Furnaces: Furnace tiles that burn fuel items. A burning furnace is hot
+//! enough to boil the water next to it (see thermal.rs), warms the air
+//! around it, glows like a torch (see emitters.rs) and cooks whatever item
+//! is loaded into it, one at a time, dropping each cooked item on top.
+//!
+//! What a furnace is doing is kept beside the tile map, keyed by its tile,
+//! and created the first time one is fed; digging the tile out drops what
+//! was still inside. Any Furnace tile (placed, built from an item, loaded
+//! from a save) starts out cold and empty.
+use serde::Serialize;
+
+use crate::emitters::Emitter;
+use crate::events::SimEvent;
+use crate::items::{ItemKind, ItemStack};
+use crate::{dimension, GameState, TileMap, TileType, OVERWORLD, TILE_SIZE_PIXELS};
+
+const FIRE_TEMPERATURE: f32 = 600.0; // The furnace tile itself while burning
+const WARM_AIR_TEMPERATURE: f32 = 40.0; // Air right next to a burning furnace
+const WARMTH_FALLOFF: f32 = 8.0; // Degrees less per tile further away
+const WARMTH_RADIUS: i32 = 4; // Tiles around a furnace whose air it warms
+
+const GLOW_RATE: f64 = 30.0; // Rays per second
+const GLOW_COLOR: u32 = 0xFF8C00; // Dark orange
+const GLOW_RANGE: f64 = 192.0; // Pixels
+
+/// Seconds of burning one item of this kind gives, None if it doesn't burn
+pub fn fuel_seconds(kind: ItemKind) -> Option<f32> {
+    match kind {
+        ItemKind::Wood => Some(30.0),
+        ItemKind::Plant => Some(8.0),
+        _ => None,
+    }
+}
+
+/// What cooking an item of this kind makes, and the seconds of fire it takes
+pub fn cooked(kind: ItemKind) -> Option<(ItemKind, f32)> {
+    match kind {
+        ItemKind::Ore => Some((ItemKind::Ingot, 10.0)),
+        ItemKind::Dirt => Some((ItemKind::Bricks, 8.0)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Furnace {
+    pub dimension: u32,
+    pub x: i32, // Tile
+    pub y: i32,
+    pub fuel: f32, // Seconds of burning left
+    pub contents: Option<ItemStack>, // Waiting to be cooked
+    pub progress: f32, // Toward cooking the next item, 0.0 to 1.0
+    #[serde(skip)]
+    emitter: Option<u32>, // Its glow while burning in the overworld
+}
+
+impl Furnace {
+    pub fn burning(&self) -> bool {
+        self.fuel > 0.0
+    }
+}
+
+impl TileMap {
+    /// Pin a burning furnace's tile at fire temperature and warm the air
+    /// around it. Does nothing on maps that haven't been heat-stepped.
+    pub fn warm_around(&mut self, x: i32, y: i32) {
+        if self.temperature.len() != self.tiles.len() {
+            return;
+        }
+        for dy in -WARMTH_RADIUS..=WARMTH_RADIUS {
+            for dx in -WARMTH_RADIUS..=WARMTH_RADIUS {
+                let Some(i) = self.index(x + dx, y + dy) else { continue };
+                if dx == 0 && dy == 0 {
+                    self.temperature[i] = FIRE_TEMPERATURE;
+                    continue;
+                }
+                if !matches!(self.tiles[i].tile_type, TileType::Air | TileType::Steam) {
+                    continue;
+                }
+                let distance = dx.abs().max(dy.abs()) as f32;
+                let warm = WARM_AIR_TEMPERATURE - WARMTH_FALLOFF * (distance - 1.0);
+                self.temperature[i] = self.temperature[i].max(warm);
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Feed an item stack lying in the same map into the furnace at tile
+    /// (x, y): fuel is burned, anything that cooks is loaded (only one kind
+    /// at a time). Returns false if the stack can't go in.
+    pub(crate) fn load_furnace(&mut self, dimension: u32, x: i32, y: i32, item_id: u32) -> bool {
+        let is_furnace = dimension::dimension_map(&self.tile_map, &self.dimensions, dimension)
+            .and_then(|map| map.get_tile(x, y))
+            .is_some_and(|tile| tile.tile_type == TileType::Furnace);
+        let Some(item) = self.ecs.item(item_id).filter(|item| item.dimension == dimension) else { return false };
+        if !is_furnace {
+            return false;
+        }
+        let (kind, count) = (item.kind, item.count);
+
+        let n = match self.furnaces.iter().position(|f| (f.dimension, f.x, f.y) == (dimension, x, y)) {
+            Some(n) => n,
+            None => {
+                self.furnaces.push(Furnace { dimension, x, y, fuel: 0.0, contents: None, progress: 0.0, emitter: None });
+                self.furnaces.len() - 1
+            }
+        };
+        let furnace = &mut self.furnaces[n];
+        if let Some(seconds) = fuel_seconds(kind) {
+            furnace.fuel += seconds * count as f32;
+        } else if cooked(kind).is_some() && furnace.contents.is_none_or(|stack| stack.kind == kind) {
+            let stack = furnace.contents.get_or_insert(ItemStack { kind, count: 0 });
+            stack.count += count;
+        } else {
+            return false;
+        }
+        self.ecs.despawn(item_id);
+        true
+    }
+
+    /// Burn fuel and cook for `dt` seconds, and keep each furnace's glow in
+    /// step with whether it's burning. Furnaces whose tile is gone drop what
+    /// they held.
+    pub(crate) fn update_furnaces(&mut self, dt: f64) {
+        let dt = dt as f32;
+        let mut furnaces = std::mem::take(&mut self.furnaces);
+        furnaces.retain(|furnace| {
+            let standing = dimension::dimension_map(&self.tile_map, &self.dimensions, furnace.dimension)
+                .and_then(|map| map.get_tile(furnace.x, furnace.y))
+                .is_some_and(|tile| tile.tile_type == TileType::Furnace);
+            if !standing {
+                if let Some(id) = furnace.emitter {
+                    self.emitters.retain(|emitter| emitter.id != id);
+                }
+                if let Some(stack) = furnace.contents {
+                    let (x, y) = tile_center(furnace.x, furnace.y);
+                    self.add_item(stack.kind, stack.count, x, y, furnace.dimension);
+                }
+            }
+            standing
+        });
+
+        for furnace in &mut furnaces {
+            if furnace.burning() {
+                furnace.fuel = (furnace.fuel - dt).max(0.0);
+                if let Some(stack) = furnace.contents {
+                    let (output, seconds) = cooked(stack.kind).unwrap();
+                    furnace.progress += dt / seconds;
+                    if furnace.progress >= 1.0 {
+                        furnace.progress = 0.0;
+                        furnace.contents = (stack.count > 1).then_some(ItemStack { count: stack.count - 1, ..stack });
+                        let (x, y) = tile_center(furnace.x, furnace.y + 1);
+                        self.add_item(output, 1, x, y, furnace.dimension);
+                        self.events.push(SimEvent::Cooked { x: furnace.x, y: furnace.y, kind: output });
+                    }
+                }
+            }
+
+            // Rays only exist in the overworld
+            let glowing = furnace.burning() && furnace.dimension == OVERWORLD;
+            match furnace.emitter {
+                None if glowing => {
+                    let id = self.next_emitter_id;
+                    self.next_emitter_id += 1;
+                    self.emitters.push(Emitter::tile(id, furnace.x, furnace.y, GLOW_RATE, GLOW_COLOR, GLOW_RANGE));
+                    furnace.emitter = Some(id);
+                }
+                Some(id) if !glowing => {
+                    self.emitters.retain(|emitter| emitter.id != id);
+                    furnace.emitter = None;
+                }
+                _ => {}
+            }
+        }
+        self.furnaces = furnaces;
+    }
+
+    /// Heat around every burning furnace, after the heat step
+    pub(crate) fn heat_furnaces(&mut self) {
+        for n in 0..self.furnaces.len() {
+            let furnace = &self.furnaces[n];
+            if !furnace.burning() {
+                continue;
+            }
+            let (x, y) = (furnace.x, furnace.y);
+            if let Some(map) = self.dimension_map_mut(furnace.dimension) {
+                map.warm_around(x, y);
+            }
+        }
+    }
+}
+
+// Pixel position of a tile's center
+fn tile_center(x: i32, y: i32) -> (f64, f64) {
+    ((x as f64 + 0.5) * TILE_SIZE_PIXELS, (y as f64 + 0.5) * TILE_SIZE_PIXELS)
+}
@@ -0,0 +1,80 @@
+//! Personality traits, and inheritance: a child's color, size and traits come
+//! from its two parents with a little random mutation.
+use serde::{Deserialize, Serialize};
+
+use crate::{random, Promiser};
+
+const TRAIT_MUTATION: f64 = 0.1; // Max drift of a trait per generation
+const COLOR_MUTATION: f64 = 16.0; // Max drift per color channel
+const SIZE_MUTATION: f64 = 0.1; // Max drift of adult size, as a fraction
+
+/// Personality, each 0.0 to 1.0 with 0.5 as the baseline
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Traits {
+    pub curiosity: f64,     // Pull toward unexplored places
+    pub restlessness: f64,  // How often the promiser changes direction
+    pub introspection: f64, // How often it stops to think
+    #[serde(default = "random")]
+    pub charisma: f64, // How readily others start following it (see leadership.rs)
+    #[serde(default = "random")]
+    pub bravery: f64, // Whether it fights hostile creatures or runs (see combat.rs)
+}
+
+impl Traits {
+    pub fn random() -> Traits {
+        Traits { curiosity: random(), restlessness: random(), introspection: random(), charisma: random(), bravery: random() }
+    }
+
+    pub fn inherit(a: &Traits, b: &Traits) -> Traits {
+        let blend = |x: f64, y: f64| (mix(x, y) + mutation(TRAIT_MUTATION)).clamp(0.0, 1.0);
+        Traits {
+            curiosity: blend(a.curiosity, b.curiosity),
+            restlessness: blend(a.restlessness, b.restlessness),
+            introspection: blend(a.introspection, b.introspection),
+            charisma: blend(a.charisma, b.charisma),
+            bravery: blend(a.bravery, b.bravery),
+        }
+    }
+
+    /// Behavior multiplier for a trait value: 0.0 to 2.0, 1.0 at the baseline
+    pub fn factor(value: f64) -> f64 {
+        value * 2.0
+    }
+}
+
+// Somewhere between the two parents' values
+fn mix(a: f64, b: f64) -> f64 {
+    a + (b - a) * random()
+}
+
+fn mutation(max: f64) -> f64 {
+    (random() * 2.0 - 1.0) * max
+}
+
+fn inherit_color(a: u32, b: u32) -> u32 {
+    let channel = |shift: u32| {
+        let (ca, cb) = (((a >> shift) & 0xFF) as f64, ((b >> shift) & 0xFF) as f64);
+        ((mix(ca, cb) + mutation(COLOR_MUTATION)).clamp(0.0, 255.0) as u32) << shift
+    };
+    0xFF000000 | channel(16) | channel(8) | channel(0)
+}
+
+impl Promiser {
+    /// Size once fully grown
+    pub(crate) fn grown_size(&self) -> f64 {
+        if self.adult_size > 0.0 { self.adult_size } else { self.size }
+    }
+
+    /// A newborn between two parents, inheriting from both
+    pub(crate) fn child_of(id: u32, a: &Promiser, b: &Promiser) -> Promiser {
+        let mut child = Promiser::with_pixel(id, (a.x + b.x) / 2.0, a.y.max(b.y), false);
+        child.dimension = a.dimension;
+        child.color = inherit_color(a.color, b.color);
+        child.adult_size = mix(a.grown_size(), b.grown_size()) * (1.0 + mutation(SIZE_MUTATION));
+        child.size = child.adult_size * 0.5;
+        child.age = 0.0;
+        child.traits = Traits::inherit(&a.traits, &b.traits);
+        child.parents = vec![a.id, b.id];
+        child
+    }
+}
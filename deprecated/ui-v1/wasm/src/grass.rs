@@ -0,0 +1,76 @@
+//! Grass: a living surface on dirt, stored as the GRASS bit of the dirt
+//! tile's variant (the low bits keep its shape). Grass takes root on exposed,
+//! moist, lit dirt and spreads from there to neighboring dirt like it; it
+//! fades once the dirt is covered, flooded or dries out. Grazing animals eat
+//! it back down to bare dirt.
+use crate::light::MIN_FOLIAGE_LIGHT;
+use crate::{random, Tile, TileMap, TileType};
+
+pub const GRASS: u8 = 0x10; // Variant bit marking grassy dirt
+const MIN_GRASS_MOISTURE: u16 = 32; // Drier dirt can't hold grass
+const SEED_CHANCE: f64 = 0.002; // Per step, for bare dirt that could hold grass
+const SPREAD_CHANCE: f64 = 0.05; // Per step, per grassy neighbor
+const FADE_CHANCE: f64 = 0.2; // Per step, for grass that can't live where it is
+
+impl TileMap {
+    pub fn is_grassy(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Dirt && tile.variant & GRASS != 0)
+    }
+
+    /// Grow, spread and fade grass for one foliage step
+    pub fn step_grass(&mut self) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut changes: Vec<(i32, i32, bool)> = Vec::new();
+        for y in 0..h {
+            for x in 0..w {
+                let tile = &self.tiles[(y * w + x) as usize];
+                if tile.tile_type != TileType::Dirt {
+                    continue;
+                }
+                let grassy = tile.variant & GRASS != 0;
+                let habitable = self.can_hold_grass(x, y);
+                if grassy && !habitable && random() < FADE_CHANCE {
+                    changes.push((x, y, false));
+                } else if !grassy && habitable {
+                    // Grass creeps along the surface, up and down one step too
+                    let neighbors = [(-1, -1), (-1, 0), (-1, 1), (1, -1), (1, 0), (1, 1)]
+                        .iter()
+                        .filter(|&&(dx, dy)| self.is_grassy(x + dx, y + dy))
+                        .count() as i32;
+                    let chance = 1.0 - (1.0 - SPREAD_CHANCE).powi(neighbors) + SEED_CHANCE;
+                    // Up to twice as likely on enriched dirt (see fertility.rs)
+                    let chance = chance * (1.0 + self.enrichment((y * w + x) as usize));
+                    if random() < chance {
+                        changes.push((x, y, true));
+                    }
+                }
+            }
+        }
+        for (x, y, grassy) in changes {
+            self.set_grass(x, y, grassy);
+        }
+    }
+
+    /// Eat the grass off tile (x, y). Returns whether there was any.
+    pub fn graze(&mut self, x: i32, y: i32) -> bool {
+        let grassy = self.is_grassy(x, y);
+        if grassy {
+            self.set_grass(x, y, false);
+        }
+        grassy
+    }
+
+    // Dirt with open air (or foliage) above it, enough moisture and enough light
+    fn can_hold_grass(&self, x: i32, y: i32) -> bool {
+        let Some(tile) = self.get_tile(x, y) else { return false };
+        let open = self.get_tile(x, y + 1).is_none_or(|above| matches!(above.tile_type, TileType::Air | TileType::Foliage));
+        let lit = !self.rules.plants_need_light || self.light_at(x, y + 1).is_none_or(|light| light >= MIN_FOLIAGE_LIGHT);
+        tile.tile_type == TileType::Dirt && tile.water_amount >= MIN_GRASS_MOISTURE && open && lit
+    }
+
+    fn set_grass(&mut self, x: i32, y: i32, grassy: bool) {
+        let Some(tile) = self.get_tile(x, y).cloned() else { return };
+        let variant = if grassy { tile.variant | GRASS } else { tile.variant & !GRASS };
+        self.set_tile(x, y, Tile { variant, ..tile });
+    }
+}
@@ -0,0 +1,81 @@
+//! Named groups of promisers with a shared color accent, and affinities between
+//! groups: promisers drift toward nearby members of groups they like and away
+//! from groups they dislike.
+use serde::Serialize;
+
+use crate::determinism::HashMap;
+use crate::{dimension, GameState};
+
+const AFFINITY_RADIUS: f64 = 128.0; // How far away other groups are noticed (pixels)
+const AFFINITY_ACCEL: f64 = 6.0; // Velocity change per second at full affinity
+const SAME_GROUP_AFFINITY: f64 = 0.3; // Mild pull toward fellow members
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Group {
+    pub id: u32,
+    pub name: String,
+    pub accent: u32, // RGB color shown alongside members' own colors
+}
+
+/// Affinity between pairs of groups, -1.0 (hostile) to 1.0 (friendly).
+/// Pairs that were never set are neutral.
+#[derive(Default)]
+pub struct Affinities {
+    pairs: HashMap<(u32, u32), f64>,
+}
+
+impl Affinities {
+    fn key(a: u32, b: u32) -> (u32, u32) {
+        (a.min(b), a.max(b))
+    }
+
+    pub fn set(&mut self, a: u32, b: u32, value: f64) {
+        self.pairs.insert(Self::key(a, b), value.clamp(-1.0, 1.0));
+    }
+
+    pub fn get(&self, a: u32, b: u32) -> f64 {
+        if a == b {
+            return SAME_GROUP_AFFINITY;
+        }
+        self.pairs.get(&Self::key(a, b)).copied().unwrap_or(0.0)
+    }
+
+    /// Forget everything about a group
+    pub fn remove_group(&mut self, group: u32) {
+        self.pairs.retain(|&(a, b), _| a != group && b != group);
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut pairs: Vec<(u32, u32, f64)> = self.pairs.iter().map(|(&(a, b), &v)| (a, b, v)).collect();
+        pairs.sort_by_key(|&(a, b, _)| (a, b));
+        serde_json::to_string(&pairs).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+impl GameState {
+    /// Nudge grouped promisers toward or away from nearby members of other
+    /// groups, by affinity
+    pub(crate) fn apply_group_affinity(&mut self, dt: f64) {
+        let grouped: Vec<(u32, u32, u32, f64, f64)> = self.promisers.values()
+            .filter(|p| p.group != 0)
+            .map(|p| (p.id, p.group, p.dimension, p.x, p.y))
+            .collect();
+
+        for (id, group, dim, x, y) in grouped {
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else {
+                continue;
+            };
+            let mut pull = 0.0;
+            for other in self.spatial.query_radius(&self.promisers, dim, x, y, AFFINITY_RADIUS) {
+                let Some(other) = self.promisers.get(&other).filter(|o| o.id != id && o.group != 0) else {
+                    continue;
+                };
+                pull += self.affinities.get(group, other.group) * map.offset_x(x, other.x).signum();
+            }
+            if pull != 0.0 {
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.vx += pull.clamp(-1.0, 1.0) * AFFINITY_ACCEL * dt;
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Build a tile map from an RGBA image so levels can be drawn in any paint tool.
+use crate::determinism::HashMap;
+use crate::{Tile, TileMap, TileType, MAX_WATER_AMOUNT, MAX_WORLD_TILES};
+
+// Used when the caller doesn't supply a palette (the renderer's tile colors)
+const DEFAULT_PALETTE: &[(u32, TileType)] = &[
+    (TileType::Air.color(), TileType::Air),
+    (TileType::Dirt.color(), TileType::Dirt),
+    (TileType::Stone.color(), TileType::Stone),
+    (TileType::Water.color(), TileType::Water),
+    (TileType::Foliage.color(), TileType::Foliage),
+    (TileType::Ore.color(), TileType::Ore),
+    (TileType::Wood.color(), TileType::Wood),
+];
+
+/// Parse a palette like `{"#8b4513": "Dirt", "#1e90ff": "Water"}`.
+/// An empty string selects the default palette.
+pub fn parse_palette(palette_json: &str) -> Result<Vec<(u32, TileType)>, String> {
+    if palette_json.trim().is_empty() {
+        return Ok(DEFAULT_PALETTE.to_vec());
+    }
+
+    let entries: HashMap<String, String> = serde_json::from_str(palette_json)
+        .map_err(|err| format!("invalid palette JSON: {}", err))?;
+
+    let mut palette = Vec::with_capacity(entries.len());
+    for (color, name) in entries {
+        let hex = color.trim_start_matches('#');
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| format!("invalid palette color '{}'", color))?;
+        let tile_type = TileType::from_name(&name)
+            .ok_or_else(|| format!("unknown tile type '{}'", name))?;
+        palette.push((rgb, tile_type));
+    }
+
+    if palette.is_empty() {
+        return Err("palette is empty".to_string());
+    }
+    Ok(palette)
+}
+
+// Closest palette entry by squared RGB distance, so anti-aliased edges still map
+fn nearest(palette: &[(u32, TileType)], r: u8, g: u8, b: u8) -> TileType {
+    let distance = |rgb: u32| {
+        let dr = ((rgb >> 16) & 0xFF) as i32 - r as i32;
+        let dg = ((rgb >> 8) & 0xFF) as i32 - g as i32;
+        let db = (rgb & 0xFF) as i32 - b as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .min_by_key(|(rgb, _)| distance(*rgb))
+        .map(|&(_, tile_type)| tile_type)
+        .unwrap_or(TileType::Air)
+}
+
+/// Convert RGBA pixels (row 0 = top of the image) into a tile map (y=0 = bottom
+/// of the world). Fully transparent pixels become air.
+pub fn tile_map_from_image(rgba: &[u8], width: usize, height: usize, palette: &[(u32, TileType)]) -> Result<TileMap, String> {
+    if width == 0 || height == 0 {
+        return Err("image has no pixels".to_string());
+    }
+    let tiles = width.checked_mul(height).filter(|&tiles| tiles <= MAX_WORLD_TILES)
+        .ok_or_else(|| format!("a {}x{} image is larger than the biggest world ({} tiles)", width, height, MAX_WORLD_TILES))?;
+    if rgba.len() != tiles * 4 {
+        return Err(format!("expected {} bytes for a {}x{} RGBA image, got {}", tiles * 4, width, height, rgba.len()));
+    }
+
+    let mut map = TileMap::new(width, height);
+    for row in 0..height {
+        let y = height - 1 - row;
+        for x in 0..width {
+            let p = (row * width + x) * 4;
+            let (r, g, b, a) = (rgba[p], rgba[p + 1], rgba[p + 2], rgba[p + 3]);
+            let tile_type = if a == 0 { TileType::Air } else { nearest(palette, r, g, b) };
+            let water_amount = if tile_type == TileType::Water { MAX_WATER_AMOUNT } else { 0 };
+            map.set_tile(x as i32, y as i32, Tile { tile_type, water_amount, variant: 0 });
+        }
+    }
+    Ok(map)
+}
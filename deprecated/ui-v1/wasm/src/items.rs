@@ -0,0 +1,137 @@
+//! Loose items lying in the world, and stockpile zones that promisers haul
+//! them to.
+use serde::{Deserialize, Serialize};
+
+use crate::shape::TileShape;
+use crate::{random, Tile, TileType, TILE_SIZE_PIXELS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Dirt,
+    Stone,
+    Ore,
+    Wood,
+    Plant, // From foliage
+    // Crafted (see crafting.rs)
+    Bricks,
+    Platform,
+    Ladder,
+    Furnace,
+    // Defenses (see defenses.rs)
+    Spikes,
+    PitCover,
+    ReinforcedWall,
+    // Cooked (see furnace.rs)
+    Ingot,
+    // Caught (see fish.rs) or left by defeated creatures (see combat.rs)
+    Fish,
+    Meat,
+    // Tools (see tools.rs)
+    Shovel,
+    Pick,
+    Bucket,
+}
+
+impl ItemKind {
+    /// Item kind from its lowercase name, e.g. "stone"
+    pub fn from_name(name: &str) -> Option<ItemKind> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+
+    /// What digging out a tile of this type yields
+    pub fn from_tile(tile_type: TileType) -> Option<ItemKind> {
+        match tile_type {
+            TileType::Dirt | TileType::Mud => Some(ItemKind::Dirt),
+            TileType::Stone => Some(ItemKind::Stone),
+            TileType::Ore => Some(ItemKind::Ore),
+            TileType::Wood => Some(ItemKind::Wood),
+            TileType::Ladder => Some(ItemKind::Ladder),
+            TileType::Foliage => Some(ItemKind::Plant),
+            TileType::Furnace => Some(ItemKind::Furnace),
+            TileType::Spikes => Some(ItemKind::Spikes),
+            TileType::PitCover => Some(ItemKind::PitCover),
+            TileType::ReinforcedWall => Some(ItemKind::ReinforcedWall),
+            TileType::Air | TileType::Water | TileType::Portal | TileType::Ice | TileType::Steam | TileType::Snow | TileType::Decay | TileType::Quicksand => None,
+        }
+    }
+
+    /// The tile one of these becomes when placed in the world, None for
+    /// tools, ingots and food
+    pub fn tile(self) -> Option<Tile> {
+        let (tile_type, shape) = match self {
+            ItemKind::Dirt => (TileType::Dirt, TileShape::Full),
+            ItemKind::Stone | ItemKind::Bricks => (TileType::Stone, TileShape::Full),
+            ItemKind::Ore => (TileType::Ore, TileShape::Full),
+            ItemKind::Wood => (TileType::Wood, TileShape::Full),
+            ItemKind::Plant => (TileType::Foliage, TileShape::Full),
+            ItemKind::Platform => (TileType::Wood, TileShape::Half),
+            ItemKind::Ladder => (TileType::Ladder, TileShape::Full),
+            ItemKind::Furnace => (TileType::Furnace, TileShape::Full),
+            ItemKind::Spikes => (TileType::Spikes, TileShape::Full),
+            ItemKind::PitCover => (TileType::PitCover, TileShape::Full),
+            ItemKind::ReinforcedWall => (TileType::ReinforcedWall, TileShape::Full),
+            ItemKind::Ingot | ItemKind::Fish | ItemKind::Meat | ItemKind::Shovel | ItemKind::Pick | ItemKind::Bucket => return None,
+        };
+        Some(Tile { tile_type, water_amount: 0, variant: shape.variant() })
+    }
+}
+
+/// Some number of one kind of item, e.g. in a promiser's hands
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+/// An item stack lying in the world (pixel position), as put together from
+/// its components (see ecs.rs)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Item {
+    pub id: u32,
+    pub kind: ItemKind,
+    pub count: u32,
+    pub x: f64,
+    pub y: f64,
+    pub dimension: u32,
+    #[serde(skip)]
+    pub reserved_by: Option<u32>, // Promiser on its way to pick this up
+}
+
+/// A rectangle of tiles where hauled items are kept
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stockpile {
+    pub id: u32,
+    pub dimension: u32,
+    pub x: i32, // Bottom-left tile
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Stockpile {
+    pub fn contains(&self, dimension: u32, px: f64, py: f64) -> bool {
+        let tx = (px / TILE_SIZE_PIXELS).floor() as i32;
+        let ty = (py / TILE_SIZE_PIXELS).floor() as i32;
+        dimension == self.dimension
+            && (self.x..self.x + self.width).contains(&tx)
+            && (self.y..self.y + self.height).contains(&ty)
+    }
+
+    /// Pixel position to walk to
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.x as f64 + self.width as f64 / 2.0) * TILE_SIZE_PIXELS,
+            (self.y as f64 + 0.5) * TILE_SIZE_PIXELS,
+        )
+    }
+
+    /// Somewhere along the bottom row to set an item down, so stored items
+    /// spread out instead of piling on one spot
+    pub fn drop_spot(&self) -> (f64, f64) {
+        (
+            (self.x as f64 + random() * self.width as f64) * TILE_SIZE_PIXELS,
+            (self.y as f64 + 0.5) * TILE_SIZE_PIXELS,
+        )
+    }
+}
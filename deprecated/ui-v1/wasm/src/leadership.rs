@@ -0,0 +1,74 @@
+//! Emergent leaders: promisers near a more charismatic one sometimes start
+//! loosely following it, so parades form on their own. Unlike follow orders
+//! from JS, these links come and go by themselves.
+use crate::determinism::HashSet;
+use crate::steering::{self, Steering};
+use crate::{dimension, random, GameState};
+
+const LEADER_RADIUS: f64 = 160.0; // How close a leader has to be to attract followers (pixels)
+const MIN_CHARISMA: f64 = 0.7; // Only promisers at least this charismatic lead
+const JOIN_CHANCE: f64 = 0.2; // Per check, for a free promiser near a leader
+const LEAVE_CHANCE: f64 = 0.05; // Per check, followers lose interest
+const FOLLOW_DISTANCE: f64 = 40.0; // Followers trail about this far behind
+const FOLLOW_STRENGTH: f64 = 0.5; // Fraction of an ordered follow's pull
+pub const LEADER_INTERVAL: u64 = 60; // Ticks between join/leave checks
+
+impl GameState {
+    /// Drop followers that lost interest or their leader, and let free
+    /// promisers near a charismatic one join its following
+    pub(crate) fn update_leaders(&mut self) {
+        let mut ids: Vec<u32> = self.promisers.keys().copied().collect();
+        ids.sort_unstable();
+
+        for &id in &ids {
+            let promiser = &self.promisers[&id];
+            let Some(leader) = promiser.leader else { continue };
+            let keep = self.promisers.get(&leader).is_some_and(|l| {
+                l.dimension == promiser.dimension && (l.x - promiser.x).hypot(l.y - promiser.y) <= LEADER_RADIUS * 2.0
+            });
+            if !keep || random() < LEAVE_CHANCE {
+                self.promisers.get_mut(&id).unwrap().leader = None;
+            }
+        }
+
+        let leading: HashSet<u32> = self.promisers.values().filter_map(|p| p.leader).collect();
+        for id in ids {
+            let promiser = &self.promisers[&id];
+            let free = promiser.leader.is_none() && promiser.task.is_none()
+                && promiser.steering == Steering::None && !promiser.controlled;
+            if !free || leading.contains(&id) || random() >= JOIN_CHANCE {
+                continue;
+            }
+            let charisma = promiser.traits.charisma;
+            let leader = self.spatial.query_radius(&self.promisers, promiser.dimension, promiser.x, promiser.y, LEADER_RADIUS)
+                .into_iter()
+                .filter_map(|other| self.promisers.get(&other))
+                .filter(|other| other.id != id && other.leader.is_none())
+                .filter(|other| other.traits.charisma >= MIN_CHARISMA && other.traits.charisma > charisma)
+                .max_by(|a, b| a.traits.charisma.total_cmp(&b.traits.charisma).then(b.id.cmp(&a.id)))
+                .map(|other| other.id);
+            if let Some(leader) = leader {
+                self.promisers.get_mut(&id).unwrap().leader = Some(leader);
+            }
+        }
+    }
+
+    /// Pull followers after their leaders, more gently than a follow order
+    pub(crate) fn follow_leaders(&mut self, dt: f64) {
+        let links: Vec<(u32, u32)> = self.promisers.values()
+            .filter_map(|p| p.leader.map(|leader| (p.id, leader)))
+            .collect();
+
+        for (id, leader) in links {
+            let Some((leader_x, leader_y)) = self.promisers.get(&leader).map(|l| (l.x, l.y)) else { continue };
+            let promiser = self.promisers.get_mut(&id).unwrap();
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else {
+                continue;
+            };
+            let dx = map.offset_x(promiser.x, leader_x);
+            if dx.hypot(leader_y - promiser.y) > FOLLOW_DISTANCE {
+                promiser.vx += steering::seek_vx(dx, dt) * FOLLOW_STRENGTH;
+            }
+        }
+    }
+}
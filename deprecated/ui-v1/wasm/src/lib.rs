@@ -1,5 +1,5 @@
 use wasm_bindgen::prelude::*;
-use std::collections::HashMap;
+use std::collections::VecDeque;
 use serde::{Serialize, Deserialize};
 
 // Import the `console.log` function from the `console` object in the web-sys crate
@@ -8,27 +8,161 @@ extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
     
-    #[wasm_bindgen(js_namespace = Math)]
-    fn random() -> f64;
+    // Only for seeding; the simulation draws from determinism::random
+    #[wasm_bindgen(js_namespace = Math, js_name = random)]
+    fn js_random() -> f64;
 }
 
-// Define a macro to make it easier to call console.log
+// Define a macro to make it easier to call console.log, filtered by level
+// and subsystem (see logging.rs): console_log!(Info, World, "...", ...)
 macro_rules! console_log {
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+    ($level:ident, $subsystem:ident, $($t:tt)*) => {
+        if $crate::logging::enabled($crate::logging::LogLevel::$level, $crate::logging::Subsystem::$subsystem) {
+            $crate::log(&format_args!($($t)*).to_string())
+        }
+    }
 }
 
+mod ai;
+mod air;
+mod autosave;
+mod birds;
+mod blueprint;
+mod borders;
+mod buffers;
+mod chronicle;
+mod collision;
+mod combat;
+mod config;
+mod crafting;
+mod creatures;
+mod decay;
+mod defenses;
+mod delta;
+mod despawn;
+mod determinism;
+mod dimension;
+mod director;
+mod disease;
+mod ecs;
+mod emitters;
+mod events;
+mod export;
+mod fertility;
+mod fish;
+mod fixed;
+mod flow;
+mod fluids;
+mod furnace;
+mod genetics;
+mod grass;
+mod groups;
+mod image_import;
+mod items;
+mod leadership;
+mod lifecycle;
+mod light;
+mod logging;
+mod lod;
+mod memory;
+mod mining;
+mod moles;
+mod msgpack;
+mod mud;
+mod nav;
+mod objectives;
+mod palette;
+mod persistence;
+mod pets;
+mod player;
+mod quicksand;
+mod raycast;
+mod regions;
+mod scenario;
+mod schematic;
+mod sequence;
+mod shape;
+mod shelter;
+mod simulation;
+mod snow;
+mod solidity;
+mod spatial;
+mod spawn;
+mod speech;
+mod steam;
+mod steering;
+mod structure;
+mod sun;
+mod tasks;
+mod thermal;
+mod threat;
+mod tools;
+mod vitals;
+mod warmth;
+mod warmup;
+mod waves;
+mod worldgen;
+
+use blueprint::Blueprint;
+use buffers::TileBuffers;
+use chronicle::Chronicle;
+use collision::CollisionFilter;
+use config::{Difficulty, Rules, SimConfig, WorldPreset};
+use creatures::CreatureKind;
+use delta::PromiserDelta;
+use determinism::{random, HashMap, HashSet};
+use dimension::{Dimension, DimensionSettings, PortalLink, OVERWORLD, OVERWORLD_NAME};
+use director::Director;
+use disease::QuarantineZone;
+use ecs::Ecs;
+use emitters::Emitter;
+use events::{EventQueue, SimEvent};
+use export::StateExport;
+use fixed::{num, real};
+use flow::FlowFields;
+use fluids::{FluidParams, Fluids};
+use furnace::Furnace;
+use genetics::Traits;
+use groups::{Affinities, Group};
+use light::AmbientLight;
+use items::{Item, ItemKind, ItemStack, Stockpile};
+use lod::{Lod, View};
+use memory::VisitedMemory;
+use nav::NavGrid;
+use objectives::{Objective, ObjectiveSpec};
+use player::Player;
+use regions::RegionMap;
+use schematic::Schematic;
+use sequence::Sequence;
+use shape::TileShape;
+use solidity::Solidity;
+use spatial::SpatialHash;
+use spawn::{BatchSpawnOptions, SpawnOptions};
+use speech::{HeardEntry, SpeechEntry};
+use steering::Steering;
+use sun::Sun;
+use tasks::Task;
+
 // Constants
 const TILE_SIZE_PIXELS: f64 = 32.0;
 const MAX_WATER_AMOUNT: u16 = 1024; // Maximum water amount (1024 = full)
+const MAX_WORLD_TILES: usize = 4096 * 4096; // Largest world imported from outside (images, snapshots)
 const MAX_DIRT_MOISTURE: u16 = 256; // Maximum moisture content for dirt (1/4 of water)
 const MIN_FOLIAGE_MOISTURE: u16 = 128; // Minimum moisture needed for foliage growth (half of max)
 const FOLIAGE_GROWTH_CHANCE: f64 = 1.0; // Chance per simulation step for foliage to grow
 const FOLIAGE_DEATH_MOISTURE: u16 = 64; // Below this moisture, foliage will die
 
-// Light ray constants
-const MAX_LIGHT_RAYS: usize = 10000; // Maximum number of active light rays
-const RAY_SPEED: f64 = 100.0; // Pixels per second
-const RAY_START_EPSILON: f64 = 2.0; // Distance to start ray from boundary
+// Light ray constants (the ray budget and speed come from SimConfig)
+const LIGHT_SPAWN_TICKS: u64 = 6; // Ticks between ray spawns (≈ 100ms at 60fps)
+
+const MAX_BATCH_SPAWN: u32 = 10000; // Most promisers spawn_promisers creates per call
+const MAX_PROMISER_META_BYTES: usize = 64 * 1024; // Size limit for set_promiser_meta
+
+const GRAVITY: f64 = 300.0; // Pixels per second squared
+
+// Stuck detection
+const STUCK_DISTANCE: f64 = 4.0; // Moving less than this (pixels) counts as no progress
+const STUCK_SECONDS: f64 = 5.0; // How long without progress before recovering
 
 // Light ray structure
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,10 +172,17 @@ pub struct LightRay {
     pub vx: f64,       // Velocity x (normalized direction * speed)
     pub vy: f64,       // Velocity y (normalized direction * speed)
     pub intensity: f64, // Light intensity (0.0 to 1.0)
+    #[serde(default = "white")]
+    pub color: u32, // RGB color of the emitter it came from
+    #[serde(default = "forever")]
+    pub ttl: f64, // Seconds until the ray fades out (short-range emitters)
 }
 
+fn white() -> u32 { 0xFFFFFF }
+fn forever() -> f64 { f64::INFINITY }
+
 impl LightRay {
-    pub fn new(start_x: f64, start_y: f64, direction_x: f64, direction_y: f64) -> Self {
+    pub fn new(start_x: f64, start_y: f64, direction_x: f64, direction_y: f64, speed: f64) -> Self {
         // Normalize direction and apply speed
         let length = (direction_x * direction_x + direction_y * direction_y).sqrt();
         let norm_x = if length > 0.0 { direction_x / length } else { 0.0 };
@@ -50,9 +191,11 @@ impl LightRay {
         LightRay {
             x: start_x, // Use the provided position directly (epsilon already applied)
             y: start_y,
-            vx: norm_x * RAY_SPEED,
-            vy: norm_y * RAY_SPEED,
+            vx: norm_x * speed,
+            vy: norm_y * speed,
             intensity: 1.0,
+            color: white(),
+            ttl: forever(),
         }
     }
     
@@ -68,7 +211,7 @@ impl LightRay {
 
 // Promiser entity that moves randomly on a 2D plane
 #[wasm_bindgen]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Promiser {
     id: u32,
     x: f64,
@@ -80,15 +223,118 @@ pub struct Promiser {
     state: u32, // 0=idle, 1=thinking, 2=speaking, 3=whispering, 4=running
     thought: String, // Current thought/message
     target_id: u32, // Target promiser for whispering (0 = none)
+    #[serde(skip)]
     state_timer: f64, // Time in current state
     is_pixel: bool, // Special promiser flag
+    dimension: u32, // Which dimension the promiser is in (0 = overworld)
+    #[serde(skip)]
+    portal_cooldown: f64, // Seconds before the promiser can use another portal
+    name: String, // Display name (empty = unnamed)
+    #[serde(default)]
+    meta: String, // Opaque JSON object owned by the JS side (empty = none)
+    #[serde(default)]
+    steering: Steering, // Follow/flee another promiser
+    #[serde(default)]
+    emote: String, // Emote shown with the current speech (empty = none)
+    #[serde(default)]
+    speech_duration: f64, // Seconds the current speech lasts (0 = default length)
+    #[serde(default)]
+    speech_history: VecDeque<SpeechEntry>, // Recent lines, oldest first
+    #[serde(default)]
+    heard: VecDeque<HeardEntry>, // Recent lines overheard from others, oldest first
+    #[serde(default)]
+    visited: VisitedMemory, // Coarse cells this promiser has been to
+    #[serde(skip)]
+    stuck_anchor: (f64, f64), // Where the promiser was when it last made progress
+    #[serde(skip)]
+    stuck_timer: f64, // Seconds spent trying to move without getting anywhere
+    #[serde(skip)]
+    climb_dir: f64, // 1.0 climbing up, -1.0 down, 0.0 not on a ladder
+    #[serde(default = "default_health")]
+    health: f64, // 0 to vitals::MAX_HEALTH; the promiser dies at 0
+    #[serde(default = "default_air")]
+    air: f64, // Seconds of breath left underwater
+    #[serde(skip)]
+    death_cause: Option<&'static str>, // What brought health to 0
+    #[serde(skip)]
+    fall_peak_y: Option<f64>, // Highest y since last standing on something (None until first landing)
+    #[serde(default)]
+    task: Option<Task>, // Current job (hauling, ...)
+    #[serde(default)]
+    carrying: Vec<ItemStack>, // Item stacks in hand, at most tasks::CARRY_SLOTS
+    #[serde(default)]
+    tool: Option<ItemKind>, // Held tool (see tools.rs)
+    #[serde(default = "lifecycle::random_adult_age")]
+    age: f64, // Seconds lived (see lifecycle::SECONDS_PER_YEAR)
+    #[serde(default = "lifecycle::random_lifespan")]
+    lifespan: f64, // Years until dying of old age
+    #[serde(default)]
+    adult_size: f64, // Fully grown size; 0 until first aged (then taken from size)
+    #[serde(default = "Traits::random")]
+    traits: Traits, // Personality, inherited by children
+    #[serde(default)]
+    parents: Vec<u32>, // Ids of both parents (empty for spawned promisers)
+    #[serde(default)]
+    illness: f64, // Seconds of illness left (0 = healthy)
+    #[serde(default)]
+    immunity: f64, // Seconds until the promiser can fall ill again
+    #[serde(default)]
+    group: u32, // Group id (0 = none)
+    #[serde(default)]
+    leader: Option<u32>, // Charismatic promiser this one is loosely following, if any
+    #[serde(skip)]
+    controlled: bool, // Moved by player input instead of wandering (Pixel only)
+    #[serde(default)]
+    glow: f64, // Light given off, 0.0 (none) to 1.0 (see light.rs)
+    #[serde(default)]
+    glow_color: u32, // RGB color of the glow, for renderers
+    #[serde(default)]
+    collision: CollisionFilter, // What the promiser bumps into (see collision.rs)
+    #[serde(skip)]
+    indoors: bool, // In a region cut off from the sky (see regions.rs)
+    #[serde(default)]
+    wetness: f64, // 0.0 dry to 1.0 soaked (see shelter.rs)
+    #[serde(skip)]
+    raining: bool, // Rain is falling in the promiser's dimension
+    #[serde(skip)]
+    chilled: bool, // The air here is cold enough to look for warmth (see warmth.rs)
+    #[serde(skip)]
+    pets: Vec<u32>, // Creatures this promiser has tamed, by id (see pets.rs)
+    #[serde(skip)]
+    struggle: f64, // Progress toward breaking free of quicksand, 1.0 escapes (see quicksand.rs)
+    #[serde(skip)]
+    quicksand_escape: f64, // Seconds left with the quicksand's pull off after breaking free
+    #[serde(skip)]
+    escaped_quicksand: bool, // Broke free this tick; cleared when the event goes out
+    #[serde(skip)]
+    threat: Option<(f64, f64)>, // Offset to danger close enough to run from (see threat.rs)
+    #[serde(skip)]
+    refuge: Option<(f64, f64)>, // Offset to where it's running to, if anywhere safer
+    #[serde(skip)]
+    foe: Option<(f64, f64)>, // Offset to the hostile creature it's standing up to (see combat.rs)
+    #[serde(skip)]
+    attack_cooldown: f64, // Seconds until it can hit again
+    #[cfg(feature = "fixed-point")]
+    #[serde(skip)]
+    motion: fixed::Motion, // Position and velocity the movement step works on (see fixed.rs)
 }
 
+fn default_health() -> f64 { vitals::MAX_HEALTH }
+
+// Name for the chronicle
+fn display_name(promiser: &Promiser) -> String {
+    if promiser.name.is_empty() { format!("Promiser {}", promiser.id) } else { promiser.name.clone() }
+}
+fn default_air() -> f64 { vitals::MAX_AIR }
+
 #[wasm_bindgen]
 impl Promiser {
     #[wasm_bindgen(constructor)]
     pub fn new(id: u32, x: f64, y: f64) -> Promiser {
-        let is_pixel = id == 0; // First promiser is Pixel
+        Promiser::with_pixel(id, x, y, id == 0) // First promiser is Pixel
+    }
+
+    fn with_pixel(id: u32, x: f64, y: f64, is_pixel: bool) -> Promiser {
         Promiser {
             id,
             x,
@@ -102,6 +348,53 @@ impl Promiser {
             target_id: 0,
             state_timer: 0.0,
             is_pixel,
+            dimension: OVERWORLD,
+            portal_cooldown: 0.0,
+            name: String::new(),
+            meta: String::new(),
+            steering: Steering::None,
+            emote: String::new(),
+            speech_duration: 0.0,
+            speech_history: VecDeque::new(),
+            heard: VecDeque::new(),
+            visited: VisitedMemory::default(),
+            stuck_anchor: (x, y),
+            stuck_timer: 0.0,
+            climb_dir: 0.0,
+            health: vitals::MAX_HEALTH,
+            air: vitals::MAX_AIR,
+            death_cause: None,
+            fall_peak_y: None,
+            task: None,
+            carrying: Vec::new(),
+            tool: None,
+            age: lifecycle::random_adult_age(),
+            lifespan: lifecycle::random_lifespan(),
+            adult_size: 0.0,
+            traits: Traits::random(),
+            parents: Vec::new(),
+            illness: 0.0,
+            immunity: 0.0,
+            group: 0,
+            leader: None,
+            controlled: false,
+            glow: 0.0,
+            glow_color: 0,
+            collision: CollisionFilter::PROMISER,
+            indoors: false,
+            wetness: 0.0,
+            raining: false,
+            chilled: false,
+            pets: Vec::new(),
+            struggle: 0.0,
+            quicksand_escape: 0.0,
+            escaped_quicksand: false,
+            threat: None,
+            refuge: None,
+            foe: None,
+            attack_cooldown: 0.0,
+            #[cfg(feature = "fixed-point")]
+            motion: fixed::Motion::default(),
         }
     }
     
@@ -113,7 +406,13 @@ impl Promiser {
     
     #[wasm_bindgen(getter)]
     pub fn y(&self) -> f64 { self.y }
-    
+
+    #[wasm_bindgen(getter)]
+    pub fn vx(&self) -> f64 { self.vx }
+
+    #[wasm_bindgen(getter)]
+    pub fn vy(&self) -> f64 { self.vy }
+
     #[wasm_bindgen(getter)]
     pub fn size(&self) -> f64 { self.size }
     
@@ -132,21 +431,60 @@ impl Promiser {
     #[wasm_bindgen(getter)]
     pub fn is_pixel(&self) -> bool { self.is_pixel }
     
+    #[wasm_bindgen(getter)]
+    pub fn dimension(&self) -> u32 { self.dimension }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String { self.name.clone() }
+
+    #[wasm_bindgen(getter)]
+    pub fn health(&self) -> f64 { self.health }
+
+    #[wasm_bindgen(getter)]
+    pub fn air(&self) -> f64 { self.air }
+
+    /// Age in years
+    #[wasm_bindgen(getter)]
+    pub fn age(&self) -> f64 { self.years() }
+
+    #[wasm_bindgen(getter)]
+    pub fn sick(&self) -> bool { self.is_sick() }
+
+    /// In an enclosed room or cave, out of sight of the sky
+    #[wasm_bindgen(getter)]
+    pub fn indoors(&self) -> bool { self.indoors }
+
+    /// 0.0 dry to 1.0 soaked
+    #[wasm_bindgen(getter)]
+    pub fn wetness(&self) -> f64 { self.wetness }
+
+    /// Group id (0 = none)
+    #[wasm_bindgen(getter)]
+    pub fn group(&self) -> u32 { self.group }
+
+    /// "child", "adult" or "elder"
+    #[wasm_bindgen(getter)]
+    pub fn stage(&self) -> String { self.life_stage().name().to_string() }
+    
     pub fn set_thought(&mut self, thought: String) {
         self.thought = thought;
-        self.state = 2; // Set to speaking state
+        self.state = ai::SPEAKING;
         self.state_timer = 0.0;
+        self.emote.clear();
+        self.speech_duration = 0.0;
     }
     
     pub fn set_whisper(&mut self, thought: String, target_id: u32) {
         self.thought = thought;
         self.target_id = target_id;
-        self.state = 3; // Set to whispering state
+        self.state = ai::WHISPERING;
         self.state_timer = 0.0;
+        self.emote.clear();
+        self.speech_duration = 0.0;
     }
     
     pub fn start_running(&mut self) {
-        self.state = 4; // Set to running state
+        self.state = ai::RUNNING;
         self.state_timer = 0.0;
         // Increase velocity when running
         self.vx *= 2.0;
@@ -155,18 +493,64 @@ impl Promiser {
 }
 
 impl Promiser {
-    // Helper method to convert pixel coordinates to tile coordinates
-    fn pixel_to_tile(pixel_coord: f64) -> usize {
-        (pixel_coord / TILE_SIZE_PIXELS).floor() as usize
+    // JSON object for the state export (vx/vy let renderers extrapolate positions)
+    fn to_json(&self, include_meta: bool) -> String {
+        let mut out = Vec::new();
+        let _ = self.write_json(&mut out, include_meta); // Writing into a Vec never fails
+        String::from_utf8(out).unwrap_or_default()
     }
-    
+
+    // Same as to_json, written without allocating (see export.rs)
+    fn write_json(&self, out: &mut Vec<u8>, include_meta: bool) -> std::io::Result<()> {
+        use std::io::Write;
+        write!(
+            out,
+            "{{\"id\":{},\"x\":{:.2},\"y\":{:.2},\"vx\":{:.2},\"vy\":{:.2},\"size\":{:.2},\"color\":{},\"state\":{},\"thought\":",
+            self.id, self.x, self.y, self.vx, self.vy, self.size, self.color, self.state
+        )?;
+        serde_json::to_writer(&mut *out, &self.thought)?;
+        write!(out, ",\"target_id\":{},\"is_pixel\":{},\"dimension\":{},\"name\":", self.target_id, self.is_pixel, self.dimension)?;
+        serde_json::to_writer(&mut *out, &self.name)?;
+        out.extend_from_slice(b",\"emote\":");
+        serde_json::to_writer(&mut *out, &self.emote)?;
+        out.extend_from_slice(b",\"speech\":");
+        serde_json::to_writer(&mut *out, &self.speech_history)?;
+        write!(
+            out,
+            ",\"health\":{:.1},\"air\":{:.1},\"age\":{:.1},\"stage\":\"{}\",\"sick\":{},\"group\":{}",
+            self.health, self.air, self.years(), self.life_stage().name(), self.is_sick(), self.group
+        )?;
+        if include_meta && !self.meta.is_empty() {
+            out.extend_from_slice(b",\"meta\":");
+            out.extend_from_slice(self.meta.as_bytes()); // Already validated JSON
+        }
+        if self.glow > 0.0 {
+            write!(out, ",\"glow\":{:.2},\"glow_color\":{}", self.glow, self.glow_color)?;
+        }
+        if let Some(tool) = self.tool {
+            out.extend_from_slice(b",\"tool\":");
+            serde_json::to_writer(&mut *out, &tool)?;
+        }
+        if !self.pets.is_empty() {
+            out.extend_from_slice(b",\"pets\":");
+            serde_json::to_writer(&mut *out, &self.pets)?;
+        }
+        out.push(b'}');
+        Ok(())
+    }
+
     // Helper method to check if a tile is solid (blocks movement)
     fn is_solid_tile(tile_type: TileType) -> bool {
         match tile_type {
-            TileType::Dirt | TileType::Stone | TileType::Foliage => true,
-            TileType::Air | TileType::Water => false,
+            TileType::Dirt | TileType::Stone | TileType::Foliage | TileType::Ore | TileType::Wood | TileType::Ice | TileType::Snow | TileType::Furnace | TileType::PitCover | TileType::ReinforcedWall => true,
+            TileType::Air | TileType::Water | TileType::Portal | TileType::Ladder | TileType::Steam | TileType::Decay | TileType::Mud | TileType::Quicksand | TileType::Spikes => false,
         }
     }
+
+    // Tiles a promiser can hold on to and climb
+    fn is_climbable_tile(tile_type: TileType) -> bool {
+        tile_type == TileType::Ladder
+    }
     
     // Check if the promiser would collide with solid tiles at given position
     fn check_tile_collision(&self, x: f64, y: f64, tile_map: &TileMap) -> bool {
@@ -183,143 +567,204 @@ impl Promiser {
             (right, top),     // top-right
         ];
         
-        for (px, py) in positions {
-            if px < 0.0 || py < 0.0 { continue; }
-            
-            let tile_x = Self::pixel_to_tile(px);
-            let tile_y = Self::pixel_to_tile(py);
-            
-            if let Some(tile) = tile_map.get_tile(tile_x, tile_y) {
-                if Self::is_solid_tile(tile.tile_type) {
-                    return true;
+        positions.iter().any(|&(px, py)| self.collision.blocked_at(tile_map, px, py))
+    }
+
+    // Nearest position to (x, y) where the promiser fits, searching outward in
+    // square rings of half-tile steps
+    fn find_open_spot(&self, x: f64, y: f64, tile_map: &TileMap) -> Option<(f64, f64)> {
+        const STEP: f64 = TILE_SIZE_PIXELS / 2.0;
+        const MAX_RINGS: i32 = 32;
+        let world_width = tile_map.width as f64 * TILE_SIZE_PIXELS;
+        let world_height = tile_map.height as f64 * TILE_SIZE_PIXELS;
+        let fits = |px: f64, py: f64| {
+            px >= self.size && px <= world_width - self.size
+                && py >= self.size && py <= world_height - self.size
+                && !self.check_tile_collision(px, py, tile_map)
+        };
+
+        for ring in 0..=MAX_RINGS {
+            let mut best: Option<(f64, f64, i32)> = None;
+            for dy in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs() != ring && dy.abs() != ring {
+                        continue; // Interior was searched by earlier rings
+                    }
+                    let (px, py) = (x + dx as f64 * STEP, y + dy as f64 * STEP);
+                    let distance = dx * dx + dy * dy;
+                    if fits(px, py) && best.is_none_or(|(_, _, d)| distance < d) {
+                        best = Some((px, py, distance));
+                    }
                 }
             }
+            if let Some((px, py, _)) = best {
+                return Some((px, py));
+            }
         }
-        
-        false
+        None
+    }
+
+    // Count time spent trying to move without getting anywhere. Returns true
+    // once that's gone on long enough to need recovery.
+    fn track_stuck(&mut self, dt: f64) -> bool {
+        let (ax, ay) = self.stuck_anchor;
+        let (dx, dy) = (self.x - ax, self.y - ay);
+        if dx * dx + dy * dy > STUCK_DISTANCE * STUCK_DISTANCE {
+            self.stuck_anchor = (self.x, self.y);
+            self.stuck_timer = 0.0;
+            return false;
+        }
+
+        let trying_to_move = self.state == ai::RUNNING || (self.state != ai::THINKING && self.vx.abs() > 0.2);
+        if trying_to_move {
+            self.stuck_timer += dt;
+        }
+        self.stuck_timer > STUCK_SECONDS
+    }
+
+    // Get a stuck promiser going again: move it out if it's wedged inside
+    // solid tiles, otherwise give it a hop. Returns the method used.
+    fn recover_from_stuck(&mut self, tile_map: &TileMap) -> &'static str {
+        self.stuck_timer = 0.0;
+        self.stuck_anchor = (self.x, self.y);
+        if self.check_tile_collision(self.x, self.y, tile_map) {
+            if let Some((x, y)) = self.find_open_spot(self.x, self.y, tile_map) {
+                self.x = x;
+                self.y = y;
+                self.vx = 0.0;
+                self.vy = 0.0;
+                return "teleport";
+            }
+        }
+        self.vx = (random() - 0.5) * 6.0;
+        self.vy = 6.0;
+        "nudge"
     }
 
     fn update(&mut self, world_width: f64, world_height: f64, dt: f64, tile_map: &TileMap) {
-        // Update state timer
-        self.state_timer += dt;
+        self.think(tile_map, dt);
         
-        // Handle state transitions
-        match self.state {
-            0 => { // Idle
-                if random() < 0.002 { // 0.2% chance per frame to start thinking
-                    self.state = 1;
-                    self.state_timer = 0.0;
-                }
-            },
-            1 => { // Thinking
-                if self.state_timer > 2.0 + random() * 3.0 { // Think for 2-5 seconds
-                    self.state = 0; // Return to idle
-                    self.state_timer = 0.0;
-                }
-            },
-            2 => { // Speaking
-                if self.state_timer > 3.0 + random() * 2.0 { // Speak for 3-5 seconds
-                    self.state = 0; // Return to idle
-                    self.thought.clear();
-                    self.state_timer = 0.0;
-                }
-            },
-            3 => { // Whispering
-                if self.state_timer > 1.0 + random() * 1.0 { // Whisper for 1-2 seconds
-                    self.state = 0; // Return to idle
-                    self.thought.clear();
-                    self.target_id = 0;
-                    self.state_timer = 0.0;
-                }
-            },
-            4 => { // Running
-                if self.state_timer > 2.0 + random() * 3.0 { // Run for 2-5 seconds
-                    self.state = 0; // Return to idle
-                    self.state_timer = 0.0;
-                    // Reduce velocity after running
-                    self.vx *= 0.6;
-                    self.vy *= 0.8;
-                }
-            },
-            _ => self.state = 0, // Reset unknown states
+        // Apply gravity to vertical velocity, unless holding on to a ladder
+        const CLIMB_SPEED: f64 = 3.0;
+        let on_ladder = tile_map.get_tile_at_pixel(self.x, self.y)
+            .is_some_and(|tile| Self::is_climbable_tile(tile.tile_type));
+        if on_ladder {
+            if self.climb_dir == 0.0 {
+                // Just grabbed on; mostly climb up, sometimes down
+                self.climb_dir = if random() < 0.7 { 1.0 } else { -1.0 };
+            }
+            self.vy = CLIMB_SPEED * self.climb_dir;
+            self.vx *= 0.8; // Stay on the ladder while climbing
+        } else {
+            if self.climb_dir > 0.0 {
+                // Climbed off the top: step sideways onto the ledge
+                self.vx += 2.0 * if self.vx >= 0.0 { 1.0 } else { -1.0 };
+            }
+            self.climb_dir = 0.0;
+            self.vy -= GRAVITY * dt;
+            self.sink_in_mud(tile_map);
+            self.sink_in_quicksand(tile_map, dt);
         }
         
-        // Apply gravity to vertical velocity
-        const GRAVITY: f64 = 300.0; // Pixels per second squared
-        self.vy -= GRAVITY * dt;
-        
         // Adjust movement speed based on state
         let speed_multiplier = match self.state {
-            4 => 2.5, // Running is faster
-            3 => 0.5, // Whispering is slower
-            1 => 0.3, // Thinking is very slow
+            ai::RUNNING => 2.5, // Running is faster
+            ai::WHISPERING => 0.5, // Whispering is slower
+            ai::THINKING => 0.3, // Thinking is very slow
             _ => 1.0, // Normal speed
-        };
+        } * self.carry_speed() * self.life_stage().speed() * tile_map.breath_speed(self.x, self.y) * self.wet_speed() * self.mud_speed(tile_map);
         
+        // From here on in Num, fixed point with the feature on (see fixed.rs)
+        let mut m = self.motion();
+        let size = num(self.size);
+
         // Store old position for collision resolution
-        let old_x = self.x;
-        let old_y = self.y;
+        let old_x = m.x;
+        let old_y = m.y;
         
         // Calculate new position based on velocity
-        let new_x = self.x + self.vx * dt * 50.0 * speed_multiplier;
-        let new_y = self.y + self.vy * dt * 50.0 * speed_multiplier;
+        let scale = num(dt * 50.0 * speed_multiplier);
+        let new_x = m.x + m.vx * scale;
+        let new_y = m.y + m.vy * scale;
         
         // Check horizontal movement first
-        self.x = new_x;
-        if self.check_tile_collision(self.x, self.y, tile_map) {
-            // Collision on horizontal movement - bounce and reset x
-            self.vx = -self.vx * 0.5; // Bounce with energy loss
-            self.x = old_x;
+        m.x = new_x;
+        if self.check_tile_collision(real(m.x), real(m.y), tile_map) {
+            // Walk up slopes and half tiles instead of bouncing off them
+            const MAX_STEP_UP: f64 = TILE_SIZE_PIXELS / 2.0 + 1.0;
+            let step_up = if m.vy <= num(0.5) {
+                (1..=MAX_STEP_UP as i32).map(|step| num(step as f64))
+                    .find(|&step| !self.check_tile_collision(real(m.x), real(m.y + step), tile_map))
+            } else {
+                None
+            };
+            if let Some(step) = step_up {
+                m.y += step;
+            } else {
+                // Collision on horizontal movement - bounce and reset x
+                m.vx = -m.vx * num(0.5); // Bounce with energy loss
+                m.x = old_x;
+            }
         }
         
         // Check vertical movement
-        self.y = new_y;
-        if self.check_tile_collision(self.x, self.y, tile_map) {
+        let mut landed = false;
+        m.y = new_y;
+        if self.check_tile_collision(real(m.x), real(m.y), tile_map) {
             // Collision on vertical movement
-            if self.vy < 0.0 {
+            if m.vy < num(0.0) {
                 // Falling down and hit something - land on tile
-                self.vy = 0.0;
-                self.y = old_y;
+                m.vy = num(0.0);
+                m.y = old_y;
                 // Add horizontal friction when landing on tiles
-                self.vx *= 0.85;
+                m.vx *= num(0.85);
+                landed = true;
             } else {
                 // Moving up and hit something - bounce down
-                self.vy = -self.vy * 0.3;
-                self.y = old_y;
+                m.vy = -m.vy * num(0.3);
+                m.y = old_y;
             }
         }
         
-        // Bounce off world boundaries
-        if self.x <= self.size || self.x >= world_width - self.size {
-            self.vx = -self.vx * 0.8; // Add some energy loss on bounce
-            self.x = self.x.clamp(self.size, world_width - self.size);
+        // Wrap around or bounce off side walls (see borders.rs)
+        let (width, height) = (num(world_width), num(world_height));
+        if tile_map.wrap_x {
+            m.x = m.x.rem_euclid(width);
+        } else if (m.x <= size && !tile_map.open_left) || (m.x >= width - size && !tile_map.open_right) {
+            m.vx = -m.vx * num(0.8); // Add some energy loss on bounce
+            m.x = m.x.clamp(size, width - size);
         }
         
-        // Ground collision with bounce (world bottom)
-        if self.y >= world_height - self.size {
-            self.vy = -self.vy * 0.7; // Bounce with energy loss
-            self.y = world_height - self.size;
+        // Ceiling collision with bounce (world top), unless it's open
+        if m.y >= height - size && !tile_map.open_top {
+            m.vy = -m.vy * num(0.7); // Bounce with energy loss
+            m.y = height - size;
             
             // Add some horizontal friction when on ground
-            self.vx *= 0.95;
+            m.vx *= num(0.95);
         }
         
-        // Ceiling collision (world top)
-        if self.y <= self.size {
-            self.vy = -self.vy * 0.5;
-            self.y = self.size;
+        // Bottom boundary (y=0); open-bottom worlds let promisers fall through
+        if m.y <= size && !tile_map.open_bottom {
+            m.vy = -m.vy * num(0.5);
+            m.y = size;
+            landed = true;
         }
-        
-        // Occasionally add some random horizontal impulse (except when thinking)
-        if self.state != 1 && random() < 0.01 {
-            self.vx += (random() - 0.5) * 2.0;
+
+        // Clamp velocities to reasonable bounds
+        let max_vx = num(if self.state == ai::RUNNING { 6.0 } else { 4.0 });
+        let max_vy = num(if self.state == ai::RUNNING { 15.0 } else { 10.0 });
+        m.vx = m.vx.clamp(-max_vx, max_vx);
+        m.vy = m.vy.clamp(-max_vy, max_vy);
+        self.set_motion(m);
+
+        if landed {
+            self.land(tile_map);
+        } else {
+            self.track_fall(on_ladder);
         }
         
-        // Clamp velocities to reasonable bounds
-        let max_vx = if self.state == 4 { 6.0 } else { 4.0 };
-        let max_vy = if self.state == 4 { 15.0 } else { 10.0 };
-        self.vx = self.vx.clamp(-max_vx, max_vx);
-        self.vy = self.vy.clamp(-max_vy, max_vy);
+        self.visited.visit(self.dimension, self.x, self.y);
     }
 }
 
@@ -334,35 +779,126 @@ pub struct GameState {
     tick_count: u64,
     tile_map: TileMap, // Add tile map to game state
     light_rays: Vec<LightRay>, // Light rays for rendering
+    config: SimConfig,
+    events: EventQueue,
+    overworld_settings: DimensionSettings,
+    fluids: Fluids, // What the water solver moves, and how thick each is (see fluids.rs)
+    dimensions: Vec<Dimension>, // Additional named tile maps; dimension id = index + 1
+    portals: Vec<PortalLink>,
+    buffers: TileBuffers, // Typed views of the overworld for renderers, synced each tick
+    export: StateExport, // Reused state export buffer (see export.rs)
+    promiser_delta: PromiserDelta, // What get_changed_promisers last sent
+    spatial: SpatialHash, // Promiser positions by grid cell, rebuilt after movement
+    export_meta: bool, // Include promiser metadata in state exports
+    export_light_rays: bool, // Include individual light rays in state exports
+    ecs: Ecs, // Entities stored as components: creatures (see ecs.rs)
+    flow_fields: FlowFields, // Shared paths toward task targets, by dimension and tile
+    lod: Lod, // What the front end is looking at, and how far behind the rest is (see lod.rs)
+    stockpiles: Vec<Stockpile>, // Zones haulers bring items to
+    next_stockpile_id: u32,
+    blueprints: Vec<Blueprint>, // Structures waiting to be built
+    next_blueprint_id: u32,
+    chronicle: Chronicle, // World history: deaths, ...
+    quarantine: Option<QuarantineZone>, // Where sick promisers are sent
+    groups: Vec<Group>,
+    next_group_id: u32,
+    affinities: Affinities, // How groups feel about each other
+    player: Player, // Input and inventory for Pixel under player control
+    sequences: Vec<Sequence>, // Scripted sequences in progress
+    next_sequence_id: u32,
+    director: Director, // Injects events into quiet worlds when enabled
+    sun: Sun, // Time of day, which drives sky light and shadows
+    ambient: AmbientLight, // Light floor and moonlight
+    emitters: Vec<Emitter>, // Everything that spawns light rays, starting with the sky
+    next_emitter_id: u32,
+    furnaces: Vec<Furnace>, // What fed furnace tiles are burning and cooking
+    wind: f64, // Pixels per second, positive blows right (see birds.rs)
+    last_wave: Option<u32>, // Day the last hostile wave came on (see waves.rs)
+    objectives: Vec<Objective>, // Goals being tracked, finished ones included
+    next_objective_id: u32,
+    autosave_ticks: u64, // Ticks between autosaves, 0 for none (see autosave.rs)
+    autosave: Option<(u64, Vec<u8>)>, // Tick and bytes of the latest autosave
 }
 
-#[wasm_bindgen]
 impl GameState {
-    #[wasm_bindgen(constructor)]
-    pub fn new(world_width_tiles: f64, world_height_tiles: f64) -> GameState {
-        console_log!("Creating new game state with world size: {}x{} tiles", world_width_tiles, world_height_tiles);
+    pub fn with_config(world_width_tiles: f64, world_height_tiles: f64, config: SimConfig) -> GameState {
+        console_log!(Debug, World, "Creating new game state with world size: {}x{} tiles", world_width_tiles, world_height_tiles);
         
         // Convert tile dimensions to pixel dimensions
         let world_width_pixels = world_width_tiles * TILE_SIZE_PIXELS;
         let world_height_pixels = world_height_tiles * TILE_SIZE_PIXELS;
         
-        console_log!("World size in pixels: {}x{}", world_width_pixels, world_height_pixels);
+        console_log!(Debug, World, "World size in pixels: {}x{}", world_width_pixels, world_height_pixels);
         
         let tile_width = world_width_tiles as usize;
         let tile_height = world_height_tiles as usize;
         
-        console_log!("Creating tile map with dimensions: {}x{} tiles ({}x{} pixels)", 
+        console_log!(Debug, World, "Creating tile map with dimensions: {}x{} tiles ({}x{} pixels)", 
                      tile_width, tile_height, world_width_pixels, world_height_pixels);
         
+        let seed = config.seed.unwrap_or_else(determinism::fresh_seed);
+        determinism::seed(seed);
+        let preset = config.preset;
+        let autosave_ticks = config.autosave_ticks;
+        let director = Director::new(config.director);
+        let sun = Sun::new(config.day_seconds.unwrap_or(sun::DEFAULT_DAY_SECONDS));
+        let ambient = AmbientLight::new(
+            config.ambient_light.unwrap_or(light::DEFAULT_AMBIENT_FLOOR),
+            config.moon_light.unwrap_or(light::DEFAULT_MOON_LIGHT),
+            config.moon_color.unwrap_or(light::DEFAULT_MOON_COLOR),
+        );
+        let mut tile_map = TileMap::new(tile_width, tile_height);
+        tile_map.set_borders(config.borders());
+        tile_map.rules = config.rules;
+        
         let mut state = GameState {
-            promisers: HashMap::new(),
+            promisers: HashMap::default(),
             next_id: 0,
             world_width: world_width_pixels,
             world_height: world_height_pixels,
             last_update: 0.0,
             tick_count: 0,
-            tile_map: TileMap::new(tile_width, tile_height),
+            tile_map,
             light_rays: Vec::new(),
+            config,
+            events: EventQueue::default(),
+            overworld_settings: DimensionSettings::default(),
+            fluids: Fluids::default(),
+            dimensions: Vec::new(),
+            portals: Vec::new(),
+            buffers: TileBuffers::default(),
+            export: StateExport::default(),
+            promiser_delta: PromiserDelta::default(),
+            spatial: SpatialHash::default(),
+            export_meta: false,
+            export_light_rays: true,
+            ecs: Ecs::default(),
+            flow_fields: FlowFields::default(),
+            lod: Lod::default(),
+            stockpiles: Vec::new(),
+            next_stockpile_id: 1,
+            blueprints: Vec::new(),
+            next_blueprint_id: 1,
+            chronicle: Chronicle::default(),
+            quarantine: None,
+            groups: Vec::new(),
+            next_group_id: 1,
+            affinities: Affinities::default(),
+            player: Player::default(),
+            sequences: Vec::new(),
+            next_sequence_id: 1,
+            director,
+            sun,
+            ambient,
+            emitters: vec![Emitter::sky()],
+            next_emitter_id: emitters::SKY_EMITTER + 1,
+            furnaces: Vec::new(),
+            wind: 0.0,
+            last_wave: None,
+            objectives: Vec::new(),
+            next_objective_id: 1,
+            autosave_ticks,
+            autosave: None,
         };
         
         // Create initial promisers
@@ -370,42 +906,336 @@ impl GameState {
             state.add_promiser();
         }
         
-        // Add some initial water tiles for testing water simulation
-        // First, create some dirt ground at the bottom for water to settle on (y=0 is bottom)
-        for x in 0..tile_width {
-            for y in 0..3 {
-                state.tile_map.set_tile(x, y, Tile {
-                    tile_type: TileType::Dirt,
-                    water_amount: 0,
-                });
+        // Generate terrain (surface, caves, underground lakes, ore)
+        console_log!(Info, World, "Generating {:?} world with seed {}", preset, seed);
+        worldgen::generate(&mut state.tile_map, &worldgen::WorldGenParams::for_preset(preset), seed);
+        state.tile_map.take_dirty(); // Renderers start from the full tile map
+        state.buffers.sync(&state.tile_map, &state.sun, &state.ambient, 0.0);
+
+        state
+    }
+
+    // Move every promiser against the tile map of the dimension it's in, then
+    // handle portal transitions and falls out of open-bottom worlds
+    fn update_promisers(&mut self, dt: f64) {
+        self.apply_player_input();
+        self.player_mine(dt);
+        self.apply_steering(dt);
+        self.run_tasks(dt);
+        self.apply_group_affinity(dt);
+        self.follow_leaders(dt);
+
+        let mut fallen = Vec::new();
+        let mut transitions = Vec::new();
+        let mut unstuck = Vec::new();
+        let mut dead = Vec::new();
+        let mut illness_events = Vec::new();
+        let harmless = !self.config.rules.entities_take_damage;
+
+        for promiser in self.promisers.values_mut() {
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else {
+                continue;
+            };
+            let world_width = map.width as f64 * TILE_SIZE_PIXELS;
+            let world_height = map.height as f64 * TILE_SIZE_PIXELS;
+            if promiser.escape_burial(map, dt) {
+                unstuck.push(SimEvent::Unstuck { id: promiser.id, x: promiser.x, y: promiser.y, method: "push_out".to_string() });
+            }
+            let health = promiser.health;
+            promiser.grow_older(dt);
+            promiser.update(world_width, world_height, dt, map);
+            if std::mem::take(&mut promiser.escaped_quicksand) {
+                unstuck.push(SimEvent::EscapedQuicksand { id: promiser.id, x: promiser.x, y: promiser.y });
+            }
+            promiser.update_air(map, dt);
+            promiser.indoors = map.is_indoors(promiser.x, promiser.y);
+            promiser.update_wetness(map, dt);
+            promiser.update_warmth(map);
+            illness_events.extend(promiser.update_illness(map, dt));
+            if harmless && promiser.death_cause != Some("old_age") {
+                // Nothing hurts with damage off, though old age still comes
+                promiser.health = promiser.health.max(health);
+                promiser.death_cause = None;
+            }
+            if promiser.is_dead() {
+                dead.push(promiser.id);
+                continue;
+            }
+
+            if map.in_void(promiser.x, promiser.y, promiser.size) {
+                fallen.push(promiser.id);
+                continue;
+            }
+
+            if promiser.track_stuck(dt) {
+                let method = promiser.recover_from_stuck(map);
+                unstuck.push(SimEvent::Unstuck { id: promiser.id, x: promiser.x, y: promiser.y, method: method.to_string() });
+            }
+
+            promiser.portal_cooldown = (promiser.portal_cooldown - dt).max(0.0);
+            if promiser.portal_cooldown == 0.0 && promiser.collision.hits(collision::TRIGGERS) {
+                let on_portal = map.get_tile_at_pixel(promiser.x, promiser.y)
+                    .is_some_and(|tile| tile.tile_type == TileType::Portal);
+                if on_portal {
+                    let tile_x = (promiser.x / TILE_SIZE_PIXELS).floor() as i32;
+                    let tile_y = (promiser.y / TILE_SIZE_PIXELS).floor() as i32;
+                    if let Some(link) = self.portals.iter()
+                        .find(|l| l.from == promiser.dimension && l.x == tile_x && l.y == tile_y)
+                    {
+                        transitions.push((promiser.id, *link));
+                    }
+                }
             }
         }
-        
-        // Place water at the center for testing gravity (it should fall down to smaller y values)
-        let center_x = tile_width / 2;
-        let center_y = tile_height / 2;
-        let water_size = 6; // 6x6 water block
-        
-        for x in (center_x.saturating_sub(water_size/2))..(center_x + water_size/2 + 1).min(tile_width) {
-            for y in (center_y)..(center_y + 6).min(tile_height) {
-                state.tile_map.set_tile(x, y, Tile {
-                    tile_type: TileType::Water,
-                    water_amount: MAX_WATER_AMOUNT,
+
+        for event in unstuck.into_iter().chain(illness_events) {
+            self.events.push(event);
+        }
+
+        for id in fallen {
+            if let Some(promiser) = self.promisers.remove(&id) {
+                self.events.push(SimEvent::FellIntoVoid { id, x: promiser.x });
+            }
+        }
+
+        for id in dead {
+            if let Some(promiser) = self.promisers.remove(&id) {
+                for stack in &promiser.carrying {
+                    self.add_item(stack.kind, stack.count, promiser.x, promiser.y, promiser.dimension);
+                }
+                if let Some(tool) = promiser.tool {
+                    self.add_item(tool, 1, promiser.x, promiser.y, promiser.dimension);
+                }
+                // The body is left to rot where the promiser fell
+                let tile_x = (promiser.x / TILE_SIZE_PIXELS).floor() as i32;
+                let tile_y = (promiser.y / TILE_SIZE_PIXELS).floor() as i32;
+                if let Some(map) = dimension::dimension_map_mut(&mut self.tile_map, &mut self.dimensions, promiser.dimension) {
+                    map.add_decay(tile_x, tile_y, decay::CORPSE_MATTER);
+                }
+                let cause = promiser.death_cause.unwrap_or("unknown").to_string();
+                console_log!(Info, Promisers, "💀 Promiser {} died ({})", id, cause);
+                let who = display_name(&promiser);
+                let how = if cause == "old_age" { "of old age".to_string() } else { format!("({})", cause) };
+                self.chronicle.record(self.tick_count, format!("{} died {} at {} years", who, how, promiser.years().floor()));
+                self.events.push(SimEvent::Died { id, x: promiser.x, y: promiser.y, cause });
+            }
+        }
+
+        for (id, link) in transitions {
+            if let Some(promiser) = self.promisers.get_mut(&id) {
+                promiser.dimension = link.to;
+                promiser.x = (link.to_x as f64 + 0.5) * TILE_SIZE_PIXELS;
+                promiser.y = (link.to_y as f64 + 0.5) * TILE_SIZE_PIXELS;
+                promiser.portal_cooldown = 1.0;
+                let from = self.dimension_name(link.from);
+                let to = self.dimension_name(link.to);
+                self.events.push(SimEvent::ChangedDimension { id, from, to });
+            }
+        }
+
+        self.spatial.rebuild(&self.promisers);
+    }
+
+    // Put an item stack down in the world and return its id
+    fn add_item(&mut self, kind: ItemKind, count: u32, x: f64, y: f64, dimension: u32) -> u32 {
+        self.ecs.spawn_item(kind, count, x, y, dimension)
+    }
+
+    // Swap in a whole new overworld tile map (image import, snapshots)
+    fn replace_tile_map(&mut self, mut tile_map: TileMap) {
+        tile_map.set_borders(self.config.borders());
+        tile_map.rules = self.config.rules;
+        self.world_width = tile_map.width as f64 * TILE_SIZE_PIXELS;
+        self.world_height = tile_map.height as f64 * TILE_SIZE_PIXELS;
+        self.tile_map = tile_map;
+        self.light_rays.clear();
+
+        // Keep promisers inside the new bounds
+        for promiser in self.promisers.values_mut() {
+            promiser.x = promiser.x.clamp(promiser.size, (self.world_width - promiser.size).max(promiser.size));
+            promiser.y = promiser.y.clamp(promiser.size, (self.world_height - promiser.size).max(promiser.size));
+        }
+    }
+
+    // Record a line of speech in the "heard" memory of everyone in earshot
+    // (same dimension, within range, no solid tiles in between) and emit a
+    // HeardSpeech event. Whispers only reach `whisper_target`.
+    fn broadcast_speech(&mut self, speaker_id: u32, text: String, whisper_target: Option<u32>) {
+        let Some(speaker) = self.promisers.get(&speaker_id) else { return };
+        let (x, y, dim) = (speaker.x, speaker.y, speaker.dimension);
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else { return };
+
+        let radius = if whisper_target.is_some() { speech::WHISPER_RADIUS } else { speech::HEARING_RADIUS };
+        let listeners: Vec<u32> = self.spatial.query_radius(&self.promisers, dim, x, y, radius)
+            .into_iter()
+            .filter(|&id| id != speaker_id && whisper_target.is_none_or(|target| target == id))
+            .filter(|id| {
+                let listener = &self.promisers[id];
+                map.line_of_sight(x, y, listener.x, listener.y)
+            })
+            .collect();
+
+        for id in &listeners {
+            if let Some(listener) = self.promisers.get_mut(id) {
+                speech::remember(&mut listener.heard, HeardEntry {
+                    tick: self.tick_count,
+                    speaker_id,
+                    text: text.clone(),
+                    whisper: whisper_target.is_some(),
                 });
             }
         }
+        if !listeners.is_empty() {
+            self.events.push(SimEvent::HeardSpeech { speaker: speaker_id, listeners, text, whisper: whisper_target.is_some() });
+        }
+    }
 
-        state
+    // Nudge following/fleeing promisers toward/away from their targets, and
+    // drop steering whose target no longer exists
+    fn apply_steering(&mut self, dt: f64) {
+        let steering: Vec<(u32, Steering)> = self.promisers.values()
+            .filter(|promiser| promiser.steering != Steering::None)
+            .map(|promiser| (promiser.id, promiser.steering))
+            .collect();
+
+        for (id, steer) in steering {
+            let Some(target) = steer.target().and_then(|target| self.promisers.get(&target)) else {
+                self.promisers.get_mut(&id).unwrap().steering = Steering::None;
+                continue;
+            };
+            let (target_x, target_y, target_dimension) = (target.x, target.y, target.dimension);
+
+            let promiser = self.promisers.get_mut(&id).unwrap();
+            if promiser.dimension != target_dimension {
+                continue; // Keep the order; it applies again if they meet up
+            }
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else {
+                continue;
+            };
+            let dx = map.offset_x(promiser.x, target_x);
+            promiser.vx += steer.delta_vx(dx, target_y - promiser.y, dt);
+        }
+    }
+
+    fn dimension_id(&self, name: &str) -> Option<u32> {
+        if name == OVERWORLD_NAME {
+            return Some(OVERWORLD);
+        }
+        self.dimensions.iter().position(|d| d.name == name).map(|i| i as u32 + 1)
+    }
+
+    fn dimension_name(&self, id: u32) -> String {
+        if id == OVERWORLD {
+            OVERWORLD_NAME.to_string()
+        } else {
+            self.dimensions.get(id as usize - 1).map(|d| d.name.clone()).unwrap_or_default()
+        }
+    }
+
+    fn dimension_map_mut(&mut self, id: u32) -> Option<&mut TileMap> {
+        if id == OVERWORLD {
+            Some(&mut self.tile_map)
+        } else {
+            self.dimensions.get_mut(id as usize - 1).map(|d| &mut d.tile_map)
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GameState {
+    #[wasm_bindgen(constructor)]
+    pub fn new(world_width_tiles: f64, world_height_tiles: f64) -> GameState {
+        GameState::with_config(world_width_tiles, world_height_tiles, SimConfig::default())
     }
     
     pub fn add_promiser(&mut self) {
         let x = random() * self.world_width;
         let y = self.world_height; // Start from world's pixel height (top of world)
         let promiser = Promiser::new(self.next_id, x, y);
+        self.spatial.insert(&promiser);
         self.promisers.insert(self.next_id, promiser);
         self.next_id += 1;
     }
     
+    /// Spawn a promiser at pixel position (x, y). `options_json` is an
+    /// optional SpawnOptions object, e.g. `{"name": "Bob", "size": 8}`.
+    /// Returns the new id, or u32::MAX if the options are invalid.
+    pub fn spawn_promiser(&mut self, x: f64, y: f64, options_json: String) -> u32 {
+        let options = match SpawnOptions::from_json(&options_json) {
+            Ok(options) => options,
+            Err(err) => {
+                console_log!(Error, Promisers, "Failed to spawn promiser: {}", err);
+                return u32::MAX;
+            }
+        };
+        let id = self.next_id;
+        let promiser = options.build(id, x, y);
+        self.spatial.insert(&promiser);
+        self.promisers.insert(id, promiser);
+        self.next_id += 1;
+        id
+    }
+
+    /// Have two adult promisers in the same dimension produce a child, which
+    /// inherits color, size and traits from both. Returns the child's id, or
+    /// u32::MAX if the pair can't have children.
+    pub fn reproduce(&mut self, parent_a: u32, parent_b: u32) -> u32 {
+        let (Some(a), Some(b)) = (self.promisers.get(&parent_a), self.promisers.get(&parent_b)) else {
+            return u32::MAX;
+        };
+        let adults = [a, b].iter().all(|p| p.life_stage() != lifecycle::LifeStage::Child);
+        if parent_a == parent_b || a.dimension != b.dimension || !adults {
+            return u32::MAX;
+        }
+
+        let id = self.next_id;
+        let child = Promiser::child_of(id, a, b);
+        let text = format!("{} was born to {} and {}", display_name(&child), display_name(a), display_name(b));
+        self.spatial.insert(&child);
+        self.promisers.insert(id, child);
+        self.next_id += 1;
+        self.chronicle.record(self.tick_count, text);
+        self.events.push(SimEvent::Born { id, parents: vec![parent_a, parent_b] });
+        id
+    }
+
+    /// Parent ids of every living promiser as JSON: [{"id": 5, "parents": [1, 2]}, ...]
+    pub fn get_lineage(&self) -> String {
+        let mut ids: Vec<u32> = self.promisers.keys().copied().collect();
+        ids.sort_unstable();
+        let lineage: Vec<serde_json::Value> = ids.iter()
+            .map(|id| serde_json::json!({ "id": id, "parents": self.promisers[id].parents }))
+            .collect();
+        serde_json::to_string(&lineage).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Spawn up to `count` promisers in one call. `options_json` is an optional
+    /// BatchSpawnOptions object, e.g. `{"distribution": "clustered", "x": 400,
+    /// "y": 300, "radius": 80, "size": 6}`. Returns the new ids.
+    pub fn spawn_promisers(&mut self, count: u32, options_json: String) -> Vec<u32> {
+        let options = match BatchSpawnOptions::from_json(&options_json) {
+            Ok(options) => options,
+            Err(err) => {
+                console_log!(Error, Promisers, "Failed to spawn promisers: {}", err);
+                return Vec::new();
+            }
+        };
+
+        let count = count.min(MAX_BATCH_SPAWN);
+        let mut ids = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (x, y) = options.pick_position(&self.tile_map);
+            let id = self.next_id;
+            let promiser = options.promiser.build(id, x, y);
+            self.spatial.insert(&promiser);
+            self.promisers.insert(id, promiser);
+            self.next_id += 1;
+            ids.push(id);
+        }
+        console_log!(Info, Promisers, "Spawned {} promisers ({:?})", ids.len(), options.distribution);
+        ids
+    }
+
     pub fn remove_promiser(&mut self, id: u32) {
         self.promisers.remove(&id);
     }
@@ -420,95 +1250,86 @@ impl GameState {
         self.last_update = current_time;
 
         // Update all promisers
-        for promiser in self.promisers.values_mut() {
-            promiser.update(self.world_width, self.world_height, dt, &self.tile_map);
-        }
+        self.update_promisers(dt);
     }
 
     /// Simple tick function that handles all internal updates
     pub fn tick(&mut self) {
         // Use a fixed timestep for consistent simulation
         let dt = 1.0 / 60.0; // 60fps
-        
-        // Update all promisers
-        for promiser in self.promisers.values_mut() {
-            promiser.update(self.world_width, self.world_height, dt, &self.tile_map);
+
+        self.sun.advance(dt);
+        self.run_sequences(dt);
+
+        // Hand out jobs, then update all promisers
+        if self.tick_count.is_multiple_of(tasks::ASSIGN_INTERVAL) {
+            self.assign_tasks();
+        }
+        if self.tick_count.is_multiple_of(leadership::LEADER_INTERVAL) {
+            self.update_leaders();
+        }
+        if self.tick_count.is_multiple_of(disease::SPREAD_INTERVAL) {
+            self.spread_illness();
+        }
+        if self.tick_count.is_multiple_of(director::CHECK_INTERVAL) {
+            self.run_director();
+        }
+        self.update_solidity();
+        self.update_regions();
+        self.update_rain(dt);
+        self.perceive_threats();
+        self.update_promisers(dt);
+        self.update_wind(dt);
+        self.update_fish(dt);
+        self.update_birds(dt);
+        self.update_moles(dt);
+        self.update_creatures(dt);
+        self.update_defenses(dt);
+        self.update_combat(dt);
+        self.update_pets();
+        self.update_furnaces(dt);
+        if self.tick_count.is_multiple_of(warmth::CHAT_INTERVAL) {
+            self.campfire_chatter();
+        }
+        if self.tick_count.is_multiple_of(creatures::AMBIENT_SPAWN_TICKS) {
+            self.spawn_ambient();
+        }
+        self.send_waves();
+        self.update_objectives(dt);
+        if self.tick_count.is_multiple_of(despawn::DESPAWN_CHECK_TICKS) {
+            self.despawn(despawn::DESPAWN_CHECK_TICKS as f64 * dt);
+        }
+        if self.tick_count.is_multiple_of(structure::STRUCTURE_CHECK_TICKS) {
+            self.check_structure();
         }
         
         // Internal timing for water simulation (every 6 ticks ≈ 100ms at 60fps)
-        if self.tick_count % 6 == 0 {
+        if self.tick_count.is_multiple_of(6) {
             self.simulate_water();
         }
          // Internal timing for foliage simulation (every 60 ticks ≈ 1 second at 60fps)
-        if self.tick_count % 60 == 0 {
+        if self.tick_count.is_multiple_of(60) {
             self.simulate_foliage();
+            self.simulate_air();
         }
         
         // Update light rays every tick (for smooth movement)
         self.update_light_rays(dt);
+        self.accumulate_light(dt);
         
-        // Generate new light rays (maintain 10000 rays)
-        if self.tick_count % 6 == 0 { // Generate new rays every 6 ticks (≈ 100ms at 60fps)
-            self.generate_light_rays();
+        // Generate new light rays from the emitters (up to the budget)
+        if self.tick_count.is_multiple_of(LIGHT_SPAWN_TICKS) {
+            self.generate_light_rays(LIGHT_SPAWN_TICKS as f64 * dt);
         }
 
+        self.buffers.sync(&self.tile_map, &self.sun, &self.ambient, self.tick_count as f64 / 60.0);
+
         self.tick_count = self.tick_count.wrapping_add(1);
+        self.autosave_if_due();
     }
 
-    /// Generate new light rays from boundary locations to maintain target count
-    fn generate_light_rays(&mut self) {
-        let current_count = self.light_rays.len();
-        if current_count >= MAX_LIGHT_RAYS {
-            return;
-        }
-        
-        let rays_to_generate = (MAX_LIGHT_RAYS - current_count).min(100); // Generate at most 100 per call
-        
-        // Calculate total perimeter for uniform distribution
-        let perimeter = 2.0 * (self.world_width + self.world_height);
-        
-        let mut rays_created = 0;
-        let mut attempts = 0;
-        let max_attempts = rays_to_generate; // Allow some retries for invalid positions
-        
-        while rays_created < rays_to_generate && attempts < max_attempts {
-            attempts += 1;
-            
-            // Choose a random position along the entire perimeter for uniform distribution
-            let perimeter_position = random() * perimeter;
-            
-            let (start_x, start_y) = if perimeter_position < self.world_width {
-                // Top edge
-                (perimeter_position, self.world_height)
-            } else if perimeter_position < self.world_width + self.world_height {
-                // Right edge
-                (self.world_width, self.world_height - (perimeter_position - self.world_width))
-            } else if perimeter_position < 2.0 * self.world_width + self.world_height {
-                // Bottom edge
-                (self.world_width - (perimeter_position - self.world_width - self.world_height), 0.0)
-            } else {
-                // Left edge
-                (0.0, perimeter_position - 2.0 * self.world_width - self.world_height)
-            };
-            
-            // 30 degrees from up vector
-            let angle = -60.0_f64.to_radians();
-            let direction_x = angle.cos();
-            let direction_y = angle.sin();
-            
-            // Move spawn position slightly inward from boundary
-            let actual_start_x = start_x + direction_x.signum() * RAY_START_EPSILON;
-            let actual_start_y = start_y + direction_y.signum() * RAY_START_EPSILON;
-            
-            // Check if spawn position is valid (within bounds and not in solid tile)
-            if !self.is_valid_spawn_position(actual_start_x, actual_start_y) {
-                continue; // Skip this ray and try again
-            }
-            
-            let light_ray = LightRay::new(actual_start_x, actual_start_y, direction_x, direction_y);
-            self.light_rays.push(light_ray);
-            rays_created += 1;
-        }
+    fn light_budget(&self) -> light::LightBudget {
+        self.config.light_budget(self.tile_map.width * self.tile_map.height)
     }
 
     /// Check if a position is valid for spawning a light ray
@@ -520,13 +1341,10 @@ impl GameState {
         }
         
         // Check tile at position
-        let tile_x = (x / TILE_SIZE_PIXELS).floor() as usize;
-        let tile_y = (y / TILE_SIZE_PIXELS).floor() as usize;
-        
-        if let Some(tile) = self.tile_map.get_tile(tile_x, tile_y) {
+        if let Some(tile) = self.tile_map.get_tile_at_pixel(x, y) {
             match tile.tile_type {
-                TileType::Air | TileType::Water => true, // Allow spawning in air and water
-                TileType::Dirt | TileType::Stone | TileType::Foliage => false, // Don't spawn in solid tiles
+                TileType::Air | TileType::Water | TileType::Portal | TileType::Ladder | TileType::Steam | TileType::Decay => true, // Allow spawning in air and water
+                TileType::Dirt | TileType::Stone | TileType::Foliage | TileType::Ore | TileType::Wood | TileType::Ice | TileType::Snow | TileType::Mud | TileType::Quicksand | TileType::Furnace | TileType::Spikes | TileType::PitCover | TileType::ReinforcedWall => false, // Don't spawn in solid tiles, mud, quicksand or traps
             }
         } else {
             false // No tile data available, consider invalid
@@ -536,31 +1354,39 @@ impl GameState {
     /// Update light ray positions and handle collisions with tiles
     fn update_light_rays(&mut self, dt: f64) {
         let mut rays_to_remove = Vec::new();
+        let mut reflected_rays = Vec::new(); // Partial reflections off the water surface
+        let active = self.lod.active_zones(&self.tile_map); // Far from every view, rays go out
         
         for (i, ray) in self.light_rays.iter_mut().enumerate() {
             // Update ray position
             ray.update(dt);
+            ray.ttl -= dt;
+            
+            // Rays leaving a wrapping edge re-enter on the other side
+            if self.tile_map.wrap_x {
+                ray.x = ray.x.rem_euclid(self.world_width);
+            }
             
-            // Check if ray is out of bounds
-            if ray.is_out_of_bounds(self.world_width, self.world_height) {
+            // Check if ray has run its course or left through the void;
+            // walls send it back in
+            let outside = ray.is_out_of_bounds(self.world_width, self.world_height);
+            let far = active.as_ref().is_some_and(|zones| !zones.steps_at_pixel(ray.x, ray.y));
+            if ray.ttl <= 0.0 || far || (outside && !self.tile_map.reflect_off_walls(ray)) {
                 rays_to_remove.push(i);
                 continue;
             }
             
             // Check for tile collision
-            let tile_x = (ray.x / TILE_SIZE_PIXELS).floor() as usize;
-            let tile_y = (ray.y / TILE_SIZE_PIXELS).floor() as usize;
-            
-            if let Some(tile) = self.tile_map.get_tile(tile_x, tile_y) {
+            if let Some(tile) = self.tile_map.get_tile_at_pixel(ray.x, ray.y) {
                 match tile.tile_type {
-                    TileType::Air => {
+                    TileType::Air | TileType::Portal | TileType::Ladder | TileType::Steam | TileType::Decay | TileType::Spikes => {
                         // Check if ray is exiting water into air
                         let prev_x = ray.x - ray.vx * dt;
                         let prev_y = ray.y - ray.vy * dt;
-                        let prev_tile_x = (prev_x / TILE_SIZE_PIXELS).floor() as usize;
-                        let prev_tile_y = (prev_y / TILE_SIZE_PIXELS).floor() as usize;
+                        let prev_tile_x = (prev_x / TILE_SIZE_PIXELS).floor();
+                        let prev_tile_y = (prev_y / TILE_SIZE_PIXELS).floor();
                         
-                        let exiting_water = if let Some(prev_tile) = self.tile_map.get_tile(prev_tile_x, prev_tile_y) {
+                        let exiting_water = if let Some(prev_tile) = self.tile_map.get_tile_at_pixel(prev_x, prev_y) {
                             prev_tile.tile_type == TileType::Water
                         } else {
                             false
@@ -569,9 +1395,8 @@ impl GameState {
                         if exiting_water {
                             // Apply refraction when exiting water to air
                             // n1 * sin(θ1) = n2 * sin(θ2)
-                            // Where n1 = 1.33 (water), n2 = 1.0 (air)
-                            const N_WATER: f64 = 1.33;
-                            const N_AIR: f64 = 1.0;
+                            // Where n1 = N_WATER, n2 = N_AIR
+                            use light::{N_AIR, N_WATER};
                             
                             let speed = (ray.vx * ray.vx + ray.vy * ray.vy).sqrt();
                             let dir_x = ray.vx / speed;
@@ -579,15 +1404,14 @@ impl GameState {
                             
                             // Determine surface normal at exit point
                             let (normal_x, normal_y) = {
-                                let rel_x = (prev_x / TILE_SIZE_PIXELS) - prev_tile_x as f64;
-                                let rel_y = (prev_y / TILE_SIZE_PIXELS) - prev_tile_y as f64;
+                                let rel_x = (prev_x / TILE_SIZE_PIXELS) - prev_tile_x;
+                                let rel_y = (prev_y / TILE_SIZE_PIXELS) - prev_tile_y;
                                 
                                 // Determine which edge of the water tile we're exiting from
                                 if rel_x < 0.1 { (-1.0, 0.0) }       // Left edge
                                 else if rel_x > 0.9 { (1.0, 0.0) }   // Right edge  
                                 else if rel_y < 0.1 { (0.0, -1.0) }  // Bottom edge
-                                else if rel_y > 0.9 { (0.0, 1.0) }   // Top edge
-                                else { (0.0, 1.0) }                  // Default to top edge
+                                else { (0.0, 1.0) }                  // Top edge (default)
                             };
                             
                             // Calculate angle of incidence
@@ -606,15 +1430,21 @@ impl GameState {
                                 let refracted_x = ratio * dir_x + (ratio * cos_incident - cos_refracted) * normal_x;
                                 let refracted_y = ratio * dir_y + (ratio * cos_incident - cos_refracted) * normal_y;
                                 
+                                // Some of the light reflects back into the water
+                                if random() < light::reflectance(cos_incident, N_WATER, N_AIR) {
+                                    let (reflect_x, reflect_y) = light::reflect(dir_x, dir_y, normal_x, normal_y);
+                                    let share = ray.intensity * light::REFLECTED_SHARE;
+                                    ray.intensity -= share;
+                                    reflected_rays.push(LightRay { x: prev_x, y: prev_y, vx: reflect_x * speed, vy: reflect_y * speed, intensity: share, ..ray.clone() });
+                                }
+                                
                                 // Apply refraction and speed up (light speeds up in air)
-                                let refracted_speed = speed * 1.33; // Restore original speed
+                                let refracted_speed = speed * N_WATER / N_AIR; // Restore original speed
                                 ray.vx = refracted_x * refracted_speed;
                                 ray.vy = refracted_y * refracted_speed;
                             } else {
                                 // Total internal reflection - bounce back into water
-                                // Reflect across the normal
-                                let reflect_x = dir_x - 2.0 * (dir_x * normal_x) * normal_x;
-                                let reflect_y = dir_y - 2.0 * (dir_y * normal_y) * normal_y;
+                                let (reflect_x, reflect_y) = light::reflect(dir_x, dir_y, normal_x, normal_y);
                                 ray.vx = reflect_x * speed;
                                 ray.vy = reflect_y * speed;
                                 ray.intensity *= 0.95; // Small energy loss on reflection
@@ -627,10 +1457,8 @@ impl GameState {
                         // Check if ray is entering water from air by looking at previous position
                         let prev_x = ray.x - ray.vx * dt;
                         let prev_y = ray.y - ray.vy * dt;
-                        let prev_tile_x = (prev_x / TILE_SIZE_PIXELS).floor() as usize;
-                        let prev_tile_y = (prev_y / TILE_SIZE_PIXELS).floor() as usize;
                         
-                        let entering_water = if let Some(prev_tile) = self.tile_map.get_tile(prev_tile_x, prev_tile_y) {
+                        let entering_water = if let Some(prev_tile) = self.tile_map.get_tile_at_pixel(prev_x, prev_y) {
                             prev_tile.tile_type != TileType::Water
                         } else {
                             true // Coming from outside bounds, consider as entering
@@ -639,9 +1467,8 @@ impl GameState {
                         if entering_water {
                             // Apply refraction using Snell's law
                             // n1 * sin(θ1) = n2 * sin(θ2)
-                            // Where n1 = 1.0 (air), n2 = 1.33 (water)
-                            const N_AIR: f64 = 1.0;
-                            const N_WATER: f64 = 1.33;
+                            // Where n1 = N_AIR, n2 = N_WATER
+                            use light::{N_AIR, N_WATER};
                             
                             // Calculate the normal to the surface at entry point
                             // For simplicity, assume surface normal depends on entry direction
@@ -700,8 +1527,16 @@ impl GameState {
                                 let refracted_x = ratio * dir_x + (ratio * cos_incident - cos_refracted) * normal_x;
                                 let refracted_y = ratio * dir_y + (ratio * cos_incident - cos_refracted) * normal_y;
                                 
+                                // Some of the light glances off the surface
+                                if random() < light::reflectance(cos_incident, N_AIR, N_WATER) {
+                                    let (reflect_x, reflect_y) = light::reflect(dir_x, dir_y, normal_x, normal_y);
+                                    let share = ray.intensity * light::REFLECTED_SHARE;
+                                    ray.intensity -= share;
+                                    reflected_rays.push(LightRay { x: prev_x, y: prev_y, vx: reflect_x * speed, vy: reflect_y * speed, intensity: share, ..ray.clone() });
+                                }
+                                
                                 // Apply refraction
-                                let refracted_speed = speed * 0.75; // Light slows down in water
+                                let refracted_speed = speed * N_AIR / N_WATER; // Light slows down in water
                                 ray.vx = refracted_x * refracted_speed;
                                 ray.vy = refracted_y * refracted_speed;
                             }
@@ -715,7 +1550,7 @@ impl GameState {
                             rays_to_remove.push(i);
                         }
                     },
-                    TileType::Dirt | TileType::Stone | TileType::Foliage => {
+                    TileType::Dirt | TileType::Stone | TileType::Foliage | TileType::Ore | TileType::Wood | TileType::Ice | TileType::Snow | TileType::Mud | TileType::Quicksand | TileType::Furnace | TileType::PitCover | TileType::ReinforcedWall => {
                         // Solid tiles always reflect light at random direction
                         let angle = random() * 2.0 * std::f64::consts::PI;
                         let speed = (ray.vx * ray.vx + ray.vy * ray.vy).sqrt();
@@ -736,477 +1571,2816 @@ impl GameState {
         for &i in rays_to_remove.iter().rev() {
             self.light_rays.remove(i);
         }
+        
+        let room = self.light_budget().max_rays.saturating_sub(self.light_rays.len());
+        self.light_rays.extend(reflected_rays.into_iter().take(room));
     }
     
     // Get compact representation for rendering
-    pub fn get_state_data(&self) -> String {
-        let mut data = Vec::new();
-        
-        for promiser in self.promisers.values() {
-            data.push(format!(
-                "{{\"id\":{},\"x\":{:.2},\"y\":{:.2},\"size\":{:.2},\"color\":{},\"state\":{},\"thought\":\"{}\",\"target_id\":{},\"is_pixel\":{}}}",
-                promiser.id,
-                promiser.x,
-                promiser.y,
-                promiser.size,
-                promiser.color,
-                promiser.state,
-                promiser.thought.replace("\"", "\\\""), // Escape quotes
-                promiser.target_id,
-                promiser.is_pixel
-            ));
-        }
-        
-        // Serialize tile map manually to JSON
-        let tile_map_json = serde_json::to_string(&self.tile_map)
-            .unwrap_or_else(|_| "null".to_string());
-        
-        // Serialize light rays
-        let mut light_ray_data = Vec::new();
-        for ray in &self.light_rays {
-            light_ray_data.push(format!(
-                "{{\"x\":{:.2},\"y\":{:.2},\"vx\":{:.2},\"vy\":{:.2},\"intensity\":{:.2}}}",
-                ray.x, ray.y, ray.vx, ray.vy, ray.intensity
-            ));
-        }
-        
-        format!("{{\"promisers\":[{}],\"tile_map\":{},\"light_rays\":[{}]}}", 
-                data.join(","), tile_map_json, light_ray_data.join(","))
+    // The tick number lets faster renderers interpolate between simulation steps
+    // Written through the reused export buffer (see export.rs), so only the
+    // returned String is allocated
+    pub fn get_state_data(&mut self) -> String {
+        self.export_state();
+        String::from_utf8(self.export.buffer.clone()).unwrap_or_default()
     }
-    
-    #[wasm_bindgen(getter)]
-    pub fn promiser_count(&self) -> usize {
-        self.promisers.len()
+
+    /// Write get_state_data's JSON into the reused export buffer (see
+    /// state_export_ptr) and return its length in bytes. Allocates nothing
+    /// once the buffer has grown to fit.
+    pub fn export_state(&mut self) -> usize {
+        let mut export = std::mem::take(&mut self.export);
+        self.write_state(&mut export);
+        self.export = export;
+        self.export.buffer.len()
     }
-    
-    #[wasm_bindgen(getter)]
-    pub fn tile_map(&self) -> JsValue {
-        // Serialize the tile map to JsValue for JS interop
-        serde_wasm_bindgen::to_value(&self.tile_map).unwrap()
+
+    /// Pointer to the last export_state output (UTF-8 JSON)
+    pub fn state_export_ptr(&self) -> *const u8 {
+        self.export.buffer.as_ptr()
+    }
+
+    /// 32-bit hash of the tick, promiser positions and velocities and the
+    /// overworld tiles, for lockstep peers to compare (see fixed.rs)
+    pub fn state_checksum(&self) -> u32 {
+        self.checksum()
+    }
+    
+    /// Same state as get_state_data, encoded as MessagePack
+    pub fn get_state_msgpack(&self) -> Vec<u8> {
+        match self.state_msgpack() {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                console_log!(Error, Save, "Failed to encode state as MessagePack: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Promisers that moved or changed since the last call, plus removed ids
+    pub fn get_changed_promisers(&mut self) -> String {
+        self.promiser_delta.collect(&self.promisers, self.tick_count, self.export_meta)
+    }
+
+    /// Make the next get_changed_promisers call return every promiser
+    pub fn reset_changed_promisers(&mut self) {
+        self.promiser_delta.reset();
+    }
+
+    /// Make a promiser light up the tiles around it (intensity 0.0 to 1.0;
+    /// 0 turns it off). The color is only passed on to renderers.
+    pub fn set_promiser_glow(&mut self, id: u32, intensity: f64, color: u32) -> bool {
+        let Some(promiser) = self.promisers.get_mut(&id) else {
+            return false;
+        };
+        promiser.glow = intensity.clamp(0.0, 1.0);
+        promiser.glow_color = color;
+        true
+    }
+
+    /// Attach a JSON object of custom data to a promiser (an empty string clears
+    /// it). The sim never reads it; it's kept in saves and, with
+    /// set_export_meta(true), included in exports as "meta".
+    pub fn set_promiser_meta(&mut self, id: u32, json: String) -> bool {
+        let Some(promiser) = self.promisers.get_mut(&id) else {
+            return false;
+        };
+        if json.trim().is_empty() {
+            promiser.meta.clear();
+            return true;
+        }
+        if json.len() > MAX_PROMISER_META_BYTES {
+            console_log!(Error, Promisers, "Promiser {} metadata is too large ({} bytes)", id, json.len());
+            return false;
+        }
+        match serde_json::from_str::<serde_json::Value>(&json) {
+            Ok(value) if value.is_object() => {
+                promiser.meta = value.to_string(); // Normalized, single line
+                true
+            }
+            _ => {
+                console_log!(Error, Promisers, "Promiser {} metadata must be a JSON object", id);
+                false
+            }
+        }
+    }
+
+    /// The promiser's metadata JSON, or an empty string if it has none
+    pub fn get_promiser_meta(&self, id: u32) -> String {
+        self.promisers.get(&id).map(|promiser| promiser.meta.clone()).unwrap_or_default()
+    }
+
+    /// Include promiser metadata in get_state_data and get_changed_promisers
+    pub fn set_export_meta(&mut self, enabled: bool) {
+        self.export_meta = enabled;
+    }
+
+    /// Include every light ray in get_state_data (on by default). Renderers
+    /// that draw from the lightmap can turn this off to save the traffic.
+    pub fn set_export_light_rays(&mut self, enabled: bool) {
+        self.export_light_rays = enabled;
+    }
+
+    /// Move a promiser to pixel position (x, y) in its current dimension. If it
+    /// wouldn't fit there, the nearest open spot is used instead. Velocity is
+    /// reset. Returns false if the id is unknown or no open spot was found.
+    pub fn teleport_promiser(&mut self, id: u32, x: f64, y: f64) -> bool {
+        let Some(promiser) = self.promisers.get_mut(&id) else {
+            return false;
+        };
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else {
+            return false;
+        };
+        let Some((x, y)) = promiser.find_open_spot(x, y, map) else {
+            console_log!(Error, Promisers, "No room to teleport promiser {} near ({:.0}, {:.0})", id, x, y);
+            return false;
+        };
+        promiser.x = x;
+        promiser.y = y;
+        promiser.vx = 0.0;
+        promiser.vy = 0.0;
+        self.spatial.insert(promiser);
+        true
+    }
+
+    /// Add (fx, fy) to a promiser's velocity (the usual speed limits still apply)
+    pub fn apply_impulse(&mut self, id: u32, fx: f64, fy: f64) -> bool {
+        let Some(promiser) = self.promisers.get_mut(&id) else {
+            return false;
+        };
+        promiser.vx += fx;
+        promiser.vy += fy;
+        true
+    }
+
+    /// Make a promiser keep within `distance` pixels of another promiser until
+    /// stopped or the target is removed
+    pub fn make_promiser_follow(&mut self, id: u32, target_id: u32, distance: f64) -> bool {
+        self.set_steering(id, Steering::Follow { target: target_id, distance: distance.max(0.0) })
+    }
+
+    /// Make a promiser run away from another promiser until stopped or the
+    /// target is removed
+    pub fn make_promiser_flee(&mut self, id: u32, target_id: u32) -> bool {
+        self.set_steering(id, Steering::Flee { target: target_id })
+    }
+
+    /// Cancel follow/flee
+    pub fn stop_promiser_steering(&mut self, id: u32) -> bool {
+        self.set_steering(id, Steering::None)
+    }
+
+    fn set_steering(&mut self, id: u32, steering: Steering) -> bool {
+        if steering.target().is_some_and(|target| target == id || !self.promisers.contains_key(&target)) {
+            return false;
+        }
+        if let Some(promiser) = self.promisers.get_mut(&id) {
+            promiser.steering = steering;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take direct control of Pixel: `move_x` from -1.0 (left) to 1.0
+    /// (right), and `jump` to jump if standing on something. While controlled,
+    /// Pixel stops wandering and isn't given jobs.
+    pub fn set_pixel_input(&mut self, move_x: f64, jump: bool) {
+        let Some(id) = self.pixel_id() else { return };
+        if !self.promisers[&id].controlled {
+            self.abandon_task(id);
+            let pixel = self.promisers.get_mut(&id).unwrap();
+            pixel.controlled = true;
+            pixel.steering = Steering::None;
+            pixel.leader = None;
+        }
+        self.player.move_x = move_x;
+        self.player.jump |= jump;
+    }
+
+    /// Hand Pixel back to the simulation
+    pub fn release_pixel(&mut self) {
+        if let Some(pixel) = self.pixel_id().and_then(|id| self.promisers.get_mut(&id)) {
+            pixel.controlled = false;
+        }
+        self.player.move_x = 0.0;
+        self.player.jump = false;
+    }
+
+    /// Start (or keep) Pixel digging at the tile at (x, y), if it's within
+    /// reach and can be dug. It breaks after its hardness in seconds of
+    /// digging (see mining.rs), going into Pixel's inventory.
+    pub fn pixel_break_tile(&mut self, x: i32, y: i32) -> bool {
+        self.player_break_tile(x, y)
+    }
+
+    /// Stop Pixel digging; the tile keeps the progress made on it
+    pub fn pixel_stop_breaking(&mut self) {
+        self.player.mining = None;
+    }
+
+    /// Fill Pixel's bucket from a water tile within reach, if it has a
+    /// bucket. Returns the water taken (a full tile is 1024).
+    pub fn pixel_scoop_water(&mut self, x: i32, y: i32) -> u32 {
+        self.player_scoop_water(x, y) as u32
+    }
+
+    /// Pour Pixel's bucket into an air or water tile within reach. Returns
+    /// the water poured; what doesn't fit stays in the bucket.
+    pub fn pixel_pour_water(&mut self, x: i32, y: i32) -> u32 {
+        self.player_pour_water(x, y) as u32
+    }
+
+    /// Water in Pixel's bucket (a full tile is 1024)
+    pub fn get_pixel_bucket(&self) -> u32 {
+        self.player.bucket_water as u32
+    }
+
+    /// Give a promiser a tool ("shovel", "pick" or "bucket") from an item
+    /// stack in its dimension; the tool it held before is dropped. Returns
+    /// false if the item isn't a tool.
+    pub fn equip_tool(&mut self, promiser_id: u32, item_id: u32) -> bool {
+        self.give_tool(promiser_id, item_id)
+    }
+
+    /// Place an item kind from Pixel's inventory as a tile at (x, y), if it's
+    /// an air tile within reach
+    pub fn pixel_place_tile(&mut self, x: i32, y: i32, kind: String) -> bool {
+        ItemKind::from_name(&kind).is_some_and(|kind| self.player_place_tile(x, y, kind))
+    }
+
+    /// Pick up every loose item within Pixel's reach. Returns the item count.
+    pub fn pixel_pick_up(&mut self) -> u32 {
+        self.player_pick_up()
+    }
+
+    /// Pixel's inventory as JSON [{"kind": "stone", "count": 3}, ...]
+    pub fn get_pixel_inventory(&self) -> String {
+        serde_json::to_string(&self.player.inventory).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Create a named group; `accent` is an RGB color renderers can draw
+    /// alongside members' own colors. Returns the group id.
+    pub fn create_group(&mut self, name: String, accent: u32) -> u32 {
+        let id = self.next_group_id;
+        self.next_group_id += 1;
+        self.groups.push(Group { id, name, accent });
+        id
+    }
+
+    /// Disband a group; its members become ungrouped
+    pub fn remove_group(&mut self, group_id: u32) -> bool {
+        let before = self.groups.len();
+        self.groups.retain(|group| group.id != group_id);
+        if self.groups.len() == before {
+            return false;
+        }
+        for promiser in self.promisers.values_mut().filter(|p| p.group == group_id) {
+            promiser.group = 0;
+        }
+        self.affinities.remove_group(group_id);
+        true
+    }
+
+    /// Put a promiser in a group (0 to take it out of its group)
+    pub fn set_promiser_group(&mut self, id: u32, group_id: u32) -> bool {
+        if group_id != 0 && !self.groups.iter().any(|group| group.id == group_id) {
+            return false;
+        }
+        match self.promisers.get_mut(&id) {
+            Some(promiser) => {
+                promiser.group = group_id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Groups with their member ids as JSON
+    pub fn get_groups(&self) -> String {
+        let groups: Vec<serde_json::Value> = self.groups.iter()
+            .map(|group| {
+                let mut members = self.group_members(group.id);
+                members.sort_unstable();
+                serde_json::json!({ "id": group.id, "name": group.name, "accent": group.accent, "members": members })
+            })
+            .collect();
+        serde_json::to_string(&groups).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// How two groups feel about each other, -1.0 (avoid) to 1.0 (seek out).
+    /// Members drift toward or away from nearby members of the other group.
+    pub fn set_group_affinity(&mut self, group_a: u32, group_b: u32, affinity: f64) {
+        self.affinities.set(group_a, group_b, affinity);
+    }
+
+    /// Affinities that have been set, as JSON [[group_a, group_b, affinity], ...]
+    pub fn get_group_affinities(&self) -> String {
+        self.affinities.to_json()
+    }
+
+    /// Send every member of a group to a pixel position, dropping their current
+    /// jobs and follow/flee orders. Returns how many were sent.
+    pub fn move_group_to(&mut self, group_id: u32, x: f64, y: f64) -> u32 {
+        let members = self.group_members(group_id);
+        for &id in &members {
+            self.abandon_task(id);
+            let promiser = self.promisers.get_mut(&id).unwrap();
+            promiser.steering = Steering::None;
+            promiser.task = Some(Task::MoveTo { x, y, elapsed: 0.0 });
+        }
+        members.len() as u32
+    }
+
+    /// Make every member of a group follow a promiser (e.g. Pixel). Returns
+    /// how many members were given the order.
+    pub fn group_follow(&mut self, group_id: u32, target_id: u32, distance: f64) -> u32 {
+        self.group_members(group_id).into_iter()
+            .filter(|&id| self.make_promiser_follow(id, target_id, distance))
+            .count() as u32
+    }
+
+    fn group_members(&self, group_id: u32) -> Vec<u32> {
+        if group_id == 0 {
+            return Vec::new(); // Not a group; means "ungrouped"
+        }
+        self.promisers.values().filter(|p| p.group == group_id).map(|p| p.id).collect()
+    }
+
+    /// Fraction (0.0 to 1.0) of the overworld's memory cells that at least one
+    /// promiser has visited
+    pub fn get_exploration_coverage(&self) -> f64 {
+        let cells_wide = self.tile_map.width.div_ceil(memory::CELL_TILES);
+        let cells_high = self.tile_map.height.div_ceil(memory::CELL_TILES);
+        let total = cells_wide * cells_high;
+        if total == 0 {
+            return 0.0;
+        }
+        let visited: HashSet<(i32, i32)> = self.promisers.values()
+            .flat_map(|promiser| promiser.visited.cells_in(OVERWORLD))
+            .filter(|&(cx, cy)| (0..cells_wide as i32).contains(&cx) && (0..cells_high as i32).contains(&cy))
+            .collect();
+        visited.len() as f64 / total as f64
+    }
+
+    /// Everything about one promiser as a JS object, or null if there's no such id
+    pub fn get_promiser(&self, id: u32) -> JsValue {
+        self.promisers.get(&id)
+            .map(|promiser| serde_wasm_bindgen::to_value(promiser).unwrap())
+            .unwrap_or(JsValue::NULL)
+    }
+
+    /// Ids of overworld promisers whose center is inside the rectangle
+    /// (x, y, w, h) in pixels, sorted
+    pub fn get_promisers_in_rect(&self, x: f64, y: f64, w: f64, h: f64) -> Vec<u32> {
+        self.spatial.query_rect(&self.promisers, OVERWORLD, x, y, w, h)
+    }
+
+    /// Ids of overworld promisers within `radius` pixels of (x, y), sorted
+    pub fn get_promisers_near(&self, x: f64, y: f64, radius: f64) -> Vec<u32> {
+        self.spatial.query_radius(&self.promisers, OVERWORLD, x, y, radius)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn promiser_count(&self) -> usize {
+        self.promisers.len()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn tile_map(&self) -> JsValue {
+        // Serialize the tile map to JsValue for JS interop
+        serde_wasm_bindgen::to_value(&self.tile_map).unwrap()
     }
     
     pub fn make_promiser_think(&mut self, id: u32) {
         if let Some(promiser) = self.promisers.get_mut(&id) {
-            promiser.state = 1; // Thinking
+            promiser.state = ai::THINKING;
+            promiser.state_timer = 0.0;
+        }
+    }
+    
+    /// `emote` and `duration` (seconds) are optional; without a duration the
+    /// speech lasts 3-5 seconds
+    pub fn make_promiser_speak(&mut self, id: u32, thought: String, emote: Option<String>, duration: Option<f64>) {
+        let tick = self.tick_count;
+        if let Some(promiser) = self.promisers.get_mut(&id) {
+            speech::remember(&mut promiser.speech_history, SpeechEntry {
+                tick,
+                text: thought.clone(),
+                emote: emote.clone(),
+                target_id: None,
+            });
+            promiser.set_thought(thought.clone());
+            promiser.emote = emote.unwrap_or_default();
+            promiser.speech_duration = duration.unwrap_or(0.0).max(0.0);
+            self.broadcast_speech(id, thought, None);
+        }
+    }
+    
+    /// Like make_promiser_speak, but aimed at `target_id` (default 1-2 seconds)
+    pub fn make_promiser_whisper(&mut self, id: u32, thought: String, target_id: u32, emote: Option<String>, duration: Option<f64>) {
+        let tick = self.tick_count;
+        if let Some(promiser) = self.promisers.get_mut(&id) {
+            speech::remember(&mut promiser.speech_history, SpeechEntry {
+                tick,
+                text: thought.clone(),
+                emote: emote.clone(),
+                target_id: Some(target_id),
+            });
+            promiser.set_whisper(thought.clone(), target_id);
+            promiser.emote = emote.unwrap_or_default();
+            promiser.speech_duration = duration.unwrap_or(0.0).max(0.0);
+            self.broadcast_speech(id, thought, Some(target_id));
+        }
+    }
+    
+    pub fn make_promiser_run(&mut self, id: u32) {
+        if let Some(promiser) = self.promisers.get_mut(&id) {
+            promiser.state = ai::RUNNING;
             promiser.state_timer = 0.0;
         }
     }
-    
-    pub fn make_promiser_speak(&mut self, id: u32, thought: String) {
-        if let Some(promiser) = self.promisers.get_mut(&id) {
-            promiser.set_thought(thought);
+
+    // Tile manipulation methods
+    /// `shape` is optional: "full" (default), "half", "slope_right" or
+    /// "slope_left". Only solid tiles take a shape.
+    pub fn place_tile(&mut self, x: i32, y: i32, tile_type: String, shape: Option<String>) {
+        let tile_type_enum = TileType::from_name(&tile_type).unwrap_or(TileType::Air); // Default to Air for unknown types
+        let shape = shape.as_deref().and_then(TileShape::from_name).unwrap_or_default();
+        
+        let new_tile = Tile {
+            tile_type: tile_type_enum,
+            water_amount: if matches!(tile_type_enum, TileType::Water) { MAX_WATER_AMOUNT } else { 0 },
+            variant: if Promiser::is_solid_tile(tile_type_enum) { shape.variant() } else { 0 },
+        };
+        
+        self.tile_map.set_tile(x, y, new_tile);
+        console_log!(Debug, Tiles, "Placed {} tile at ({}, {})", tile_type, x, y);
+    }
+
+    /// Dig out an overworld tile, leaving air and dropping what it was made
+    /// of as an item. Returns false if there was nothing to dig.
+    pub fn dig_tile(&mut self, x: i32, y: i32) -> bool {
+        let Some(kind) = self.tile_map.get_tile(x, y).and_then(|tile| ItemKind::from_tile(tile.tile_type)) else {
+            return false;
+        };
+        self.tile_map.set_tile(x, y, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 });
+        let center_x = (x as f64 + 0.5) * TILE_SIZE_PIXELS;
+        let center_y = (y as f64 + 0.5) * TILE_SIZE_PIXELS;
+        self.add_item(kind, 1, center_x, center_y, OVERWORLD);
+        true
+    }
+
+    /// Take all the water out of an overworld tile, for the UI's bucket to
+    /// hold until poured. Returns the amount (a full tile is 1024).
+    pub fn scoop_water(&mut self, x: i32, y: i32) -> u32 {
+        self.tile_map.take_water(x, y, tools::BUCKET_CAPACITY) as u32
+    }
+
+    /// Pour up to `amount` water into an overworld air or water tile.
+    /// Returns how much went in; the rest is the caller's to keep, so no
+    /// water is made or lost.
+    pub fn pour_water(&mut self, x: i32, y: i32, amount: u32) -> u32 {
+        self.tile_map.add_water(x, y, amount.min(MAX_WATER_AMOUNT as u32) as u16) as u32
+    }
+
+    /// Send a promiser to dig out the tile at (x, y) in its dimension, which
+    /// takes the tile's hardness in seconds (see mining.rs) and drops what it
+    /// was made of. Returns false if there's no such promiser or nothing to dig.
+    /// Ore needs a pick (see tools.rs).
+    pub fn order_dig(&mut self, id: u32, x: i32, y: i32) -> bool {
+        let Some((dim, tool)) = self.promisers.get(&id).map(|promiser| (promiser.dimension, promiser.tool)) else { return false };
+        let diggable = dimension::dimension_map(&self.tile_map, &self.dimensions, dim)
+            .filter(|map| map.can_mine(x, y))
+            .and_then(|map| map.get_tile(x, y))
+            .is_some_and(|tile| tools::dig_speed(tool, tile.tile_type) > 0.0);
+        if !diggable {
+            return false;
+        }
+        self.abandon_task(id);
+        let promiser = self.promisers.get_mut(&id).unwrap();
+        promiser.steering = Steering::None;
+        promiser.task = Some(Task::Dig { x, y, elapsed: 0.0 });
+        true
+    }
+
+    /// Drop `count` items of a kind ("dirt", "stone", "wood", "bricks", ...;
+    /// see items.rs) at an overworld pixel position. Returns the item id, or u32::MAX for an
+    /// unknown kind.
+    pub fn drop_item(&mut self, kind: String, x: f64, y: f64, count: u32) -> u32 {
+        match ItemKind::from_name(&kind) {
+            Some(kind) if count > 0 => self.add_item(kind, count, x, y, OVERWORLD),
+            _ => u32::MAX,
+        }
+    }
+
+    /// All loose and stored items as a JSON array
+    pub fn get_items(&self) -> String {
+        let mut items: Vec<Item> = self.ecs.items().collect();
+        items.sort_by_key(|item| item.id);
+        serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Put a creature ("fish", "bird", "rabbit", "mole" or "wolf") in the overworld at pixel
+    /// (x, y). Returns its id, or u32::MAX for an unknown kind or a hostile
+    /// one in peaceful mode.
+    pub fn spawn_creature(&mut self, kind: String, x: f64, y: f64) -> u32 {
+        match serde_json::from_value::<CreatureKind>(serde_json::Value::String(kind)) {
+            Ok(kind) if kind.menace() > 0.0 && self.config.difficulty == Difficulty::Peaceful => u32::MAX,
+            Ok(kind) => self.add_creature(kind, x, y, OVERWORLD),
+            Err(_) => u32::MAX,
+        }
+    }
+
+    /// Remove every entity of a class ("items", "creatures" or "promisers"),
+    /// creature kind ("fish", ...) or item kind ("stone", ...). Returns how
+    /// many were removed.
+    pub fn remove_all(&mut self, kind: String) -> u32 {
+        self.remove_entities(&kind)
+    }
+
+    /// Set the collision layers a promiser is on and the ones it collides
+    /// with (bit flags, see collision.rs). Returns false for an unknown id.
+    pub fn set_collision_filter(&mut self, id: u32, layer: u8, mask: u8) -> bool {
+        let Some(promiser) = self.promisers.get_mut(&id) else { return false };
+        promiser.collision = CollisionFilter { layer, mask };
+        true
+    }
+
+    /// All creatures as JSON [{"id", "kind", "x", "y", "vx", "vy", "dimension"}, ...];
+    /// pets also have "owner" and, if loaded, "carrying", and birds resting
+    /// in a tree "perched": true
+    pub fn get_creatures(&self) -> String {
+        self.creatures_json()
+    }
+
+    /// Have a promiser within reach feed a creature something it eats from
+    /// what it carries (Pixel also from its inventory); enough meals tame
+    /// rabbits and birds (see pets.rs). Returns false if nothing was fed.
+    pub fn feed_creature(&mut self, promiser_id: u32, creature_id: u32) -> bool {
+        self.feed_to_creature(promiser_id, creature_id)
+    }
+
+    /// Load a pet with items from a loose stack in its dimension. Returns
+    /// how many it took (rabbits carry 2, birds 1).
+    pub fn give_to_pet(&mut self, creature_id: u32, item_id: u32) -> u32 {
+        self.load_pet(creature_id, item_id)
+    }
+
+    /// Have a pet set down what it carries. Returns false if it had nothing.
+    pub fn drop_pet_item(&mut self, creature_id: u32) -> bool {
+        self.unload_pet(creature_id)
+    }
+
+    /// Have a promiser within reach catch a fish as a food item, into what it
+    /// carries (Pixel's inventory). Returns false if it couldn't.
+    pub fn catch_fish(&mut self, promiser_id: u32, creature_id: u32) -> bool {
+        self.catch(promiser_id, creature_id)
+    }
+
+    /// The region (connected open area) containing overworld tile (x, y) as
+    /// JSON {"id", "size", "open_to_sky"}, or null for solid tiles
+    pub fn get_region_at(&self, x: i32, y: i32) -> String {
+        serde_json::to_string(&self.tile_map.region_at(x, y)).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Tell the simulation a camera (or anything else keeping an eye on the
+    /// world) with this id is looking at an overworld rectangle (pixels,
+    /// bottom-left corner x, y). Once any view is set, the world far from
+    /// every view is simulated in less detail (see lod.rs).
+    pub fn set_view(&mut self, id: u32, x: f64, y: f64, width: f64, height: f64) {
+        self.lod.set_view(id, View { x, y, width, height });
+    }
+
+    /// Forget a view; with none left, the whole world is simulated in full
+    pub fn remove_view(&mut self, id: u32) -> bool {
+        self.lod.remove_view(id)
+    }
+
+    /// Steps from every overworld tile to tile (x, y) for a walking promiser
+    /// (row-major, 65535 = can't get there). Promisers heading for the same
+    /// tile share the field, which is recomputed when the terrain changes.
+    pub fn request_flow_field(&mut self, x: i32, y: i32) -> Vec<u16> {
+        self.flow_fields.get(&mut self.tile_map, OVERWORLD, (x, y), self.tick_count).distance.clone()
+    }
+
+    /// Mark a rectangle of overworld tiles (bottom-left corner x, y) as a
+    /// stockpile. Idle promisers haul loose items into it. Returns its id.
+    pub fn add_stockpile(&mut self, x: i32, y: i32, width: i32, height: i32) -> u32 {
+        let id = self.next_stockpile_id;
+        self.next_stockpile_id += 1;
+        self.stockpiles.push(Stockpile { id, dimension: OVERWORLD, x, y, width: width.max(1), height: height.max(1) });
+        id
+    }
+
+    /// Remove a stockpile; items already in it stay where they are
+    pub fn remove_stockpile(&mut self, id: u32) -> bool {
+        let before = self.stockpiles.len();
+        self.stockpiles.retain(|stockpile| stockpile.id != id);
+        self.stockpiles.len() != before
+    }
+
+    pub fn get_stockpiles(&self) -> String {
+        serde_json::to_string(&self.stockpiles).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Lay out a schematic (rows of ASCII art, see schematic.rs) as ghost
+    /// tiles with its bottom-left corner at overworld tile (x, y). Idle
+    /// promisers fetch a matching item for each ghost and build it. Returns
+    /// the blueprint id, or u32::MAX if the schematic has nothing to build.
+    pub fn place_blueprint(&mut self, schematic: String, x: i32, y: i32) -> u32 {
+        let rows: Vec<&str> = schematic.lines().collect();
+        let schematic = Schematic::from_rows(&rows);
+        let blueprint = Blueprint::new(self.next_blueprint_id, OVERWORLD, &schematic, x, y);
+        if blueprint.total == 0 {
+            return u32::MAX;
+        }
+        self.next_blueprint_id += 1;
+        let id = blueprint.id;
+        self.blueprints.push(blueprint);
+        id
+    }
+
+    /// Drop a blueprint; tiles already built stay
+    pub fn cancel_blueprint(&mut self, id: u32) -> bool {
+        let before = self.blueprints.len();
+        self.blueprints.retain(|blueprint| blueprint.id != id);
+        self.blueprints.len() != before
+    }
+
+    /// Track an objective, e.g. {"type": "foliage", "count": 50, "label":
+    /// "Grow a forest", "time_limit": 600}; types are "foliage" (count),
+    /// "survive" (seconds), "storm" and "depth" (tiles below the surface), see
+    /// objectives.rs. Returns its id, or u32::MAX for malformed JSON.
+    pub fn add_objective(&mut self, json: String) -> u32 {
+        match serde_json::from_str::<ObjectiveSpec>(&json) {
+            Ok(spec) => self.set_objective(spec),
+            Err(err) => {
+                console_log!(Error, Core, "Invalid objective: {}", err);
+                u32::MAX
+            }
+        }
+    }
+
+    /// Stop tracking an objective, finished or not
+    pub fn remove_objective(&mut self, id: u32) -> bool {
+        let before = self.objectives.len();
+        self.objectives.retain(|objective| objective.id != id);
+        self.objectives.len() != before
+    }
+
+    /// Objectives with their progress and status ("active", "completed" or
+    /// "failed") as JSON
+    pub fn get_objectives(&self) -> String {
+        serde_json::to_string(&self.objectives).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Make a promiser fall ill (e.g. to start an outbreak). Returns false if
+    /// it's already sick or doesn't exist.
+    pub fn infect_promiser(&mut self, id: u32) -> bool {
+        match self.promisers.get_mut(&id) {
+            Some(promiser) if !promiser.is_sick() => {
+                promiser.fall_ill();
+                self.events.push(SimEvent::FellIll { id, source: "infected".to_string() });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Set the overworld quarantine zone (tile rect, bottom-left x, y). Sick
+    /// promisers walk there and stay until they recover. A zero width or
+    /// height clears it.
+    pub fn set_quarantine_zone(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.quarantine = (width > 0 && height > 0).then_some(QuarantineZone { dimension: OVERWORLD, x, y, width, height });
+    }
+
+    /// Start a scripted sequence (a JSON array of actions, see sequence.rs).
+    /// It runs over the following ticks and emits sequence_complete when done.
+    /// Returns the sequence id, or u32::MAX if the JSON is invalid.
+    pub fn play_sequence(&mut self, json: String) -> u32 {
+        let id = self.next_sequence_id;
+        match Sequence::from_json(id, &json) {
+            Ok(sequence) => {
+                self.next_sequence_id += 1;
+                self.sequences.push(sequence);
+                id
+            }
+            Err(err) => {
+                console_log!(Error, Core, "Invalid sequence: {}", err);
+                u32::MAX
+            }
+        }
+    }
+
+    /// Turn the director on (0.0 to 1.0; higher steps in sooner after the
+    /// world goes quiet) or off (0.0)
+    pub fn set_director_intensity(&mut self, intensity: f64) {
+        self.director.intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    /// Wind speed in pixels per second (positive blows right), which carries
+    /// birds in flight along
+    pub fn get_wind(&self) -> f64 {
+        self.wind
+    }
+
+    /// Set the wind speed (clamped to ±60 px/s); it keeps gusting and
+    /// settling from there
+    pub fn set_wind(&mut self, speed: f64) {
+        self.set_wind_speed(speed);
+    }
+
+    /// Jump to a time of day (0.0 is midnight, 0.25 sunrise, 0.5 noon, 0.75
+    /// sunset); a day_seconds of 0 also stops the clock
+    pub fn set_time_of_day(&mut self, time: f64, day_seconds: Option<f64>) {
+        self.sun.time = time.rem_euclid(1.0);
+        if let Some(day_seconds) = day_seconds {
+            self.sun.day_seconds = day_seconds.max(0.0);
+        }
+    }
+
+    /// Change a fluid's parameters from JSON, e.g. `{"viscosity": 0.8}` (see
+    /// fluids.rs). Returns false for an unknown tile type, one that isn't a
+    /// fluid, or invalid JSON.
+    pub fn set_fluid(&mut self, tile_type: &str, json: &str) -> bool {
+        let Some(tile_type) = TileType::from_name(tile_type) else { return false };
+        match serde_json::from_str::<FluidParams>(json) {
+            Ok(params) => self.fluids.set(tile_type, params),
+            Err(_) => false,
+        }
+    }
+
+    /// Every fluid's parameters as JSON, by tile type name
+    pub fn get_fluids(&self) -> String {
+        self.fluids.to_json()
+    }
+
+    /// Set the brightness floor (0 allows pitch black), and the brightness
+    /// and RGB color of moonlight
+    pub fn set_ambient_light(&mut self, floor: f64, moon: f64, moon_color: u32) {
+        self.ambient = AmbientLight::new(floor, moon, moon_color);
+    }
+
+    /// Register a light emitter from JSON (see emitters.rs). Returns its id,
+    /// or u32::MAX if the JSON is invalid.
+    pub fn add_light_emitter(&mut self, json: String) -> u32 {
+        match serde_json::from_str::<Emitter>(&json) {
+            Ok(mut emitter) => {
+                emitter.id = self.next_emitter_id;
+                self.next_emitter_id += 1;
+                self.emitters.push(emitter);
+                self.next_emitter_id - 1
+            }
+            Err(err) => {
+                console_log!(Error, Core, "Invalid light emitter: {}", err);
+                u32::MAX
+            }
+        }
+    }
+
+    /// Remove an emitter; its rays already in flight carry on. Removing the
+    /// sky (id 1) leaves the world without daylight.
+    pub fn remove_light_emitter(&mut self, id: u32) -> bool {
+        let before = self.emitters.len();
+        self.emitters.retain(|emitter| emitter.id != id);
+        self.emitters.len() != before
+    }
+
+    pub fn get_light_emitters(&self) -> String {
+        serde_json::to_string(&self.emitters).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Flash of light at overworld pixel (x, y): `count` rays (at most
+    /// 2000) flying out in a ring, then bouncing like any other light.
+    /// Returns how many rays were spawned.
+    pub fn spawn_light_burst(&mut self, x: f64, y: f64, count: u32, intensity: f64) -> u32 {
+        self.light_burst(x, y, count, intensity)
+    }
+
+    /// Time of day and sun angle as JSON (see sun.rs)
+    pub fn get_sun(&self) -> String {
+        self.sun.to_json()
+    }
+
+    /// Stop a running sequence where it is
+    pub fn stop_sequence(&mut self, id: u32) -> bool {
+        let before = self.sequences.len();
+        self.sequences.retain(|sequence| sequence.id != id);
+        self.sequences.len() != before
+    }
+
+    /// The world's history so far as a JSON array of {tick, text}
+    pub fn get_chronicle(&self) -> String {
+        self.chronicle.to_json()
+    }
+
+    /// Blueprints with their remaining ghost tiles as JSON
+    pub fn get_blueprints(&self) -> String {
+        serde_json::to_string(&self.blueprints).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Make a recipe (see get_recipes) from items stored in a stockpile; the
+    /// result is set down in the same stockpile. Returns false if the recipe
+    /// is unknown or the stockpile doesn't have the inputs.
+    pub fn craft(&mut self, recipe: String, stockpile_id: u32) -> bool {
+        let Some(recipe) = crafting::find(&recipe) else {
+            return false;
+        };
+        let Some((x, y, dim)) = self.stockpiles.iter()
+            .find(|stockpile| stockpile.id == stockpile_id)
+            .map(|stockpile| {
+                let (x, y) = stockpile.drop_spot();
+                (x, y, stockpile.dimension)
+            })
+        else {
+            return false;
+        };
+        if recipe.inputs.iter().any(|&(kind, count)| self.stockpile_count(stockpile_id, kind) < count) {
+            return false;
+        }
+
+        for &(kind, count) in recipe.inputs {
+            self.take_from_stockpile(stockpile_id, kind, count);
+        }
+        let (kind, count) = recipe.output;
+        self.add_item(kind, count, x, y, dim);
+        self.events.push(SimEvent::Crafted { recipe: recipe.name.to_string(), stockpile: stockpile_id });
+        true
+    }
+
+    pub fn get_recipes(&self) -> String {
+        serde_json::to_string(crafting::RECIPES).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Feed a whole overworld item stack into the furnace tile at (x, y):
+    /// wood and plants burn, ore and dirt get cooked (see furnace.rs).
+    /// Returns false if it isn't a furnace or the stack can't go in.
+    pub fn feed_furnace(&mut self, item_id: u32, x: i32, y: i32) -> bool {
+        self.load_furnace(OVERWORLD, x, y, item_id)
+    }
+
+    /// Every furnace that's been fed, as JSON [{"dimension", "x", "y",
+    /// "fuel", "contents", "progress"}, ...] (fuel in seconds left)
+    pub fn get_furnaces(&self) -> String {
+        serde_json::to_string(&self.furnaces).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Use one item from an overworld stack to build its tile at (x, y),
+    /// e.g. a platform item becomes a half wood tile. The spot must be air.
+    pub fn place_item(&mut self, item_id: u32, x: i32, y: i32) -> bool {
+        let Some(item) = self.ecs.item(item_id).filter(|item| item.dimension == OVERWORLD) else {
+            return false;
+        };
+        if self.tile_map.get_tile(x, y).is_none_or(|tile| tile.tile_type != TileType::Air) {
+            return false;
+        }
+        let Some(tile) = item.kind.tile() else { return false };
+        self.tile_map.set_tile(x, y, tile);
+        self.ecs.take_item(item_id, 1);
+        true
+    }
+
+    /// Every tile type an editor can offer, as JSON [{"id": 1, "name": "Dirt",
+    /// "color": 9127187, "solid": true}, ...] (color is RGB)
+    pub fn get_tile_palette(&self) -> String {
+        palette::palette_json()
+    }
+
+    /// Eyedropper: the place_tile arguments for a tile like the overworld
+    /// one at (x, y), as JSON {"tile_type": "Stone", "shape": "half"}, or
+    /// "null" outside the map
+    pub fn sample_tile(&self, x: i32, y: i32) -> String {
+        self.tile_map.sample_tile(x, y)
+    }
+
+    pub fn get_tile_at(&self, x: i32, y: i32) -> String {
+        if let Some(tile) = self.tile_map.get_tile(x, y) {
+            tile.tile_type.name().to_string()
+        } else {
+            "Air".to_string() // Default to Air for out-of-bounds
+        }
+    }
+
+    /// Follow a ray from pixel (x, y) along (dx, dy) through the overworld
+    /// and return the first thing it hits within max_dist pixels, as JSON:
+    /// `{"hit": "tile", "tile_x", "tile_y", "x", "y", "distance"}` or
+    /// `{"hit": "promiser", "id", "x", "y", "distance"}`, or null for nothing
+    pub fn raycast(&self, x: f64, y: f64, dx: f64, dy: f64, max_dist: f64) -> String {
+        let hit = self.cast_ray(OVERWORLD, x, y, dx, dy, max_dist);
+        serde_json::to_string(&hit).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Whether nothing solid lies on the straight line between two overworld
+    /// pixel positions (promisers don't block it)
+    pub fn has_line_of_sight(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
+        self.tile_map.line_of_sight(x1, y1, x2, y2)
+    }
+
+    /// Temperature of an overworld tile in °C (the air temperature until the
+    /// water simulation has run)
+    pub fn get_temperature_at(&self, x: i32, y: i32) -> f32 {
+        self.tile_map.temperature_at(x, y).unwrap_or_else(|| self.air_temperature())
+    }
+
+    /// Light energy of an overworld tile (0.0 is dark, 1.0 and up fully lit)
+    pub fn get_light_at(&self, x: i32, y: i32) -> f32 {
+        self.tile_map.light_at(x, y).unwrap_or(0.0)
+    }
+
+    /// Replace the world with one drawn as an RGBA image (e.g. from a canvas'
+    /// getImageData). `palette_json` maps "#rrggbb" colors to tile type names;
+    /// pass an empty string to use the default tile colors. Returns false if
+    /// the image or palette is invalid, leaving the current world untouched.
+    pub fn load_world_from_image(&mut self, rgba_bytes: &[u8], width: usize, height: usize, palette_json: String) -> bool {
+        let loaded = image_import::parse_palette(&palette_json)
+            .and_then(|palette| image_import::tile_map_from_image(rgba_bytes, width, height, &palette));
+
+        match loaded {
+            Ok(tile_map) => {
+                self.replace_tile_map(tile_map);
+                console_log!(Info, Save, "Loaded {}x{} world from image", width, height);
+                true
+            }
+            Err(err) => {
+                console_log!(Error, Save, "Failed to load world from image: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Save the overworld (tiles and soil fertility), all promisers (with
+    /// their metadata and what they carry), loose items in every dimension
+    /// and Pixel's inventory as bytes. Anything else (creatures and pets,
+    /// other dimensions' tiles, stockpiles, blueprints, furnaces, the sun,
+    /// weather and the tick count) isn't saved.
+    pub fn save_game(&self) -> Vec<u8> {
+        let mut promisers: Vec<Promiser> = self.promisers.values().cloned().collect();
+        promisers.sort_by_key(|promiser| promiser.id);
+        let mut items: Vec<Item> = self.ecs.items().collect();
+        items.sort_by_key(|item| item.id);
+        persistence::encode_save(&self.tile_map, &persistence::SavedEntities {
+            next_id: self.next_id,
+            promisers,
+            items,
+            inventory: self.player.inventory.clone(),
+        })
+    }
+
+    /// Restore a save from save_game, replacing what it covers. Every
+    /// promiser comes back without a task, having set down what it carried,
+    /// since tasks point at items and blueprints by id.
+    pub fn load_game(&mut self, bytes: &[u8]) -> bool {
+        match persistence::decode_save(bytes) {
+            Ok((tile_map, saved)) => {
+                self.promisers = saved.promisers.into_iter().map(|promiser| (promiser.id, promiser)).collect();
+                let max_id = self.promisers.keys().max().map_or(0, |id| id + 1);
+                self.next_id = saved.next_id.max(max_id);
+                self.replace_tile_map(tile_map);
+
+                self.ecs.despawn_where(|ecs, id| ecs.stacks.get(id).is_some());
+                for item in saved.items {
+                    if dimension::dimension_map(&self.tile_map, &self.dimensions, item.dimension).is_some() {
+                        self.add_item(item.kind, item.count, item.x, item.y, item.dimension);
+                    }
+                }
+                self.player.inventory = saved.inventory;
+                let mut ids: Vec<u32> = self.promisers.keys().copied().collect();
+                ids.sort_unstable();
+                for id in ids {
+                    self.abandon_task(id);
+                }
+
+                self.spatial.rebuild(&self.promisers);
+                self.promiser_delta.reset();
+                console_log!(Info, Save, "Loaded save with {} promisers", self.promisers.len());
+                true
+            }
+            Err(err) => {
+                console_log!(Error, Save, "Failed to load save: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Save every `ticks` ticks, raising an "autosaved" event each time; 0
+    /// turns autosaving off (see autosave.rs)
+    pub fn set_autosave(&mut self, ticks: u32) {
+        self.set_autosave_interval(ticks as u64);
+    }
+
+    /// The latest autosave, as save_game bytes; empty before the first
+    pub fn latest_autosave(&self) -> Vec<u8> {
+        self.latest_save().map(<[u8]>::to_vec).unwrap_or_default()
+    }
+
+    /// Go back to the latest autosave; false if there's none yet
+    pub fn restore_autosave(&mut self) -> bool {
+        self.rewind_to_autosave()
+    }
+
+    /// Run-length encoded snapshot of the overworld tiles (see persistence.rs)
+    pub fn export_tiles(&self) -> Vec<u8> {
+        persistence::encode_tiles(&self.tile_map)
+    }
+
+    /// Replace the overworld with a snapshot from export_tiles
+    pub fn import_tiles(&mut self, bytes: &[u8]) -> bool {
+        match persistence::decode_tiles(bytes) {
+            Ok(tile_map) => {
+                console_log!(Info, Save, "Imported {}x{} world snapshot", tile_map.width, tile_map.height);
+                self.replace_tile_map(tile_map);
+                true
+            }
+            Err(err) => {
+                console_log!(Error, Save, "Failed to import world snapshot: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Grow or crop the world to new_width x new_height tiles. `anchor` says
+    /// which part of the existing world stays put: "bottom-left" (default),
+    /// "bottom", "bottom-right", "left", "center", "right", "top-left", "top"
+    /// or "top-right". Promisers and light rays move with the content.
+    pub fn resize_world(&mut self, new_width: usize, new_height: usize, anchor: String) {
+        let old_width = self.tile_map.width as i64;
+        let old_height = self.tile_map.height as i64;
+        let (grow_x, grow_y) = (new_width as i64 - old_width, new_height as i64 - old_height);
+
+        let (horizontal, vertical) = match anchor.as_str() {
+            "bottom-left" | "" => (0, 0),
+            "bottom" => (1, 0),
+            "bottom-right" => (2, 0),
+            "left" => (0, 1),
+            "center" => (1, 1),
+            "right" => (2, 1),
+            "top-left" => (0, 2),
+            "top" => (1, 2),
+            "top-right" => (2, 2),
+            _ => {
+                console_log!(Error, World, "Unknown resize anchor '{}', using bottom-left", anchor);
+                (0, 0)
+            }
+        };
+        // Where the old bottom-left corner lands in the new map
+        let offset_x = grow_x * horizontal / 2;
+        let offset_y = grow_y * vertical / 2;
+
+        self.tile_map = self.tile_map.resized(new_width, new_height, offset_x, offset_y);
+        self.world_width = new_width as f64 * TILE_SIZE_PIXELS;
+        self.world_height = new_height as f64 * TILE_SIZE_PIXELS;
+
+        let shift_x = offset_x as f64 * TILE_SIZE_PIXELS;
+        let shift_y = offset_y as f64 * TILE_SIZE_PIXELS;
+        for promiser in self.promisers.values_mut() {
+            promiser.x = (promiser.x + shift_x).clamp(promiser.size, (self.world_width - promiser.size).max(promiser.size));
+            promiser.y = (promiser.y + shift_y).clamp(promiser.size, (self.world_height - promiser.size).max(promiser.size));
+        }
+        for ray in &mut self.light_rays {
+            ray.x += shift_x;
+            ray.y += shift_y;
+        }
+        let (world_width, world_height) = (self.world_width, self.world_height);
+        self.light_rays.retain(|ray| !ray.is_out_of_bounds(world_width, world_height));
+
+        console_log!(Info, World, "Resized world to {}x{} tiles (anchor {})", new_width, new_height, anchor);
+    }
+
+    /// Add a named dimension with its own generated tile map. `preset` is a
+    /// worldgen preset name ("caverns", "flat", ...). Returns the dimension id,
+    /// or the existing id if the name is already taken.
+    pub fn add_dimension(&mut self, name: String, width: usize, height: usize, preset: String) -> u32 {
+        if let Some(id) = self.dimension_id(&name) {
+            console_log!(Error, World, "Dimension '{}' already exists", name);
+            return id;
+        }
+
+        let preset = WorldPreset::from_name(&preset).unwrap_or_default();
+        let mut tile_map = TileMap::new(width, height);
+        tile_map.set_borders(self.config.borders());
+        tile_map.rules = self.config.rules;
+        let seed = (random() * u32::MAX as f64) as u64;
+        worldgen::generate(&mut tile_map, &worldgen::WorldGenParams::for_preset(preset), seed);
+
+        console_log!(Info, World, "Added {:?} dimension '{}' ({}x{})", preset, name, width, height);
+        self.dimensions.push(Dimension { name, tile_map, settings: DimensionSettings::default() });
+        self.dimensions.len() as u32
+    }
+
+    /// Connect (x, y) in one dimension with (to_x, to_y) in another, both ways.
+    /// Both ends become portal tiles.
+    pub fn link_portal(&mut self, from: String, x: i32, y: i32, to: String, to_x: i32, to_y: i32) -> bool {
+        let (Some(from_id), Some(to_id)) = (self.dimension_id(&from), self.dimension_id(&to)) else {
+            console_log!(Error, World, "Cannot link portal: unknown dimension '{}' or '{}'", from, to);
+            return false;
+        };
+
+        for (dim, px, py) in [(from_id, x, y), (to_id, to_x, to_y)] {
+            if let Some(map) = self.dimension_map_mut(dim) {
+                map.set_tile(px, py, Tile { tile_type: TileType::Portal, water_amount: 0, variant: 0 });
+            }
+        }
+        self.portals.push(PortalLink { from: from_id, x, y, to: to_id, to_x, to_y });
+        self.portals.push(PortalLink { from: to_id, x: to_x, y: to_y, to: from_id, to_x: x, to_y: y });
+        true
+    }
+
+    /// Toggle which simulations run in a dimension ("overworld" included)
+    pub fn set_dimension_simulation(&mut self, name: String, water: bool, foliage: bool) -> bool {
+        let settings = DimensionSettings { simulate_water: water, simulate_foliage: foliage };
+        match self.dimension_id(&name) {
+            Some(OVERWORLD) => self.overworld_settings = settings,
+            Some(id) => self.dimensions[id as usize - 1].settings = settings,
+            None => return false,
+        }
+        true
+    }
+
+    /// Tile map of a named dimension, in the same shape as the tile_map getter
+    pub fn get_dimension_tile_map(&self, name: String) -> JsValue {
+        self.dimension_id(&name)
+            .and_then(|id| dimension::dimension_map(&self.tile_map, &self.dimensions, id))
+            .map(|map| serde_wasm_bindgen::to_value(map).unwrap())
+            .unwrap_or(JsValue::NULL)
+    }
+
+    /// Overworld tiles modified since the last call (by water, foliage or
+    /// edits), as a JSON array of [x, y, tile] triples
+    pub fn get_changed_tiles(&mut self) -> String {
+        let width = self.tile_map.width;
+        let changed: Vec<(usize, usize, &Tile)> = self.tile_map.take_dirty()
+            .into_iter()
+            .map(|i| (i % width, i / width, &self.tile_map.tiles[i]))
+            .collect();
+        serde_json::to_string(&changed).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Pointer to the tile type ids (one u8 per tile, TileType order)
+    pub fn tile_types_ptr(&self) -> *const u8 {
+        self.buffers.types.as_ptr()
+    }
+
+    /// Pointer to the tile shapes (one u8 per tile, see shape.rs)
+    pub fn tile_variants_ptr(&self) -> *const u8 {
+        self.buffers.variants.as_ptr()
+    }
+
+    /// Pointer to the water amounts (one u16 per tile)
+    pub fn tile_water_ptr(&self) -> *const u16 {
+        self.buffers.water.as_ptr()
+    }
+
+    /// Pointer to the light brightness (one f32 per tile, 0.0 to 1.0)
+    pub fn tile_brightness_ptr(&self) -> *const f32 {
+        self.buffers.brightness.as_ptr()
+    }
+
+    /// Pointer to the sun shadow mask (one u8 per tile, 1 = shaded, see sun.rs)
+    pub fn tile_shadow_ptr(&self) -> *const u8 {
+        self.buffers.shadow.as_ptr()
+    }
+
+    /// Pointer to the RGBA lightmap (four u8 per tile, see buffers.rs)
+    pub fn lightmap_ptr(&self) -> *const u8 {
+        self.buffers.lightmap.as_ptr()
+    }
+
+    /// Pointer to the soil fertility (one u8 per tile, 0-255, see fertility.rs)
+    pub fn tile_fertility_ptr(&self) -> *const u8 {
+        self.buffers.fertility.as_ptr()
+    }
+
+    /// Number of tiles in each buffer (width * height)
+    pub fn tile_buffer_len(&self) -> usize {
+        self.buffers.types.len()
+    }
+
+    /// Take all events raised since the last call, as a JSON array
+    pub fn drain_events(&mut self) -> String {
+        self.events.drain_json()
+    }
+
+    pub fn get_pixel_id(&self) -> u32 {
+        // Return the ID of the first promiser with is_pixel=true, or 0 if none found
+        for promiser in self.promisers.values() {
+            if promiser.is_pixel {
+                return promiser.id;
+            }
+        }
+        0 // No pixel found
+    }
+
+    pub fn get_random_promiser_id(&self) -> u32 {
+        if self.promisers.is_empty() {
+            return 0;
+        }
+        
+        let promiser_ids: Vec<u32> = self.promisers.keys().cloned().collect();
+        let random_index = (random() * promiser_ids.len() as f64) as usize;
+        promiser_ids.get(random_index).copied().unwrap_or(0)
+    }
+
+    /// Order-independent cellular-automata water step, for every dimension
+    /// that has water simulation enabled.
+    pub fn simulate_water(&mut self) {
+        let (air, ground) = (self.air_temperature(), self.ground_temperature());
+        if self.overworld_settings.simulate_water {
+            let mut lost_to_void = 0;
+            for mask in self.lod.water_steps(&self.tile_map) {
+                lost_to_void += self.tile_map.step_water_zones(mask.as_ref(), &self.fluids);
+            }
+            if lost_to_void > 0 {
+                self.events.push(SimEvent::WaterFellIntoVoid { amount: lost_to_void });
+            }
+            self.tile_map.step_heat(air, ground);
+            self.tile_map.step_steam();
+            self.tile_map.step_snow();
+            let trodden = self.trodden_tiles(OVERWORLD);
+            self.tile_map.step_mud(&trodden);
+            self.tile_map.step_quicksand();
+        }
+        for n in 0..self.dimensions.len() {
+            let trodden = self.trodden_tiles(n as u32 + 1);
+            let dimension = &mut self.dimensions[n];
+            if dimension.settings.simulate_water {
+                dimension.tile_map.step_water(&self.fluids);
+                dimension.tile_map.step_heat(air, ground);
+                dimension.tile_map.step_steam();
+                dimension.tile_map.step_snow();
+                dimension.tile_map.step_mud(&trodden);
+                dimension.tile_map.step_quicksand();
+            }
+        }
+        self.heat_furnaces();
+    }
+
+    // Air temperature right now (see thermal.rs)
+    fn air_temperature(&self) -> f32 {
+        thermal::air_temperature(
+            &self.sun,
+            self.config.day_temperature.unwrap_or(thermal::DEFAULT_DAY_TEMPERATURE),
+            self.config.night_temperature.unwrap_or(thermal::DEFAULT_NIGHT_TEMPERATURE),
+        )
+    }
+
+    // Deep ground temperature (see thermal.rs)
+    fn ground_temperature(&self) -> f32 {
+        thermal::ground_temperature(
+            self.config.day_temperature.unwrap_or(thermal::DEFAULT_DAY_TEMPERATURE),
+            self.config.night_temperature.unwrap_or(thermal::DEFAULT_NIGHT_TEMPERATURE),
+        )
+    }
+
+    /// Step the oxygen field of every tile map, with each promiser breathing
+    /// in the dimension it's in
+    pub fn simulate_air(&mut self) {
+        let breathers = |dim: u32| -> Vec<(f64, f64)> {
+            self.promisers.values().filter(|p| p.dimension == dim).map(|p| (p.x, p.y)).collect()
+        };
+        let overworld = breathers(OVERWORLD);
+        let others: Vec<Vec<(f64, f64)>> = (0..self.dimensions.len()).map(|i| breathers(i as u32 + 1)).collect();
+        self.tile_map.step_air(&overworld);
+        for (dimension, breathers) in self.dimensions.iter_mut().zip(others) {
+            dimension.tile_map.step_air(&breathers);
+        }
+    }
+
+    /// Oxygen level (0.0 stale to 1.0 fresh) at an overworld pixel position;
+    /// 1.0 before the air has been simulated
+    pub fn get_oxygen_at(&self, x: f64, y: f64) -> f64 {
+        self.tile_map.oxygen_at_pixel(x, y).unwrap_or(1.0) as f64
+    }
+
+    /// Simulate foliage and grass growth and death based on dirt moisture levels
+    pub fn simulate_foliage(&mut self) {
+        if self.overworld_settings.simulate_foliage {
+            self.tile_map.step_fertility();
+            for mask in self.lod.foliage_steps(&self.tile_map) {
+                self.tile_map.step_foliage_zones(mask.as_ref());
+            }
+            self.tile_map.step_grass();
+            self.tile_map.step_decay();
+        }
+        for dimension in &mut self.dimensions {
+            if dimension.settings.simulate_foliage {
+                dimension.tile_map.step_fertility();
+                dimension.tile_map.step_foliage();
+                dimension.tile_map.step_grass();
+                dimension.tile_map.step_decay();
+            }
+        }
+    }
+}
+
+/// Global game state instance
+static mut GAME_STATE: Option<GameState> = None;
+
+/// Log level: "off", "error", "info" (the default) or "debug" (see logging.rs).
+/// Returns false for an unknown level.
+#[wasm_bindgen]
+pub fn set_log_level(level: &str) -> bool {
+    let Some(level) = logging::LogLevel::from_name(level) else { return false };
+    logging::set_level(level);
+    true
+}
+
+/// Turn one subsystem's logging on or off: "core", "world", "tiles",
+/// "foliage", "promisers" or "save". Returns false for an unknown one.
+#[wasm_bindgen]
+pub fn set_log_subsystem(subsystem: &str, enabled: bool) -> bool {
+    let Some(subsystem) = logging::Subsystem::from_name(subsystem) else { return false };
+    logging::set_enabled(subsystem, enabled);
+    true
+}
+
+/// `config_json` is an optional SimConfig, e.g. `{"seed": 42, "preset": "islands"}`.
+/// With `warmup_ticks` in it, the world runs that long before this returns,
+/// calling `on_progress(done, total)` along the way if given (see warmup.rs).
+#[wasm_bindgen]
+pub fn init_game(world_width_tiles: f64, world_height_tiles: f64, config_json: Option<String>, on_progress: Option<js_sys::Function>) {
+    console_log!(Info, World, "Initializing game with world size: {}x{} tiles", world_width_tiles, world_height_tiles);
+    let config = config_json.map(|json| SimConfig::from_json(&json)).unwrap_or_default();
+    let mut state = GameState::with_config(world_width_tiles, world_height_tiles, config);
+    state.warm_up_with_callback(on_progress);
+    unsafe {
+        GAME_STATE = Some(state);
+    }
+}
+
+/// Start over in the world a scenario describes (JSON, see scenario.rs):
+/// size, config, starting roster, timeline and objectives. Returns false,
+/// leaving the current world alone, if the JSON is invalid. Warms up like
+/// init_game when its config asks to.
+#[wasm_bindgen]
+pub fn load_scenario(json: String, on_progress: Option<js_sys::Function>) -> bool {
+    match GameState::from_scenario(&json) {
+        Ok(mut state) => {
+            state.warm_up_with_callback(on_progress);
+            unsafe {
+                GAME_STATE = Some(state);
+            }
+            true
+        }
+        Err(err) => {
+            console_log!(Error, Core, "Invalid scenario: {}", err);
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn update_game(current_time: f64) -> String {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.update(current_time);
+            state.get_state_data()
+        } else {
+            "{}".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tick() -> String {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.tick();
+            state.get_state_data()
+        } else {
+            "{}".to_string()
+        }
+    }
+}
+
+/// update_game without the String: the state goes into the export buffer
+/// (read it at state_export_ptr), and the length in bytes comes back
+#[wasm_bindgen]
+pub fn update_game_export(current_time: f64) -> usize {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.update(current_time);
+            state.export_state()
+        } else {
+            0
+        }
+    }
+}
+
+/// tick without the String, like update_game_export
+#[wasm_bindgen]
+pub fn tick_export() -> usize {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.tick();
+            state.export_state()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn export_state() -> usize {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.export_state()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn state_export_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.state_export_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn state_checksum() -> u32 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.state_checksum()
+        } else {
+            0
+        }
+    }
+}
+
+/// Current state as MessagePack (does not advance the simulation)
+#[wasm_bindgen]
+pub fn get_state_msgpack() -> Vec<u8> {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_state_msgpack()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_changed_promisers() -> String {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.get_changed_promisers()
+        } else {
+            "{}".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn reset_changed_promisers() {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.reset_changed_promisers();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_promiser() {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.add_promiser();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn reproduce(parent_a: u32, parent_b: u32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.reproduce(parent_a, parent_b)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_lineage() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_lineage()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn spawn_promiser(x: f64, y: f64, options_json: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.spawn_promiser(x, y, options_json)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn spawn_promisers(count: u32, options_json: String) -> Vec<u32> {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.spawn_promisers(count, options_json)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_promiser(id: u32) -> JsValue {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_promiser(id)
+        } else {
+            JsValue::NULL
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_promisers_in_rect(x: f64, y: f64, w: f64, h: f64) -> Vec<u32> {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_promisers_in_rect(x, y, w, h)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_promisers_near(x: f64, y: f64, radius: f64) -> Vec<u32> {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_promisers_near(x, y, radius)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_promiser_glow(id: u32, intensity: f64, color: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_promiser_glow(id, intensity, color)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_promiser_meta(id: u32, json: String) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_promiser_meta(id, json)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_promiser_meta(id: u32) -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_promiser_meta(id)
+        } else {
+            String::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_fluid(tile_type: &str, json: &str) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_fluid(tile_type, json)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_fluids() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_fluids()
+        } else {
+            "{}".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_export_light_rays(enabled: bool) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_export_light_rays(enabled);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_export_meta(enabled: bool) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_export_meta(enabled);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn teleport_promiser(id: u32, x: f64, y: f64) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.teleport_promiser(id, x, y)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn apply_impulse(id: u32, fx: f64, fy: f64) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.apply_impulse(id, fx, fy)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_follow(id: u32, target_id: u32, distance: f64) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_follow(id, target_id, distance)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_flee(id: u32, target_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_flee(id, target_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn stop_promiser_steering(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.stop_promiser_steering(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_pixel_input(move_x: f64, jump: bool) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_pixel_input(move_x, jump);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn release_pixel() {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.release_pixel();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_break_tile(x: i32, y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_break_tile(x, y)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_stop_breaking() {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_stop_breaking();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_scoop_water(x: i32, y: i32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_scoop_water(x, y)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_pour_water(x: i32, y: i32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_pour_water(x, y)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_pixel_bucket() -> u32 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_pixel_bucket()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn equip_tool(promiser_id: u32, item_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.equip_tool(promiser_id, item_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_place_tile(x: i32, y: i32, kind: String) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_place_tile(x, y, kind)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pixel_pick_up() -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pixel_pick_up()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_pixel_inventory() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_pixel_inventory()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn create_group(name: String, accent: u32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.create_group(name, accent)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_group(group_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_group(group_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_promiser_group(id: u32, group_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_promiser_group(id, group_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_groups() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_groups()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_group_affinity(group_a: u32, group_b: u32, affinity: f64) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_group_affinity(group_a, group_b, affinity);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_group_affinities() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_group_affinities()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn move_group_to(group_id: u32, x: f64, y: f64) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.move_group_to(group_id, x, y)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn group_follow(group_id: u32, target_id: u32, distance: f64) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.group_follow(group_id, target_id, distance)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_exploration_coverage() -> f64 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_exploration_coverage()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_promiser_count() -> usize {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.promiser_count()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_think(id: u32) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_think(id);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_speak(id: u32, thought: String, emote: Option<String>, duration: Option<f64>) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_speak(id, thought, emote, duration);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_whisper(id: u32, thought: String, target_id: u32, emote: Option<String>, duration: Option<f64>) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_whisper(id, thought, target_id, emote, duration);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn make_promiser_run(id: u32) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.make_promiser_run(id);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn resize_world(new_width: usize, new_height: usize, anchor: String) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.resize_world(new_width, new_height, anchor);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_dimension(name: String, width: usize, height: usize, preset: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.add_dimension(name, width, height, preset)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn link_portal(from: String, x: i32, y: i32, to: String, to_x: i32, to_y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.link_portal(from, x, y, to, to_x, to_y)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_dimension_simulation(name: String, water: bool, foliage: bool) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_dimension_simulation(name, water, foliage)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_dimension_tile_map(name: String) -> JsValue {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_dimension_tile_map(name)
+        } else {
+            JsValue::NULL
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_changed_tiles() -> String {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.get_changed_tiles()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_types_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_types_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_variants_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_variants_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_water_ptr() -> *const u16 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_water_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_brightness_ptr() -> *const f32 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_brightness_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_shadow_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_shadow_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn lightmap_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.lightmap_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_fertility_ptr() -> *const u8 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_fertility_ptr()
+        } else {
+            std::ptr::null()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn tile_buffer_len() -> usize {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.tile_buffer_len()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn drain_events() -> String {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.drain_events()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_pixel_id() -> u32 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_pixel_id()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_random_promiser_id() -> u32 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_random_promiser_id()
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn place_tile(x: i32, y: i32, tile_type: String, shape: Option<String>) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.place_tile(x, y, tile_type, shape);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn scoop_water(x: i32, y: i32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.scoop_water(x, y)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn pour_water(x: i32, y: i32, amount: u32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.pour_water(x, y, amount)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn order_dig(id: u32, x: i32, y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.order_dig(id, x, y)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn dig_tile(x: i32, y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.dig_tile(x, y)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn drop_item(kind: String, x: f64, y: f64, count: u32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.drop_item(kind, x, y, count)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn spawn_creature(kind: String, x: f64, y: f64) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.spawn_creature(kind, x, y)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_all(kind: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_all(kind)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_collision_filter(id: u32, layer: u8, mask: u8) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_collision_filter(id, layer, mask)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_creatures() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_creatures()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn feed_creature(promiser_id: u32, creature_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.feed_creature(promiser_id, creature_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn give_to_pet(creature_id: u32, item_id: u32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.give_to_pet(creature_id, item_id)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn drop_pet_item(creature_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.drop_pet_item(creature_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn catch_fish(promiser_id: u32, creature_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.catch_fish(promiser_id, creature_id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_region_at(x: i32, y: i32) -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_region_at(x, y)
+        } else {
+            "null".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_view(id: u32, x: f64, y: f64, width: f64, height: f64) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_view(id, x, y, width, height);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_view(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_view(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn request_flow_field(x: i32, y: i32) -> Vec<u16> {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.request_flow_field(x, y)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_items() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_items()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_stockpile(x: i32, y: i32, width: i32, height: i32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.add_stockpile(x, y, width, height)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_stockpile(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_stockpile(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_stockpiles() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_stockpiles()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn place_blueprint(schematic: String, x: i32, y: i32) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.place_blueprint(schematic, x, y)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn cancel_blueprint(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.cancel_blueprint(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_objective(json: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.add_objective(json)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_objective(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_objective(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_objectives() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_objectives()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn infect_promiser(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.infect_promiser(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_quarantine_zone(x: i32, y: i32, width: i32, height: i32) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_quarantine_zone(x, y, width, height);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn play_sequence(json: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.play_sequence(json)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_director_intensity(intensity: f64) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_director_intensity(intensity);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_wind() -> f64 {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_wind()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_wind(speed: f64) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_wind(speed);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_time_of_day(time: f64, day_seconds: Option<f64>) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_time_of_day(time, day_seconds);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_ambient_light(floor: f64, moon: f64, moon_color: u32) {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.set_ambient_light(floor, moon, moon_color);
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn add_light_emitter(json: String) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.add_light_emitter(json)
+        } else {
+            u32::MAX
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn remove_light_emitter(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.remove_light_emitter(id)
+        } else {
+            false
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_light_emitters() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_light_emitters()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn spawn_light_burst(x: f64, y: f64, count: u32, intensity: f64) -> u32 {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.spawn_light_burst(x, y, count, intensity)
+        } else {
+            0
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_sun() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_sun()
+        } else {
+            "{}".to_string()
         }
     }
-    
-    pub fn make_promiser_whisper(&mut self, id: u32, thought: String, target_id: u32) {
-        if let Some(promiser) = self.promisers.get_mut(&id) {
-            promiser.set_whisper(thought, target_id);
+}
+
+#[wasm_bindgen]
+pub fn stop_sequence(id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.stop_sequence(id)
+        } else {
+            false
         }
     }
-    
-    pub fn make_promiser_run(&mut self, id: u32) {
-        if let Some(promiser) = self.promisers.get_mut(&id) {
-            promiser.state = 3; // Running
-            promiser.state_timer = 0.0;
+}
+
+#[wasm_bindgen]
+pub fn get_chronicle() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_chronicle()
+        } else {
+            "[]".to_string()
         }
     }
+}
 
-    // Tile manipulation methods
-    pub fn place_tile(&mut self, x: usize, y: usize, tile_type: String) {
-        let tile_type_enum = match tile_type.as_str() {
-            "Dirt" => TileType::Dirt,
-            "Stone" => TileType::Stone,
-            "Water" => TileType::Water,
-            "Air" => TileType::Air,
-            "Foliage" => TileType::Foliage,
-            _ => TileType::Air, // Default to Air for unknown types
-        };
-        
-        let new_tile = Tile {
-            tile_type: tile_type_enum,
-            water_amount: if matches!(tile_type_enum, TileType::Water) { MAX_WATER_AMOUNT } else { 0 },
-        };
-        
-        self.tile_map.set_tile(x, y, new_tile);
-        console_log!("Placed {} tile at ({}, {})", tile_type, x, y);
+#[wasm_bindgen]
+pub fn get_blueprints() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_blueprints()
+        } else {
+            "[]".to_string()
+        }
     }
+}
 
-    pub fn get_tile_at(&self, x: usize, y: usize) -> String {
-        if let Some(tile) = self.tile_map.get_tile(x, y) {
-            match tile.tile_type {
-                TileType::Dirt => "Dirt".to_string(),
-                TileType::Stone => "Stone".to_string(),
-                TileType::Water => "Water".to_string(),
-                TileType::Air => "Air".to_string(),
-                TileType::Foliage => "Foliage".to_string(),
-            }
+#[wasm_bindgen]
+pub fn craft(recipe: String, stockpile_id: u32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.craft(recipe, stockpile_id)
         } else {
-            "Air".to_string() // Default to Air for out-of-bounds
+            false
         }
     }
+}
 
-    pub fn get_pixel_id(&self) -> u32 {
-        // Return the ID of the first promiser with is_pixel=true, or 0 if none found
-        for promiser in self.promisers.values() {
-            if promiser.is_pixel {
-                return promiser.id;
-            }
+#[wasm_bindgen]
+pub fn get_recipes() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_recipes()
+        } else {
+            "[]".to_string()
         }
-        0 // No pixel found
     }
+}
 
-    pub fn get_random_promiser_id(&self) -> u32 {
-        if self.promisers.is_empty() {
-            return 0;
+#[wasm_bindgen]
+pub fn place_item(item_id: u32, x: i32, y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.place_item(item_id, x, y)
+        } else {
+            false
         }
-        
-        let promiser_ids: Vec<u32> = self.promisers.keys().cloned().collect();
-        let random_index = (random() * promiser_ids.len() as f64) as usize;
-        promiser_ids.get(random_index).copied().unwrap_or(0)
     }
+}
 
-    /// Order-independent cellular-automata water step.
-    pub fn simulate_water(&mut self) {
-        let w  = self.tile_map.width;
-        let h  = self.tile_map.height;
-        let len = w * h;
-
-        // Signed changes for each tile (outflow = negative, inflow = positive)
-        let mut delta: Vec<i32> = vec![0; len];
-
-        // --- 1 ░ Gather phase -------------------------------------------------
-        for y in 0..h {
-            for x in 0..w {
-                let i = y * w + x;
-                let tile = &self.tile_map.tiles[i];
-
-                // Only flowing water can move
-                if tile.tile_type != TileType::Water || tile.water_amount == 0 {
-                    continue;
-                }
-
-                let mut remaining = tile.water_amount;
-
-                // helper to register a flow
-                let mut push = |from_idx: usize, to_idx: usize, amount: u16| {
-                    if amount == 0 { return; }
-                    delta[from_idx] -= amount as i32;
-                    delta[to_idx]   += amount as i32;
-                };
-
-                // ── a) Vertical – gravity first (toward smaller world-y)
-                if y > 0 {
-                    let j = (y - 1) * w + x;
-                    let below = &self.tile_map.tiles[j];
-
-                    if below.tile_type == TileType::Air ||
-                       (below.tile_type == TileType::Water &&
-                        below.water_amount < MAX_WATER_AMOUNT)
-                    {
-                        let room   = MAX_WATER_AMOUNT - below.water_amount;
-                        let flow   = remaining.min(room);
-                        remaining -= flow;
-                        push(i, j, flow);
-                    } else if below.tile_type == TileType::Dirt {
-                        // Water can seep into dirt below due to gravity
-                        let current_moisture = below.water_amount;
-                        if current_moisture < MAX_DIRT_MOISTURE && remaining > 0 {
-                            // Vertical seepage can be faster than horizontal due to gravity
-                            let seepage_rate = 4; // Higher rate for downward seepage
-                            let max_seepage = (MAX_DIRT_MOISTURE - current_moisture).min(seepage_rate).min(remaining);
-                            if max_seepage > 0 {
-                                remaining -= max_seepage;
-                                push(i, j, max_seepage);
-                            }
-                        }
-                    }
-                }
-
-                // ── b) Horizontal – equalise with neighbours
-                // Only move half the height difference to avoid “teleporting”
-                let neighbours = [
-                    (x.wrapping_sub(1), y),      // left  (wraps harmlessly for x=0)
-                    (x + 1,             y),      // right
-                ];
-
-                for (nx, ny) in neighbours {
-                    if nx >= w { continue; }
-                    let j = ny * w + nx;
-                    let n_tile = &self.tile_map.tiles[j];
-
-                    // Stone blocks water completely
-                    if n_tile.tile_type == TileType::Stone {
-                        continue;
-                    }
-
-                    // Handle water seepage into dirt
-                    if n_tile.tile_type == TileType::Dirt {
-                        
-                        // Water can seep into dirt slowly
-                        let current_moisture = n_tile.water_amount; 
-                        if current_moisture < MAX_DIRT_MOISTURE && remaining > 0 {
-                            // Slow seepage - only small amounts at a time
-                            let seepage_rate = 2; // Units per simulation step
-                            let max_seepage = (MAX_DIRT_MOISTURE - current_moisture).min(seepage_rate).min(remaining);
-                            if max_seepage > 0 {
-                                remaining -= max_seepage;
-                                push(i, j, max_seepage);
-                            }
-                        }
-                        continue; 
-                    }
-
-                    // Regular water flow for air and water tiles
-                    let target = (remaining as i32 + n_tile.water_amount as i32) / 2;
-                    if remaining as i32 > target {
-                        let flow = (remaining as i32 - target) as u16;
-                        remaining -= flow;
-                        push(i, j, flow);
-                    }
-                }
-
-                // ── c) Optional small upflow (pressure equalisation) -------------
-                // Not strictly needed – comment out if you want one-way gravity.
-            }
+#[wasm_bindgen]
+pub fn feed_furnace(item_id: u32, x: i32, y: i32) -> bool {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.feed_furnace(item_id, x, y)
+        } else {
+            false
         }
+    }
+}
 
-        // --- 2 ░ Apply phase ---------------------------------------------------
-        for idx in 0..len {
-            let change = delta[idx];
-            if change == 0 { continue; }
-
-            let t = &mut self.tile_map.tiles[idx];
-            let new_amt = (t.water_amount as i32 + change)
-                .clamp(0, MAX_WATER_AMOUNT as i32) as u16;
+#[wasm_bindgen]
+pub fn get_furnaces() -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_furnaces()
+        } else {
+            "[]".to_string()
+        }
+    }
+}
 
-            // Handle tile type transitions based on water content
-            match t.tile_type {
-                TileType::Water => {
-                    if new_amt == 0 {
-                        t.tile_type = TileType::Air;
-                    }
-                },
-                TileType::Dirt => {
-                    // Dirt can absorb water but stays dirt (just becomes moist)
-                    // No tile type change needed
-                },
-                TileType::Air => {
-                    if new_amt > 0 {
-                        t.tile_type = TileType::Water;
-                    }
-                },
-                TileType::Stone => {
-                    // Stone doesn't change type
-                },
-                TileType::Foliage => {
-                    // Foliage doesn't absorb water but can be destroyed if dry
-                    // For now, foliage is stable
-                },
-            }
+#[wasm_bindgen]
+pub fn get_tile_palette() -> String {
+    palette::palette_json()
+}
 
-            t.water_amount = new_amt;
+#[wasm_bindgen]
+pub fn sample_tile(x: i32, y: i32) -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.sample_tile(x, y)
+        } else {
+            "null".to_string()
         }
     }
+}
 
-    /// Simulate foliage growth and death based on dirt moisture levels
-    pub fn simulate_foliage(&mut self) {
-        let w = self.tile_map.width;
-        let h = self.tile_map.height;
-        
-        // Collect changes to apply after scanning
-        let mut changes: Vec<(usize, usize, TileType)> = Vec::new();
-        
-        for y in 0..h {
-            for x in 0..w {
-                let i = y * w + x;
-                let tile = &self.tile_map.tiles[i];
-                
-                match tile.tile_type {
-                    TileType::Dirt => {
-                        // Check if dirt has enough moisture to grow foliage
-                        if tile.water_amount >= MIN_FOLIAGE_MOISTURE {
-                            // Check if there's space above for foliage (if not at top edge)
-                            if y + 1 < h {
-                                let above_idx = (y + 1) * w + x;
-                                let above_tile = &self.tile_map.tiles[above_idx];
-                                
-                                // Only grow foliage on air tiles above dirt
-                                if above_tile.tile_type == TileType::Air && random() < FOLIAGE_GROWTH_CHANCE {
-                                    // Schedule foliage growth above the dirt
-                                    changes.push((x, y + 1, TileType::Foliage));
-                                }
-                            }
-                        }
-                    },
-                    TileType::Foliage => {
-                        // Check if foliage should die due to lack of moisture in dirt below
-                        if y > 0 {
-                            let below_idx = (y - 1) * w + x;
-                            let below_tile = &self.tile_map.tiles[below_idx];
-                            
-                            // Foliage dies if the dirt below doesn't have enough moisture
-                            if below_tile.tile_type == TileType::Dirt && 
-                               below_tile.water_amount < FOLIAGE_DEATH_MOISTURE {
-                                changes.push((x, y, TileType::Air));
-                            }
-                        } else {
-                            // Foliage at ground level (y=0) dies immediately (no soil support)
-                            changes.push((x, y, TileType::Air));
-                        }
-                    },
-                    _ => {
-                        // Other tile types don't participate in foliage simulation
-                    }
-                }
-            }
-        }
-        
-        // Apply all changes
-        for (x, y, new_type) in changes {
-            let new_tile = Tile {
-                tile_type: new_type,
-                water_amount: 0, // Foliage and air don't store water
-            };
-            self.tile_map.set_tile(x, y, new_tile);
-            
-            match new_type {
-                TileType::Foliage => console_log!("🌱 Foliage grew at ({}, {})", x, y),
-                TileType::Air => console_log!("🍂 Foliage died at ({}, {})", x, y),
-                _ => {}
-            }
+#[wasm_bindgen]
+pub fn get_tile_at(x: i32, y: i32) -> String {
+    unsafe {
+        if let Some(ref state) = GAME_STATE {
+            state.get_tile_at(x, y)
+        } else {
+            "Air".to_string()
         }
     }
 }
 
-/// Global game state instance
-static mut GAME_STATE: Option<GameState> = None;
-
 #[wasm_bindgen]
-pub fn init_game(world_width_tiles: f64, world_height_tiles: f64) {
-    console_log!("Initializing game with world size: {}x{} tiles", world_width_tiles, world_height_tiles);
+pub fn raycast(x: f64, y: f64, dx: f64, dy: f64, max_dist: f64) -> String {
     unsafe {
-        GAME_STATE = Some(GameState::new(world_width_tiles, world_height_tiles));
+        if let Some(ref state) = GAME_STATE {
+            state.raycast(x, y, dx, dy, max_dist)
+        } else {
+            "null".to_string()
+        }
     }
 }
 
 #[wasm_bindgen]
-pub fn update_game(current_time: f64) -> String {
+pub fn has_line_of_sight(x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
     unsafe {
-        if let Some(ref mut state) = GAME_STATE {
-            state.update(current_time);
-            state.get_state_data()
+        if let Some(ref state) = GAME_STATE {
+            state.has_line_of_sight(x1, y1, x2, y2)
         } else {
-            "{}".to_string()
+            false
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn tick() -> String {
+pub fn get_temperature_at(x: i32, y: i32) -> f32 {
     unsafe {
-        if let Some(ref mut state) = GAME_STATE {
-            state.tick();
-            state.get_state_data()
+        if let Some(ref state) = GAME_STATE {
+            state.get_temperature_at(x, y)
         } else {
-            "{}".to_string()
+            0.0
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn add_promiser() {
+pub fn get_oxygen_at(x: f64, y: f64) -> f64 {
     unsafe {
-        if let Some(ref mut state) = GAME_STATE {
-            state.add_promiser();
+        if let Some(ref state) = GAME_STATE {
+            state.get_oxygen_at(x, y)
+        } else {
+            1.0
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn get_promiser_count() -> usize {
+pub fn get_light_at(x: i32, y: i32) -> f32 {
     unsafe {
         if let Some(ref state) = GAME_STATE {
-            state.promiser_count()
+            state.get_light_at(x, y)
         } else {
-            0
+            0.0
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn make_promiser_think(id: u32) {
+pub fn load_world_from_image(rgba_bytes: &[u8], width: usize, height: usize, palette_json: String) -> bool {
     unsafe {
         if let Some(ref mut state) = GAME_STATE {
-            state.make_promiser_think(id);
+            state.load_world_from_image(rgba_bytes, width, height, palette_json)
+        } else {
+            false
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn make_promiser_speak(id: u32, thought: String) {
+pub fn save_game() -> Vec<u8> {
     unsafe {
-        if let Some(ref mut state) = GAME_STATE {
-            state.make_promiser_speak(id, thought);
+        if let Some(ref state) = GAME_STATE {
+            state.save_game()
+        } else {
+            Vec::new()
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn make_promiser_whisper(id: u32, thought: String, target_id: u32) {
+pub fn load_game(bytes: &[u8]) -> bool {
     unsafe {
         if let Some(ref mut state) = GAME_STATE {
-            state.make_promiser_whisper(id, thought, target_id);
+            state.load_game(bytes)
+        } else {
+            false
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn make_promiser_run(id: u32) {
+pub fn set_autosave(ticks: u32) {
     unsafe {
         if let Some(ref mut state) = GAME_STATE {
-            state.make_promiser_run(id);
+            state.set_autosave(ticks);
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn get_pixel_id() -> u32 {
+pub fn latest_autosave() -> Vec<u8> {
     unsafe {
         if let Some(ref state) = GAME_STATE {
-            state.get_pixel_id()
+            state.latest_autosave()
         } else {
-            0
+            Vec::new()
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn get_random_promiser_id() -> u32 {
+pub fn restore_autosave() -> bool {
     unsafe {
-        if let Some(ref state) = GAME_STATE {
-            state.get_random_promiser_id()
+        if let Some(ref mut state) = GAME_STATE {
+            state.restore_autosave()
         } else {
-            0
+            false
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn place_tile(x: usize, y: usize, tile_type: String) {
+pub fn export_tiles() -> Vec<u8> {
     unsafe {
-        if let Some(ref mut state) = GAME_STATE {
-            state.place_tile(x, y, tile_type);
+        if let Some(ref state) = GAME_STATE {
+            state.export_tiles()
+        } else {
+            Vec::new()
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn get_tile_at(x: usize, y: usize) -> String {
+pub fn import_tiles(bytes: &[u8]) -> bool {
     unsafe {
-        if let Some(ref state) = GAME_STATE {
-            state.get_tile_at(x, y)
+        if let Some(ref mut state) = GAME_STATE {
+            state.import_tiles(bytes)
         } else {
-            "Air".to_string()
+            false
         }
     }
 }
@@ -1229,10 +4403,19 @@ pub fn simulate_foliage() {
     }
 }
 
+#[wasm_bindgen]
+pub fn simulate_air() {
+    unsafe {
+        if let Some(ref mut state) = GAME_STATE {
+            state.simulate_air();
+        }
+    }
+}
+
 // Called when the wasm module is instantiated
 #[wasm_bindgen(start)]
 pub fn main() {
-    console_log!("WASM game module loaded successfully!");
+    console_log!(Info, Core, "WASM game module loaded successfully!");
 }
 
 
@@ -1245,12 +4428,105 @@ pub enum TileType {
     Stone,
     Water,
     Foliage,
+    Ore,
+    Wood,
+    Portal,
+    Ladder,
+    Ice, // Frozen water; keeps its water amount for when it melts (see thermal.rs)
+    Steam, // Evaporated water rising as vapor and gathering as clouds (see steam.rs)
+    Snow, // Fallen snow; its water amount is its depth (see snow.rs)
+    Decay, // Rotting foliage or remains; its water amount is the matter left (see decay.rs)
+    Mud, // Dirt soaked through under a load; its water amount is its moisture (see mud.rs)
+    Quicksand, // Saturated sand that pulls promisers under; its water amount is its saturation (see quicksand.rs)
+    Furnace, // Burns fuel items for heat and light, and cooks items (see furnace.rs)
+    Spikes, // Wounds hostile creatures that walk through (see defenses.rs)
+    PitCover, // Holds promisers, gives way under hostile creatures (see defenses.rs)
+    ReinforcedWall, // Hard to dig, and hostile creatures only slowly chew through (see defenses.rs)
+}
+
+impl TileType {
+    pub fn from_name(name: &str) -> Option<TileType> {
+        match name {
+            "Air" => Some(TileType::Air),
+            "Dirt" => Some(TileType::Dirt),
+            "Stone" => Some(TileType::Stone),
+            "Water" => Some(TileType::Water),
+            "Foliage" => Some(TileType::Foliage),
+            "Ore" => Some(TileType::Ore),
+            "Wood" => Some(TileType::Wood),
+            "Portal" => Some(TileType::Portal),
+            "Ladder" => Some(TileType::Ladder),
+            "Ice" => Some(TileType::Ice),
+            "Steam" => Some(TileType::Steam),
+            "Snow" => Some(TileType::Snow),
+            "Decay" => Some(TileType::Decay),
+            "Mud" => Some(TileType::Mud),
+            "Quicksand" => Some(TileType::Quicksand),
+            "Furnace" => Some(TileType::Furnace),
+            "Spikes" => Some(TileType::Spikes),
+            "PitCover" => Some(TileType::PitCover),
+            "ReinforcedWall" => Some(TileType::ReinforcedWall),
+            _ => None,
+        }
+    }
+
+    /// Tile type from its numeric id (`tile_type as u8`)
+    pub fn from_id(id: u8) -> Option<TileType> {
+        match id {
+            0 => Some(TileType::Air),
+            1 => Some(TileType::Dirt),
+            2 => Some(TileType::Stone),
+            3 => Some(TileType::Water),
+            4 => Some(TileType::Foliage),
+            5 => Some(TileType::Ore),
+            6 => Some(TileType::Wood),
+            7 => Some(TileType::Portal),
+            8 => Some(TileType::Ladder),
+            9 => Some(TileType::Ice),
+            10 => Some(TileType::Steam),
+            11 => Some(TileType::Snow),
+            12 => Some(TileType::Decay),
+            13 => Some(TileType::Mud),
+            14 => Some(TileType::Quicksand),
+            15 => Some(TileType::Furnace),
+            16 => Some(TileType::Spikes),
+            17 => Some(TileType::PitCover),
+            18 => Some(TileType::ReinforcedWall),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileType::Air => "Air",
+            TileType::Dirt => "Dirt",
+            TileType::Stone => "Stone",
+            TileType::Water => "Water",
+            TileType::Foliage => "Foliage",
+            TileType::Ore => "Ore",
+            TileType::Wood => "Wood",
+            TileType::Portal => "Portal",
+            TileType::Ladder => "Ladder",
+            TileType::Ice => "Ice",
+            TileType::Steam => "Steam",
+            TileType::Snow => "Snow",
+            TileType::Decay => "Decay",
+            TileType::Mud => "Mud",
+            TileType::Quicksand => "Quicksand",
+            TileType::Furnace => "Furnace",
+            TileType::Spikes => "Spikes",
+            TileType::PitCover => "PitCover",
+            TileType::ReinforcedWall => "ReinforcedWall",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tile {
     pub tile_type: TileType,
     pub water_amount: u16, // 0 = dry, 1024 = full
+    #[serde(default)]
+    pub variant: u8, // Shape of solid tiles (see shape.rs); 0 = full block. Bit 0x10 marks grassy dirt (see grass.rs)
 }
 
 // Tile map structure
@@ -1259,27 +4535,170 @@ pub struct TileMap {
     pub width: usize,
     pub height: usize,
     pub tiles: Vec<Tile>,
+    pub wrap_x: bool, // Left and right edges connect (toroidal world)
+    pub open_bottom: bool, // Nothing holds things up at y=0; they fall into the void
+    #[serde(default)]
+    pub open_left: bool, // The left edge is void rather than a wall (see borders.rs)
+    #[serde(default)]
+    pub open_right: bool,
+    #[serde(default)]
+    pub open_top: bool,
+    #[serde(skip)]
+    dirty: Vec<usize>, // Indices of tiles modified since the last take_dirty()
+    #[serde(skip)]
+    dirty_mask: Vec<bool>, // Whether each tile is already in `dirty`
+    #[serde(skip)]
+    pub light: Vec<f32>, // Light energy per tile; empty unless rays light this map (see light.rs)
+    #[serde(skip)]
+    pub temperature: Vec<f32>, // °C per tile; empty until the first heat step (see thermal.rs)
+    #[serde(skip)]
+    pub ambient: Vec<f32>, // °C each tile settles toward, as of the last heat step (see thermal.rs)
+    #[serde(skip)]
+    pub fertility: Vec<u8>, // Soil fertility per tile; empty (all natural) until first changed (see fertility.rs); saved through persistence.rs
+    #[serde(skip)]
+    pub oxygen: Vec<f32>, // Oxygen level per air cell; empty until the first air step (see air.rs)
+    #[serde(skip)]
+    pub rain_seconds: f32, // Rain keeps falling this long (see shelter.rs)
+    #[serde(skip)]
+    regions: RegionMap, // Connected open areas; empty until first updated (see regions.rs)
+    #[serde(skip)]
+    pub nav: NavGrid, // Moves promisers can make from each tile; empty until pathfinding needs it (see nav.rs)
+    #[serde(skip)]
+    solidity: Solidity, // Packed solid flags per tile; empty until first built (see solidity.rs)
+    #[serde(skip)]
+    pub cracks: HashMap<usize, f32>, // Digging progress (0.0 to 1.0) per partly dug tile (see mining.rs)
+    #[serde(skip)]
+    pub rules: Rules, // What the tile steps allow, from the config (see config.rs)
 }
 impl TileMap {
     pub fn new(width: usize, height: usize) -> Self {
         let tiles = vec![Tile {
             tile_type: TileType::Air,
             water_amount: 0,
+            variant: 0,
         }; width * height];
-        TileMap { width, height, tiles }
+        TileMap {
+            width,
+            height,
+            dirty_mask: vec![false; tiles.len()],
+            tiles,
+            wrap_x: false,
+            open_bottom: false,
+            open_left: false,
+            open_right: false,
+            open_top: false,
+            dirty: Vec::new(),
+            light: Vec::new(),
+            temperature: Vec::new(),
+            ambient: Vec::new(),
+            fertility: Vec::new(),
+            oxygen: Vec::new(),
+            rain_seconds: 0.0,
+            regions: RegionMap::default(),
+            nav: NavGrid::default(),
+            solidity: Solidity::default(),
+            cracks: HashMap::default(),
+            rules: Rules::default(),
+        }
     }
 
-    pub fn get_tile(&self, x: usize, y: usize) -> Option<&Tile> {
-        if x < self.width && y < self.height {
-            Some(&self.tiles[y * self.width + x])
-        } else {
-            None
+    /// Copy of this map with a new size, with the old tile (0, 0) placed at
+    /// (offset_x, offset_y). Tiles pushed outside are cropped; new space is air.
+    pub fn resized(&self, new_width: usize, new_height: usize, offset_x: i64, offset_y: i64) -> TileMap {
+        let mut map = TileMap::new(new_width, new_height);
+        map.wrap_x = self.wrap_x;
+        map.open_bottom = self.open_bottom;
+        map.open_left = self.open_left;
+        map.open_right = self.open_right;
+        map.open_top = self.open_top;
+        map.rules = self.rules;
+        if !self.fertility.is_empty() {
+            map.fertility = vec![fertility::NATURAL_FERTILITY; new_width * new_height];
+        }
+
+        for y in 0..self.height {
+            let ny = y as i64 + offset_y;
+            if ny < 0 || ny >= new_height as i64 {
+                continue;
+            }
+            for x in 0..self.width {
+                let nx = x as i64 + offset_x;
+                if nx < 0 || nx >= new_width as i64 {
+                    continue;
+                }
+                let (from, to) = (y * self.width + x, ny as usize * new_width + nx as usize);
+                map.tiles[to] = self.tiles[from].clone();
+                if !map.fertility.is_empty() {
+                    map.fertility[to] = self.fertility(from);
+                }
+            }
+        }
+        map
+    }
+
+    /// Index into `tiles` for signed tile coordinates. x wraps around in
+    /// toroidal worlds; anything else outside the map (including negative
+    /// space) has no tile for now.
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if y < 0 || y as usize >= self.height || self.width == 0 {
+            return None;
+        }
+        let x = if self.wrap_x { x.rem_euclid(self.width as i32) } else { x };
+        if x < 0 || x as usize >= self.width {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    /// Horizontal pixel offset from `from_x` to `to_x`, going around the seam
+    /// of a wrapping world when that's shorter
+    pub fn offset_x(&self, from_x: f64, to_x: f64) -> f64 {
+        let dx = to_x - from_x;
+        if !self.wrap_x {
+            return dx;
+        }
+        let world_width = self.width as f64 * TILE_SIZE_PIXELS;
+        dx - world_width * (dx / world_width).round()
+    }
+
+    /// Tile containing the given pixel position
+    pub fn get_tile_at_pixel(&self, px: f64, py: f64) -> Option<&Tile> {
+        let tx = (px / TILE_SIZE_PIXELS).floor() as i32;
+        let ty = (py / TILE_SIZE_PIXELS).floor() as i32;
+        self.get_tile(tx, ty)
+    }
+
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.index(x, y).map(|i| &self.tiles[i])
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        if let Some(i) = self.index(x, y) {
+            self.tiles[i] = tile;
+            self.mark_dirty(i);
+        }
+    }
+
+    /// Record that the tile at index `i` changed
+    pub fn mark_dirty(&mut self, i: usize) {
+        self.refresh_solidity(i);
+        self.refresh_nav_around(i);
+        self.note_region_change(i);
+        self.cracks.remove(&i);
+        if self.dirty_mask.len() != self.tiles.len() {
+            self.dirty_mask = vec![false; self.tiles.len()];
+        }
+        if !self.dirty_mask[i] {
+            self.dirty_mask[i] = true;
+            self.dirty.push(i);
         }
     }
 
-    pub fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
-        if x < self.width && y < self.height {
-            self.tiles[y * self.width + x] = tile;
+    /// Indices of tiles modified since the last call
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        for &i in &self.dirty {
+            self.dirty_mask[i] = false;
         }
+        std::mem::take(&mut self.dirty)
     }
 }
\ No newline at end of file
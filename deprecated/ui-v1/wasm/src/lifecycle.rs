@@ -0,0 +1,97 @@
+//! Aging: promisers grow up, slow down in old age and eventually die of it.
+use crate::{random, Promiser};
+
+pub const SECONDS_PER_YEAR: f64 = 60.0; // Simulated seconds per year of age
+const ADULT_AGE: f64 = 16.0; // Years
+const ELDER_AGE: f64 = 60.0;
+const LIFESPAN_MIN: f64 = 70.0; // Each promiser dies of old age somewhere in 70-90 years
+const LIFESPAN_RANGE: f64 = 20.0;
+const CHILD_MIN_SCALE: f64 = 0.5; // Size of a newborn relative to its adult size
+const ELDER_SCALE: f64 = 0.9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LifeStage {
+    Child,
+    Adult,
+    Elder,
+}
+
+impl LifeStage {
+    pub fn of(years: f64) -> LifeStage {
+        if years < ADULT_AGE {
+            LifeStage::Child
+        } else if years < ELDER_AGE {
+            LifeStage::Adult
+        } else {
+            LifeStage::Elder
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LifeStage::Child => "child",
+            LifeStage::Adult => "adult",
+            LifeStage::Elder => "elder",
+        }
+    }
+
+    /// Movement speed multiplier
+    pub fn speed(self) -> f64 {
+        match self {
+            LifeStage::Child => 1.2,
+            LifeStage::Adult => 1.0,
+            LifeStage::Elder => 0.6,
+        }
+    }
+
+    /// Multiplier on the chance of stopping to think
+    pub fn thoughtfulness(self) -> f64 {
+        match self {
+            LifeStage::Child => 0.5,
+            LifeStage::Adult => 1.0,
+            LifeStage::Elder => 2.0,
+        }
+    }
+}
+
+/// Age in seconds for a promiser starting out as a young-to-middle-aged adult
+pub fn random_adult_age() -> f64 {
+    (ADULT_AGE + random() * 30.0) * SECONDS_PER_YEAR
+}
+
+/// Years a new promiser will live
+pub fn random_lifespan() -> f64 {
+    LIFESPAN_MIN + random() * LIFESPAN_RANGE
+}
+
+impl Promiser {
+    pub(crate) fn years(&self) -> f64 {
+        self.age / SECONDS_PER_YEAR
+    }
+
+    pub(crate) fn life_stage(&self) -> LifeStage {
+        LifeStage::of(self.years())
+    }
+
+    /// Advance age by dt seconds: children grow toward their adult size, elders
+    /// shrink a little, and past its lifespan a promiser dies of old age.
+    /// Pixel doesn't die of old age.
+    pub(crate) fn grow_older(&mut self, dt: f64) {
+        if self.adult_size == 0.0 {
+            self.adult_size = self.size; // Spawned or loaded at full size
+        }
+        self.age += dt;
+
+        let years = self.years();
+        let scale = match LifeStage::of(years) {
+            LifeStage::Child => CHILD_MIN_SCALE + (1.0 - CHILD_MIN_SCALE) * years / ADULT_AGE,
+            LifeStage::Adult => 1.0,
+            LifeStage::Elder => ELDER_SCALE,
+        };
+        self.size = self.adult_size * scale;
+
+        if years >= self.lifespan && !self.is_pixel {
+            self.damage(self.health, "old_age");
+        }
+    }
+}
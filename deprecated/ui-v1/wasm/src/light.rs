@@ -0,0 +1,135 @@
+//! Light ray budgets, optics at the air–water boundary (partial reflection,
+//! and the shimmer the water surface adds to the exported brightness), and
+//! per-tile light energy: rays and glowing promisers deposit energy into
+//! nearby tiles, and it fades once they're gone.
+use crate::{GameState, TileMap, TileType, OVERWORLD, TILE_SIZE_PIXELS};
+
+pub const N_AIR: f64 = 1.0; // Refraction index of air
+pub const N_WATER: f64 = 1.33; // Refraction index of water
+pub const REFLECTED_SHARE: f64 = 0.5; // Intensity a reflected ray takes from the one that made it
+
+const LIGHT_FADE_SECONDS: f64 = 0.5; // Time constant of light energy decay
+const GLOW_RADIUS: f64 = 4.0; // Tiles lit by a promiser glowing at full intensity
+pub const MIN_FOLIAGE_LIGHT: f32 = 0.1; // Foliage only grows into tiles at least this bright
+
+pub const DEFAULT_AMBIENT_FLOOR: f64 = 0.03; // Nights are dim, never pitch black, unless configured
+pub const DEFAULT_MOON_LIGHT: f64 = 0.15;
+pub const DEFAULT_MOON_COLOR: u32 = 0x8CA0D8; // Pale blue
+
+const SHIMMER_AMOUNT: f32 = 0.3; // Brightness swing at the water surface, as a fraction
+const SHIMMER_SPEED: f64 = 2.5; // Radians per second
+
+/// How many light rays a world keeps alive, and how fast they are replaced
+/// (see SimConfig::light_budget)
+#[derive(Clone, Copy, Debug)]
+pub struct LightBudget {
+    pub max_rays: usize,
+    pub spawn_rate: f64, // Fraction of max_rays spawned per second
+    pub ray_speed: f64,  // Pixels per second
+}
+
+/// Light that doesn't come from rays: a floor no tile goes below, and
+/// moonlight on tiles open to the night sky
+#[derive(Clone, Debug)]
+pub struct AmbientLight {
+    pub floor: f32,      // Minimum brightness everywhere, 0.0 to 1.0
+    pub moon: f32,       // Brightness the moon adds at night, 0.0 to 1.0
+    pub moon_color: u32, // RGB tint of moonlight in the lightmap
+}
+
+impl AmbientLight {
+    pub fn new(floor: f64, moon: f64, moon_color: u32) -> AmbientLight {
+        AmbientLight { floor: floor.clamp(0.0, 1.0) as f32, moon: moon.clamp(0.0, 1.0) as f32, moon_color }
+    }
+
+    /// Moon color channels, 0.0 to 1.0 each
+    pub fn moon_rgb(&self) -> [f32; 3] {
+        [16, 8, 0].map(|shift| ((self.moon_color >> shift) & 0xFF) as f32 / 255.0)
+    }
+}
+
+/// Fraction of light reflected when crossing from index n1 into n2
+/// (Schlick's approximation), given the cosine of the angle of incidence
+pub fn reflectance(cos_incident: f64, n1: f64, n2: f64) -> f64 {
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos_incident.abs()).powi(5)
+}
+
+/// Reflect a direction across a surface normal
+pub fn reflect(dir_x: f64, dir_y: f64, normal_x: f64, normal_y: f64) -> (f64, f64) {
+    let dot = dir_x * normal_x + dir_y * normal_y;
+    (dir_x - 2.0 * dot * normal_x, dir_y - 2.0 * dot * normal_y)
+}
+
+/// Brightness multiplier for a tile: water tiles with no water above them
+/// ripple over time, everything else is 1.0
+pub fn shimmer(map: &TileMap, tile_x: i32, tile_y: i32, seconds: f64) -> f32 {
+    let is_water = |y: i32| map.get_tile(tile_x, y).is_some_and(|tile| tile.tile_type == TileType::Water);
+    if !is_water(tile_y) || is_water(tile_y + 1) {
+        return 1.0;
+    }
+    // Two waves at different speeds and wavelengths so the pattern never repeats exactly
+    let x = tile_x as f64;
+    let wave = (seconds * SHIMMER_SPEED + x * 0.7).sin() * (seconds * SHIMMER_SPEED * 0.53 + x * 1.9).sin();
+    1.0 + SHIMMER_AMOUNT * wave as f32
+}
+
+impl TileMap {
+    /// Light energy of a tile, 0.0 (dark) up. None for maps without light
+    /// rays (other dimensions), which count as lit.
+    pub fn light_at(&self, x: i32, y: i32) -> Option<f32> {
+        let i = self.index(x, y)?;
+        self.light.get(i).copied()
+    }
+
+    /// Brightness of the tile at index `i` for rendering, 0.0 to 1.0
+    pub fn brightness(&self, i: usize) -> f32 {
+        self.light.get(i).map_or(0.0, |energy| energy.min(1.0))
+    }
+}
+
+impl GameState {
+    /// Fade the overworld's light energy, then let every ray add to the tile
+    /// it's in and glowing promisers light the tiles around them. A ray
+    /// sitting in a tile brings it to the ray's intensity.
+    pub(crate) fn accumulate_light(&mut self, dt: f64) {
+        let map = &mut self.tile_map;
+        if map.light.len() != map.tiles.len() {
+            // Allocated on first use, so maps without rays stay unlit
+            map.light = vec![0.0; map.tiles.len()];
+        }
+        let fade = (-dt / LIGHT_FADE_SECONDS).exp() as f32;
+        for energy in &mut map.light {
+            *energy *= fade;
+        }
+        let deposit = (1.0 - fade) as f64;
+        for ray in &self.light_rays {
+            let tile_x = (ray.x / TILE_SIZE_PIXELS).floor() as i32;
+            let tile_y = (ray.y / TILE_SIZE_PIXELS).floor() as i32;
+            if let Some(i) = map.index(tile_x, tile_y) {
+                map.light[i] += (ray.intensity * deposit) as f32;
+            }
+        }
+
+        for promiser in self.promisers.values().filter(|p| p.glow > 0.0 && p.dimension == OVERWORLD) {
+            // Brightest on the promiser's own tile, fading out to the edge of
+            // the radius; walls block the glow
+            let radius = GLOW_RADIUS * promiser.glow;
+            let center_x = (promiser.x / TILE_SIZE_PIXELS).floor() as i32;
+            let center_y = (promiser.y / TILE_SIZE_PIXELS).floor() as i32;
+            let reach = radius.ceil() as i32;
+            for tile_y in center_y - reach..=center_y + reach {
+                for tile_x in center_x - reach..=center_x + reach {
+                    let distance = ((tile_x - center_x) as f64).hypot((tile_y - center_y) as f64);
+                    let Some(i) = map.index(tile_x, tile_y).filter(|_| distance < radius) else { continue };
+                    let tile_center_x = (tile_x as f64 + 0.5) * TILE_SIZE_PIXELS;
+                    let tile_center_y = (tile_y as f64 + 0.5) * TILE_SIZE_PIXELS;
+                    let blocked = distance >= 1.0 && !map.line_of_sight(promiser.x, promiser.y, tile_center_x, tile_center_y);
+                    if !blocked {
+                        map.light[i] += (promiser.glow * (1.0 - distance / radius) * deposit) as f32;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Level of detail for big worlds. The overworld is split into square zones;
+//! the front end tells the simulation what its cameras (or anything else
+//! subscribed to part of the world) are looking at with set_view, and zones
+//! near a view are active. Inactive zones step water and foliage only every
+//! LOD_INTERVAL steps (so time runs slower there) and have no light rays at
+//! all. A zone keeps count of the steps it skipped (up to MAX_MISSED_STEPS,
+//! past which it stays behind), and once it turns active it makes them up a
+//! few extra passes per step, so it's soon in step with its neighbors again
+//! without one step doing a pile of full-map passes. With no views set,
+//! every zone is active.
+use crate::determinism::HashMap;
+use crate::{TileMap, TILE_SIZE_PIXELS};
+
+pub const LOD_ZONE_TILES: usize = 32; // Zone width and height
+const LOD_INTERVAL: u64 = 8; // Inactive zones step once every this many steps
+const VIEW_MARGIN_ZONES: f64 = 1.0; // Zones this far around a view are active too
+const MAX_MISSED_STEPS: u32 = 256; // Most skipped steps a zone keeps count of
+const CATCH_UP_PASSES: u32 = 2; // Most extra passes per step for zones catching up
+
+/// A rectangle of the overworld someone is looking at (pixels)
+#[derive(Clone, Copy, Debug)]
+pub struct View {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Which zones of a map take part in a step
+pub struct ZoneMask {
+    zones_wide: usize,
+    stepping: Vec<bool>,
+}
+
+impl ZoneMask {
+    /// Whether tile (x, y) takes part
+    pub fn steps(&self, x: usize, y: usize) -> bool {
+        self.stepping.get((y / LOD_ZONE_TILES) * self.zones_wide + x / LOD_ZONE_TILES).copied().unwrap_or(true)
+    }
+
+    pub fn steps_at_pixel(&self, px: f64, py: f64) -> bool {
+        if px < 0.0 || py < 0.0 {
+            return true; // Off the map; the borders decide (see borders.rs)
+        }
+        self.steps((px / TILE_SIZE_PIXELS) as usize, (py / TILE_SIZE_PIXELS) as usize)
+    }
+}
+
+// Steps skipped per zone, for one system
+#[derive(Default)]
+struct Schedule {
+    step: u64,
+    missed: Vec<u32>,
+}
+
+impl Schedule {
+    // Masks to step with this time, in order: catch-up passes for active
+    // zones still behind, then this step's
+    fn plan(&mut self, active: &ZoneMask) -> Vec<ZoneMask> {
+        if self.missed.len() != active.stepping.len() {
+            self.missed = vec![0; active.stepping.len()];
+        }
+        self.step += 1;
+        let catching_up: Vec<u32> = active.stepping.iter().zip(&self.missed)
+            .map(|(&active, &missed)| if active { missed.min(CATCH_UP_PASSES) } else { 0 })
+            .collect();
+        let passes = catching_up.iter().copied().max().unwrap_or(0);
+        let mut masks: Vec<ZoneMask> = (0..passes)
+            .map(|pass| ZoneMask { zones_wide: active.zones_wide, stepping: catching_up.iter().map(|&missed| missed > pass).collect() })
+            .collect();
+        for (missed, caught_up) in self.missed.iter_mut().zip(catching_up) {
+            *missed -= caught_up;
+        }
+
+        // Beat steps only keep inactive zones moving; they don't make up
+        // for the steps skipped in between
+        let on_beat = self.step.is_multiple_of(LOD_INTERVAL);
+        let stepping: Vec<bool> = active.stepping.iter().map(|&active| active || on_beat).collect();
+        for (missed, &steps) in self.missed.iter_mut().zip(&stepping) {
+            if !steps {
+                *missed = (*missed + 1).min(MAX_MISSED_STEPS);
+            }
+        }
+        masks.push(ZoneMask { zones_wide: active.zones_wide, stepping });
+        masks
+    }
+}
+
+/// Views and per-system schedules for the overworld
+#[derive(Default)]
+pub struct Lod {
+    views: HashMap<u32, View>,
+    water: Schedule,
+    foliage: Schedule,
+}
+
+impl Lod {
+    pub fn set_view(&mut self, id: u32, view: View) {
+        self.views.insert(id, view);
+    }
+
+    pub fn remove_view(&mut self, id: u32) -> bool {
+        self.views.remove(&id).is_some()
+    }
+
+    /// Zones near any view, or None when there are no views (everything's active)
+    pub fn active_zones(&self, map: &TileMap) -> Option<ZoneMask> {
+        if self.views.is_empty() {
+            return None;
+        }
+        let zones_wide = map.width.div_ceil(LOD_ZONE_TILES);
+        let zones_high = map.height.div_ceil(LOD_ZONE_TILES);
+        let zone_size = LOD_ZONE_TILES as f64 * TILE_SIZE_PIXELS;
+        let margin = VIEW_MARGIN_ZONES * zone_size;
+        let stepping = (0..zones_wide * zones_high)
+            .map(|zone| {
+                let center_x = ((zone % zones_wide) as f64 + 0.5) * zone_size;
+                let center_y = ((zone / zones_wide) as f64 + 0.5) * zone_size;
+                self.views.values().any(|view| {
+                    let gap_x = map.offset_x(center_x, view.x + view.width / 2.0).abs() - (view.width + zone_size) / 2.0;
+                    let gap_y = (view.y + view.height / 2.0 - center_y).abs() - (view.height + zone_size) / 2.0;
+                    gap_x <= margin && gap_y <= margin
+                })
+            })
+            .collect();
+        Some(ZoneMask { zones_wide, stepping })
+    }
+
+    /// Water step masks for this time (see Schedule::plan); one full step
+    /// when there are no views
+    pub fn water_steps(&mut self, map: &TileMap) -> Vec<Option<ZoneMask>> {
+        match self.active_zones(map) {
+            Some(active) => self.water.plan(&active).into_iter().map(Some).collect(),
+            None => vec![None],
+        }
+    }
+
+    /// Foliage step masks for this time, like water_steps
+    pub fn foliage_steps(&mut self, map: &TileMap) -> Vec<Option<ZoneMask>> {
+        match self.active_zones(map) {
+            Some(active) => self.foliage.plan(&active).into_iter().map(Some).collect(),
+            None => vec![None],
+        }
+    }
+}
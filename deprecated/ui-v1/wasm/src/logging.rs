@@ -0,0 +1,78 @@
+//! Log levels for console_log!. Every message has a level and a subsystem;
+//! it's only formatted and sent to console.log if its level is at or below
+//! the current one and its subsystem isn't muted. The default level is Info,
+//! which leaves out per-tile chatter (placed tiles, foliage growing and dying,
+//! worldgen details) that is slow to log in hot paths.
+//!
+//! The settings are global rather than per GameState so messages from
+//! anywhere (config parsing, the tile map) can be filtered.
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Info,
+    Debug,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    Core,      // Module loading, config, scripted sequences and emitters
+    World,     // World creation, generation, resizing and dimensions
+    Tiles,     // Tile edits
+    Foliage,   // Foliage growth and death
+    Promisers, // Spawning, deaths and per-promiser settings
+    Save,      // Saves, snapshots, image imports and exports
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static MUTED: AtomicU32 = AtomicU32::new(0); // One bit per Subsystem
+
+impl LogLevel {
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        match name {
+            "off" => Some(LogLevel::Off),
+            "error" => Some(LogLevel::Error),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            _ => None,
+        }
+    }
+}
+
+impl Subsystem {
+    pub fn from_name(name: &str) -> Option<Subsystem> {
+        match name {
+            "core" => Some(Subsystem::Core),
+            "world" => Some(Subsystem::World),
+            "tiles" => Some(Subsystem::Tiles),
+            "foliage" => Some(Subsystem::Foliage),
+            "promisers" => Some(Subsystem::Promisers),
+            "save" => Some(Subsystem::Save),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Mute or unmute one subsystem, whatever the level
+pub fn set_enabled(subsystem: Subsystem, enabled: bool) {
+    if enabled {
+        MUTED.fetch_and(!subsystem.bit(), Ordering::Relaxed);
+    } else {
+        MUTED.fetch_or(subsystem.bit(), Ordering::Relaxed);
+    }
+}
+
+/// Whether a message at `level` from `subsystem` gets logged
+pub fn enabled(level: LogLevel, subsystem: Subsystem) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed) && MUTED.load(Ordering::Relaxed) & subsystem.bit() == 0
+}
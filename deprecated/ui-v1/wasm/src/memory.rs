@@ -0,0 +1,48 @@
+//! Coarse memory of where a promiser has been, used to nudge wandering toward
+//! places it hasn't seen yet.
+use serde::{Deserialize, Serialize};
+
+use crate::determinism::HashSet;
+use crate::TILE_SIZE_PIXELS;
+
+/// Edge of a memory cell in tiles; remembering single tiles would be noise
+pub const CELL_TILES: usize = 4;
+const CELL_PIXELS: f64 = CELL_TILES as f64 * TILE_SIZE_PIXELS;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VisitedMemory {
+    cells: HashSet<(u32, i32, i32)>, // (dimension, cell x, cell y)
+}
+
+fn cell_of(x: f64, y: f64) -> (i32, i32) {
+    ((x / CELL_PIXELS).floor() as i32, (y / CELL_PIXELS).floor() as i32)
+}
+
+impl VisitedMemory {
+    pub fn visit(&mut self, dimension: u32, x: f64, y: f64) {
+        let (cx, cy) = cell_of(x, y);
+        self.cells.insert((dimension, cx, cy));
+    }
+
+    pub fn cells_in(&self, dimension: u32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells.iter().filter(move |cell| cell.0 == dimension).map(|&(_, cx, cy)| (cx, cy))
+    }
+
+    /// -1.0 if only the cell to the left is unexplored, 1.0 if only the one to
+    /// the right is, 0.0 otherwise. Beyond the edge of a `cells_wide` world
+    /// counts as explored unless the world wraps.
+    pub fn explore_bias(&self, dimension: u32, x: f64, y: f64, cells_wide: i32, wrap: bool) -> f64 {
+        let (cx, cy) = cell_of(x, y);
+        let unexplored = |cx: i32| {
+            let cx = if wrap { cx.rem_euclid(cells_wide.max(1)) } else { cx };
+            (0..cells_wide).contains(&cx) && !self.cells.contains(&(dimension, cx, cy))
+        };
+        let left_new = unexplored(cx - 1);
+        let right_new = unexplored(cx + 1);
+        match (left_new, right_new) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
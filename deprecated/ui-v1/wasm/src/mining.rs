@@ -0,0 +1,60 @@
+//! Mining: breaking a tile takes work. Every diggable tile type has a
+//! hardness, the seconds of bare-handed digging it takes; progress on a tile
+//! builds up tick by tick (as cracks renderers can draw) until it breaks, and
+//! is lost if the tile changes in the meantime. Pixel mines whatever tile the
+//! player is holding it to (see player.rs), promisers dig out tiles they're
+//! ordered to (Task::Dig). The editor's dig_tile still breaks tiles at once.
+use std::io::{self, Write};
+
+use crate::items::ItemKind;
+use crate::{Tile, TileMap, TileType};
+
+pub const DIG_REACH: f64 = 48.0; // How close a digger has to be to the tile (pixels)
+
+/// Seconds of digging a tile of this type takes, None if it can't be dug
+pub fn hardness(tile_type: TileType) -> Option<f32> {
+    match tile_type {
+        TileType::Foliage | TileType::Ladder | TileType::Mud | TileType::PitCover => Some(0.5),
+        TileType::Dirt | TileType::Spikes => Some(1.0),
+        TileType::Wood => Some(1.5),
+        TileType::Stone | TileType::Furnace => Some(3.0),
+        TileType::Ore => Some(4.0),
+        TileType::ReinforcedWall => Some(8.0),
+        TileType::Air | TileType::Water | TileType::Portal | TileType::Ice | TileType::Steam | TileType::Snow | TileType::Decay | TileType::Quicksand => None,
+    }
+}
+
+impl TileMap {
+    /// Whether tile (x, y) is something that can be dug out
+    pub fn can_mine(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_some_and(|tile| hardness(tile.tile_type).is_some() && ItemKind::from_tile(tile.tile_type).is_some())
+    }
+
+    /// Dig at tile (x, y) for `seconds`. Returns what it yields once it
+    /// breaks, leaving air.
+    pub fn mine(&mut self, x: i32, y: i32, seconds: f32) -> Option<ItemKind> {
+        let i = self.index(x, y)?;
+        let tile_type = self.tiles[i].tile_type;
+        let (hardness, kind) = (hardness(tile_type)?, ItemKind::from_tile(tile_type)?);
+        let progress = self.cracks.entry(i).or_insert(0.0);
+        *progress += seconds / hardness;
+        if *progress < 1.0 {
+            return None;
+        }
+        self.set_tile(x, y, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 }); // Clears the crack
+        Some(kind)
+    }
+
+    /// Partly dug tiles as JSON [[x, y, progress], ...], progress 0.0 to 1.0,
+    /// sorted in `scratch` (see export.rs)
+    pub fn write_cracks(&self, out: &mut impl Write, scratch: &mut Vec<(usize, f32)>) -> io::Result<()> {
+        scratch.clear();
+        scratch.extend(self.cracks.iter().map(|(&i, &progress)| (i, progress)));
+        scratch.sort_unstable_by_key(|&(i, _)| i);
+        out.write_all(b"[")?;
+        for (n, &(i, progress)) in scratch.iter().enumerate() {
+            write!(out, "{}[{},{},{:.2}]", if n > 0 { "," } else { "" }, i % self.width, i / self.width, progress)?;
+        }
+        out.write_all(b"]")
+    }
+}
@@ -0,0 +1,101 @@
+//! Moles: creatures that live in the dirt and dig their own tunnels through
+//! it, a tile at a time, the same way promisers dig (see mining.rs), only
+//! slower and keeping nothing they dig out. They meander mostly level, turn
+//! back from stone and leave the crust under open sky alone, so over time
+//! their tunnels grow into networks of small caves. Dirt that borders water
+//! usually turns them back too, but now and then one digs through and the
+//! water floods its tunnels.
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::{dimension, random, GameState, Promiser, TileMap, TileType, TILE_SIZE_PIXELS};
+
+const DIG_SPEED: f32 = 0.1; // Of bare-handed digging (see tools.rs): 10 seconds a dirt tile
+const MAX_CLIMB: f64 = 0.5; // Radians off level a mole heads at most
+const BREACH_CHANCE: f64 = 0.05; // Each time a mole comes up against dirt next to water
+
+impl CreatureKind {
+    // A burrowing heading: mostly level, either way
+    pub(crate) fn burrow_heading(self) -> (f64, f64) {
+        let angle = (random() - 0.5) * 2.0 * MAX_CLIMB;
+        let direction = if random() < 0.5 { -1.0 } else { 1.0 };
+        (self.speed() * angle.cos() * direction, self.speed() * angle.sin())
+    }
+}
+
+impl TileMap {
+    /// Whether tile (x, y) is dirt a mole will dig: not part of the crust
+    /// under open sky
+    pub fn is_burrowable(&self, x: i32, y: i32) -> bool {
+        let surface = self.get_tile(x, y + 1).is_some_and(|tile| !Promiser::is_solid_tile(tile.tile_type)) && !self.is_sheltered(x, y + 1);
+        self.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Dirt) && !surface
+    }
+
+    /// Whether tile (x, y) borders water
+    pub fn is_wet(&self, x: i32, y: i32) -> bool {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+            .any(|&(dx, dy)| self.get_tile(x + dx, y + dy).is_some_and(|tile| tile.tile_type == TileType::Water))
+    }
+
+    /// Whether tile (x, y) is open with something solid around it for a
+    /// mole to cling to, like its tunnels
+    pub fn is_burrow(&self, x: i32, y: i32) -> bool {
+        let solid = |x, y| self.get_tile(x, y).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type));
+        !solid(x, y) && (-1..=1).any(|dy| (-1..=1).any(|dx| (dx, dy) != (0, 0) && solid(x + dx, y + dy)))
+    }
+}
+
+impl GameState {
+    /// Have every mole dig into the dirt ahead of it, or turn from whatever
+    /// it can't dig, before the creatures move
+    pub(crate) fn update_moles(&mut self, dt: f64) {
+        let moles: Vec<u32> = self.ecs.creatures.iter()
+            .filter(|&(_, &kind)| kind == CreatureKind::Mole)
+            .map(|(id, _)| id)
+            .collect();
+        let reach = CreatureKind::Mole.size() + 2.0;
+        for id in moles {
+            let Some(&body) = self.ecs.bodies.get(id) else { continue };
+            let here = ((body.x / TILE_SIZE_PIXELS).floor() as i32, (body.y / TILE_SIZE_PIXELS).floor() as i32);
+            let (heading_x, heading_y) = match self.ecs.burrows.get(id) {
+                Some(&heading) => heading,
+                None if body.vx != 0.0 || body.vy != 0.0 => (body.vx, body.vy),
+                None => CreatureKind::Mole.burrow_heading(),
+            };
+            let length = heading_x.hypot(heading_y).max(1.0);
+            let ahead_x = body.x + heading_x / length * reach;
+            let ahead_y = body.y + heading_y / length * reach;
+            let ahead = ((ahead_x / TILE_SIZE_PIXELS).floor() as i32, (ahead_y / TILE_SIZE_PIXELS).floor() as i32);
+
+            let Some(map) = dimension::dimension_map_mut(&mut self.tile_map, &mut self.dimensions, body.dimension) else { continue };
+            // Free itself first if it's in the dirt (spawned there, or buried)
+            let target = if map.is_burrowable(here.0, here.1) { here } else { ahead };
+            let committed = self.ecs.burrows.get(id).is_some();
+            let dig = map.is_burrowable(target.0, target.1) && (committed || !map.is_wet(target.0, target.1) || random() < BREACH_CHANCE);
+            if !dig {
+                // Turn from dirt it won't dig; the creature update bounces it
+                // off stone and moves it along open tunnel
+                self.ecs.burrows.remove(id);
+                if map.get_tile(ahead.0, ahead.1).is_some_and(|tile| tile.tile_type == TileType::Dirt) {
+                    let body = self.ecs.bodies.get_mut(id).unwrap();
+                    (body.vx, body.vy) = CreatureKind::Mole.burrow_heading();
+                }
+                continue;
+            }
+
+            let broken = map.mine(target.0, target.1, dt as f32 * DIG_SPEED).is_some();
+            let breached = broken && map.is_wet(target.0, target.1);
+            let body = self.ecs.bodies.get_mut(id).unwrap();
+            if broken {
+                self.ecs.burrows.remove(id);
+                (body.vx, body.vy) = (heading_x, heading_y);
+            } else {
+                // Hold still while digging, keeping the heading for after
+                self.ecs.burrows.insert(id, (heading_x, heading_y));
+                (body.vx, body.vy) = (0.0, 0.0);
+            }
+            if breached {
+                self.events.push(SimEvent::Breached { x: target.0, y: target.1 });
+            }
+        }
+    }
+}
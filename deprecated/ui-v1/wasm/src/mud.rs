@@ -0,0 +1,70 @@
+//! Mud: dirt soaked through (MUD_MOISTURE or wetter) turns to mud once it's
+//! under a load, water pooled on it or a promiser standing on it. Mud isn't
+//! solid; promisers wading in it are slowed and sink through it slowly until
+//! they reach firm ground. With nothing wet on top it dries a little every
+//! water step and turns back into dirt once it's below DRY_MOISTURE. Like
+//! dirt, a mud tile's water amount is its moisture; water pools on mud
+//! rather than flowing into it.
+use crate::{GameState, Promiser, Tile, TileMap, TileType, MAX_DIRT_MOISTURE, TILE_SIZE_PIXELS};
+
+pub const MUD_MOISTURE: u16 = MAX_DIRT_MOISTURE * 7 / 8; // Dirt this wet turns to mud under load
+const DRY_MOISTURE: u16 = MAX_DIRT_MOISTURE / 2; // Mud drier than this is dirt again
+const DRYING_RATE: u16 = 1; // Moisture mud loses per water step with nothing wet on it
+const MUD_SPEED: f64 = 0.4; // Speed multiplier wading through mud
+const MUD_SINK_SPEED: f64 = 0.5; // Fastest a promiser sinks through mud (velocity units)
+
+impl TileMap {
+    pub fn is_mud_at_pixel(&self, px: f64, py: f64) -> bool {
+        self.get_tile_at_pixel(px, py).is_some_and(|tile| tile.tile_type == TileType::Mud)
+    }
+
+    /// Turn saturated dirt under a load into mud, and dry out mud. `trodden`
+    /// are the tiles promisers are standing on.
+    pub fn step_mud(&mut self, trodden: &[(i32, i32)]) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        for y in 0..h {
+            for x in 0..w {
+                let tile = &self.tiles[(y * w + x) as usize];
+                let wet_above = self.get_tile(x, y + 1).is_some_and(|above| above.tile_type == TileType::Water);
+                match tile.tile_type {
+                    TileType::Dirt if tile.water_amount >= MUD_MOISTURE && (wet_above || trodden.contains(&(x, y))) => {
+                        let water_amount = tile.water_amount;
+                        self.set_tile(x, y, Tile { tile_type: TileType::Mud, water_amount, variant: 0 });
+                    }
+                    TileType::Mud if !wet_above => {
+                        let water_amount = tile.water_amount.saturating_sub(DRYING_RATE);
+                        let tile_type = if water_amount < DRY_MOISTURE { TileType::Dirt } else { TileType::Mud };
+                        self.set_tile(x, y, Tile { tile_type, water_amount, variant: 0 });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Tiles promisers in a dimension are standing on
+    pub(crate) fn trodden_tiles(&self, dimension: u32) -> Vec<(i32, i32)> {
+        self.promisers.values().filter(|p| p.dimension == dimension).map(Promiser::footing_tile).collect()
+    }
+}
+
+impl Promiser {
+    /// Tile a promiser standing here has under its feet
+    pub(crate) fn footing_tile(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE_PIXELS).floor() as i32, ((self.y - self.size - 1.0) / TILE_SIZE_PIXELS).floor() as i32)
+    }
+
+    /// Speed multiplier for wading through mud
+    pub(crate) fn mud_speed(&self, map: &TileMap) -> f64 {
+        if map.is_mud_at_pixel(self.x, self.y) { MUD_SPEED } else { 1.0 }
+    }
+
+    /// Sink through mud slowly instead of falling
+    pub(crate) fn sink_in_mud(&mut self, map: &TileMap) {
+        if map.is_mud_at_pixel(self.x, self.y) {
+            self.vy = self.vy.max(-MUD_SINK_SPEED);
+        }
+    }
+}
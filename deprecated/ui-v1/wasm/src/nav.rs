@@ -0,0 +1,107 @@
+//! Navigation data: for every tile, which neighboring tiles a walking
+//! promiser can get to from it. Promisers walk between open tiles and into
+//! half tiles and slopes, step up from those onto the next tile, climb
+//! ladders and drop off ledges; they can't jump, so steps and ladders are the
+//! only links upward.
+//!
+//! The moves are cached per tile map, built in full the first time something
+//! needs them (see flow.rs) and then kept current by recomputing only the
+//! tiles around each changed one (TileMap::mark_dirty). Every change to the
+//! moves bumps the grid's revision, so anything derived from them knows when
+//! it's out of date.
+use crate::shape::TileShape;
+use crate::{Promiser, TileMap};
+
+/// Moves in tiles, in the order of the bits in NavGrid::moves
+pub const NEIGHBORS: [(i32, i32); 6] = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, 1), (1, 1)];
+
+#[derive(Clone, Debug, Default)]
+pub struct NavGrid {
+    moves: Vec<u8>, // Per tile, bit n set if NEIGHBORS[n] is a move from it; empty until built
+    pub revision: u64, // Bumped whenever any tile's moves change
+}
+
+impl TileMap {
+    // Tiles a promiser can be in: open ones, and steps
+    fn is_passable(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_some_and(|tile| !Promiser::is_solid_tile(tile.tile_type)) || self.is_step(x, y)
+    }
+
+    // Half tiles and slopes, which promisers stand in the top part of
+    fn is_step(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_some_and(|tile| {
+            Promiser::is_solid_tile(tile.tile_type) && TileShape::from_variant(tile.variant) != TileShape::Full
+        })
+    }
+
+    pub fn is_climbable(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_some_and(|tile| Promiser::is_climbable_tile(tile.tile_type))
+    }
+
+    // Whether a promiser in tile (x, y) can get to the neighboring tile `dx`,
+    // `dy` away in one step: falling if nothing holds it up, otherwise
+    // walking, stepping up or climbing
+    fn compute_move(&self, x: i32, y: i32, (dx, dy): (i32, i32)) -> bool {
+        if !self.is_passable(x, y) || !self.is_passable(x + dx, y + dy) {
+            return false;
+        }
+        let climbing = self.is_climbable(x, y);
+        let on_step = self.is_step(x, y);
+        let standing = climbing || on_step || !self.is_passable(x, y - 1);
+        match (dx, dy) {
+            (0, -1) => !on_step, // Falling, or climbing down
+            _ if !standing => false,
+            (0, 1) => climbing,
+            (-1 | 1, 0) => true,
+            (-1 | 1, 1) => on_step && self.is_passable(x, y + 1),
+            _ => false,
+        }
+    }
+
+    fn compute_moves(&self, x: i32, y: i32) -> u8 {
+        NEIGHBORS.iter().enumerate()
+            .filter(|&(_, &step)| self.compute_move(x, y, step))
+            .fold(0, |moves, (n, _)| moves | 1 << n)
+    }
+
+    /// Build the navigation grid if it isn't yet (or the map was resized)
+    pub fn ensure_nav(&mut self) {
+        if self.nav.moves.len() == self.tiles.len() {
+            return;
+        }
+        let moves = (0..self.tiles.len())
+            .map(|i| self.compute_moves((i % self.width) as i32, (i / self.width) as i32))
+            .collect();
+        self.nav.moves = moves;
+        self.nav.revision += 1;
+    }
+
+    /// Whether a promiser in tile (x, y) can move by `step` (one of
+    /// NEIGHBORS), from the cache once it's built
+    pub fn can_move(&self, x: i32, y: i32, step: (i32, i32)) -> bool {
+        let Some(i) = self.index(x, y) else { return false };
+        match (self.nav.moves.get(i), NEIGHBORS.iter().position(|&n| n == step)) {
+            (Some(&moves), Some(n)) if self.nav.moves.len() == self.tiles.len() => moves & 1 << n != 0,
+            _ => self.compute_move(x, y, step),
+        }
+    }
+
+    /// Recompute the cached moves of the tiles whose moves depend on tile
+    /// index `i` (the ones around it), if the grid is built
+    pub(crate) fn refresh_nav_around(&mut self, i: usize) {
+        if self.nav.moves.len() != self.tiles.len() {
+            return;
+        }
+        let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                let Some(n) = self.index(nx, ny) else { continue };
+                let moves = self.compute_moves(nx, ny);
+                if self.nav.moves[n] != moves {
+                    self.nav.moves[n] = moves;
+                    self.nav.revision += 1;
+                }
+            }
+        }
+    }
+}
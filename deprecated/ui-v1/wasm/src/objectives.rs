@@ -0,0 +1,160 @@
+//! Objectives: goals set from JS, or by the director (see director.rs), and
+//! checked every tick. An objective stays active until its goal is met or it
+//! fails (someone it was keeping alive died, or its time ran out), raising an
+//! event either way; finished ones stay listed with their status until
+//! they're removed.
+use serde::{Deserialize, Serialize};
+
+use crate::events::SimEvent;
+use crate::{GameState, Promiser, TileMap, TileType, OVERWORLD, TILE_SIZE_PIXELS};
+
+/// What an objective asks for
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Goal {
+    /// At least `count` foliage tiles in the overworld
+    Foliage { count: usize },
+    /// Nobody alive when it was set dies for `seconds`
+    Survive { seconds: f64 },
+    /// Nobody alive when it was set dies until rain has come and gone
+    Storm,
+    /// A promiser gets `depth` tiles below the overworld's surface as it was
+    /// when the objective was set
+    Depth { depth: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Active,
+    Completed,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Objective {
+    pub id: u32,
+    pub label: String,
+    #[serde(flatten)]
+    pub goal: Goal,
+    pub time_limit: Option<f64>, // Seconds to meet it in; no limit when omitted
+    pub elapsed: f64,
+    pub progress: f64, // 0.0 to 1.0
+    pub status: Status,
+    #[serde(skip)]
+    everyone: Vec<u32>, // Promisers alive when it was set
+    #[serde(skip)]
+    rained: bool, // Whether the rain has come yet, for storms
+    #[serde(skip)]
+    surface: Vec<Option<i32>>, // Top solid tile of each overworld column when it was set, for depths
+}
+
+/// An objective as JS sets it, e.g. {"type": "foliage", "count": 50,
+/// "label": "Grow a forest", "time_limit": 600}
+#[derive(Clone, Debug, Deserialize)]
+pub struct ObjectiveSpec {
+    #[serde(default)]
+    pub label: String,
+    #[serde(flatten)]
+    pub goal: Goal,
+    #[serde(default)]
+    pub time_limit: Option<f64>,
+}
+
+impl TileMap {
+    /// How many tiles of a type there are
+    pub fn count_tiles(&self, tile_type: TileType) -> usize {
+        self.tiles.iter().filter(|tile| tile.tile_type == tile_type).count()
+    }
+
+    /// The top solid tile of every column, if it has one
+    pub fn surface(&self) -> Vec<Option<i32>> {
+        (0..self.width as i32)
+            .map(|x| (0..self.height as i32).rev().find(|&y| self.get_tile(x, y).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type))))
+            .collect()
+    }
+}
+
+impl Objective {
+    // How many tiles below the surface it was set with the pixel position is,
+    // 0.0 at or above it
+    fn depth_at(&self, x: f64, y: f64) -> f64 {
+        let column = (x / TILE_SIZE_PIXELS).floor();
+        let surface = (column >= 0.0).then(|| self.surface.get(column as usize).copied().flatten()).flatten();
+        surface.map_or(0.0, |top| (top as f64 + 1.0 - y / TILE_SIZE_PIXELS).max(0.0))
+    }
+}
+
+impl GameState {
+    /// Start tracking an objective; returns its id
+    pub(crate) fn set_objective(&mut self, spec: ObjectiveSpec) -> u32 {
+        let id = self.next_objective_id;
+        self.next_objective_id += 1;
+        let mut everyone: Vec<u32> = self.promisers.keys().copied().collect();
+        everyone.sort_unstable();
+        let surface = if matches!(spec.goal, Goal::Depth { .. }) { self.tile_map.surface() } else { Vec::new() };
+        self.objectives.push(Objective {
+            id,
+            label: spec.label,
+            goal: spec.goal,
+            time_limit: spec.time_limit,
+            elapsed: 0.0,
+            progress: 0.0,
+            status: Status::Active,
+            everyone,
+            rained: false,
+            surface,
+        });
+        id
+    }
+
+    /// Check every active objective, marking the ones met or failed
+    pub(crate) fn update_objectives(&mut self, dt: f64) {
+        if self.objectives.iter().all(|objective| objective.status != Status::Active) {
+            return;
+        }
+        let raining = self.tile_map.is_raining();
+        let foliage = self.objectives.iter()
+            .any(|objective| objective.status == Status::Active && matches!(objective.goal, Goal::Foliage { .. }))
+            .then(|| self.tile_map.count_tiles(TileType::Foliage));
+        let overworld: Vec<(f64, f64)> = self.promisers.values().filter(|p| p.dimension == OVERWORLD).map(|p| (p.x, p.y)).collect();
+
+        for objective in &mut self.objectives {
+            if objective.status != Status::Active {
+                continue;
+            }
+            objective.elapsed += dt;
+            let lost = objective.everyone.iter().any(|id| !self.promisers.contains_key(id));
+            let progress = match objective.goal {
+                Goal::Foliage { count } => foliage.unwrap_or(0) as f64 / count.max(1) as f64,
+                Goal::Survive { seconds } => objective.elapsed / seconds.max(f64::EPSILON),
+                Goal::Storm => {
+                    objective.rained |= raining;
+                    if !objective.rained { 0.0 } else if raining { 0.5 } else { 1.0 }
+                }
+                Goal::Depth { depth } => {
+                    let deepest = overworld.iter().map(|&(x, y)| objective.depth_at(x, y)).fold(0.0, f64::max);
+                    objective.progress.max(deepest / depth.max(1) as f64)
+                }
+            };
+            objective.progress = progress.min(1.0);
+
+            let keeps_alive = matches!(objective.goal, Goal::Survive { .. } | Goal::Storm);
+            let failure = if keeps_alive && lost {
+                Some("death")
+            } else if objective.progress < 1.0 && objective.time_limit.is_some_and(|limit| objective.elapsed >= limit) {
+                Some("time")
+            } else {
+                None
+            };
+            let (id, label) = (objective.id, objective.label.clone());
+            if let Some(reason) = failure {
+                objective.status = Status::Failed;
+                self.events.push(SimEvent::ObjectiveFailed { id, label, reason: reason.to_string() });
+            } else if objective.progress >= 1.0 {
+                objective.status = Status::Completed;
+                self.events.push(SimEvent::ObjectiveCompleted { id, label });
+            }
+        }
+    }
+}
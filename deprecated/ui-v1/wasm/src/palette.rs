@@ -0,0 +1,68 @@
+//! The tile palette for editor UIs: every tile type with its name and the
+//! color renderers draw it, read off the tile type ids so it follows the
+//! registry, and an eyedropper that turns a tile in the world back into the
+//! arguments place_tile takes to place another like it.
+use serde_json::{json, Value};
+
+use crate::shape::TileShape;
+use crate::{Promiser, TileMap, TileType};
+
+impl TileType {
+    /// Every tile type, in id order
+    pub fn all() -> impl Iterator<Item = TileType> {
+        (0..=u8::MAX).map_while(TileType::from_id)
+    }
+
+    /// RGB color renderers draw this tile type (dry, plain) in
+    pub const fn color(self) -> u32 {
+        match self {
+            TileType::Air => 0x87CEEB,     // Sky blue
+            TileType::Dirt => 0x8B4513,    // Saddle brown
+            TileType::Stone => 0x696969,   // Dim gray
+            TileType::Water => 0x1E90FF,   // Dodger blue
+            TileType::Foliage => 0x228B22, // Forest green
+            TileType::Ore => 0xB8860B,     // Dark goldenrod
+            TileType::Wood => 0xDEB887,    // Burlywood
+            TileType::Portal => 0x9932CC,  // Dark orchid
+            TileType::Ladder => 0xA0522D,  // Sienna
+            TileType::Ice => 0xB0E0E6,     // Powder blue
+            TileType::Steam => 0xDCDCDC,   // Gainsboro
+            TileType::Snow => 0xFFFAFA,    // Snow
+            TileType::Decay => 0x556B2F,   // Dark olive green
+            TileType::Mud => 0x5C4033,     // Dark brown
+            TileType::Quicksand => 0xC2B280, // Sand
+            TileType::Furnace => 0xB22222, // Firebrick
+            TileType::Spikes => 0x708090,  // Slate gray
+            TileType::PitCover => 0xA0855B, // Straw
+            TileType::ReinforcedWall => 0x4A4A4A, // Iron gray
+        }
+    }
+}
+
+/// Every tile type as JSON [{"id", "name", "color", "solid"}, ...]; solid
+/// ones can take a shape when placed
+pub fn palette_json() -> String {
+    let entries: Vec<Value> = TileType::all()
+        .map(|tile_type| json!({
+            "id": tile_type as u8,
+            "name": tile_type.name(),
+            "color": tile_type.color(),
+            "solid": Promiser::is_solid_tile(tile_type),
+        }))
+        .collect();
+    serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl TileMap {
+    /// What it takes to place a tile like the one at (x, y), as JSON
+    /// {"tile_type": "Stone", "shape": "half"} (shape only for solid tiles),
+    /// or "null" outside the map
+    pub fn sample_tile(&self, x: i32, y: i32) -> String {
+        let Some(tile) = self.get_tile(x, y) else { return "null".to_string() };
+        let mut sample = json!({ "tile_type": tile.tile_type.name() });
+        if Promiser::is_solid_tile(tile.tile_type) {
+            sample["shape"] = json!(TileShape::from_variant(tile.variant).name());
+        }
+        sample.to_string()
+    }
+}
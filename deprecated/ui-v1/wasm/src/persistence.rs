@@ -0,0 +1,173 @@
+//! Compact binary tile snapshots for saves and network frames, and full saves
+//! that bundle a tile snapshot and soil fertility with the promisers and
+//! items.
+//!
+//! Tiles are run-length encoded: typical worlds are mostly air (and solid rock
+//! below), so long runs of identical tiles collapse to a few bytes each.
+//!
+//! Layout (all integers little-endian):
+//!   "MTR2" | width: u32 | height: u32 | runs of (tile type: u8, variant: u8, water: u16, count: u32)
+use serde::{Deserialize, Serialize};
+
+use crate::items::{Item, ItemStack};
+use crate::{Promiser, Tile, TileMap, TileType, MAX_WORLD_TILES};
+
+const MAGIC: &[u8; 4] = b"MTR2";
+const HEADER_LEN: usize = 12;
+const RUN_LEN: usize = 8;
+
+/// Run-length encode a tile map
+pub fn encode_tiles(map: &TileMap) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + RUN_LEN * 64);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(map.width as u32).to_le_bytes());
+    out.extend_from_slice(&(map.height as u32).to_le_bytes());
+
+    let mut tiles = map.tiles.iter().peekable();
+    while let Some(tile) = tiles.next() {
+        let mut count: u32 = 1;
+        while let Some(next) = tiles.peek() {
+            let same = next.tile_type == tile.tile_type && next.variant == tile.variant && next.water_amount == tile.water_amount;
+            if !same || count == u32::MAX {
+                break;
+            }
+            tiles.next();
+            count += 1;
+        }
+        out.push(tile.tile_type as u8);
+        out.push(tile.variant);
+        out.extend_from_slice(&tile.water_amount.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+    }
+    out
+}
+
+/// Decode a tile map produced by `encode_tiles`. The result has no borders
+/// set (see borders.rs); the caller applies its own config.
+pub fn decode_tiles(bytes: &[u8]) -> Result<TileMap, String> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        return Err("not a tile snapshot".to_string());
+    }
+    let read_u32 = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    let width = read_u32(4) as usize;
+    let height = read_u32(8) as usize;
+    if width == 0 || height == 0 {
+        return Err("snapshot has no tiles".to_string());
+    }
+
+    let body = &bytes[HEADER_LEN..];
+    if !body.len().is_multiple_of(RUN_LEN) {
+        return Err("truncated snapshot".to_string());
+    }
+
+    // Snapshots come over the network too; don't let one ask for any size
+    let total = width.checked_mul(height).filter(|&total| total <= MAX_WORLD_TILES)
+        .ok_or_else(|| format!("a {}x{} snapshot is larger than the biggest world ({} tiles)", width, height, MAX_WORLD_TILES))?;
+    let mut tiles = Vec::new();
+    for run in body.chunks_exact(RUN_LEN) {
+        let tile_type = TileType::from_id(run[0])
+            .ok_or_else(|| format!("unknown tile type id {}", run[0]))?;
+        let variant = run[1];
+        let water_amount = u16::from_le_bytes([run[2], run[3]]);
+        let count = u32::from_le_bytes([run[4], run[5], run[6], run[7]]) as usize;
+        if tiles.len().checked_add(count).is_none_or(|end| end > total) {
+            return Err("snapshot has more tiles than its size".to_string());
+        }
+        tiles.extend(std::iter::repeat_n(Tile { tile_type, water_amount, variant }, count));
+    }
+    if tiles.len() != total {
+        return Err(format!("expected {} tiles, got {}", total, tiles.len()));
+    }
+
+    let mut map = TileMap::new(width, height);
+    map.tiles = tiles;
+    Ok(map)
+}
+
+// Full saves wrap a tile snapshot, soil fertility and the entities:
+//   "MSV2" | tile snapshot length: u32 | tile snapshot
+//   | fertility length: u32 | runs of (fertility: u8, count: u32) | entities JSON
+// No fertility runs means untouched soil (see fertility.rs).
+const SAVE_MAGIC: &[u8; 4] = b"MSV2";
+const FERTILITY_RUN_LEN: usize = 5;
+
+/// Promisers, loose items and Pixel's inventory, as saved
+#[derive(Serialize, Deserialize)]
+pub struct SavedEntities {
+    pub next_id: u32,
+    pub promisers: Vec<Promiser>,
+    #[serde(default)]
+    pub items: Vec<Item>, // In every dimension
+    #[serde(default)]
+    pub inventory: Vec<ItemStack>,
+}
+
+/// Encode the overworld (with its soil fertility), all promisers (including
+/// their metadata) and items
+pub fn encode_save(map: &TileMap, entities: &SavedEntities) -> Vec<u8> {
+    let tiles = encode_tiles(map);
+    let fertility = encode_fertility(&map.fertility);
+    let mut out = Vec::with_capacity(12 + tiles.len() + fertility.len());
+    out.extend_from_slice(SAVE_MAGIC);
+    out.extend_from_slice(&(tiles.len() as u32).to_le_bytes());
+    out.extend_from_slice(&tiles);
+    out.extend_from_slice(&(fertility.len() as u32).to_le_bytes());
+    out.extend_from_slice(&fertility);
+    out.extend_from_slice(serde_json::to_string(entities).unwrap_or_default().as_bytes());
+    out
+}
+
+pub fn decode_save(bytes: &[u8]) -> Result<(TileMap, SavedEntities), String> {
+    if bytes.len() < 8 || &bytes[0..4] != SAVE_MAGIC {
+        return Err("not a save file".to_string());
+    }
+    // A length-prefixed section starting at `at`, and where it ends
+    let section = |at: usize| -> Result<(&[u8], usize), String> {
+        let len_bytes = bytes.get(at..at + 4).ok_or("truncated save file")?;
+        let len = u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+        let end = (at + 4).checked_add(len).filter(|&end| end <= bytes.len())
+            .ok_or("truncated save file")?;
+        Ok((&bytes[at + 4..end], end))
+    };
+    let (tiles, tiles_end) = section(4)?;
+    let mut map = decode_tiles(tiles)?;
+    let (fertility, fertility_end) = section(tiles_end)?;
+    map.fertility = decode_fertility(fertility, map.tiles.len())?;
+    let entities = serde_json::from_slice(&bytes[fertility_end..])
+        .map_err(|err| format!("invalid entity data: {}", err))?;
+    Ok((map, entities))
+}
+
+// Run-length encode fertility; empty (untouched soil) stays empty
+fn encode_fertility(fertility: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut values = fertility.iter().peekable();
+    while let Some(&value) = values.next() {
+        let mut count: u32 = 1;
+        while values.peek().is_some_and(|&&next| next == value) && count < u32::MAX {
+            values.next();
+            count += 1;
+        }
+        out.push(value);
+        out.extend_from_slice(&count.to_le_bytes());
+    }
+    out
+}
+
+fn decode_fertility(bytes: &[u8], total: usize) -> Result<Vec<u8>, String> {
+    if !bytes.len().is_multiple_of(FERTILITY_RUN_LEN) {
+        return Err("truncated fertility data".to_string());
+    }
+    let mut fertility = Vec::new();
+    for run in bytes.chunks_exact(FERTILITY_RUN_LEN) {
+        let count = u32::from_le_bytes([run[1], run[2], run[3], run[4]]) as usize;
+        if fertility.len().checked_add(count).is_none_or(|end| end > total) {
+            return Err("more fertility than tiles".to_string());
+        }
+        fertility.extend(std::iter::repeat_n(run[0], count));
+    }
+    if !fertility.is_empty() && fertility.len() != total {
+        return Err(format!("expected fertility for {} tiles, got {}", total, fertility.len()));
+    }
+    Ok(fertility)
+}
@@ -0,0 +1,161 @@
+//! Pets: creatures won over by feeding. A promiser within reach feeding a
+//! creature something it eats, out of what it carries (Pixel also out of its
+//! inventory), earns its trust; after enough meals the creature is tamed,
+//! stops running from promisers and follows its owner around (see
+//! creatures.rs). Pets carry a small stack of items for their owner.
+//! Feeding a creature someone else was taming starts over with the new
+//! feeder, and a pet whose owner is gone goes wild again, dropping what it
+//! carried.
+use serde::Serialize;
+
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::items::{ItemKind, ItemStack};
+use crate::{dimension, GameState, TILE_SIZE_PIXELS};
+
+const FEED_REACH: f64 = 48.0; // How close a promiser has to be to feed a creature (pixels)
+pub const PET_DISTANCE: f64 = 2.0 * TILE_SIZE_PIXELS; // Pets catch up when farther than this from their owner
+
+/// Trust a creature has in a promiser, tamed once it's been fed enough
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Pet {
+    pub owner: u32,
+    pub fed: u32, // Meals so far
+    pub carrying: Option<ItemStack>,
+}
+
+impl CreatureKind {
+    /// Meals it takes to tame one, None if it can't be tamed
+    pub fn meals_to_tame(self) -> Option<u32> {
+        match self {
+            CreatureKind::Rabbit => Some(3),
+            CreatureKind::Bird => Some(5),
+            CreatureKind::Fish | CreatureKind::Mole | CreatureKind::Wolf => None,
+        }
+    }
+
+    pub fn eats(self, kind: ItemKind) -> bool {
+        matches!((self, kind), (CreatureKind::Rabbit | CreatureKind::Bird, ItemKind::Plant) | (CreatureKind::Bird, ItemKind::Fish))
+    }
+
+    /// Most items a pet of this kind can carry
+    pub fn carry_capacity(self) -> u32 {
+        match self {
+            CreatureKind::Rabbit => 2,
+            CreatureKind::Bird => 1,
+            CreatureKind::Fish | CreatureKind::Mole | CreatureKind::Wolf => 0,
+        }
+    }
+}
+
+impl Pet {
+    pub fn tamed(&self, kind: CreatureKind) -> bool {
+        kind.meals_to_tame().is_some_and(|meals| self.fed >= meals)
+    }
+}
+
+impl GameState {
+    /// Tamed creature `id`'s owner, if it is a pet
+    pub(crate) fn pet_owner(&self, id: u32) -> Option<u32> {
+        let kind = *self.ecs.creatures.get(id)?;
+        self.ecs.pets.get(id).filter(|pet| pet.tamed(kind)).map(|pet| pet.owner)
+    }
+
+    /// Have a promiser within reach feed a creature one thing it eats, from
+    /// what the promiser carries (or Pixel's inventory). Returns false if it
+    /// had nothing to give or the creature can't be tamed.
+    pub(crate) fn feed_to_creature(&mut self, promiser_id: u32, creature_id: u32) -> bool {
+        let (Some(promiser), Some(&kind), Some(body)) = (
+            self.promisers.get(&promiser_id),
+            self.ecs.creatures.get(creature_id),
+            self.ecs.bodies.get(creature_id),
+        ) else {
+            return false;
+        };
+        let Some(meals) = kind.meals_to_tame() else { return false };
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else { return false };
+        if body.dimension != promiser.dimension || map.offset_x(promiser.x, body.x).hypot(body.y - promiser.y) > FEED_REACH {
+            return false;
+        }
+
+        // Pixel feeds from its inventory first, then its hands
+        let edible = |stacks: &[ItemStack]| stacks.iter().find(|stack| kind.eats(stack.kind)).map(|stack| stack.kind);
+        let from_inventory = if promiser.is_pixel { edible(&self.player.inventory) } else { None };
+        let from_hands = edible(&promiser.carrying);
+        match (from_inventory, from_hands) {
+            (Some(food), _) => {
+                self.player.take(food);
+            }
+            (None, Some(food)) => self.promisers.get_mut(&promiser_id).unwrap().use_carried(food),
+            (None, None) => return false,
+        }
+
+        let pet = match self.ecs.pets.get_mut(creature_id) {
+            Some(pet) if pet.owner == promiser_id => pet,
+            _ => {
+                self.ecs.pets.insert(creature_id, Pet { owner: promiser_id, fed: 0, carrying: None });
+                self.ecs.pets.get_mut(creature_id).unwrap()
+            }
+        };
+        pet.fed += 1;
+        if pet.fed == meals {
+            self.events.push(SimEvent::Tamed { creature: creature_id, owner: promiser_id });
+        }
+        true
+    }
+
+    /// Load a pet with items from a loose stack in its dimension, as many as
+    /// it has room for (one kind at a time). Returns how many it took.
+    pub(crate) fn load_pet(&mut self, creature_id: u32, item_id: u32) -> u32 {
+        let Some(&kind) = self.ecs.creatures.get(creature_id).filter(|_| self.pet_owner(creature_id).is_some()) else {
+            return 0;
+        };
+        let dimension = self.ecs.bodies.get(creature_id).map(|body| body.dimension);
+        let Some(item) = self.ecs.item(item_id).filter(|item| Some(item.dimension) == dimension) else { return 0 };
+        let pet = self.ecs.pets.get_mut(creature_id).unwrap();
+        let held = match pet.carrying {
+            Some(stack) if stack.kind != item.kind => return 0,
+            Some(stack) => stack.count,
+            None => 0,
+        };
+        let taken = item.count.min(kind.carry_capacity().saturating_sub(held));
+        if taken == 0 {
+            return 0;
+        }
+        pet.carrying = Some(ItemStack { kind: item.kind, count: held + taken });
+        self.ecs.take_item(item_id, taken)
+    }
+
+    /// Have a pet set down what it carries where it is
+    pub(crate) fn unload_pet(&mut self, creature_id: u32) -> bool {
+        let Some(stack) = self.ecs.pets.get_mut(creature_id).and_then(|pet| pet.carrying.take()) else { return false };
+        let body = *self.ecs.bodies.get(creature_id).unwrap();
+        self.add_item(stack.kind, stack.count, body.x, body.y, body.dimension);
+        true
+    }
+
+    /// Let pets whose owner is gone go wild, and list every promiser's pets
+    /// on it for the export
+    pub(crate) fn update_pets(&mut self) {
+        let orphaned: Vec<u32> = self.ecs.pets.iter()
+            .filter(|(_, pet)| !self.promisers.contains_key(&pet.owner))
+            .map(|(id, _)| id)
+            .collect();
+        for id in orphaned {
+            self.unload_pet(id);
+            self.ecs.pets.remove(id);
+        }
+
+        for promiser in self.promisers.values_mut() {
+            promiser.pets.clear();
+        }
+        let mut tamed: Vec<(u32, u32)> = self.ecs.pets.iter()
+            .filter(|&(id, pet)| self.ecs.creatures.get(id).is_some_and(|&kind| pet.tamed(kind)))
+            .map(|(id, pet)| (pet.owner, id))
+            .collect();
+        tamed.sort_unstable();
+        for (owner, id) in tamed {
+            self.promisers.get_mut(&owner).unwrap().pets.push(id);
+        }
+    }
+}
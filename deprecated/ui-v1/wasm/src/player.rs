@@ -0,0 +1,142 @@
+//! Pixel as a player character: the front end steers it directly, and it can
+//! dig out and place tiles and pick up items within a generous reach, keeping
+//! what it collects in an inventory of its own.
+use crate::items::{ItemKind, ItemStack};
+use crate::{dimension, GameState, TileType, TILE_SIZE_PIXELS};
+
+pub const PLAYER_REACH: f64 = 96.0; // Three tiles, from Pixel's center (pixels)
+const PLAYER_SPEED: f64 = 4.0; // Horizontal velocity at full input
+const JUMP_SPEED: f64 = 10.0;
+
+/// Input and inventory for the player-controlled Pixel
+#[derive(Default)]
+pub struct Player {
+    pub move_x: f64, // -1.0 (left) to 1.0 (right)
+    pub jump: bool,  // Jump on the next tick if standing on something
+    pub mining: Option<(i32, i32)>, // Tile Pixel is digging at (see mining.rs)
+    pub bucket_water: u16, // Water in Pixel's bucket (see tools.rs)
+    pub inventory: Vec<ItemStack>,
+}
+
+impl Player {
+    pub fn add(&mut self, kind: ItemKind, count: u32) {
+        match self.inventory.iter_mut().find(|stack| stack.kind == kind) {
+            Some(stack) => stack.count += count,
+            None => self.inventory.push(ItemStack { kind, count }),
+        }
+    }
+
+    /// Take one item of a kind out of the inventory, if there is one
+    pub fn take(&mut self, kind: ItemKind) -> bool {
+        let Some(index) = self.inventory.iter().position(|stack| stack.kind == kind) else {
+            return false;
+        };
+        self.inventory[index].count -= 1;
+        if self.inventory[index].count == 0 {
+            self.inventory.remove(index);
+        }
+        true
+    }
+}
+
+impl GameState {
+    pub(crate) fn pixel_id(&self) -> Option<u32> {
+        self.promisers.values().find(|p| p.is_pixel).map(|p| p.id)
+    }
+
+    /// Apply the player's movement input to Pixel, if it is under control
+    pub(crate) fn apply_player_input(&mut self) {
+        let Some(id) = self.pixel_id() else { return };
+        let promiser = self.promisers.get_mut(&id).unwrap();
+        if !promiser.controlled {
+            return;
+        }
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else {
+            return;
+        };
+        promiser.vx = self.player.move_x.clamp(-1.0, 1.0) * PLAYER_SPEED;
+        let grounded = map.is_solid_at_pixel(promiser.x, promiser.y - promiser.size - 1.0);
+        // Caught in quicksand, jumping is struggling (see quicksand.rs)
+        if self.player.jump && !promiser.struggle(map) && grounded {
+            promiser.vy = JUMP_SPEED;
+        }
+        self.player.jump = false;
+    }
+
+    /// Pixel's dimension, if the tile there is within its reach
+    pub(crate) fn pixel_reaching(&self, tile_x: i32, tile_y: i32) -> Option<u32> {
+        let pixel = self.promisers.get(&self.pixel_id()?)?;
+        let map = dimension::dimension_map(&self.tile_map, &self.dimensions, pixel.dimension)?;
+        let center_x = (tile_x as f64 + 0.5) * TILE_SIZE_PIXELS;
+        let center_y = (tile_y as f64 + 0.5) * TILE_SIZE_PIXELS;
+        let in_reach = map.offset_x(pixel.x, center_x).hypot(center_y - pixel.y) <= PLAYER_REACH;
+        in_reach.then_some(pixel.dimension)
+    }
+
+    /// Start Pixel digging at a tile within its reach (and that it has the
+    /// tools for)
+    pub(crate) fn player_break_tile(&mut self, x: i32, y: i32) -> bool {
+        let Some(dim) = self.pixel_reaching(x, y) else { return false };
+        let can_mine = dimension::dimension_map(&self.tile_map, &self.dimensions, dim).is_some_and(|map| map.can_mine(x, y));
+        if !can_mine || self.player_dig_speed(dim, x, y) == 0.0 {
+            return false;
+        }
+        self.player.mining = Some((x, y));
+        true
+    }
+
+    /// Keep digging at Pixel's mining target; once it breaks, it goes straight
+    /// into the inventory. Stops when the tile is out of reach or gone.
+    pub(crate) fn player_mine(&mut self, dt: f64) {
+        let Some((x, y)) = self.player.mining else { return };
+        let Some(dim) = self.pixel_reaching(x, y) else {
+            self.player.mining = None;
+            return;
+        };
+        let speed = self.player_dig_speed(dim, x, y);
+        let Some(map) = self.dimension_map_mut(dim).filter(|map| map.can_mine(x, y) && speed > 0.0) else {
+            self.player.mining = None;
+            return;
+        };
+        if let Some(kind) = map.mine(x, y, dt as f32 * speed) {
+            self.player.add(kind, 1);
+            self.player.mining = None;
+        }
+    }
+
+    /// Place a tile from Pixel's inventory into an air tile within reach
+    pub(crate) fn player_place_tile(&mut self, x: i32, y: i32, kind: ItemKind) -> bool {
+        let Some(dim) = self.pixel_reaching(x, y) else { return false };
+        if !self.player.inventory.iter().any(|stack| stack.kind == kind) {
+            return false;
+        }
+        let Some(map) = self.dimension_map_mut(dim) else { return false };
+        let Some(tile) = kind.tile().filter(|_| map.get_tile(x, y).is_some_and(|tile| tile.tile_type == TileType::Air)) else {
+            return false;
+        };
+        map.set_tile(x, y, tile);
+        self.player.take(kind);
+        true
+    }
+
+    /// Move every item within reach of Pixel into its inventory. Returns how
+    /// many items were picked up.
+    pub(crate) fn player_pick_up(&mut self) -> u32 {
+        let Some(pixel) = self.pixel_id().and_then(|id| self.promisers.get(&id)) else { return 0 };
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, pixel.dimension) else { return 0 };
+        let nearby: Vec<u32> = self.ecs.items()
+            .filter(|item| item.dimension == pixel.dimension && item.reserved_by.is_none())
+            .filter(|item| map.offset_x(pixel.x, item.x).hypot(item.y - pixel.y) <= PLAYER_REACH)
+            .map(|item| item.id)
+            .collect();
+
+        let mut picked = 0;
+        for id in nearby {
+            let stack = *self.ecs.stacks.get(id).unwrap();
+            self.ecs.despawn(id);
+            self.player.add(stack.kind, stack.count);
+            picked += stack.count;
+        }
+        picked
+    }
+}
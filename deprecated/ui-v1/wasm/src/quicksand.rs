@@ -0,0 +1,86 @@
+//! Quicksand: saturated sand that swallows whoever walks into it. A
+//! quicksand tile's water amount is its saturation; water soaks into it the
+//! way it does into dirt (see simulation.rs), and with nothing wet on top it
+//! slowly dries out. The wetter it is, the harder it pulls: promisers caught
+//! in it sink steadily and can barely move sideways, and one whose head goes
+//! under runs out of air (see vitals.rs).
+//!
+//! Struggling is the way out. Every jump (Pixel's jump input, or a caught
+//! promiser thrashing about on its own) builds up struggle, which fades
+//! again over time; enough of it and the promiser breaks free and climbs up
+//! for a moment (about two tiles), and an EscapedQuicksand event goes out.
+//! From deeper down it takes more than one.
+use crate::{random, Promiser, Tile, TileMap, TileType, MAX_DIRT_MOISTURE};
+
+const SINK_SPEED: f64 = 0.4; // Velocity a fully saturated tile pulls a promiser down at
+const QUICKSAND_DRAG: f64 = 0.5; // Horizontal velocity kept per tick while caught
+const STRUGGLE_PER_JUMP: f64 = 0.25;
+const STRUGGLE_FADE: f64 = 0.1; // Struggle lost per second
+const AI_STRUGGLES_PER_SECOND: f64 = 1.5; // How often a caught promiser thrashes on its own
+const ESCAPE_SPEED: f64 = 6.0; // Velocity a promiser climbs out at after breaking free
+const ESCAPE_SECONDS: f64 = 0.25; // How long it climbs (about two tiles)
+const DRYING_CHANCE: f64 = 0.05; // Per water step, with nothing wet on top
+
+impl TileMap {
+    /// How hard the quicksand at a pixel position pulls, 0.0 to 1.0, or None
+    /// if it isn't quicksand
+    pub fn quicksand_pull_at(&self, px: f64, py: f64) -> Option<f64> {
+        let tile = self.get_tile_at_pixel(px, py).filter(|tile| tile.tile_type == TileType::Quicksand)?;
+        Some((tile.water_amount as f64 / MAX_DIRT_MOISTURE as f64).min(1.0))
+    }
+
+    /// Dry out quicksand with nothing wet on top
+    pub fn step_quicksand(&mut self) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        for y in 0..h {
+            for x in 0..w {
+                let tile = &self.tiles[(y * w + x) as usize];
+                if tile.tile_type != TileType::Quicksand || tile.water_amount == 0 {
+                    continue;
+                }
+                let wet_above = self.get_tile(x, y + 1).is_some_and(|above| above.tile_type == TileType::Water);
+                if !wet_above && random() < DRYING_CHANCE {
+                    let water_amount = tile.water_amount - 1;
+                    self.set_tile(x, y, Tile { tile_type: TileType::Quicksand, water_amount, variant: 0 });
+                }
+            }
+        }
+    }
+}
+
+impl Promiser {
+    /// One jump's worth of struggling, if caught in quicksand
+    pub(crate) fn struggle(&mut self, map: &TileMap) -> bool {
+        if map.quicksand_pull_at(self.x, self.y).is_none() {
+            return false;
+        }
+        self.struggle += STRUGGLE_PER_JUMP;
+        true
+    }
+
+    /// Get pulled down by quicksand, or break free of it. Run after gravity.
+    pub(crate) fn sink_in_quicksand(&mut self, map: &TileMap, dt: f64) {
+        self.quicksand_escape = (self.quicksand_escape - dt).max(0.0);
+        let Some(pull) = map.quicksand_pull_at(self.x, self.y) else {
+            self.struggle = 0.0;
+            return;
+        };
+        if self.quicksand_escape > 0.0 {
+            self.vy = ESCAPE_SPEED;
+            return;
+        }
+        if !self.controlled && random() < AI_STRUGGLES_PER_SECOND * dt {
+            self.struggle(map);
+        }
+        self.struggle = (self.struggle - STRUGGLE_FADE * dt).max(0.0);
+        if self.struggle >= 1.0 {
+            self.struggle = 0.0;
+            self.quicksand_escape = ESCAPE_SECONDS;
+            self.escaped_quicksand = true;
+            self.vy = ESCAPE_SPEED;
+            return;
+        }
+        self.vy = -SINK_SPEED * pull;
+        self.vx *= QUICKSAND_DRAG;
+    }
+}
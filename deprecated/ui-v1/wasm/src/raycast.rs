@@ -0,0 +1,138 @@
+//! Straight-line queries against a tile map (hearing, sight, beams). Rays
+//! march through the tile grid one tile boundary at a time (DDA), so no tile
+//! is ever skipped over, however thin.
+use serde::Serialize;
+
+use crate::collision::CollisionFilter;
+use crate::{dimension, GameState, TileMap, TILE_SIZE_PIXELS};
+
+const SHAPE_SAMPLES: usize = 8; // Points checked inside a partly filled tile
+
+/// Where a ray stopped on a solid tile
+#[derive(Clone, Copy, Debug)]
+pub struct TileHit {
+    pub tile_x: i32,
+    pub tile_y: i32,
+    pub x: f64, // Hit point (pixels)
+    pub y: f64,
+    pub distance: f64,
+}
+
+/// The first thing a raycast ran into
+#[derive(Debug, Serialize)]
+#[serde(tag = "hit", rename_all = "snake_case")]
+pub enum RayHit {
+    Tile { tile_x: i32, tile_y: i32, x: f64, y: f64, distance: f64 },
+    Promiser { id: u32, x: f64, y: f64, distance: f64 },
+}
+
+impl TileMap {
+    /// March from (x0, y0) along (dx, dy) for up to `max_dist` pixels and
+    /// return the first solid tile the ray enters. Partly filled tiles only
+    /// count where the ray crosses their filled part. Positions outside the
+    /// map are open.
+    pub fn cast_ray(&self, x0: f64, y0: f64, dx: f64, dy: f64, max_dist: f64) -> Option<TileHit> {
+        let length = dx.hypot(dy);
+        if length == 0.0 || max_dist.is_nan() || max_dist <= 0.0 {
+            return None;
+        }
+        let (dir_x, dir_y) = (dx / length, dy / length);
+        // Wrapping worlds could otherwise loop around forever
+        let max_dist = max_dist.min(4.0 * (self.width + self.height) as f64 * TILE_SIZE_PIXELS);
+
+        let mut tile_x = (x0 / TILE_SIZE_PIXELS).floor() as i32;
+        let mut tile_y = (y0 / TILE_SIZE_PIXELS).floor() as i32;
+        let step_x = if dir_x > 0.0 { 1 } else { -1 };
+        let step_y = if dir_y > 0.0 { 1 } else { -1 };
+        // Distance along the ray to cross one whole tile, and to the first boundary
+        let delta_x = if dir_x != 0.0 { TILE_SIZE_PIXELS / dir_x.abs() } else { f64::INFINITY };
+        let delta_y = if dir_y != 0.0 { TILE_SIZE_PIXELS / dir_y.abs() } else { f64::INFINITY };
+        let boundary = |pos: f64, tile: i32, step: i32| {
+            let edge = (tile + (step > 0) as i32) as f64 * TILE_SIZE_PIXELS;
+            (edge - pos).abs()
+        };
+        let mut next_x = if dir_x != 0.0 { boundary(x0, tile_x, step_x) / dir_x.abs() } else { f64::INFINITY };
+        let mut next_y = if dir_y != 0.0 { boundary(y0, tile_y, step_y) / dir_y.abs() } else { f64::INFINITY };
+
+        let mut entered = 0.0;
+        while entered <= max_dist {
+            let exit = next_x.min(next_y).min(max_dist);
+            if let Some(distance) = self.solid_between(tile_x, tile_y, (x0, y0), (dir_x, dir_y), entered, exit) {
+                return Some(TileHit { tile_x, tile_y, x: x0 + dir_x * distance, y: y0 + dir_y * distance, distance });
+            }
+            if next_x < next_y {
+                entered = next_x;
+                next_x += delta_x;
+                tile_x += step_x;
+            } else {
+                entered = next_y;
+                next_y += delta_y;
+                tile_y += step_y;
+            }
+        }
+        None
+    }
+
+    // First distance between `from` and `to` along the ray where it is inside
+    // the filled part of tile (x, y), if any
+    fn solid_between(&self, x: i32, y: i32, (x0, y0): (f64, f64), (dir_x, dir_y): (f64, f64), from: f64, to: f64) -> Option<f64> {
+        match self.solidity_at(self.index(x, y)?) {
+            (false, _) => return None,
+            (true, false) => return Some(from), // Full block
+            (true, true) => {}
+        }
+        (0..=SHAPE_SAMPLES)
+            .map(|i| from + (to - from) * i as f64 / SHAPE_SAMPLES as f64)
+            .find(|&t| self.is_solid_at_pixel(x0 + dir_x * t, y0 + dir_y * t))
+    }
+
+    /// Whether a straight line between two pixel positions crosses no solid
+    /// tile (the short way around in wrapping worlds). Positions outside the
+    /// map count as open.
+    pub fn line_of_sight(&self, x0: f64, y0: f64, x1: f64, y1: f64) -> bool {
+        let (dx, dy) = (self.offset_x(x0, x1), y1 - y0);
+        let distance = dx.hypot(dy);
+        if distance == 0.0 {
+            return !self.is_solid_at_pixel(x0, y0);
+        }
+        self.cast_ray(x0, y0, dx, dy, distance).is_none()
+    }
+}
+
+impl GameState {
+    /// The first solid tile or promiser a ray from (x, y) along (dx, dy) runs
+    /// into within `max_dist` pixels. Promisers the ray starts inside of are
+    /// passed through, so a promiser can look out from its own position.
+    pub(crate) fn cast_ray(&self, dim: u32, x: f64, y: f64, dx: f64, dy: f64, max_dist: f64) -> Option<RayHit> {
+        let map = dimension::dimension_map(&self.tile_map, &self.dimensions, dim)?;
+        let tile_hit = map.cast_ray(x, y, dx, dy, max_dist);
+        let reach = tile_hit.map_or(max_dist, |hit| hit.distance);
+
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return None;
+        }
+        let (dir_x, dir_y) = (dx / length, dy / length);
+        let promiser_hit = self.promisers.values()
+            .filter(|p| p.dimension == dim && CollisionFilter::RAY.hits(p.collision.layer))
+            .filter_map(|p| {
+                // Nearest entry point of the ray into the promiser's circle
+                let (cx, cy) = (map.offset_x(x, p.x), p.y - y);
+                let along = cx * dir_x + cy * dir_y;
+                let miss = cx * cx + cy * cy - along * along;
+                let radius = p.size;
+                if miss > radius * radius {
+                    return None;
+                }
+                let distance = along - (radius * radius - miss).sqrt();
+                (distance >= 0.0 && distance <= reach).then_some((p.id, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        match (promiser_hit, tile_hit) {
+            (Some((id, distance)), _) => Some(RayHit::Promiser { id, x: x + dir_x * distance, y: y + dir_y * distance, distance }),
+            (None, Some(hit)) => Some(RayHit::Tile { tile_x: hit.tile_x, tile_y: hit.tile_y, x: hit.x, y: hit.y, distance: hit.distance }),
+            (None, None) => None,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Regions: connected areas of open tiles (air, water, ladders, ...), such as
+//! the open world above ground, a cave or a sealed room. A region that
+//! reaches the top of the map is open to the sky; anything in one that
+//! isn't is indoors. Enclosed regions keep their temperature (see thermal.rs)
+//! and are where the air goes stale (see air.rs).
+//!
+//! The map is labeled in full the first time it's needed and then kept up to
+//! date once per tick: only the regions around tiles that turned solid or
+//! open since are flooded again, each at most once however many of its tiles
+//! changed.
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::determinism::HashMap;
+use crate::{GameState, Promiser, TileMap, TILE_SIZE_PIXELS};
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Region {
+    pub id: u32,
+    pub size: u32, // Tiles
+    pub open_to_sky: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RegionMap {
+    labels: Vec<u32>, // Region id per tile, 0 for solid tiles; empty until built
+    regions: HashMap<u32, Region>,
+    next_id: u32,
+    pending: Vec<usize>, // Tiles that turned solid or open since the last update
+}
+
+impl TileMap {
+    fn is_open_tile(&self, i: usize) -> bool {
+        !Promiser::is_solid_tile(self.tiles[i].tile_type)
+    }
+
+    /// Region containing tile (x, y), if it's open and regions are built
+    pub fn region_at(&self, x: i32, y: i32) -> Option<Region> {
+        let label = *self.regions.labels.get(self.index(x, y)?)?;
+        self.regions.regions.get(&label).copied()
+    }
+
+    /// Whether tile index `i` is in a region cut off from the sky
+    pub fn is_enclosed(&self, i: usize) -> bool {
+        let label = self.regions.labels.get(i).copied().unwrap_or(0);
+        self.regions.regions.get(&label).is_some_and(|region| !region.open_to_sky)
+    }
+
+    /// Whether a pixel position is indoors (in an enclosed region)
+    pub fn is_indoors(&self, px: f64, py: f64) -> bool {
+        let (x, y) = ((px / TILE_SIZE_PIXELS).floor() as i32, (py / TILE_SIZE_PIXELS).floor() as i32);
+        self.region_at(x, y).is_some_and(|region| !region.open_to_sky)
+    }
+
+    /// Note a changed tile (see mark_dirty) if that made it solid or open
+    pub(crate) fn note_region_change(&mut self, i: usize) {
+        if self.regions.labels.len() == self.tiles.len() && (self.regions.labels[i] != 0) != self.is_open_tile(i) {
+            self.regions.pending.push(i);
+        }
+    }
+
+    /// Build the regions, or bring them up to date with the changed tiles
+    pub fn update_regions(&mut self) {
+        if self.regions.labels.len() != self.tiles.len() {
+            self.regions = RegionMap { labels: vec![0; self.tiles.len()], ..RegionMap::default() };
+            for i in 0..self.tiles.len() {
+                if self.regions.labels[i] == 0 && self.is_open_tile(i) {
+                    self.flood_region(i);
+                }
+            }
+            return;
+        }
+        let pending = std::mem::take(&mut self.regions.pending);
+        if pending.is_empty() {
+            return;
+        }
+        // Every region touching a changed tile goes; whatever's open around
+        // the changes is flooded again (merging or splitting them)
+        let first_new = self.regions.next_id + 1;
+        let mut seeds = Vec::new();
+        for i in pending {
+            let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+            seeds.push(i);
+            seeds.extend([(-1, 0), (1, 0), (0, -1), (0, 1)].into_iter().filter_map(|(dx, dy)| self.index(x + dx, y + dy)));
+        }
+        for &i in &seeds {
+            let label = std::mem::take(&mut self.regions.labels[i]);
+            self.regions.regions.remove(&label);
+            if self.is_open_tile(i) {
+                self.regions.labels[i] = label;
+            }
+        }
+        for i in seeds {
+            if self.is_open_tile(i) && self.regions.labels[i] < first_new {
+                self.flood_region(i);
+            }
+        }
+    }
+
+    // Label everything open that's connected to tile index `start` as a new region
+    fn flood_region(&mut self, start: usize) {
+        self.regions.next_id += 1;
+        let id = self.regions.next_id;
+        let mut region = Region { id, size: 0, open_to_sky: false };
+        let mut queue = VecDeque::from([start]);
+        self.regions.labels[start] = id;
+        while let Some(i) = queue.pop_front() {
+            region.size += 1;
+            let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+            region.open_to_sky |= y as usize == self.height - 1;
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let Some(j) = self.index(x + dx, y + dy) else { continue };
+                if self.regions.labels[j] != id && self.is_open_tile(j) {
+                    self.regions.labels[j] = id;
+                    queue.push_back(j);
+                }
+            }
+        }
+        self.regions.regions.insert(id, region);
+    }
+}
+
+impl GameState {
+    /// Bring the regions of every tile map up to date
+    pub(crate) fn update_regions(&mut self) {
+        self.tile_map.update_regions();
+        for dimension in &mut self.dimensions {
+            dimension.tile_map.update_regions();
+        }
+    }
+}
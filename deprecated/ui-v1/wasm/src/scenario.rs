@@ -0,0 +1,151 @@
+//! Scenarios: a curated experience (a tutorial, "flood survival") defined as
+//! data. A scenario is a fresh world (size, plus a sim config with its
+//! worldgen preset), who's in it to begin with, a timeline of scripted
+//! actions (see sequence.rs) and the objectives to meet (see objectives.rs):
+//!
+//! `{"name": "Flood survival", "width": 80, "height": 40,
+//!   "config": {"seed": 3, "preset": "islands", "difficulty": "easy"},
+//!   "roster": {"promisers": [{"x": 640, "y": 700, "name": "Ada"}],
+//!              "creatures": [{"kind": "fish", "x": 900, "y": 200}],
+//!              "items": [{"kind": "wood", "count": 4, "x": 660, "y": 700}]},
+//!   "timeline": [{"at": 30, "action": "rain", "drops": 800},
+//!                {"at": 90, "action": "spawn_creature", "kind": "wolf", "x": 100, "y": 700}],
+//!   "objectives": [{"type": "storm", "label": "Keep everyone dry and alive"}]}`
+//!
+//! The timeline runs as one sequence, in order of `at` (seconds from the
+//! start); a walk_to that waits holds up everything after it.
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use crate::config::{Difficulty, SimConfig};
+use crate::creatures::CreatureKind;
+use crate::items::ItemKind;
+use crate::objectives::ObjectiveSpec;
+use crate::sequence::{Action, Sequence};
+use crate::spawn::SpawnOptions;
+use crate::{GameState, OVERWORLD};
+
+fn default_width() -> f64 {
+    100.0
+}
+
+fn default_height() -> f64 {
+    50.0
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_width")]
+    pub width: f64, // Tiles
+    #[serde(default = "default_height")]
+    pub height: f64,
+    #[serde(default)]
+    pub config: SimConfig,
+    #[serde(default)]
+    pub roster: Roster,
+    #[serde(default)]
+    pub timeline: Vec<Moment>,
+    #[serde(default)]
+    pub objectives: Vec<ObjectiveSpec>,
+}
+
+/// Who's in the world to begin with (pixel positions, overworld)
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Roster {
+    pub promisers: Option<Vec<RosterPromiser>>, // Replaces the usual starting promisers when given
+    pub creatures: Vec<RosterCreature>,
+    pub items: Vec<RosterItem>,
+}
+
+#[derive(Deserialize)]
+pub struct RosterPromiser {
+    pub x: f64,
+    pub y: f64,
+    #[serde(flatten)]
+    pub options: SpawnOptions,
+}
+
+#[derive(Deserialize)]
+pub struct RosterCreature {
+    pub kind: CreatureKind,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Deserialize)]
+pub struct RosterItem {
+    pub kind: ItemKind,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// An action on the timeline, `at` seconds from the start
+#[derive(Deserialize)]
+pub struct Moment {
+    pub at: f64,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+impl GameState {
+    /// Build the world a scenario (JSON) describes, ready to run
+    pub fn from_scenario(json: &str) -> Result<GameState, String> {
+        let scenario: Scenario = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        console_log!(Info, World, "Loading scenario \"{}\"", scenario.name);
+        let mut state = GameState::with_config(scenario.width, scenario.height, scenario.config);
+        let roster = scenario.roster;
+
+        if let Some(promisers) = roster.promisers {
+            // Starting over from id 0, so the first is Pixel as usual
+            state.remove_entities("promisers");
+            state.next_id = 0;
+            for promiser in promisers {
+                let id = state.next_id;
+                state.promisers.insert(id, promiser.options.build(id, promiser.x, promiser.y));
+                state.next_id += 1;
+            }
+            state.spatial.rebuild(&state.promisers);
+        }
+        for creature in roster.creatures {
+            if creature.kind.menace() > 0.0 && state.config.difficulty == Difficulty::Peaceful {
+                continue;
+            }
+            state.add_creature(creature.kind, creature.x, creature.y, OVERWORLD);
+        }
+        for item in roster.items {
+            state.add_item(item.kind, item.count, item.x, item.y, OVERWORLD);
+        }
+
+        let mut timeline = scenario.timeline;
+        timeline.sort_by(|a, b| a.at.total_cmp(&b.at));
+        let mut actions = VecDeque::new();
+        let mut now = 0.0;
+        for moment in timeline {
+            if moment.at > now {
+                actions.push_back(Action::Wait { seconds: moment.at - now });
+                now = moment.at;
+            }
+            actions.push_back(moment.action);
+        }
+        if !actions.is_empty() {
+            let id = state.next_sequence_id;
+            state.next_sequence_id += 1;
+            state.sequences.push(Sequence::new(id, actions));
+        }
+
+        for objective in scenario.objectives {
+            state.set_objective(objective);
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,62 @@
+//! Schematics: small tile patterns written as ASCII art that can be pasted
+//! into a tile map. Used by worldgen to place prefabricated structures.
+use crate::{Tile, TileMap, TileType, MAX_WATER_AMOUNT};
+
+/// A rectangular tile pattern. `None` cells are transparent and leave the
+/// underlying tile untouched.
+#[derive(Clone, Debug)]
+pub struct Schematic {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Option<TileType>>, // Row-major, top row first
+}
+
+impl Schematic {
+    /// Parse rows of ASCII art (top row first). Legend:
+    /// ' ' keep, '.' air, '#' stone, 'd' dirt, '~' water, 'w' wood, '*' ore, 'f' foliage, 'H' ladder
+    pub fn from_rows(rows: &[&str]) -> Schematic {
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let height = rows.len();
+        let mut cells = vec![None; width * height];
+
+        for (row, line) in rows.iter().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                cells[row * width + col] = match ch {
+                    '.' => Some(TileType::Air),
+                    '#' => Some(TileType::Stone),
+                    'd' => Some(TileType::Dirt),
+                    '~' => Some(TileType::Water),
+                    'w' => Some(TileType::Wood),
+                    '*' => Some(TileType::Ore),
+                    'f' => Some(TileType::Foliage),
+                    'H' => Some(TileType::Ladder),
+                    _ => None,
+                };
+            }
+        }
+
+        Schematic { width, height, cells }
+    }
+
+    /// Tile at schematic-local (x, y), with y=0 being the bottom row like the world
+    pub fn get(&self, x: usize, y: usize) -> Option<TileType> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let row = self.height - 1 - y;
+        self.cells[row * self.width + x]
+    }
+
+    /// Paste with the schematic's bottom-left corner at tile (x, y).
+    /// Cells falling outside the map are clipped.
+    pub fn paste(&self, map: &mut TileMap, x: i32, y: i32) {
+        for sy in 0..self.height {
+            for sx in 0..self.width {
+                if let Some(tile_type) = self.get(sx, sy) {
+                    let water_amount = if tile_type == TileType::Water { MAX_WATER_AMOUNT } else { 0 };
+                    map.set_tile(x + sx as i32, y + sy as i32, Tile { tile_type, water_amount, variant: 0 });
+                }
+            }
+        }
+    }
+}
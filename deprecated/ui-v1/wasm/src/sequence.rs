@@ -0,0 +1,149 @@
+//! Scripted sequences for story moments: a list of timed actions the
+//! simulation works through over the following ticks.
+//!
+//! A sequence is a JSON array of actions, run in order:
+//! `[{"action": "camera", "x": 400, "y": 300, "zoom": 2},
+//!   {"action": "walk_to", "id": 3, "x": 640, "y": 96, "wait": true},
+//!   {"action": "speak", "id": 3, "text": "Rain is coming", "emote": "☁️"},
+//!   {"action": "wait", "seconds": 2},
+//!   {"action": "rain", "drops": 200},
+//!   {"action": "spawn_creature", "kind": "wolf", "x": 100, "y": 300},
+//!   {"action": "objective", "type": "survive", "seconds": 60}]`
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use crate::config::Difficulty;
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::objectives::ObjectiveSpec;
+use crate::steering::Steering;
+use crate::tasks::Task;
+use crate::{GameState, OVERWORLD, TILE_SIZE_PIXELS};
+
+// Raindrops for a rain action that doesn't say
+fn default_drops() -> u32 {
+    100
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Ask the front end to move the camera (the sim has no camera of its own)
+    Camera { x: f64, y: f64, #[serde(default)] zoom: Option<f64> },
+    /// Send a promiser walking to a pixel position; with `wait`, the sequence
+    /// pauses until it gets there or gives up
+    WalkTo { id: u32, x: f64, y: f64, #[serde(default)] wait: bool },
+    Speak { id: u32, text: String, #[serde(default)] emote: Option<String>, #[serde(default)] duration: Option<f64> },
+    Wait { seconds: f64 },
+    /// Rain over the overworld, or over `width` pixels starting at `x`
+    Rain {
+        #[serde(default)]
+        x: Option<f64>,
+        #[serde(default)]
+        width: Option<f64>,
+        #[serde(default = "default_drops")]
+        drops: u32,
+    },
+    /// Put a creature in the overworld at a pixel position (not hostile ones
+    /// in peaceful mode)
+    SpawnCreature { kind: CreatureKind, x: f64, y: f64 },
+    /// Start tracking an objective (see objectives.rs)
+    Objective {
+        #[serde(flatten)]
+        objective: ObjectiveSpec,
+    },
+}
+
+pub struct Sequence {
+    pub id: u32,
+    actions: VecDeque<Action>,
+    wait: f64,           // Seconds left on a wait action
+    walker: Option<u32>, // Promiser whose walk_to the sequence is waiting on
+}
+
+impl Sequence {
+    pub fn from_json(id: u32, json: &str) -> Result<Sequence, String> {
+        let actions: VecDeque<Action> = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        Ok(Sequence::new(id, actions))
+    }
+
+    pub fn new(id: u32, actions: VecDeque<Action>) -> Sequence {
+        Sequence { id, actions, wait: 0.0, walker: None }
+    }
+}
+
+impl GameState {
+    /// Advance every running sequence by dt seconds
+    pub(crate) fn run_sequences(&mut self, dt: f64) {
+        let mut sequences = std::mem::take(&mut self.sequences);
+        sequences.retain_mut(|sequence| {
+            let running = self.step_sequence(sequence, dt);
+            if !running {
+                self.events.push(SimEvent::SequenceComplete { id: sequence.id });
+            }
+            running
+        });
+        self.sequences = sequences;
+    }
+
+    // Run actions until one blocks; returns false once the sequence is done
+    fn step_sequence(&mut self, sequence: &mut Sequence, dt: f64) -> bool {
+        if sequence.wait > 0.0 {
+            sequence.wait -= dt;
+            if sequence.wait > 0.0 {
+                return true;
+            }
+        }
+        if let Some(walker) = sequence.walker {
+            let walking = self.promisers.get(&walker)
+                .is_some_and(|promiser| matches!(promiser.task, Some(Task::MoveTo { .. })));
+            if walking {
+                return true;
+            }
+            sequence.walker = None;
+        }
+
+        while let Some(action) = sequence.actions.pop_front() {
+            match action {
+                Action::Camera { x, y, zoom } => {
+                    self.events.push(SimEvent::Camera { sequence: sequence.id, x, y, zoom });
+                }
+                Action::WalkTo { id, x, y, wait } => {
+                    self.abandon_task(id);
+                    if let Some(promiser) = self.promisers.get_mut(&id) {
+                        promiser.steering = Steering::None;
+                        promiser.task = Some(Task::MoveTo { x, y, elapsed: 0.0 });
+                        if wait {
+                            sequence.walker = Some(id);
+                            return true;
+                        }
+                    }
+                }
+                Action::Speak { id, text, emote, duration } => {
+                    self.make_promiser_speak(id, text, emote, duration);
+                }
+                Action::Wait { seconds } => {
+                    sequence.wait = seconds;
+                    if seconds > 0.0 {
+                        return true;
+                    }
+                }
+                Action::Rain { x, width, drops } => {
+                    let x0 = x.map_or(0, |x| (x / TILE_SIZE_PIXELS).floor() as i32);
+                    let columns = width.map_or(self.tile_map.width as i32, |w| (w / TILE_SIZE_PIXELS).ceil() as i32);
+                    self.tile_map.rain(x0, columns, drops);
+                }
+                Action::SpawnCreature { kind, x, y } => {
+                    if kind.menace() == 0.0 || self.config.difficulty != Difficulty::Peaceful {
+                        self.add_creature(kind, x, y, OVERWORLD);
+                    }
+                }
+                Action::Objective { objective } => {
+                    self.set_objective(objective);
+                }
+            }
+        }
+        false
+    }
+}
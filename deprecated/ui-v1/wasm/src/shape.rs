@@ -0,0 +1,97 @@
+//! Tile shapes stored in `Tile::variant` for solid tiles: full blocks, half
+//! blocks and slopes. Collision tests points against the shape so promisers can
+//! walk up slopes, and water on top of a slope runs downhill. The shape uses
+//! the low bits of the variant; higher bits are flags such as grass (see
+//! grass.rs).
+use crate::{TileMap, TILE_SIZE_PIXELS};
+
+const SHAPE_BITS: u8 = 0x0F; // Variant bits holding the shape
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileShape {
+    #[default]
+    Full,
+    Half,       // Bottom half filled
+    SlopeRight, // Rises to the right: ◢
+    SlopeLeft,  // Rises to the left: ◣
+}
+
+impl TileShape {
+    pub fn from_variant(variant: u8) -> TileShape {
+        match variant & SHAPE_BITS {
+            1 => TileShape::Half,
+            2 => TileShape::SlopeRight,
+            3 => TileShape::SlopeLeft,
+            _ => TileShape::Full,
+        }
+    }
+
+    pub fn variant(self) -> u8 {
+        match self {
+            TileShape::Full => 0,
+            TileShape::Half => 1,
+            TileShape::SlopeRight => 2,
+            TileShape::SlopeLeft => 3,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TileShape> {
+        match name {
+            "full" => Some(TileShape::Full),
+            "half" => Some(TileShape::Half),
+            "slope_right" => Some(TileShape::SlopeRight),
+            "slope_left" => Some(TileShape::SlopeLeft),
+            _ => None,
+        }
+    }
+
+    /// Name as taken by from_name
+    pub fn name(self) -> &'static str {
+        match self {
+            TileShape::Full => "full",
+            TileShape::Half => "half",
+            TileShape::SlopeRight => "slope_right",
+            TileShape::SlopeLeft => "slope_left",
+        }
+    }
+
+    /// Whether the point (lx, ly) inside the tile, each 0.0 to 1.0 with
+    /// (0, 0) at the bottom-left, is filled
+    pub fn covers(self, lx: f64, ly: f64) -> bool {
+        match self {
+            TileShape::Full => true,
+            TileShape::Half => ly < 0.5,
+            TileShape::SlopeRight => ly < lx,
+            TileShape::SlopeLeft => ly < 1.0 - lx,
+        }
+    }
+
+    /// Direction water on top of this tile runs (-1 left, 1 right), if any
+    pub fn downhill(self) -> Option<i32> {
+        match self {
+            TileShape::SlopeRight => Some(-1),
+            TileShape::SlopeLeft => Some(1),
+            TileShape::Full | TileShape::Half => None,
+        }
+    }
+}
+
+impl TileMap {
+    /// Whether the pixel position is inside the filled part of a solid tile
+    pub fn is_solid_at_pixel(&self, px: f64, py: f64) -> bool {
+        let tx = (px / TILE_SIZE_PIXELS).floor() as i32;
+        let ty = (py / TILE_SIZE_PIXELS).floor() as i32;
+        let Some(i) = self.index(tx, ty) else {
+            return false;
+        };
+        match self.solidity_at(i) {
+            (false, _) => false,
+            (true, false) => true, // Full block
+            (true, true) => {
+                let lx = (px / TILE_SIZE_PIXELS).rem_euclid(1.0);
+                let ly = (py / TILE_SIZE_PIXELS).rem_euclid(1.0);
+                TileShape::from_variant(self.tiles[i].variant).covers(lx, ly)
+            }
+        }
+    }
+}
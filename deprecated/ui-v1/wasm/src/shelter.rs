@@ -0,0 +1,65 @@
+//! Rain and shelter. Rain falling on a tile map keeps falling for
+//! RAIN_SECONDS after the last drops; promisers out in it get wet, and dry
+//! off under cover. A tile is sheltered when it's indoors (see regions.rs)
+//! or has a ceiling: anything solid above it. Wet promisers are slower, and
+//! soaked ones in cold air get chilled; while it rains, promisers without
+//! anything better to do look for shelter (see ai.rs).
+use crate::{GameState, Promiser, TileMap, TILE_SIZE_PIXELS};
+
+pub const RAIN_SECONDS: f32 = 20.0; // How long a shower lasts after the last drops
+pub const SHELTER_RANGE: i32 = 12; // Tiles a promiser looks for shelter
+const WETTING_RATE: f64 = 0.2; // Wetness per second in the rain or in water
+const DRYING_RATE: f64 = 0.02; // Wetness lost per second elsewhere
+const WET_SLOWDOWN: f64 = 0.3; // Speed lost when soaked through
+const SOAKED: f64 = 0.5; // Wetter than this, cold air chills
+const CHILL_TEMPERATURE: f32 = 10.0; // °C
+const CHILL_DAMAGE: f64 = 0.5; // Health lost per second, soaked in cold air
+
+impl TileMap {
+    pub fn is_raining(&self) -> bool {
+        self.rain_seconds > 0.0
+    }
+
+    /// Whether tile (x, y) is open and out of the rain: indoors, or under
+    /// something solid
+    pub fn is_sheltered(&self, x: i32, y: i32) -> bool {
+        let Some(i) = self.index(x, y).filter(|&i| !Promiser::is_solid_tile(self.tiles[i].tile_type)) else {
+            return false;
+        };
+        self.is_enclosed(i)
+            || (y + 1..self.height as i32).any(|above| self.get_tile(x, above).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type)))
+    }
+}
+
+impl Promiser {
+    /// Get wetter in the rain or in water and dry off elsewhere; soaked in
+    /// cold air, lose health
+    pub(crate) fn update_wetness(&mut self, map: &TileMap, dt: f64) {
+        let (x, y) = ((self.x / TILE_SIZE_PIXELS).floor() as i32, (self.y / TILE_SIZE_PIXELS).floor() as i32);
+        self.raining = map.is_raining();
+        let wetting = (self.raining && !map.is_sheltered(x, y)) || self.is_submerged(map);
+        self.wetness = if wetting {
+            (self.wetness + WETTING_RATE * dt).min(1.0)
+        } else {
+            (self.wetness - DRYING_RATE * dt).max(0.0)
+        };
+        if self.wetness > SOAKED && map.temperature_at(x, y).is_some_and(|t| t < CHILL_TEMPERATURE) {
+            self.damage(CHILL_DAMAGE * dt, "cold");
+        }
+    }
+
+    /// Speed multiplier for being wet
+    pub(crate) fn wet_speed(&self) -> f64 {
+        1.0 - WET_SLOWDOWN * self.wetness
+    }
+}
+
+impl GameState {
+    /// Let the rain on every tile map run down
+    pub(crate) fn update_rain(&mut self, dt: f64) {
+        let maps = std::iter::once(&mut self.tile_map).chain(self.dimensions.iter_mut().map(|d| &mut d.tile_map));
+        for map in maps {
+            map.rain_seconds = (map.rain_seconds - dt as f32).max(0.0);
+        }
+    }
+}
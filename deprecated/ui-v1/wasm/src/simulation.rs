@@ -0,0 +1,339 @@
+//! Per-tile-map simulation steps (water flow, foliage), run by GameState for
+//! each dimension that has them enabled.
+use crate::decay::FOLIAGE_MATTER;
+use crate::fertility::GROWTH_COST;
+use crate::fluids::Fluids;
+use crate::light::MIN_FOLIAGE_LIGHT;
+use crate::lod::ZoneMask;
+use crate::shape::TileShape;
+use crate::shelter::RAIN_SECONDS;
+use crate::thermal::FREEZING;
+use crate::{
+    random, Tile, TileMap, TileType, FOLIAGE_DEATH_MOISTURE, FOLIAGE_GROWTH_CHANCE, MAX_DIRT_MOISTURE,
+    MAX_WATER_AMOUNT,
+};
+
+impl TileMap {
+    /// Order-independent cellular-automata water step, moving every fluid
+    /// (see fluids.rs). Returns the amount that drained out of an open bottom.
+    pub fn step_water(&mut self, fluids: &Fluids) -> u32 {
+        self.step_water_zones(None, fluids)
+    }
+
+    /// Water step in just the zones of `mask` (see lod.rs): water elsewhere
+    /// stays put, though it can still flow in from the stepping zones
+    pub fn step_water_zones(&mut self, mask: Option<&ZoneMask>, fluids: &Fluids) -> u32 {
+        let w  = self.width;
+        let h  = self.height;
+        let len = w * h;
+
+        // Signed changes for each tile (outflow = negative, inflow = positive)
+        let mut delta: Vec<i32> = vec![0; len];
+        // Fluid flowing into each air tile; the first one to reach it claims it
+        let mut claimed: Vec<TileType> = vec![TileType::Air; len];
+        // Water that drained out of an open bottom this step
+        let mut lost_to_void: u32 = 0;
+        // Infinite water (see config.rs) soaks into dirt and foliage without
+        // running low, never drains away, and what the apply phase can't fit
+        // goes back where it came from
+        let infinite = self.rules.infinite_water;
+
+        // --- 1 ░ Gather phase -------------------------------------------------
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let tile = &self.tiles[i];
+                let fluid_type = tile.tile_type;
+
+                // Only fluids can move
+                let Some(fluid) = fluids.get(fluid_type) else { continue };
+                if tile.water_amount == 0 || mask.is_some_and(|mask| !mask.steps(x, y)) {
+                    continue;
+                }
+                // Air, or more of the same fluid (then `room` is how much fits)
+                let mut enter = |j: usize| -> Option<u16> {
+                    let target = &self.tiles[j];
+                    if target.tile_type == fluid_type {
+                        return Some(MAX_WATER_AMOUNT - target.water_amount);
+                    }
+                    if target.tile_type != TileType::Air || (claimed[j] != TileType::Air && claimed[j] != fluid_type) {
+                        return None;
+                    }
+                    claimed[j] = fluid_type;
+                    Some(MAX_WATER_AMOUNT - target.water_amount)
+                };
+
+                let mut remaining = tile.water_amount;
+                let mut drained: u16 = 0;
+                let mut soaked: u16 = 0; // Seeped into dirt, given back with infinite water
+
+                // helper to register a flow
+                let mut push = |from_idx: usize, to_idx: usize, amount: u16| {
+                    if amount == 0 { return; }
+                    delta[from_idx] -= amount as i32;
+                    delta[to_idx]   += amount as i32;
+                };
+
+                // ── a) Vertical – gravity first (toward smaller world-y)
+                if let Some(j) = self.index(x as i32, y as i32 - 1) {
+                    let below = &self.tiles[j];
+
+                    if let Some(room) = enter(j).filter(|&room| room > 0) {
+                        let flow   = fluid.flow(remaining.min(room));
+                        remaining -= flow;
+                        push(i, j, flow);
+                    } else if matches!(below.tile_type, TileType::Dirt | TileType::Quicksand) && fluid.seeps {
+                        // Water can seep into dirt below due to gravity
+                        let current_moisture = below.water_amount;
+                        if current_moisture < MAX_DIRT_MOISTURE && remaining > 0 {
+                            // Vertical seepage can be faster than horizontal due to gravity
+                            let seepage_rate = 4; // Higher rate for downward seepage
+                            let max_seepage = (MAX_DIRT_MOISTURE - current_moisture).min(seepage_rate).min(remaining);
+                            if max_seepage > 0 {
+                                remaining -= max_seepage;
+                                soaked += max_seepage;
+                                push(i, j, max_seepage);
+                            }
+                        }
+                    }
+                } else if self.open_bottom && !infinite {
+                    // Bottom row of an open-bottom world drains into the void
+                    drained += remaining;
+                    remaining = 0;
+                }
+
+                // Water resting on a slope runs off downhill
+                let downhill = self.index(x as i32, y as i32 - 1)
+                    .and_then(|j| TileShape::from_variant(self.tiles[j].variant).downhill());
+                if let Some(dir) = downhill {
+                    if let Some(j) = self.index(x as i32 + dir, y as i32) {
+                        if let Some(room) = enter(j) {
+                            let flow = fluid.flow((remaining / 2).min(room));
+                            remaining -= flow;
+                            push(i, j, flow);
+                        }
+                    }
+                }
+
+                // ── b) Horizontal – equalise with neighbours
+                // Only move half the height difference to avoid “teleporting”
+                for dx in [-1, 1] { // left, right
+                    let Some(j) = self.index(x as i32 + dx, y as i32) else {
+                        // Off an open side, half of it runs out into the void
+                        if !infinite && ((dx < 0 && self.open_left) || (dx > 0 && self.open_right)) {
+                            let flow = fluid.flow(remaining / 2);
+                            remaining -= flow;
+                            drained += flow;
+                        }
+                        continue;
+                    };
+                    let n_tile = &self.tiles[j];
+
+                    // Handle water seepage into dirt (and quicksand)
+                    if matches!(n_tile.tile_type, TileType::Dirt | TileType::Quicksand) && fluid.seeps {
+                        
+                        // Water can seep into dirt slowly
+                        let current_moisture = n_tile.water_amount; 
+                        if current_moisture < MAX_DIRT_MOISTURE && remaining > 0 {
+                            // Slow seepage - only small amounts at a time
+                            let seepage_rate = 2; // Units per simulation step
+                            let max_seepage = (MAX_DIRT_MOISTURE - current_moisture).min(seepage_rate).min(remaining);
+                            if max_seepage > 0 {
+                                remaining -= max_seepage;
+                                soaked += max_seepage;
+                                push(i, j, max_seepage);
+                            }
+                        }
+                        continue; 
+                    }
+
+                    // Regular flow into air and the same fluid (and water soaking
+                    // into foliage); anything else blocks it completely
+                    let n_amount = n_tile.water_amount;
+                    let open = (n_tile.tile_type == TileType::Foliage && fluid.seeps) || enter(j).is_some();
+                    if !open {
+                        continue;
+                    }
+                    let target = (remaining as i32 + n_amount as i32) / 2;
+                    if remaining as i32 > target {
+                        let flow = fluid.flow((remaining as i32 - target) as u16);
+                        remaining -= flow;
+                        if n_tile.tile_type == TileType::Foliage {
+                            soaked += flow;
+                        }
+                        push(i, j, flow);
+                    }
+                }
+
+                if drained > 0 {
+                    delta[i] -= drained as i32;
+                    lost_to_void += drained as u32;
+                }
+                if infinite {
+                    delta[i] += soaked as i32;
+                }
+
+                // ── c) Optional small upflow (pressure equalisation) -------------
+                // Not strictly needed – comment out if you want one-way gravity.
+            }
+        }
+
+        // --- 2 ░ Apply phase ---------------------------------------------------
+        // Fluid that didn't fit where it flowed (two sources filling the same
+        // room), and the tiles it flowed out of, for infinite water
+        let mut overflow: u32 = 0;
+        let mut sources: Vec<(usize, TileType)> = Vec::new();
+        for (idx, &change) in delta.iter().enumerate() {
+            if change == 0 { continue; }
+
+            let t = &mut self.tiles[idx];
+            let new_amt = (t.water_amount as i32 + change)
+                .clamp(0, MAX_WATER_AMOUNT as i32) as u16;
+            let old = (t.tile_type, t.water_amount);
+            if infinite {
+                if change < 0 && fluids.get(t.tile_type).is_some() {
+                    sources.push((idx, t.tile_type));
+                } else if fluids.get(claimed[idx]).is_some() || fluids.get(t.tile_type).is_some() {
+                    overflow += (t.water_amount as i32 + change - MAX_WATER_AMOUNT as i32).max(0) as u32;
+                }
+            }
+
+            // Drained fluid tiles turn to air and air filled with a fluid
+            // becomes it; dirt and foliage just get wetter
+            if new_amt == 0 && fluids.get(t.tile_type).is_some() {
+                t.tile_type = TileType::Air;
+            } else if t.tile_type == TileType::Air && new_amt > 0 {
+                t.tile_type = claimed[idx];
+            }
+
+            t.water_amount = new_amt;
+
+            if old != (t.tile_type, t.water_amount) {
+                self.mark_dirty(idx);
+            }
+        }
+
+        // Top the sources back up with what overflowed, so none is lost
+        for (idx, fluid_type) in sources {
+            if overflow == 0 {
+                break;
+            }
+            let t = &mut self.tiles[idx];
+            let refill = (-delta[idx] as u32).min(overflow).min((MAX_WATER_AMOUNT - t.water_amount) as u32) as u16;
+            if refill == 0 {
+                continue;
+            }
+            overflow -= refill as u32;
+            t.tile_type = fluid_type;
+            t.water_amount += refill;
+            self.mark_dirty(idx);
+        }
+
+        lost_to_void
+    }
+
+    /// Drop `drops` raindrops (a quarter tile of water each) into random air
+    /// tiles along the top row, between tile columns x0 and x0 + width. Below
+    /// freezing they fall as snow instead. Promisers count it as raining for
+    /// a while afterwards (see shelter.rs). Returns how many landed.
+    pub fn rain(&mut self, x0: i32, width: i32, drops: u32) -> u32 {
+        let top = self.height as i32 - 1;
+        let mut landed = 0;
+        for _ in 0..drops {
+            let x = x0 + (random() * width.max(1) as f64) as i32;
+            let Some(i) = self.index(x, top) else { continue };
+            if self.temperature.get(i).is_some_and(|&t| t < FREEZING) {
+                landed += self.add_snow(x, top, MAX_WATER_AMOUNT / 4) as u32;
+                continue;
+            }
+            let tile = &mut self.tiles[i];
+            match tile.tile_type {
+                TileType::Air => *tile = Tile { tile_type: TileType::Water, water_amount: MAX_WATER_AMOUNT / 4, variant: 0 },
+                TileType::Water => tile.water_amount = (tile.water_amount + MAX_WATER_AMOUNT / 4).min(MAX_WATER_AMOUNT),
+                _ => continue,
+            }
+            self.mark_dirty(i);
+            landed += 1;
+        }
+        if landed > 0 {
+            self.rain_seconds = RAIN_SECONDS;
+        }
+        landed
+    }
+
+    /// Foliage growth and death based on dirt moisture levels
+    pub fn step_foliage(&mut self) {
+        self.step_foliage_zones(None);
+    }
+
+    /// Foliage step in just the zones of `mask` (see lod.rs)
+    pub fn step_foliage_zones(&mut self, mask: Option<&ZoneMask>) {
+        let w = self.width;
+        let h = self.height;
+        
+        // Collect changes to apply after scanning
+        let mut changes: Vec<(i32, i32, TileType)> = Vec::new();
+        
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let tile = &self.tiles[i];
+                if mask.is_some_and(|mask| !mask.steps(x, y)) {
+                    continue;
+                }
+                
+                match tile.tile_type {
+                    // Dirt with enough moisture grows foliage if there's space above (not at top edge).
+                    // Enriched dirt needs as little as half as much; exhausted dirt grows nothing.
+                    TileType::Dirt if tile.water_amount >= self.foliage_moisture_needed(i)
+                        && self.fertility(i) >= GROWTH_COST && y + 1 < h => {
+                        let above_tile = &self.tiles[i + w];
+                        
+                        // Only grow foliage on air tiles above dirt, and only where light reaches
+                        let lit = !self.rules.plants_need_light || self.light_at(x as i32, y as i32 + 1).is_none_or(|light| light >= MIN_FOLIAGE_LIGHT);
+                        if above_tile.tile_type == TileType::Air && lit && random() < FOLIAGE_GROWTH_CHANCE {
+                            // Schedule foliage growth above the dirt
+                            changes.push((x as i32, y as i32 + 1, TileType::Foliage));
+                        }
+                    },
+                    TileType::Foliage => {
+                        // Check if foliage should die due to lack of moisture in dirt below
+                        if let Some(below_tile) = self.get_tile(x as i32, y as i32 - 1) {
+                            // Foliage dies if the dirt below doesn't have enough moisture
+                            if below_tile.tile_type == TileType::Dirt && 
+                               below_tile.water_amount < FOLIAGE_DEATH_MOISTURE {
+                                changes.push((x as i32, y as i32, TileType::Decay));
+                            }
+                        } else {
+                            // Foliage at ground level (y=0) dies immediately (no soil support)
+                            changes.push((x as i32, y as i32, TileType::Air));
+                        }
+                    },
+                    _ => {
+                        // Other tile types don't participate in foliage simulation
+                    }
+                }
+            }
+        }
+        
+        // Apply all changes
+        for (x, y, new_type) in changes {
+            let new_tile = Tile {
+                tile_type: new_type,
+                water_amount: if new_type == TileType::Decay { FOLIAGE_MATTER } else { 0 }, // Foliage and air don't store water
+                variant: 0,
+            };
+            self.set_tile(x, y, new_tile);
+            if new_type == TileType::Foliage {
+                // Growing draws on the dirt below (see fertility.rs)
+                self.consume_fertility(((y - 1) as usize) * w + x as usize);
+            }
+            
+            match new_type {
+                TileType::Foliage => console_log!(Debug, Foliage, "🌱 Foliage grew at ({}, {})", x, y),
+                TileType::Air | TileType::Decay => console_log!(Debug, Foliage, "🍂 Foliage died at ({}, {})", x, y),
+                _ => {}
+            }
+        }
+    }
+}
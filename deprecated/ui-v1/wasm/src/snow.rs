@@ -0,0 +1,79 @@
+//! Snow: precipitation that falls while it's below freezing (rain from the
+//! top of the map or clouds condensing, see steam.rs) lands as a Snow layer
+//! instead of water. A tile's `water_amount` is its snow depth, from a dusting
+//! (a half block) up to a full tile, above which snow piles onto the next
+//! tile up. Buried snow slowly compresses into ice, and snow melts back into
+//! water once warmed (see thermal.rs).
+use crate::shape::TileShape;
+use crate::{random, Tile, TileMap, TileType, MAX_WATER_AMOUNT};
+
+const COMPRESSION_CHANCE: f64 = 0.002; // Per step, for full snow buried under snow or ice
+
+impl TileMap {
+    /// Drop `amount` of snow straight down column x from tile row y, onto the
+    /// first surface below. Snow landing in water melts into it. Returns
+    /// whether it landed anywhere.
+    pub fn add_snow(&mut self, x: i32, y: i32, amount: u16) -> bool {
+        let mut y = y;
+        loop {
+            let Some(tile) = self.get_tile(x, y).cloned() else { return false };
+            match tile.tile_type {
+                TileType::Water => {
+                    let water_amount = tile.water_amount.saturating_add(amount).min(MAX_WATER_AMOUNT);
+                    self.set_tile(x, y, Tile { water_amount, ..tile });
+                    return true;
+                }
+                TileType::Snow if tile.water_amount < MAX_WATER_AMOUNT => {
+                    let depth = tile.water_amount.saturating_add(amount).min(MAX_WATER_AMOUNT);
+                    self.set_tile(x, y, snow_tile(depth));
+                    return true;
+                }
+                TileType::Air | TileType::Steam => {
+                    // Keep falling while there's open space below
+                    let below = self.get_tile(x, y - 1).map(|below| below.tile_type);
+                    let lands = match below {
+                        None => true, // Bottom of the map
+                        Some(TileType::Air | TileType::Steam | TileType::Water) => false,
+                        Some(TileType::Snow) => self.get_tile(x, y - 1).is_some_and(|below| below.water_amount >= MAX_WATER_AMOUNT),
+                        Some(_) => true,
+                    };
+                    if lands {
+                        // Snow can't settle inside a cloud
+                        if tile.tile_type == TileType::Steam {
+                            return false;
+                        }
+                        self.set_tile(x, y, snow_tile(amount.min(MAX_WATER_AMOUNT)));
+                        return true;
+                    }
+                    y -= 1;
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    /// Compress buried snow into ice. Run after the heat step.
+    pub fn step_snow(&mut self) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        for y in 0..h - 1 {
+            for x in 0..w {
+                let tile = &self.tiles[(y * w + x) as usize];
+                if tile.tile_type != TileType::Snow || tile.water_amount < MAX_WATER_AMOUNT {
+                    continue;
+                }
+                let buried = self.get_tile(x, y + 1).is_some_and(|above| {
+                    matches!(above.tile_type, TileType::Snow | TileType::Ice) && above.water_amount >= MAX_WATER_AMOUNT / 2
+                });
+                if buried && random() < COMPRESSION_CHANCE {
+                    self.set_tile(x, y, Tile { tile_type: TileType::Ice, water_amount: MAX_WATER_AMOUNT, variant: 0 });
+                }
+            }
+        }
+    }
+}
+
+// A snow tile of the given depth, shaped as a half block while shallow
+fn snow_tile(depth: u16) -> Tile {
+    let shape = if depth < MAX_WATER_AMOUNT / 2 { TileShape::Half } else { TileShape::Full };
+    Tile { tile_type: TileType::Snow, water_amount: depth, variant: shape.variant() }
+}
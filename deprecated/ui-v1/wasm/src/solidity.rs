@@ -0,0 +1,87 @@
+//! Solidity bits: for every tile, whether it's solid and whether it's solid
+//! with a shape (a half tile or slope) rather than a full block, packed 64
+//! tiles to a word alongside the tile array. Collision and raycasts check
+//! these instead of fetching each tile and matching on its type; only shaped
+//! tiles need a closer look.
+//!
+//! The bits are built once per tile map (see GameState::update_solidity) and
+//! then kept current from TileMap::mark_dirty. Until they're built, queries
+//! fall back to the tiles themselves.
+use crate::shape::TileShape;
+use crate::{GameState, Promiser, Tile, TileMap};
+
+#[derive(Clone, Debug, Default)]
+pub struct Solidity {
+    solid: Vec<u64>,
+    shaped: Vec<u64>, // Solid but not a full block
+    tiles: usize, // Tile count the bits were built for
+}
+
+fn bits(tile: &Tile) -> (bool, bool) {
+    let solid = Promiser::is_solid_tile(tile.tile_type);
+    (solid, solid && TileShape::from_variant(tile.variant) != TileShape::Full)
+}
+
+fn set_bit(words: &mut [u64], i: usize, on: bool) {
+    if on {
+        words[i / 64] |= 1 << (i % 64);
+    } else {
+        words[i / 64] &= !(1 << (i % 64));
+    }
+}
+
+fn bit(words: &[u64], i: usize) -> bool {
+    words[i / 64] & 1 << (i % 64) != 0
+}
+
+impl TileMap {
+    fn solidity_built(&self) -> bool {
+        self.solidity.tiles == self.tiles.len() && !self.tiles.is_empty()
+    }
+
+    /// Build the solidity bits if they aren't yet (or the map was resized)
+    pub fn ensure_solidity(&mut self) {
+        if self.solidity_built() {
+            return;
+        }
+        let words = self.tiles.len().div_ceil(64);
+        self.solidity = Solidity { solid: vec![0; words], shaped: vec![0; words], tiles: self.tiles.len() };
+        for i in 0..self.tiles.len() {
+            self.refresh_solidity(i);
+        }
+    }
+
+    /// Bring tile index `i`'s bits up to date (see mark_dirty), if built
+    pub(crate) fn refresh_solidity(&mut self, i: usize) {
+        if !self.solidity_built() {
+            return;
+        }
+        let (solid, shaped) = bits(&self.tiles[i]);
+        set_bit(&mut self.solidity.solid, i, solid);
+        set_bit(&mut self.solidity.shaped, i, shaped);
+    }
+
+    /// (solid, shaped) for tile index `i`
+    pub fn solidity_at(&self, i: usize) -> (bool, bool) {
+        if self.solidity_built() {
+            (bit(&self.solidity.solid, i), bit(&self.solidity.shaped, i))
+        } else {
+            bits(&self.tiles[i])
+        }
+    }
+
+    /// Whether tile (x, y) is solid; nothing outside the map is
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.solidity_at(i).0)
+    }
+}
+
+impl GameState {
+    /// Build the solidity bits of any tile map that doesn't have them
+    pub(crate) fn update_solidity(&mut self) {
+        self.tile_map.ensure_solidity();
+        for dimension in &mut self.dimensions {
+            dimension.tile_map.ensure_solidity();
+        }
+    }
+}
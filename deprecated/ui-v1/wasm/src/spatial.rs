@@ -0,0 +1,90 @@
+//! Uniform-grid spatial hash over promiser positions, rebuilt after every
+//! movement step so region queries don't have to scan every promiser.
+use crate::determinism::HashMap;
+use crate::Promiser;
+
+// Cell edge in pixels (two tiles); promisers are at most ~30px across
+const CELL_SIZE: f64 = 64.0;
+
+type CellKey = (u32, i32, i32); // (dimension, cell x, cell y)
+
+#[derive(Default)]
+pub struct SpatialHash {
+    cells: HashMap<CellKey, Vec<u32>>,
+}
+
+fn cell_of(x: f64, y: f64) -> (i32, i32) {
+    ((x / CELL_SIZE).floor() as i32, (y / CELL_SIZE).floor() as i32)
+}
+
+impl SpatialHash {
+    pub fn rebuild(&mut self, promisers: &HashMap<u32, Promiser>) {
+        for ids in self.cells.values_mut() {
+            ids.clear(); // Keep allocations; empty cells are cheap
+        }
+        for promiser in promisers.values() {
+            self.insert(promiser);
+        }
+    }
+
+    pub fn insert(&mut self, promiser: &Promiser) {
+        let (cx, cy) = cell_of(promiser.x, promiser.y);
+        self.cells.entry((promiser.dimension, cx, cy)).or_default().push(promiser.id);
+    }
+
+    /// Ids of promisers in `dimension` whose center lies in the rectangle
+    /// [x, x + w] x [y, y + h]. Positions are checked against `promisers`,
+    /// so entries gone stale since the last rebuild are filtered out.
+    pub fn query_rect(&self, promisers: &HashMap<u32, Promiser>, dimension: u32, x: f64, y: f64, w: f64, h: f64) -> Vec<u32> {
+        let (min_x, max_x) = (x.min(x + w), x.max(x + w));
+        let (min_y, max_y) = (y.min(y + h), y.max(y + h));
+        let (cx0, cy0) = cell_of(min_x, min_y);
+        let (cx1, cy1) = cell_of(max_x, max_y);
+
+        let mut ids = Vec::new();
+        let mut check_cell = |cell: &Vec<u32>| {
+            for id in cell {
+                let Some(promiser) = promisers.get(id) else { continue };
+                if promiser.dimension == dimension
+                    && (min_x..=max_x).contains(&promiser.x)
+                    && (min_y..=max_y).contains(&promiser.y)
+                {
+                    ids.push(*id);
+                }
+            }
+        };
+
+        let span = (cx1 as i64 - cx0 as i64 + 1) * (cy1 as i64 - cy0 as i64 + 1);
+        if span > self.cells.len() as i64 {
+            // Huge rectangle: cheaper to walk the occupied cells than the grid
+            for (&(d, cx, cy), cell) in &self.cells {
+                if d == dimension && (cx0..=cx1).contains(&cx) && (cy0..=cy1).contains(&cy) {
+                    check_cell(cell);
+                }
+            }
+        } else {
+            for cy in cy0..=cy1 {
+                for cx in cx0..=cx1 {
+                    if let Some(cell) = self.cells.get(&(dimension, cx, cy)) {
+                        check_cell(cell);
+                    }
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Ids of promisers in `dimension` within `radius` pixels of (x, y)
+    pub fn query_radius(&self, promisers: &HashMap<u32, Promiser>, dimension: u32, x: f64, y: f64, radius: f64) -> Vec<u32> {
+        let radius = radius.abs();
+        let mut ids = self.query_rect(promisers, dimension, x - radius, y - radius, radius * 2.0, radius * 2.0);
+        ids.retain(|id| {
+            let promiser = &promisers[id];
+            let (dx, dy) = (promiser.x - x, promiser.y - y);
+            dx * dx + dy * dy <= radius * radius
+        });
+        ids
+    }
+}
@@ -0,0 +1,120 @@
+//! Options for spawning promisers from JS (`spawn_promiser`, `spawn_promisers`).
+//! Every field is optional; anything left out is randomized the same way
+//! add_promiser does.
+use serde::Deserialize;
+
+use crate::{random, Promiser, TileMap, TILE_SIZE_PIXELS};
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SpawnOptions {
+    pub size: Option<f64>,      // Radius in pixels
+    pub color: Option<u32>,     // ARGB color, e.g. 0xFF00FF00
+    pub name: Option<String>,   // Display name
+    pub is_pixel: Option<bool>, // Override the "first promiser is Pixel" rule
+}
+
+impl SpawnOptions {
+    /// Parse options from JSON; an empty string means all defaults
+    pub fn from_json(json: &str) -> Result<SpawnOptions, String> {
+        if json.trim().is_empty() {
+            return Ok(SpawnOptions::default());
+        }
+        serde_json::from_str(json).map_err(|err| format!("invalid spawn options: {}", err))
+    }
+
+    /// Create a promiser at (x, y) with these options applied
+    pub fn build(&self, id: u32, x: f64, y: f64) -> Promiser {
+        let is_pixel = self.is_pixel.unwrap_or(id == 0);
+        let mut promiser = Promiser::with_pixel(id, x, y, is_pixel);
+        if let Some(size) = self.size {
+            promiser.size = size.max(1.0);
+        }
+        if let Some(color) = self.color {
+            promiser.color = color;
+        }
+        if let Some(name) = &self.name {
+            promiser.name = name.clone();
+        }
+        promiser
+    }
+}
+
+/// How spawn_promisers spreads promisers over the world
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    #[default]
+    Uniform,   // Anywhere in open space
+    Clustered, // Within `radius` of (x, y)
+    Surface,   // Standing on top of the highest solid tile in a random column
+}
+
+// Attempts to find an open spot before settling for a possibly blocked one
+const PLACEMENT_ATTEMPTS: usize = 16;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BatchSpawnOptions {
+    pub distribution: Distribution,
+    pub x: f64,      // Cluster center (pixels)
+    pub y: f64,
+    pub radius: f64, // Cluster radius (pixels)
+    #[serde(flatten)]
+    pub promiser: SpawnOptions, // Applied to every spawned promiser
+}
+
+impl Default for BatchSpawnOptions {
+    fn default() -> Self {
+        BatchSpawnOptions {
+            distribution: Distribution::Uniform,
+            x: 0.0,
+            y: 0.0,
+            radius: 64.0,
+            promiser: SpawnOptions::default(),
+        }
+    }
+}
+
+impl BatchSpawnOptions {
+    pub fn from_json(json: &str) -> Result<BatchSpawnOptions, String> {
+        if json.trim().is_empty() {
+            return Ok(BatchSpawnOptions::default());
+        }
+        serde_json::from_str(json).map_err(|err| format!("invalid spawn options: {}", err))
+    }
+
+    /// Pick a spawn position (pixels) in `map` for one promiser
+    pub fn pick_position(&self, map: &TileMap) -> (f64, f64) {
+        let world_width = map.width as f64 * TILE_SIZE_PIXELS;
+        let world_height = map.height as f64 * TILE_SIZE_PIXELS;
+        let open = |x: f64, y: f64| {
+            map.get_tile_at_pixel(x, y).is_some_and(|tile| !Promiser::is_solid_tile(tile.tile_type))
+        };
+
+        let mut candidate = (random() * world_width, world_height);
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            candidate = match self.distribution {
+                Distribution::Uniform => (random() * world_width, random() * world_height),
+                Distribution::Clustered => {
+                    // Uniform over the disc, not bunched at the center
+                    let angle = random() * std::f64::consts::TAU;
+                    let distance = self.radius * random().sqrt();
+                    (self.x + angle.cos() * distance, self.y + angle.sin() * distance)
+                }
+                Distribution::Surface => {
+                    let tile_x = (random() * map.width as f64) as i32;
+                    let ground = (0..map.height as i32).rev().find(|&y| {
+                        map.get_tile(tile_x, y).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type))
+                    });
+                    let y = ground.map_or(0.0, |y| (y + 1) as f64 * TILE_SIZE_PIXELS);
+                    ((tile_x as f64 + 0.5) * TILE_SIZE_PIXELS, y + TILE_SIZE_PIXELS * 0.5)
+                }
+            };
+            if open(candidate.0, candidate.1) {
+                break;
+            }
+        }
+        candidate
+    }
+}
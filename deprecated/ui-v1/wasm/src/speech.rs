@@ -0,0 +1,40 @@
+//! Per-promiser speech history (what they said and what they overheard), so
+//! the UI can show chat logs and AI prompts can include recent conversation.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+// Lines kept per promiser (said and heard each); older ones are dropped
+const SPEECH_HISTORY_LEN: usize = 8;
+
+/// How far speech carries, in pixels
+pub const HEARING_RADIUS: f64 = 192.0;
+/// Whispers only reach their target, and only this close
+pub const WHISPER_RADIUS: f64 = 64.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeechEntry {
+    pub tick: u64,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emote: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<u32>, // Set for whispers
+}
+
+// Something a promiser overheard
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeardEntry {
+    pub tick: u64,
+    pub speaker_id: u32,
+    pub text: String,
+    pub whisper: bool,
+}
+
+/// Append to a said/heard history, dropping the oldest line when full
+pub fn remember<T>(history: &mut VecDeque<T>, entry: T) {
+    if history.len() >= SPEECH_HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(entry);
+}
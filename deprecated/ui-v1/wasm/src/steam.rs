@@ -0,0 +1,98 @@
+//! Steam: water that evaporated (see thermal.rs) rises through air and water
+//! as Steam tiles, gathers under the top of the world or a ceiling as
+//! clouds, and condenses back into falling water once it cools below the dew
+//! point or a cloud grows heavy enough to rain.
+use crate::thermal::FREEZING;
+use crate::{random, Tile, TileMap, TileType, MAX_WATER_AMOUNT};
+
+const DEW_POINT: f32 = 10.0; // Steam colder than this condenses
+const RAIN_AMOUNT: u16 = MAX_WATER_AMOUNT / 2; // A stuck cloud this dense starts raining
+const DRIFT_CHANCE: f64 = 0.3; // Per step, for steam that can't rise any further
+
+impl TileMap {
+    /// Add `amount` of evaporated water as steam to tile (x, y), which must be
+    /// air or steam. Returns false if it can't take it.
+    pub fn add_steam(&mut self, x: i32, y: i32, amount: u16) -> bool {
+        let Some(tile) = self.get_tile(x, y) else { return false };
+        let water_amount = match tile.tile_type {
+            TileType::Air => amount,
+            TileType::Steam => tile.water_amount.saturating_add(amount).min(MAX_WATER_AMOUNT),
+            _ => return false,
+        };
+        self.set_tile(x, y, Tile { tile_type: TileType::Steam, water_amount, variant: 0 });
+        true
+    }
+
+    /// Move steam up one tile (or sideways under a ceiling), merging into
+    /// steam it runs into, and condense it where it's cool or heavy. Returns
+    /// the amount of water that condensed.
+    pub fn step_steam(&mut self) -> u32 {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut condensed = 0;
+        // Top down, so steam moves at most one tile per step
+        for y in (0..h).rev() {
+            for x in 0..w {
+                let i = (y * w + x) as usize;
+                if self.tiles[i].tile_type != TileType::Steam {
+                    continue;
+                }
+                let amount = self.tiles[i].water_amount;
+                let cold = self.temperature.get(i).is_some_and(|&t| t < DEW_POINT);
+                if cold {
+                    self.condense(x, y);
+                    condensed += amount as u32;
+                } else if !self.move_steam(x, y, x, y + 1) {
+                    // Stuck under the top of the map or a ceiling: a cloud
+                    if amount >= RAIN_AMOUNT {
+                        self.condense(x, y);
+                        condensed += amount as u32;
+                    } else if random() < DRIFT_CHANCE {
+                        let dx = if random() < 0.5 { -1 } else { 1 };
+                        self.move_steam(x, y, x + dx, y);
+                    }
+                }
+            }
+        }
+        condensed
+    }
+
+    // Move the steam at (x, y) into (to_x, to_y) if that's air, water (which
+    // it bubbles through) or steam with room. Returns whether it moved.
+    fn move_steam(&mut self, x: i32, y: i32, to_x: i32, to_y: i32) -> bool {
+        let (Some(from), Some(to)) = (self.index(x, y), self.index(to_x, to_y)) else { return false };
+        let steam = self.tiles[from].clone();
+        let target = self.tiles[to].clone();
+        match target.tile_type {
+            TileType::Air | TileType::Water => {
+                // Trade places (the water sinks into the steam's spot)
+                self.set_tile(to_x, to_y, steam);
+                self.set_tile(x, y, target);
+                if self.temperature.len() == self.tiles.len() {
+                    self.temperature.swap(from, to);
+                }
+                true
+            }
+            TileType::Steam if target.water_amount < MAX_WATER_AMOUNT => {
+                let moved = steam.water_amount.min(MAX_WATER_AMOUNT - target.water_amount);
+                let left = steam.water_amount - moved;
+                self.set_tile(to_x, to_y, Tile { water_amount: target.water_amount + moved, ..target });
+                let rest = if left == 0 { Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 } } else { Tile { water_amount: left, ..steam } };
+                self.set_tile(x, y, rest);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Turn steam back into water, which the water step then lets fall as
+    // rain, or into snow when it's below freezing
+    fn condense(&mut self, x: i32, y: i32) {
+        let Some(tile) = self.get_tile(x, y).cloned() else { return };
+        if self.temperature_at(x, y).is_some_and(|t| t < FREEZING) {
+            self.set_tile(x, y, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 });
+            self.add_snow(x, y, tile.water_amount);
+        } else {
+            self.set_tile(x, y, Tile { tile_type: TileType::Water, ..tile });
+        }
+    }
+}
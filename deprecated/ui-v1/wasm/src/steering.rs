@@ -0,0 +1,48 @@
+//! Follow/flee steering: a horizontal acceleration toward or away from another
+//! promiser, added on top of the normal wandering movement.
+use serde::{Deserialize, Serialize};
+
+// Horizontal acceleration from steering (velocity units per second)
+const STEERING_ACCEL: f64 = 12.0;
+// Fleeing stops mattering once the threat is this far away (pixels)
+const FLEE_RADIUS: f64 = 256.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Steering {
+    #[default]
+    None,
+    /// Stay within `distance` pixels of the target
+    Follow { target: u32, distance: f64 },
+    /// Keep away from the target
+    Flee { target: u32 },
+}
+
+impl Steering {
+    pub fn target(&self) -> Option<u32> {
+        match *self {
+            Steering::None => None,
+            Steering::Follow { target, .. } | Steering::Flee { target } => Some(target),
+        }
+    }
+
+    /// Change in vx for one step, given the offset (dx, dy) from the steering
+    /// promiser to its target
+    pub fn delta_vx(&self, dx: f64, dy: f64, dt: f64) -> f64 {
+        let distance = (dx * dx + dy * dy).sqrt();
+        let toward = dx.signum() * STEERING_ACCEL * dt;
+        match *self {
+            Steering::None => 0.0,
+            Steering::Follow { distance: keep, .. } if distance > keep => toward,
+            Steering::Follow { .. } => 0.0,
+            Steering::Flee { .. } if distance < FLEE_RADIUS => -toward,
+            Steering::Flee { .. } => 0.0,
+        }
+    }
+}
+
+/// Change in vx for one step of heading toward a point `dx` pixels away
+/// (used by tasks)
+pub fn seek_vx(dx: f64, dt: f64) -> f64 {
+    dx.signum() * STEERING_ACCEL * dt
+}
@@ -0,0 +1,89 @@
+//! Structural integrity, an optional rule (SimConfig::support_span): loose
+//! tiles (dirt and snow) need to be held up. Solid tiles in the bottom row are
+//! grounded; a tile resting on a held-up tile, or a rigid tile (stone, wood,
+//! ice, ...) touching one, is held up just as well, but every loose tile
+//! reached sideways or hanging underneath counts toward a span. Loose tiles
+//! further than `support_span` tiles of span from the ground collapse: they
+//! fall straight down through air and land on whatever is below, so dirt
+//! bridges and overhangs only reach so far.
+use std::collections::VecDeque;
+
+use crate::events::SimEvent;
+use crate::{GameState, Promiser, TileMap, TileType};
+
+pub const STRUCTURE_CHECK_TICKS: u64 = 60; // Ticks between checks (≈ 1s at 60fps)
+
+fn is_loose(tile_type: TileType) -> bool {
+    matches!(tile_type, TileType::Dirt | TileType::Snow)
+}
+
+impl TileMap {
+    /// Span (see above) of every tile from the ground, u32::MAX for open
+    /// tiles and solid ones not connected to the ground
+    fn support_spans(&self) -> Vec<u32> {
+        let solid = |i: usize| Promiser::is_solid_tile(self.tiles[i].tile_type);
+        let mut span = vec![u32::MAX; self.tiles.len()];
+        let mut queue: VecDeque<usize> = (0..self.width).filter(|&i| solid(i)).collect();
+        for &i in &queue {
+            span[i] = 0;
+        }
+        // 0-1 breadth-first search: free steps go to the front of the queue
+        while let Some(i) = queue.pop_front() {
+            let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+            for (dx, dy) in [(0, 1), (-1, 0), (1, 0), (0, -1)] {
+                let Some(j) = self.index(x + dx, y + dy).filter(|&j| solid(j)) else { continue };
+                let cost = u32::from(is_loose(self.tiles[j].tile_type) && dy != 1);
+                if span[i] + cost < span[j] {
+                    span[j] = span[i] + cost;
+                    if cost == 0 {
+                        queue.push_front(j);
+                    } else {
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        span
+    }
+
+    /// Drop every loose tile held up by a span longer than `max_span`.
+    /// Returns how many fell.
+    pub fn step_structure(&mut self, max_span: u32) -> u32 {
+        let spans = self.support_spans();
+        let mut fallen = 0;
+        // Bottom up, so stacks come down together
+        for (i, span) in spans.into_iter().enumerate() {
+            if !is_loose(self.tiles[i].tile_type) || span <= max_span {
+                continue;
+            }
+            let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+            let mut land_y = y;
+            while self.get_tile(x, land_y - 1).is_some_and(|below| below.tile_type == TileType::Air) {
+                land_y -= 1;
+            }
+            if land_y == y {
+                continue; // Wedged; nowhere to fall
+            }
+            let tile = self.tiles[i].clone();
+            let air = self.get_tile(x, land_y).unwrap().clone();
+            self.set_tile(x, y, air);
+            self.set_tile(x, land_y, tile);
+            fallen += 1;
+        }
+        fallen
+    }
+}
+
+impl GameState {
+    /// Collapse unsupported terrain on every tile map, if the rule is on
+    pub(crate) fn check_structure(&mut self) {
+        let Some(max_span) = self.config.support_span else { return };
+        let collapsed = self.tile_map.step_structure(max_span);
+        if collapsed > 0 {
+            self.events.push(SimEvent::Collapsed { tiles: collapsed });
+        }
+        for dimension in &mut self.dimensions {
+            dimension.tile_map.step_structure(max_span);
+        }
+    }
+}
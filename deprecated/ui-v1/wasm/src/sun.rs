@@ -0,0 +1,120 @@
+//! Day and night: the sun crosses the sky once per day, rising on the left and
+//! setting on the right, and the moon takes the same path at night. Sky light
+//! rays travel in the sun's direction, and terrain casts shadows away from
+//! whichever of the two is up.
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use crate::{Promiser, TileMap};
+
+pub const DEFAULT_DAY_SECONDS: f64 = 240.0; // Length of a full day and night
+const SUNRISE: f64 = 0.25; // Time of day (0.0 is midnight, 0.5 noon)
+const SUNSET: f64 = 0.75;
+const START_TIME: f64 = 0.25 + 0.5 / 3.0; // Mid-morning, sun 60° above the left horizon
+
+#[derive(Clone, Debug)]
+pub struct Sun {
+    pub time: f64,        // Time of day, 0.0 to 1.0
+    pub day_seconds: f64, // Seconds per day; 0 stops the clock
+    pub day: u32,         // Days gone by, counted at midnight
+}
+
+impl Sun {
+    pub fn new(day_seconds: f64) -> Sun {
+        Sun { time: START_TIME, day_seconds: day_seconds.max(0.0), day: 0 }
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        if self.day_seconds > 0.0 {
+            let time = self.time + dt / self.day_seconds;
+            if time >= 1.0 {
+                self.day += 1;
+            }
+            self.time = time.rem_euclid(1.0);
+        }
+    }
+
+    /// Whether the sun has set on today (night before midnight)
+    pub fn evening(&self) -> bool {
+        self.time >= SUNSET
+    }
+
+    /// Angle of the sun above the left horizon in radians: 0 at sunrise, π/2
+    /// at noon and π at sunset. None at night.
+    pub fn angle(&self) -> Option<f64> {
+        (self.time > SUNRISE && self.time < SUNSET).then(|| PI * (self.time - SUNRISE) / (SUNSET - SUNRISE))
+    }
+
+    /// Unit vector sunlight travels along (always downward). None at night.
+    pub fn light_direction(&self) -> Option<(f64, f64)> {
+        self.angle().map(|angle| (angle.cos(), -angle.sin()))
+    }
+
+    /// Like light_direction, for the moon, which rises at sunset
+    pub fn moon_direction(&self) -> Option<(f64, f64)> {
+        let night = (self.time - SUNSET).rem_euclid(1.0);
+        let night_length = 1.0 - (SUNSET - SUNRISE);
+        (night > 0.0 && night < night_length).then(|| {
+            let angle = PI * night / night_length;
+            (angle.cos(), -angle.sin())
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = Vec::new();
+        let _ = self.write_json(&mut out); // Writing into a Vec never fails
+        String::from_utf8(out).unwrap_or_default()
+    }
+
+    /// JSON {"time", "day", "angle", "up"} written without allocating (see
+    /// export.rs)
+    pub fn write_json(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{{\"time\":{:.4},\"day\":{},\"angle\":", self.time, self.day)?;
+        match self.angle() {
+            Some(angle) => write!(out, "{:.4},\"up\":true}}", angle),
+            None => write!(out, "null,\"up\":false}}"),
+        }
+    }
+}
+
+impl TileMap {
+    /// Fill `mask` (one byte per tile) with 1 for tiles in shadow and 0 for
+    /// lit ones, for light traveling along `direction`. Each column's top
+    /// solid tile casts a shadow away from the light; everything below it is
+    /// shaded too. With no light at all, every tile is shaded.
+    pub fn cast_shadows(&self, direction: Option<(f64, f64)>, mask: &mut [u8]) {
+        let Some((dir_x, dir_y)) = direction else {
+            mask.fill(1);
+            return;
+        };
+        let (width, height) = (self.width, self.height);
+        if width == 0 {
+            return;
+        }
+
+        // Height just above the top solid tile of each column (0 when empty)
+        let surface: Vec<f64> = (0..width)
+            .map(|x| {
+                (0..height).rev()
+                    .find(|&y| Promiser::is_solid_tile(self.tiles[y * width + x].tile_type))
+                    .map_or(0.0, |y| (y + 1) as f64)
+            })
+            .collect();
+
+        // Sweep columns in the direction the light travels, carrying the
+        // height of the shadow line down by `drop` tiles per column. Wrapping
+        // worlds go around twice so shadows cross the seam.
+        let drop = if dir_x.abs() < 1e-6 { f64::INFINITY } else { -dir_y / dir_x.abs() };
+        let laps = if self.wrap_x { 2 } else { 1 };
+        let mut shadow = f64::NEG_INFINITY;
+        for step in 0..width * laps {
+            let x = if dir_x >= 0.0 { step % width } else { width - 1 - step % width };
+            shadow -= drop;
+            let shaded_below = shadow.max(surface[x] - 1.0);
+            for y in 0..height {
+                mask[y * width + x] = ((y as f64) < shaded_below) as u8;
+            }
+            shadow = shadow.max(surface[x]);
+        }
+    }
+}
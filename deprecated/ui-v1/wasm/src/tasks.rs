@@ -0,0 +1,334 @@
+//! Jobs promisers take on by themselves: hauling loose items (a few at a
+//! time, up to the carry slots) to the nearest stockpile in the same
+//! dimension, and building blueprints (see blueprint.rs). Digging out tiles
+//! (see mining.rs) is only ever ordered.
+use serde::{Deserialize, Serialize};
+
+use crate::blueprint::{BUILD_REACH, BUILD_SECONDS};
+use crate::events::SimEvent;
+use crate::items::{ItemKind, ItemStack, Stockpile};
+use crate::mining::DIG_REACH;
+use crate::steering::Steering;
+use crate::{dimension, tools, GameState, Promiser, TILE_SIZE_PIXELS};
+
+pub const CARRY_SLOTS: usize = 2; // Item stacks a promiser can hold at once
+const CARRY_SLOWDOWN: f64 = 0.2; // Speed lost per occupied carry slot
+const REACH: f64 = 24.0; // Close enough to pick something up (pixels)
+const GATHER_RADIUS: f64 = 128.0; // Look this far for more items before delivering
+const TASK_TIMEOUT: f64 = 30.0; // Give up after this many seconds (item out of reach, ...)
+pub const ASSIGN_INTERVAL: u64 = 30; // Ticks between handing out new jobs
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Task {
+    /// Walk to `item` and pick it up, then take it to `stockpile`
+    Haul { item: u32, stockpile: u32, elapsed: f64 },
+    /// Walk to `stockpile` and set down everything being carried
+    Deliver { stockpile: u32, elapsed: f64 },
+    /// Pick up one of `item` to build the blueprint ghost at tile (x, y)
+    Fetch { blueprint: u32, x: i32, y: i32, item: u32, elapsed: f64 },
+    /// Build the ghost at tile (x, y) with the item in hand
+    Build { blueprint: u32, x: i32, y: i32, progress: f64, elapsed: f64 },
+    /// Go to the quarantine zone and stay there until recovered
+    Quarantine { elapsed: f64 },
+    /// Walk to a pixel position (group orders)
+    MoveTo { x: f64, y: f64, elapsed: f64 },
+    /// Dig out tile (x, y), dropping what it was made of there
+    Dig { x: i32, y: i32, elapsed: f64 },
+}
+
+impl Task {
+    fn elapsed_mut(&mut self) -> &mut f64 {
+        match self {
+            Task::Haul { elapsed, .. }
+            | Task::Deliver { elapsed, .. }
+            | Task::Fetch { elapsed, .. }
+            | Task::Build { elapsed, .. }
+            | Task::Quarantine { elapsed }
+            | Task::MoveTo { elapsed, .. }
+            | Task::Dig { elapsed, .. } => elapsed,
+        }
+    }
+
+    /// Item this task has reserved, if any
+    fn item(&self) -> Option<u32> {
+        match *self {
+            Task::Haul { item, .. } | Task::Fetch { item, .. } => Some(item),
+            Task::Deliver { .. } | Task::Build { .. } | Task::Quarantine { .. } | Task::MoveTo { .. } | Task::Dig { .. } => None,
+        }
+    }
+
+    /// Whether this task is working on the given blueprint ghost
+    pub fn builds(&self, blueprint_id: u32, tile_x: i32, tile_y: i32) -> bool {
+        match *self {
+            Task::Fetch { blueprint, x, y, .. } | Task::Build { blueprint, x, y, .. } => {
+                blueprint == blueprint_id && x == tile_x && y == tile_y
+            }
+            Task::Haul { .. } | Task::Deliver { .. } | Task::Quarantine { .. } | Task::MoveTo { .. } | Task::Dig { .. } => false,
+        }
+    }
+}
+
+impl Promiser {
+    /// Speed multiplier for what the promiser is carrying
+    pub(crate) fn carry_speed(&self) -> f64 {
+        1.0 - CARRY_SLOWDOWN * self.carrying.len() as f64
+    }
+
+    pub(crate) fn can_carry(&self, kind: ItemKind) -> bool {
+        self.carrying.len() < CARRY_SLOTS || self.carrying.iter().any(|stack| stack.kind == kind)
+    }
+
+    pub(crate) fn pick_up(&mut self, kind: ItemKind, count: u32) {
+        match self.carrying.iter_mut().find(|stack| stack.kind == kind) {
+            Some(stack) => stack.count += count,
+            None => self.carrying.push(ItemStack { kind, count }),
+        }
+    }
+
+    /// Use up one carried item of a kind (e.g. building with it)
+    pub(crate) fn use_carried(&mut self, kind: ItemKind) {
+        if let Some(index) = self.carrying.iter().position(|stack| stack.kind == kind) {
+            self.carrying[index].count -= 1;
+            if self.carrying[index].count == 0 {
+                self.carrying.remove(index);
+            }
+        }
+    }
+}
+
+impl GameState {
+    fn stockpile(&self, id: u32) -> Option<&Stockpile> {
+        self.stockpiles.iter().find(|stockpile| stockpile.id == id)
+    }
+
+    fn in_stockpile(&self, dimension: u32, x: f64, y: f64) -> bool {
+        self.stockpiles.iter().any(|stockpile| stockpile.contains(dimension, x, y))
+    }
+
+    // Loose item nearest to (x, y) that nobody is fetching yet
+    fn nearest_loose_item(&self, dimension: u32, x: f64, y: f64, max_distance: f64) -> Option<u32> {
+        let map = dimension::dimension_map(&self.tile_map, &self.dimensions, dimension)?;
+        self.ecs.items()
+            .filter(|item| item.dimension == dimension && item.reserved_by.is_none())
+            .filter(|item| !self.in_stockpile(dimension, item.x, item.y))
+            .map(|item| (item.id, map.offset_x(x, item.x).hypot(item.y - y)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(id, _)| id)
+    }
+
+    /// Nearest promiser to (x, y) that is free to take a job
+    pub(crate) fn idle_worker(&self, dimension: u32, x: f64, y: f64) -> Option<u32> {
+        let map = dimension::dimension_map(&self.tile_map, &self.dimensions, dimension)?;
+        self.promisers.values()
+            .filter(|p| p.dimension == dimension && p.task.is_none() && p.steering == Steering::None)
+            .filter(|p| p.carrying.is_empty() && !p.is_sick() && !p.controlled)
+            .map(|p| (p.id, map.offset_x(p.x, x).hypot(y - p.y)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(id, _)| id)
+    }
+
+    /// Hand out jobs: the sick go to quarantine, then idle promisers get
+    /// blueprint construction, then hauling each loose item (not already in a
+    /// stockpile) if there is a stockpile in its dimension to take it to
+    pub(crate) fn assign_tasks(&mut self) {
+        self.assign_quarantine();
+        self.assign_construction();
+
+        // Free items whose hauler died or gave up
+        let promisers = &self.promisers;
+        let stale: Vec<u32> = self.ecs.reservations.iter()
+            .filter(|&(item, &id)| {
+                promisers.get(&id).is_none_or(|promiser| promiser.task.and_then(|task| task.item()) != Some(item))
+            })
+            .map(|(item, _)| item)
+            .collect();
+        for item in stale {
+            self.ecs.reservations.remove(item);
+        }
+
+        let mut loose: Vec<(u32, u32, f64, f64)> = self.ecs.items()
+            .filter(|item| item.reserved_by.is_none() && !self.in_stockpile(item.dimension, item.x, item.y))
+            .map(|item| (item.id, item.dimension, item.x, item.y))
+            .collect();
+        loose.sort_by_key(|&(id, ..)| id);
+
+        for (item_id, dim, x, y) in loose {
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else {
+                continue;
+            };
+            let Some(stockpile) = self.stockpiles.iter()
+                .filter(|stockpile| stockpile.dimension == dim)
+                .min_by(|a, b| {
+                    let (ax, ay) = a.center();
+                    let (bx, by) = b.center();
+                    map.offset_x(x, ax).hypot(ay - y).total_cmp(&map.offset_x(x, bx).hypot(by - y))
+                })
+                .map(|stockpile| stockpile.id)
+            else {
+                continue;
+            };
+            let Some(worker) = self.idle_worker(dim, x, y) else {
+                break; // Everyone is busy
+            };
+            self.promisers.get_mut(&worker).unwrap().task = Some(Task::Haul { item: item_id, stockpile, elapsed: 0.0 });
+            self.ecs.reservations.insert(item_id, worker);
+        }
+    }
+
+    /// Move every promiser with a task one step along it
+    pub(crate) fn run_tasks(&mut self, dt: f64) {
+        let working: Vec<u32> = self.promisers.values()
+            .filter(|promiser| promiser.task.is_some())
+            .map(|promiser| promiser.id)
+            .collect();
+        for id in working {
+            self.run_task(id, dt);
+        }
+    }
+
+    fn run_task(&mut self, id: u32, dt: f64) {
+        let promiser = &self.promisers[&id];
+        let Some(mut task) = promiser.task else { return };
+        let (x, y, dim) = (promiser.x, promiser.y, promiser.dimension);
+
+        if matches!(task, Task::Quarantine { .. }) && !promiser.is_sick() {
+            self.promisers.get_mut(&id).unwrap().task = None; // Recovered
+            return;
+        }
+        *task.elapsed_mut() += dt;
+        if *task.elapsed_mut() > TASK_TIMEOUT {
+            self.abandon_task(id);
+            return;
+        }
+
+        let target = match task {
+            Task::Haul { item, .. } => self.ecs.item(item).map(|item| (item.x, item.y)),
+            Task::Deliver { stockpile, .. } => self.stockpile(stockpile).map(|stockpile| stockpile.center()),
+            Task::Fetch { item, .. } => self.ecs.item(item).map(|item| (item.x, item.y)),
+            Task::Build { blueprint, x, y, .. } => self.blueprints.iter()
+                .find(|b| b.id == blueprint)
+                .and_then(|b| b.ghost(x, y))
+                .map(|ghost| ghost.center()),
+            Task::Quarantine { .. } => self.quarantine.as_ref().map(|zone| zone.center()),
+            Task::MoveTo { x, y, .. } => Some((x, y)),
+            Task::Dig { x: tile_x, y: tile_y, .. } => dimension::dimension_map(&self.tile_map, &self.dimensions, dim)
+                .filter(|map| map.can_mine(tile_x, tile_y))
+                .map(|_| ((tile_x as f64 + 0.5) * TILE_SIZE_PIXELS, (tile_y as f64 + 0.5) * TILE_SIZE_PIXELS)),
+        };
+        let Some((target_x, target_y)) = target else {
+            // Item taken, stockpile removed, blueprint cancelled or tile gone
+            self.abandon_task(id);
+            return;
+        };
+        let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, dim) else {
+            return;
+        };
+        let dx = map.offset_x(x, target_x);
+
+        match task {
+            Task::Haul { item, stockpile, .. } if dx.hypot(target_y - y) <= REACH => {
+                let stack = *self.ecs.stacks.get(item).unwrap();
+                self.ecs.despawn(item);
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.pick_up(stack.kind, stack.count);
+
+                // Grab more on the way if there's room
+                let next = self.nearest_loose_item(dim, x, y, GATHER_RADIUS)
+                    .filter(|&next| self.promisers[&id].can_carry(self.ecs.stacks.get(next).unwrap().kind));
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.task = Some(match next {
+                    Some(next) => Task::Haul { item: next, stockpile, elapsed: 0.0 },
+                    None => Task::Deliver { stockpile, elapsed: 0.0 },
+                });
+                if let Some(next) = next {
+                    self.ecs.reservations.insert(next, id);
+                }
+            }
+            Task::Deliver { stockpile, .. } if self.stockpile(stockpile).is_some_and(|s| s.contains(dim, x, y)) => {
+                let spots: Vec<(f64, f64)> = {
+                    let stockpile = self.stockpile(stockpile).unwrap();
+                    self.promisers[&id].carrying.iter().map(|_| stockpile.drop_spot()).collect()
+                };
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.task = None;
+                let carried = std::mem::take(&mut promiser.carrying);
+                for (stack, (drop_x, drop_y)) in carried.into_iter().zip(spots) {
+                    self.add_item(stack.kind, stack.count, drop_x, drop_y, dim);
+                    self.events.push(SimEvent::ItemStored {
+                        promiser: id,
+                        stockpile,
+                        kind: stack.kind,
+                        count: stack.count,
+                    });
+                }
+            }
+            Task::Fetch { blueprint, x: tile_x, y: tile_y, item: item_id, .. } if dx.hypot(target_y - y) <= REACH => {
+                let kind = self.ecs.stacks.get(item_id).unwrap().kind;
+                self.ecs.reservations.remove(item_id);
+                self.ecs.take_item(item_id, 1);
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.pick_up(kind, 1);
+                promiser.task = Some(Task::Build { blueprint, x: tile_x, y: tile_y, progress: 0.0, elapsed: 0.0 });
+            }
+            Task::Build { blueprint, x: tile_x, y: tile_y, progress, elapsed } if dx.hypot(target_y - y) <= BUILD_REACH => {
+                let progress = progress + dt;
+                if progress >= BUILD_SECONDS {
+                    self.promisers.get_mut(&id).unwrap().task = None;
+                    self.finish_ghost(id, blueprint, tile_x, tile_y);
+                } else {
+                    self.promisers.get_mut(&id).unwrap().task = Some(Task::Build { blueprint, x: tile_x, y: tile_y, progress, elapsed });
+                }
+            }
+            Task::Quarantine { .. } if self.quarantine.as_ref().is_some_and(|zone| zone.contains(dim, x, y)) => {
+                // Stay put; being here doesn't count toward giving up
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.vx *= 0.9;
+                promiser.task = Some(Task::Quarantine { elapsed: 0.0 });
+            }
+            Task::MoveTo { .. } if dx.abs() <= REACH => {
+                self.promisers.get_mut(&id).unwrap().task = None;
+            }
+            Task::Dig { x: tile_x, y: tile_y, .. } if dx.hypot(target_y - y) <= DIG_REACH => {
+                let tool = self.promisers[&id].tool;
+                let map = self.dimension_map_mut(dim).unwrap();
+                let speed = map.get_tile(tile_x, tile_y).map_or(0.0, |tile| tools::dig_speed(tool, tile.tile_type));
+                let broken = map.mine(tile_x, tile_y, dt as f32 * speed);
+                let promiser = self.promisers.get_mut(&id).unwrap();
+                promiser.vx *= 0.9;
+                match broken {
+                    Some(kind) => {
+                        promiser.task = None;
+                        self.add_item(kind, 1, target_x, target_y, dim);
+                    }
+                    None => promiser.task = Some(task),
+                }
+            }
+            _ => {
+                self.steer_along_flow(id, target_x, target_y, dt);
+                self.promisers.get_mut(&id).unwrap().task = Some(task);
+            }
+        }
+    }
+
+    /// Stop the promiser's current task, setting down whatever it carries
+    pub(crate) fn abandon_task(&mut self, id: u32) {
+        let Some(promiser) = self.promisers.get_mut(&id) else { return };
+        let task = promiser.task.take();
+        let carried = std::mem::take(&mut promiser.carrying);
+        let (x, y, dim) = (promiser.x, promiser.y, promiser.dimension);
+
+        if let Some(item) = task.and_then(|task| task.item()) {
+            self.ecs.reservations.remove(item);
+        }
+        for ghost in self.blueprints.iter_mut().flat_map(|b| b.ghosts.iter_mut()) {
+            if ghost.claimed_by == Some(id) {
+                ghost.claimed_by = None;
+            }
+        }
+        for stack in carried {
+            self.add_item(stack.kind, stack.count, x, y, dim);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Temperature (°C) per tile, stepped along with the water. Air follows the
+//! time of day; water trades heat with the air above it, the ground around it
+//! and other water, and sunlight warms it. Warm surface water evaporates into
+//! steam (see steam.rs), water below freezing turns to ice, and ice and snow
+//! melt once warmed again. Rooms and caves cut off from the sky (see
+//! regions.rs) follow the air temperature only slowly.
+//!
+//! What everything settles toward (the ambient temperature) depends on where
+//! it is. Above ground it's the air, colder the higher it is above the
+//! average ground level. Underground the day and night swings fade out within
+//! a few tiles, and below that it's the ground temperature (the day and night
+//! average), warmer the deeper down it is. Maps start out at their ambient
+//! temperatures, so deep water is warm and mountain tops cold from the start.
+//! The last step's ambient temperatures are kept, so whatever warms a tile
+//! (see furnace.rs) can be told apart from the weather (see warmth.rs).
+use crate::sun::Sun;
+use crate::{random, Promiser, Tile, TileMap, TileType};
+
+pub const DEFAULT_DAY_TEMPERATURE: f64 = 20.0; // Air at noon
+pub const DEFAULT_NIGHT_TEMPERATURE: f64 = 6.0; // Air at night
+const HEAT_STEP_SECONDS: f32 = 0.1; // One water step (every 6 ticks)
+
+pub const FREEZING: f32 = 0.0; // Water colder than this turns to ice
+const MELTING: f32 = 0.5; // Ice and snow warmer than this turn back to water
+const EVAPORATION_TEMPERATURE: f32 = 22.0; // Surface water warmer than this evaporates
+const EVAPORATION_RATE: f32 = 2.0; // Water units per second per degree above it
+
+const AIR_EXCHANGE: f32 = 0.05; // Per second, water with air above toward the air temperature
+const CONDUCTION: f32 = 0.02; // Per second, water toward a solid neighbor's temperature
+const MIXING: f32 = 0.5; // Per second, water toward neighboring water
+const AMBIENT_RELAX: f32 = 0.2; // Per second, everything else toward the air temperature
+const INDOOR_RELAX: f32 = 0.02; // Per second, the same in enclosed regions
+const SUN_HEATING: f32 = 1.5; // Degrees per second at full light energy
+
+const LAPSE_RATE: f32 = 0.15; // Degrees colder per tile above the average ground level
+const GEOTHERMAL_GRADIENT: f32 = 0.2; // Degrees warmer per tile below the surface
+const INSULATION_DEPTH: f32 = 8.0; // Tiles underground before day and night stop reaching
+
+/// Air temperature for the time of day: warmest at noon, night
+/// temperature from sunset to sunrise
+pub fn air_temperature(sun: &Sun, day: f64, night: f64) -> f32 {
+    let daylight = sun.angle().map_or(0.0, f64::sin);
+    (night + (day - night) * daylight) as f32
+}
+
+/// Temperature the ground settles at below the reach of day and night
+pub fn ground_temperature(day: f64, night: f64) -> f32 {
+    ((day + night) / 2.0) as f32
+}
+
+impl TileMap {
+    /// Ambient temperature (see above) of every tile, given the air and
+    /// ground temperatures
+    pub fn ambient_temperatures(&self, air: f32, ground: f32) -> Vec<f32> {
+        let (w, h) = (self.width, self.height);
+        // Top solid tile of each column, -1 where there's none
+        let surface: Vec<i32> = (0..w)
+            .map(|x| (0..h).rev().find(|&y| Promiser::is_solid_tile(self.tiles[y * w + x].tile_type)).map_or(-1, |y| y as i32))
+            .collect();
+        let ground_level = surface.iter().map(|&y| y as f32).sum::<f32>() / w.max(1) as f32;
+        let mut ambient = vec![air; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let depth = (surface[x] - y as i32) as f32;
+                ambient[y * w + x] = if depth > 0.0 {
+                    let deep = ground + GEOTHERMAL_GRADIENT * depth;
+                    air + (deep - air) * (depth / INSULATION_DEPTH).min(1.0)
+                } else {
+                    air - LAPSE_RATE * (y as f32 - ground_level).max(0.0)
+                };
+            }
+        }
+        ambient
+    }
+
+    /// Temperature of a tile, if this map has been heat-stepped
+    pub fn temperature_at(&self, x: i32, y: i32) -> Option<f32> {
+        let i = self.index(x, y)?;
+        self.temperature.get(i).copied()
+    }
+
+    /// Advance temperatures by one water step and apply freezing, melting and
+    /// evaporation (into steam above the water). Returns the amount of water
+    /// that evaporated.
+    pub fn step_heat(&mut self, air: f32, ground: f32) -> u32 {
+        let dt = HEAT_STEP_SECONDS;
+        let ambient = self.ambient_temperatures(air, ground);
+        if self.temperature.len() != self.tiles.len() {
+            self.temperature = ambient.clone();
+        }
+        let old = self.temperature.clone();
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut evaporated = 0;
+        let mut changes: Vec<(i32, i32, Tile)> = Vec::new();
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) as usize;
+                let tile = self.tiles[i].clone();
+                let t = old[i];
+                if !matches!(tile.tile_type, TileType::Water | TileType::Ice | TileType::Snow) {
+                    let relax = if self.is_enclosed(i) { INDOOR_RELAX } else { AMBIENT_RELAX };
+                    self.temperature[i] = t + (ambient[i] - t) * (relax * dt).min(1.0);
+                    continue;
+                }
+
+                let mut flow = 0.0;
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    // Outside the map: open sky above, nothing elsewhere
+                    let Some(j) = self.index(x + dx, y + dy) else {
+                        if y + dy >= h {
+                            flow += AIR_EXCHANGE * (ambient[i] - t);
+                        }
+                        continue;
+                    };
+                    flow += match self.tiles[j].tile_type {
+                        TileType::Water | TileType::Ice | TileType::Snow => MIXING * (old[j] - t) / 4.0,
+                        TileType::Air if dy == 1 => AIR_EXCHANGE * (ambient[j] - t),
+                        TileType::Air => 0.0,
+                        _ => CONDUCTION * (old[j] - t),
+                    };
+                }
+                let light = self.light.get(i).copied().unwrap_or(0.0);
+                let t = t + flow * dt + light * SUN_HEATING * dt;
+                self.temperature[i] = t;
+
+                if tile.tile_type == TileType::Water && t < FREEZING {
+                    changes.push((x, y, Tile { tile_type: TileType::Ice, ..tile }));
+                } else if matches!(tile.tile_type, TileType::Ice | TileType::Snow) && t > MELTING {
+                    changes.push((x, y, Tile { tile_type: TileType::Water, variant: 0, ..tile }));
+                } else if tile.tile_type == TileType::Water && t > EVAPORATION_TEMPERATURE && !self.rules.infinite_water {
+                    let open_above = self.get_tile(x, y + 1).is_none_or(|above| matches!(above.tile_type, TileType::Air | TileType::Steam));
+                    if open_above {
+                        // Round randomly so slow evaporation still happens
+                        let amount = (EVAPORATION_RATE * (t - EVAPORATION_TEMPERATURE) * dt + random() as f32) as u16;
+                        let amount = amount.min(tile.water_amount);
+                        if amount > 0 {
+                            evaporated += amount as u32;
+                            let water_amount = tile.water_amount - amount;
+                            let tile_type = if water_amount == 0 { TileType::Air } else { TileType::Water };
+                            changes.push((x, y, Tile { tile_type, water_amount, ..tile }));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (x, y, tile) in changes {
+            // What left a water tile rises from the tile above it (or escapes
+            // off the top of the map)
+            if tile.tile_type != TileType::Ice && self.tiles[(y * w + x) as usize].tile_type == TileType::Water {
+                let amount = self.tiles[(y * w + x) as usize].water_amount - tile.water_amount;
+                if amount > 0 {
+                    self.add_steam(x, y + 1, amount);
+                }
+            }
+            self.set_tile(x, y, tile);
+        }
+        self.ambient = ambient;
+        evaporated
+    }
+}
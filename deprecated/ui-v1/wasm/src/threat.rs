@@ -0,0 +1,151 @@
+//! Threat: what promisers run from. Hostile creatures (wolves) menace every
+//! promiser within PERCEPTION_RADIUS, more the closer they are, and hazards
+//! (quicksand, scalding tiles) anyone about to step into them. A promiser
+//! that perceives enough threat panics: it starts running (see ai.rs) for
+//! the nearest refuge well on the far side of the danger, trying home first
+//! (indoors or under a roof), then a crowd of other promisers, then light if
+//! it's in the dark, and otherwise just away. Brave promisers go for hostile
+//! creatures instead (see combat.rs).
+use crate::creatures::CreatureKind;
+use crate::{dimension, GameState, Promiser, TileMap, TileType, TILE_SIZE_PIXELS};
+
+pub const PERCEPTION_RADIUS: f64 = 6.0 * TILE_SIZE_PIXELS; // How far off a promiser notices hostile creatures
+const HAZARD_RADIUS: f64 = 1.5 * TILE_SIZE_PIXELS; // How close a hazard has to be to alarm anyone
+const PANIC_LEVEL: f64 = 0.5; // Threat that sets a promiser running
+const REFUGE_RANGE: i32 = 12; // Tiles a promiser looks for a refuge
+const REFUGE_MARGIN: f64 = 2.0 * TILE_SIZE_PIXELS; // How much farther from the threat a refuge has to be
+const CROWD_SIZE: usize = 2; // Others it takes to make a crowd worth running to
+const LIT: f32 = 0.5; // Light that counts as a refuge
+const SCALDING_TEMPERATURE: f32 = 100.0; // Tiles this hot (boiling water, a burning furnace) are hazards
+
+impl CreatureKind {
+    /// How much threat one of these puts out, 0.0 for harmless creatures
+    pub fn menace(self) -> f64 {
+        match self {
+            CreatureKind::Wolf => 1.0,
+            CreatureKind::Fish | CreatureKind::Bird | CreatureKind::Rabbit | CreatureKind::Mole => 0.0,
+        }
+    }
+}
+
+impl TileMap {
+    /// How dangerous it is to step into tile (x, y), 0.0 for safe tiles
+    pub fn hazard_at(&self, x: i32, y: i32) -> f64 {
+        let Some(i) = self.index(x, y) else { return 0.0 };
+        if self.tiles[i].tile_type == TileType::Quicksand {
+            return 1.0;
+        }
+        if self.temperature.get(i).is_some_and(|&t| t >= SCALDING_TEMPERATURE) {
+            return 1.0;
+        }
+        0.0
+    }
+}
+
+// Threat sources around a promiser: (dx, dy, weight) each
+fn sense(map: &TileMap, promiser: &Promiser, creatures: &[(f64, f64, f64)]) -> Vec<(f64, f64, f64)> {
+    let mut sources: Vec<(f64, f64, f64)> = creatures.iter()
+        .map(|&(x, y, menace)| (map.offset_x(promiser.x, x), y - promiser.y, menace))
+        .filter_map(|(dx, dy, menace)| {
+            let distance = dx.hypot(dy);
+            (distance < PERCEPTION_RADIUS).then_some((dx, dy, menace * (1.0 - distance / PERCEPTION_RADIUS)))
+        })
+        .collect();
+    let (tile_x, tile_y) = ((promiser.x / TILE_SIZE_PIXELS).floor() as i32, (promiser.y / TILE_SIZE_PIXELS).floor() as i32);
+    let reach = (HAZARD_RADIUS / TILE_SIZE_PIXELS).ceil() as i32;
+    for y in tile_y - reach..=tile_y + reach {
+        for x in tile_x - reach..=tile_x + reach {
+            let hazard = map.hazard_at(x, y);
+            if hazard == 0.0 {
+                continue;
+            }
+            let (dx, dy) = (map.offset_x(promiser.x, (x as f64 + 0.5) * TILE_SIZE_PIXELS), (y as f64 + 0.5) * TILE_SIZE_PIXELS - promiser.y);
+            let distance = dx.hypot(dy);
+            if distance < HAZARD_RADIUS {
+                sources.push((dx, dy, hazard * (1.0 - distance / HAZARD_RADIUS)));
+            }
+        }
+    }
+    sources
+}
+
+// Nearest safe place for a promiser at (x, y) to run to, as an offset, well
+// farther than it is from the threat at offset `threat`
+fn find_refuge(map: &TileMap, x: f64, y: f64, threat: (f64, f64), others: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let away = |dx: f64, dy: f64| (dx - threat.0).hypot(dy - threat.1) > threat.0.hypot(threat.1) + REFUGE_MARGIN;
+    let (tile_x, tile_y) = ((x / TILE_SIZE_PIXELS).floor() as i32, (y / TILE_SIZE_PIXELS).floor() as i32);
+    // Nearest column first, and this row before the ones around it
+    let tiles = || {
+        (0..=2 * REFUGE_RANGE)
+            .map(|i| if i % 2 == 1 { (i + 1) / 2 } else { -i / 2 })
+            .flat_map(|dx| [0, -1, 1].map(|dy| (tile_x + dx, tile_y + dy)))
+            .map(|(tx, ty)| (tx, ty, map.offset_x(x, (tx as f64 + 0.5) * TILE_SIZE_PIXELS), (ty as f64 + 0.5) * TILE_SIZE_PIXELS - y))
+            .filter(|&(tx, ty, dx, dy)| map.get_tile(tx, ty).is_some_and(|tile| !Promiser::is_solid_tile(tile.tile_type)) && away(dx, dy))
+    };
+
+    let sheltered = tiles().find(|&(tx, ty, ..)| map.is_sheltered(tx, ty));
+    if let Some((.., dx, dy)) = sheltered {
+        return Some((dx, dy));
+    }
+
+    let range = REFUGE_RANGE as f64 * TILE_SIZE_PIXELS;
+    let crowd: Vec<(f64, f64)> = others.iter()
+        .map(|&(ox, oy)| (map.offset_x(x, ox), oy - y))
+        .filter(|&(dx, dy)| dx.hypot(dy) < range && away(dx, dy))
+        .collect();
+    if crowd.len() >= CROWD_SIZE {
+        let n = crowd.len() as f64;
+        return Some((crowd.iter().map(|c| c.0).sum::<f64>() / n, crowd.iter().map(|c| c.1).sum::<f64>() / n));
+    }
+
+    // Light only helps in the dark
+    let lit = |tx, ty| map.light_at(tx, ty).is_some_and(|light| light >= LIT);
+    if lit(tile_x, tile_y) {
+        return None;
+    }
+    tiles().find(|&(tx, ty, ..)| lit(tx, ty)).map(|(.., dx, dy)| (dx, dy))
+}
+
+impl GameState {
+    /// Let every promiser take in the threats around it: set what it's
+    /// running from, if it's enough to panic, and where it's running to
+    pub(crate) fn perceive_threats(&mut self) {
+        let hostiles: Vec<(u32, f64, f64, f64)> = self.ecs.creatures.iter()
+            .filter(|&(_, &kind)| kind.menace() > 0.0)
+            .filter_map(|(id, &kind)| self.ecs.bodies.get(id).map(|body| (body.dimension, body.x, body.y, kind.menace())))
+            .collect();
+        let positions: Vec<(u32, u32, f64, f64)> = self.promisers.values().map(|p| (p.id, p.dimension, p.x, p.y)).collect();
+
+        for promiser in self.promisers.values_mut() {
+            let Some(map) = dimension::dimension_map(&self.tile_map, &self.dimensions, promiser.dimension) else { continue };
+            let creatures: Vec<(f64, f64, f64)> = hostiles.iter()
+                .filter(|h| h.0 == promiser.dimension)
+                .map(|&(_, x, y, menace)| (x, y, menace))
+                .collect();
+            // The brave take on the nearest hostile creature instead of
+            // running from them; hazards still send them running
+            promiser.foe = creatures.iter()
+                .map(|&(x, y, _)| (map.offset_x(promiser.x, x), y - promiser.y))
+                .filter(|&(dx, dy)| dx.hypot(dy) < PERCEPTION_RADIUS)
+                .min_by(|a, b| a.0.hypot(a.1).total_cmp(&b.0.hypot(b.1)))
+                .filter(|_| promiser.fights_back());
+            let sources = sense(map, promiser, if promiser.foe.is_some() { &[] } else { &creatures });
+            let level: f64 = sources.iter().map(|s| s.2).sum();
+            if level < PANIC_LEVEL {
+                (promiser.threat, promiser.refuge) = (None, None);
+                continue;
+            }
+            // Where the danger is, weighted toward the worst of it
+            let threat = (
+                sources.iter().map(|s| s.0 * s.2).sum::<f64>() / level,
+                sources.iter().map(|s| s.1 * s.2).sum::<f64>() / level,
+            );
+            let others: Vec<(f64, f64)> = positions.iter()
+                .filter(|o| o.0 != promiser.id && o.1 == promiser.dimension)
+                .map(|&(_, _, x, y)| (x, y))
+                .collect();
+            promiser.threat = Some(threat);
+            promiser.refuge = find_refuge(map, promiser.x, promiser.y, threat, &others);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Tools: items that change what whoever holds one can do. A pick digs stone
+//! and ore three times as fast, and ore can't be dug at all without one; a
+//! shovel does the same for dirt and foliage. A bucket scoops up to a tile's
+//! worth of water and pours it back out, taking the water out of the map
+//! and putting it back in, so the solver (see simulation.rs) never gains or
+//! loses any. Promisers hold one tool each (equip_tool); Pixel can use any
+//! in its inventory.
+use crate::items::ItemKind;
+use crate::{dimension, GameState, Tile, TileMap, TileType, MAX_WATER_AMOUNT};
+
+const TOOL_SPEEDUP: f32 = 3.0; // Dig speed multiplier with the right tool
+pub const BUCKET_CAPACITY: u16 = MAX_WATER_AMOUNT; // One full tile of water
+
+impl ItemKind {
+    pub fn is_tool(self) -> bool {
+        matches!(self, ItemKind::Shovel | ItemKind::Pick | ItemKind::Bucket)
+    }
+}
+
+/// Dig speed multiplier for a tile type with these tools at hand, 0.0 if it
+/// can't be dug with them
+pub fn dig_speed(tools: impl IntoIterator<Item = ItemKind>, tile_type: TileType) -> f32 {
+    let tools: Vec<ItemKind> = tools.into_iter().collect();
+    match tile_type {
+        TileType::Stone | TileType::Ore | TileType::Furnace | TileType::ReinforcedWall if tools.contains(&ItemKind::Pick) => TOOL_SPEEDUP,
+        TileType::Ore => 0.0,
+        TileType::Dirt | TileType::Mud | TileType::Foliage if tools.contains(&ItemKind::Shovel) => TOOL_SPEEDUP,
+        _ => 1.0,
+    }
+}
+
+impl TileMap {
+    /// Take up to `max` water out of tile (x, y). Returns how much was taken.
+    pub fn take_water(&mut self, x: i32, y: i32, max: u16) -> u16 {
+        let Some(tile) = self.get_tile(x, y).filter(|tile| tile.tile_type == TileType::Water) else { return 0 };
+        let taken = tile.water_amount.min(max);
+        let left = tile.water_amount - taken;
+        let tile_type = if left == 0 { TileType::Air } else { TileType::Water };
+        self.set_tile(x, y, Tile { tile_type, water_amount: left, variant: 0 });
+        taken
+    }
+
+    /// Put up to `amount` water into tile (x, y) if it's air or water, as
+    /// much as it has room for. Returns how much went in.
+    pub fn add_water(&mut self, x: i32, y: i32, amount: u16) -> u16 {
+        let Some(tile) = self.get_tile(x, y).filter(|tile| matches!(tile.tile_type, TileType::Air | TileType::Water)) else {
+            return 0;
+        };
+        let added = amount.min(MAX_WATER_AMOUNT - tile.water_amount);
+        if added > 0 {
+            self.set_tile(x, y, Tile { tile_type: TileType::Water, water_amount: tile.water_amount + added, variant: 0 });
+        }
+        added
+    }
+}
+
+impl GameState {
+    /// Hand a promiser a tool from a loose or stored item stack, in place of
+    /// (and dropping) the one it held
+    pub(crate) fn give_tool(&mut self, promiser_id: u32, item_id: u32) -> bool {
+        let Some(promiser) = self.promisers.get(&promiser_id) else { return false };
+        let Some(item) = self.ecs.item(item_id).filter(|item| item.kind.is_tool() && item.dimension == promiser.dimension) else {
+            return false;
+        };
+        let kind = item.kind;
+        self.ecs.take_item(item_id, 1);
+        let promiser = self.promisers.get_mut(&promiser_id).unwrap();
+        let (x, y, dim) = (promiser.x, promiser.y, promiser.dimension);
+        if let Some(old) = promiser.tool.replace(kind) {
+            self.add_item(old, 1, x, y, dim);
+        }
+        true
+    }
+
+    /// Fill Pixel's bucket (it must have one) from a water tile within reach.
+    /// Returns how much water it took.
+    pub(crate) fn player_scoop_water(&mut self, x: i32, y: i32) -> u16 {
+        if !self.player.inventory.iter().any(|stack| stack.kind == ItemKind::Bucket) {
+            return 0;
+        }
+        let Some(dim) = self.pixel_reaching(x, y) else { return 0 };
+        let room = BUCKET_CAPACITY - self.player.bucket_water;
+        let taken = self.dimension_map_mut(dim).map_or(0, |map| {
+            let taken = map.take_water(x, y, room);
+            if map.rules.infinite_water {
+                map.add_water(x, y, taken);
+            }
+            taken
+        });
+        self.player.bucket_water += taken;
+        taken
+    }
+
+    /// Empty Pixel's bucket into an air or water tile within reach, as much
+    /// as fits. Returns how much water it poured.
+    pub(crate) fn player_pour_water(&mut self, x: i32, y: i32) -> u16 {
+        let Some(dim) = self.pixel_reaching(x, y) else { return 0 };
+        let water = self.player.bucket_water;
+        let poured = self.dimension_map_mut(dim).map_or(0, |map| map.add_water(x, y, water));
+        self.player.bucket_water -= poured;
+        poured
+    }
+
+    /// Dig speed for a tile of Pixel's map at (x, y) with the tools in its
+    /// inventory
+    pub(crate) fn player_dig_speed(&self, dim: u32, x: i32, y: i32) -> f32 {
+        let tools = self.player.inventory.iter().map(|stack| stack.kind);
+        dimension::dimension_map(&self.tile_map, &self.dimensions, dim)
+            .and_then(|map| map.get_tile(x, y))
+            .map_or(0.0, |tile| dig_speed(tools, tile.tile_type))
+    }
+}
@@ -0,0 +1,122 @@
+//! Promiser health and the hazards that drain it.
+use crate::{Promiser, TileMap, TileType, GRAVITY};
+
+pub const MAX_HEALTH: f64 = 100.0;
+
+// Breath
+pub const MAX_AIR: f64 = 10.0; // Seconds of air when fully rested
+const AIR_RECOVERY_RATE: f64 = 3.0; // Air regained per second out of water
+const PANIC_AIR: f64 = 3.0; // Below this, swim hard for the surface
+const PANIC_SWIM_SPEED: f64 = 6.0; // Upward velocity while panicking
+const DROWNING_DAMAGE: f64 = 10.0; // Health lost per second without air
+
+// Falls
+const SAFE_IMPACT_SPEED: f64 = 320.0; // Pixels per second; about a 5-tile drop
+const FALL_DAMAGE_PER_SPEED: f64 = 0.25; // Health per px/s over the safe speed, for a size-10 promiser
+const WATER_CUSHION: f64 = 0.2; // Fraction of fall damage taken when landing in water
+
+// Being buried in solid tiles
+const SUFFOCATION_DAMAGE: f64 = 20.0; // Health lost per second with no way out
+
+impl Promiser {
+    pub(crate) fn damage(&mut self, amount: f64, cause: &'static str) {
+        if amount <= 0.0 || self.health <= 0.0 {
+            return;
+        }
+        self.health = (self.health - amount).max(0.0);
+        if self.health == 0.0 {
+            self.death_cause = Some(cause);
+        }
+    }
+
+    pub(crate) fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Track the highest point of the current fall
+    pub(crate) fn track_fall(&mut self, grounded: bool) {
+        match self.fall_peak_y {
+            Some(peak) if !grounded && peak >= self.y => {}
+            Some(_) => self.fall_peak_y = Some(self.y),
+            None if grounded => self.fall_peak_y = Some(self.y),
+            None => {} // Still dropping in from spawn
+        }
+    }
+
+    /// Landed after falling from fall_peak_y: take damage for hard impacts.
+    /// The impact speed is worked out from the drop height, since the velocity
+    /// cap makes the actual vy at touchdown the same for almost any fall.
+    /// The first landing after spawning is always safe.
+    pub(crate) fn land(&mut self, tile_map: &TileMap) {
+        let drop = self.fall_peak_y.map_or(0.0, |peak| (peak - self.y).max(0.0));
+        self.fall_peak_y = Some(self.y);
+
+        let impact_speed = (2.0 * GRAVITY * drop).sqrt();
+        if impact_speed <= SAFE_IMPACT_SPEED {
+            return;
+        }
+        let mut damage = (impact_speed - SAFE_IMPACT_SPEED) * FALL_DAMAGE_PER_SPEED * self.size / 10.0;
+        let in_water = tile_map.get_tile_at_pixel(self.x, self.y)
+            .is_some_and(|tile| tile.tile_type == TileType::Water);
+        if in_water {
+            damage *= WATER_CUSHION;
+        }
+        self.damage(damage, "fall");
+    }
+
+    // Center and all four corners are inside whatever the promiser collides with
+    fn is_buried(&self, tile_map: &TileMap) -> bool {
+        let s = self.size;
+        [(0.0, 0.0), (-s, -s), (s, -s), (-s, s), (s, s)]
+            .iter()
+            .all(|&(dx, dy)| self.collision.blocked_at(tile_map, self.x + dx, self.y + dy))
+    }
+
+    /// A promiser buried by placed tiles or shifting terrain is moved to the
+    /// nearest open spot, or suffocates if there isn't one. Returns true if it
+    /// was moved.
+    pub(crate) fn escape_burial(&mut self, tile_map: &TileMap, dt: f64) -> bool {
+        if !self.is_buried(tile_map) {
+            return false;
+        }
+        if let Some((x, y)) = self.find_open_spot(self.x, self.y, tile_map) {
+            self.x = x;
+            self.y = y;
+            self.vx = 0.0;
+            self.vy = 0.0;
+            self.fall_peak_y = Some(y);
+            return true;
+        }
+        self.damage(SUFFOCATION_DAMAGE * dt, "suffocation");
+        false
+    }
+
+    // Head under water (or quicksand): the tile at the top of the promiser is water
+    pub(crate) fn is_submerged(&self, tile_map: &TileMap) -> bool {
+        self.submerged_in(tile_map).is_some()
+    }
+
+    fn submerged_in(&self, tile_map: &TileMap) -> Option<TileType> {
+        tile_map.get_tile_at_pixel(self.x, self.y + self.size * 0.8)
+            .map(|tile| tile.tile_type)
+            .filter(|&tile_type| matches!(tile_type, TileType::Water | TileType::Quicksand))
+    }
+
+    /// Use up air while submerged and get it back in the open. Out of air,
+    /// the promiser drowns; low on air, it panics and swims for the surface.
+    pub(crate) fn update_air(&mut self, tile_map: &TileMap, dt: f64) {
+        if !self.is_submerged(tile_map) {
+            self.air = (self.air + AIR_RECOVERY_RATE * dt).min(MAX_AIR);
+            return;
+        }
+
+        self.air = (self.air - dt).max(0.0);
+        if self.air < PANIC_AIR {
+            self.vy = self.vy.max(PANIC_SWIM_SPEED);
+        }
+        if self.air == 0.0 {
+            let cause = if self.submerged_in(tile_map) == Some(TileType::Quicksand) { "quicksand" } else { "drowning" };
+            self.damage(DROWNING_DAMAGE * dt, cause);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Warmth: when the air where they are turns cold (nights, high ground; see
+//! thermal.rs), promisers without a task head for the nearest heat source,
+//! such as a burning furnace (see furnace.rs), and stay by it, so circles
+//! form around fires after dark (see ai.rs). Promisers gathered by a fire
+//! with company strike up conversation now and then, which they otherwise
+//! only do when told to.
+use crate::speech::HEARING_RADIUS;
+use crate::{ai, dimension, random, GameState, Promiser, TileMap, TILE_SIZE_PIXELS};
+
+pub const COLD_TEMPERATURE: f32 = 10.0; // Ambient air colder than this sends promisers looking for warmth
+pub const WARMTH_RANGE: i32 = 16; // Tiles a promiser looks for a heat source
+const WARM_MARGIN: f32 = 8.0; // Degrees above ambient that make a tile warm
+pub const CHAT_INTERVAL: u64 = 120; // Ticks between campfire conversation checks
+const CHAT_CHANCE: f64 = 0.2; // Per check, for each gathered promiser with company
+
+const CAMPFIRE_LINES: &[&str] = &[
+    "Come closer, there's room by the fire.",
+    "Cold one tonight.",
+    "Did you hear something out there?",
+    "Remember the last time the water rose?",
+    "I could sit here until sunrise.",
+    "Pass me another log.",
+];
+
+impl TileMap {
+    /// Ambient temperature (see thermal.rs) of a tile, if this map has been
+    /// heat-stepped
+    pub fn ambient_at(&self, x: i32, y: i32) -> Option<f32> {
+        let i = self.index(x, y)?;
+        self.ambient.get(i).copied()
+    }
+
+    /// Whether tile (x, y) is open and well warmer than it would be without
+    /// a heat source nearby
+    pub fn is_warm(&self, x: i32, y: i32) -> bool {
+        let Some(i) = self.index(x, y).filter(|&i| !Promiser::is_solid_tile(self.tiles[i].tile_type)) else {
+            return false;
+        };
+        match (self.temperature.get(i), self.ambient.get(i)) {
+            (Some(&t), Some(&ambient)) => t >= ambient + WARM_MARGIN,
+            _ => false,
+        }
+    }
+}
+
+impl Promiser {
+    /// Feel whether the air here is cold
+    pub(crate) fn update_warmth(&mut self, map: &TileMap) {
+        let (x, y) = ((self.x / TILE_SIZE_PIXELS).floor() as i32, (self.y / TILE_SIZE_PIXELS).floor() as i32);
+        self.chilled = map.ambient_at(x, y).is_some_and(|t| t < COLD_TEMPERATURE);
+    }
+
+    /// Whether this promiser is cold and sitting by a fire
+    pub(crate) fn is_gathered(&self, map: &TileMap) -> bool {
+        let (x, y) = ((self.x / TILE_SIZE_PIXELS).floor() as i32, (self.y / TILE_SIZE_PIXELS).floor() as i32);
+        self.chilled && map.is_warm(x, y)
+    }
+}
+
+impl GameState {
+    /// Let idle promisers gathered by a fire, with someone else there to
+    /// hear, sometimes say something
+    pub(crate) fn campfire_chatter(&mut self) {
+        let mut gathered: Vec<(u32, u32, f64, f64)> = self.promisers.values()
+            .filter(|p| dimension::dimension_map(&self.tile_map, &self.dimensions, p.dimension).is_some_and(|map| p.is_gathered(map)))
+            .map(|p| (p.id, p.dimension, p.x, p.y))
+            .collect();
+        gathered.sort_unstable_by_key(|&(id, ..)| id);
+
+        for &(id, dim, x, y) in &gathered {
+            let company = gathered.iter()
+                .any(|&(other, other_dim, ox, oy)| other != id && other_dim == dim && (ox - x).hypot(oy - y) < HEARING_RADIUS);
+            let idle = self.promisers.get(&id).is_some_and(|p| p.state == ai::IDLE);
+            if !company || !idle || random() >= CHAT_CHANCE {
+                continue;
+            }
+            let line = CAMPFIRE_LINES[(random() * CAMPFIRE_LINES.len() as f64) as usize % CAMPFIRE_LINES.len()];
+            self.make_promiser_speak(id, line.to_string(), None, None);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Warm-up: a fresh world runs for a while before its first frame, so it
+//! shows up settled instead of settling in front of the player: water finds
+//! its level, foliage and grass grow in, the air mixes and the light builds
+//! up. Only the land moves on; the sun holds at the hour the world starts at,
+//! the weather waits, and promisers, creatures and items stay where they
+//! were put. Set it with SimConfig::warmup_ticks.
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+
+use crate::{GameState, LIGHT_SPAWN_TICKS};
+
+const PROGRESS_TICKS: u32 = 60; // Ticks between progress reports
+
+impl GameState {
+    /// Run the land for `ticks` ticks, calling `progress` with the ticks done
+    /// and the total every so often and once at the end
+    pub(crate) fn warm_up(&mut self, ticks: u32, mut progress: impl FnMut(u32, u32)) {
+        if ticks == 0 {
+            return;
+        }
+        console_log!(Info, World, "Warming up the world for {} ticks", ticks);
+        let dt = 1.0 / 60.0;
+        for tick in 0..ticks {
+            let tick_u64 = tick as u64;
+            self.update_solidity();
+            self.update_regions();
+            if tick_u64.is_multiple_of(6) {
+                self.simulate_water();
+            }
+            if tick_u64.is_multiple_of(60) {
+                self.simulate_foliage();
+                self.simulate_air();
+            }
+            self.update_light_rays(dt);
+            self.accumulate_light(dt);
+            if tick_u64.is_multiple_of(LIGHT_SPAWN_TICKS) {
+                self.generate_light_rays(LIGHT_SPAWN_TICKS as f64 * dt);
+            }
+            if (tick + 1) % PROGRESS_TICKS == 0 && tick + 1 < ticks {
+                progress(tick + 1, ticks);
+            }
+        }
+        progress(ticks, ticks);
+
+        // The settling isn't news (water lost to the void and the like)
+        self.events.clear();
+        self.buffers.sync(&self.tile_map, &self.sun, &self.ambient, 0.0);
+    }
+
+    /// warm_up for SimConfig::warmup_ticks, reporting progress to an optional
+    /// JS callback as `(done, total)`
+    pub(crate) fn warm_up_with_callback(&mut self, callback: Option<Function>) {
+        self.warm_up(self.config.warmup_ticks, |done, total| {
+            let Some(callback) = &callback else { return };
+            if let Err(err) = callback.call2(&JsValue::NULL, &JsValue::from(done), &JsValue::from(total)) {
+                console_log!(Error, Core, "Warm-up progress callback failed: {:?}", err);
+            }
+        });
+    }
+}
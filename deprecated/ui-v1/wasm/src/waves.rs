@@ -0,0 +1,83 @@
+//! Hostile waves: each nightfall, hostile creatures (see threat.rs) come in
+//! from the surface as far from the promisers as they can. The first days are quiet;
+//! after that each wave is bigger than the last, and its creatures tougher,
+//! scaled by the difficulty in the config. Peaceful worlds get no waves, and
+//! no hostile creatures at all (see spawn_creature). Waves are ambient
+//! spawning, so they're off along with it.
+use crate::config::Difficulty;
+use crate::creatures::CreatureKind;
+use crate::events::SimEvent;
+use crate::{random, GameState, Promiser, TileType, OVERWORLD, TILE_SIZE_PIXELS};
+
+const QUIET_DAYS: u32 = 2; // Nights without a wave, from the first
+const DAYS_PER_WOLF: u32 = 2; // Waves grow by one every this many days (at normal difficulty)
+const MAX_WAVE: u32 = 10;
+const TOUGHENING: f64 = 0.15; // Extra health share per day after the quiet ones (at normal difficulty)
+const MAX_TOUGHNESS: f64 = 3.0; // Most health a wave creature gets, as a share of the usual
+const WAVE_TRIES: usize = 20; // Random columns looked at per creature, for the one farthest from any promiser
+
+impl Difficulty {
+    /// (wave size, toughening) as shares of normal
+    fn scales(self) -> (f64, f64) {
+        match self {
+            Difficulty::Peaceful => (0.0, 0.0),
+            Difficulty::Easy => (0.5, 0.5),
+            Difficulty::Normal => (1.0, 1.0),
+            Difficulty::Hard => (2.0, 1.5),
+        }
+    }
+
+    /// How many hostile creatures come on `day`, and their health as a
+    /// share of the usual
+    pub fn wave(self, day: u32) -> (u32, f64) {
+        let Some(days) = day.checked_sub(QUIET_DAYS) else { return (0, 1.0) };
+        let (size, toughening) = self.scales();
+        let count = ((1 + days / DAYS_PER_WOLF) as f64 * size).round() as u32;
+        (count.min(MAX_WAVE), (1.0 + TOUGHENING * toughening * days as f64).min(MAX_TOUGHNESS))
+    }
+}
+
+impl GameState {
+    /// Send tonight's wave once the sun has set, if there is one
+    pub(crate) fn send_waves(&mut self) {
+        let day = self.sun.day;
+        if !self.sun.evening() || self.last_wave == Some(day) {
+            return;
+        }
+        self.last_wave = Some(day);
+        if !self.config.ambient_spawning.unwrap_or(true) {
+            return;
+        }
+        let (count, toughness) = self.config.difficulty.wave(day);
+        let mut size = 0;
+        for _ in 0..count {
+            let Some((x, y)) = self.wave_spot() else { break };
+            let id = self.add_creature(CreatureKind::Wolf, x, y, OVERWORLD);
+            self.ecs.health.insert(id, CreatureKind::Wolf.max_health() * toughness);
+            size += 1;
+        }
+        if size > 0 {
+            self.events.push(SimEvent::Wave { day, size });
+        }
+    }
+
+    // Pixel position on the overworld surface, open to the sky, the farthest
+    // from any promiser there of a few random columns
+    fn wave_spot(&self) -> Option<(f64, f64)> {
+        let map = &self.tile_map;
+        let spots = (0..WAVE_TRIES).filter_map(|_| {
+            let x = (random() * map.width as f64) as i32;
+            let ground = (0..map.height as i32).rev()
+                .find(|&y| map.get_tile(x, y).is_some_and(|tile| Promiser::is_solid_tile(tile.tile_type)))?;
+            let open = map.get_tile(x, ground + 1).is_some_and(|tile| tile.tile_type == TileType::Air);
+            open.then_some(((x as f64 + 0.5) * TILE_SIZE_PIXELS, (ground as f64 + 1.5) * TILE_SIZE_PIXELS))
+        });
+        let clearance = |&(x, _): &(f64, f64)| {
+            self.promisers.values()
+                .filter(|p| p.dimension == OVERWORLD)
+                .map(|p| map.offset_x(x, p.x).abs())
+                .fold(f64::INFINITY, f64::min)
+        };
+        spots.max_by(|a, b| clearance(a).total_cmp(&clearance(b)))
+    }
+}
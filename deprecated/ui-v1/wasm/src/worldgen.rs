@@ -0,0 +1,517 @@
+//! World generation: layered terrain (dirt over stone) with carved cave
+//! systems, underground lakes, ore pockets and the occasional prefabricated
+//! structure. Everything is driven by a seed so the same seed always produces
+//! the same world.
+use std::collections::VecDeque;
+
+use crate::config::WorldPreset;
+use crate::schematic::Schematic;
+use crate::{Tile, TileMap, TileType, MAX_WATER_AMOUNT};
+
+// Small deterministic PRNG (xorshift64*) so worldgen doesn't depend on Math.random
+pub struct WorldRng {
+    state: u64,
+}
+
+impl WorldRng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift, so mix the seed first
+        WorldRng { state: mix(seed) | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in [lo, hi)
+    pub fn range(&mut self, lo: usize, hi: usize) -> usize {
+        if hi <= lo {
+            return lo;
+        }
+        lo + (self.next_u64() % (hi - lo) as u64) as usize
+    }
+}
+
+// splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Hash a lattice point into [0, 1) for value noise
+fn lattice(ix: i64, iy: i64, seed: u64) -> f64 {
+    let h = mix(mix(seed) ^ (ix as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (iy as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F));
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// 2D value noise in [0, 1), `scale` is the lattice spacing in tiles
+pub fn value_noise(x: f64, y: f64, scale: f64, seed: u64) -> f64 {
+    let fx = x / scale;
+    let fy = y / scale;
+    let ix = fx.floor() as i64;
+    let iy = fy.floor() as i64;
+    let tx = smooth(fx - ix as f64);
+    let ty = smooth(fy - iy as f64);
+
+    let a = lattice(ix, iy, seed);
+    let b = lattice(ix + 1, iy, seed);
+    let c = lattice(ix, iy + 1, seed);
+    let d = lattice(ix + 1, iy + 1, seed);
+
+    let top = a + (b - a) * tx;
+    let bottom = c + (d - c) * tx;
+    top + (bottom - top) * ty
+}
+
+/// Generator parameters
+#[derive(Clone, Debug)]
+pub struct WorldGenParams {
+    pub surface_level: f64,      // Surface height as a fraction of world height
+    pub surface_roughness: f64,  // Max surface deviation in tiles
+    pub surface_scale: f64,      // Horizontal size of hills in tiles
+    pub sea_level: f64,          // Open air below this fraction of world height is flooded (0.0 = no sea)
+    pub dirt_depth: usize,       // Dirt layer thickness above the stone
+    pub cave_threshold: f64,     // Noise value above which stone is hollowed out (1.0 = no noise caves)
+    pub cave_worms: usize,       // Number of worm tunnels per 100 tiles of width
+    pub worm_length: usize,      // Steps per worm
+    pub lake_chance: f64,        // Chance that a cave pocket gets flooded
+    pub ore_pockets: usize,      // Number of ore pockets per 100 tiles of width
+    pub structures: usize,       // Structure placement attempts per 100 tiles of width
+}
+
+impl Default for WorldGenParams {
+    fn default() -> Self {
+        WorldGenParams {
+            surface_level: 0.55,
+            surface_roughness: 3.0,
+            surface_scale: 12.0,
+            sea_level: 0.0,
+            dirt_depth: 3,
+            cave_threshold: 0.68,
+            cave_worms: 6,
+            worm_length: 40,
+            lake_chance: 0.35,
+            ore_pockets: 8,
+            structures: 3,
+        }
+    }
+}
+
+impl WorldGenParams {
+    /// Generator parameters for a named preset
+    pub fn for_preset(preset: WorldPreset) -> WorldGenParams {
+        let classic = WorldGenParams::default();
+        match preset {
+            WorldPreset::Classic => classic,
+            WorldPreset::Flat => WorldGenParams {
+                surface_roughness: 0.0,
+                cave_threshold: 1.0,
+                cave_worms: 0,
+                lake_chance: 0.0,
+                ..classic
+            },
+            WorldPreset::Islands => WorldGenParams {
+                surface_level: 0.4,
+                surface_roughness: 7.0,
+                surface_scale: 9.0,
+                sea_level: 0.45,
+                cave_worms: 3,
+                ..classic
+            },
+            WorldPreset::Caverns => WorldGenParams {
+                surface_level: 0.8,
+                cave_threshold: 0.55,
+                cave_worms: 12,
+                worm_length: 60,
+                lake_chance: 0.5,
+                ore_pockets: 14,
+                ..classic
+            },
+            WorldPreset::Waterworld => WorldGenParams {
+                surface_level: 0.25,
+                surface_roughness: 2.0,
+                sea_level: 0.7,
+                cave_worms: 2,
+                structures: 0,
+                ..classic
+            },
+        }
+    }
+}
+
+/// Fill the tile map with generated terrain
+pub fn generate(map: &mut TileMap, params: &WorldGenParams, seed: u64) {
+    let mut rng = WorldRng::new(seed);
+    let w = map.width;
+    let h = map.height;
+    if w == 0 || h == 0 {
+        return;
+    }
+
+    // --- 1 ░ Surface and layers ----------------------------------------------
+    let surface = surface_heights(w, h, params, seed);
+    for (x, &top) in surface.iter().enumerate() {
+        let stone_top = top.saturating_sub(params.dirt_depth);
+        for y in 0..h {
+            let tile_type = if y < stone_top {
+                TileType::Stone
+            } else if y < top {
+                TileType::Dirt
+            } else {
+                TileType::Air
+            };
+            map.set_tile(x as i32, y as i32, Tile { tile_type, water_amount: 0, variant: 0 });
+        }
+    }
+
+    // --- 2 ░ Caves -----------------------------------------------------------
+    carve_noise_caves(map, &surface, params, seed);
+    carve_worms(map, &surface, params, &mut rng);
+
+    // --- 3 ░ Underground lakes and ore ---------------------------------------
+    fill_lakes(map, &surface, params, &mut rng);
+    place_ore(map, &surface, params, &mut rng);
+
+    // --- 4 ░ Sea and structures -----------------------------------------------
+    let sea_row = (params.sea_level * h as f64).round() as usize;
+    fill_sea(map, &surface, sea_row);
+    place_structures(map, &surface, sea_row, params, &mut rng);
+}
+
+// Surface height per column (first air row), from two octaves of 1D noise
+fn surface_heights(w: usize, h: usize, params: &WorldGenParams, seed: u64) -> Vec<usize> {
+    let base = params.surface_level * h as f64;
+    (0..w)
+        .map(|x| {
+            let n = value_noise(x as f64, 0.0, params.surface_scale, seed) * 0.7
+                + value_noise(x as f64, 0.0, params.surface_scale / 3.0, seed.wrapping_add(1)) * 0.3;
+            let height = base + (n - 0.5) * 2.0 * params.surface_roughness;
+            (height.round().max(1.0) as usize).min(h)
+        })
+        .collect()
+}
+
+// True if the tile sits inside the stone layer (below the dirt) and isn't the bedrock row
+fn in_stone_layer(surface: &[usize], params: &WorldGenParams, x: usize, y: usize) -> bool {
+    y > 0 && y + params.dirt_depth < surface[x]
+}
+
+fn carve_noise_caves(map: &mut TileMap, surface: &[usize], params: &WorldGenParams, seed: u64) {
+    if params.cave_threshold >= 1.0 {
+        return;
+    }
+    let cave_seed = seed.wrapping_add(0xCA7E);
+    for x in 0..map.width {
+        for y in 0..map.height {
+            if !in_stone_layer(surface, params, x, y) {
+                continue;
+            }
+            // Stretch horizontally so caves read as galleries rather than blobs
+            let n = value_noise(x as f64, y as f64 * 1.6, 7.0, cave_seed);
+            if n > params.cave_threshold {
+                map.set_tile(x as i32, y as i32, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 });
+            }
+        }
+    }
+}
+
+// Random-walk "worms" that carve winding tunnels through the stone
+fn carve_worms(map: &mut TileMap, surface: &[usize], params: &WorldGenParams, rng: &mut WorldRng) {
+    let w = map.width;
+    let worms = (params.cave_worms * w).div_ceil(100);
+    for _ in 0..worms {
+        let mut x = rng.range(0, w) as f64;
+        let max_y = surface[x as usize].saturating_sub(params.dirt_depth + 1);
+        if max_y <= 1 {
+            continue;
+        }
+        let mut y = rng.range(1, max_y) as f64;
+        let mut angle = rng.next_f64() * std::f64::consts::TAU;
+
+        for _ in 0..params.worm_length {
+            let radius = if rng.next_f64() < 0.3 { 1 } else { 0 };
+            carve_disc(map, surface, params, x.round() as i64, y.round() as i64, radius);
+
+            // Drift the heading, favouring horizontal tunnels
+            angle += (rng.next_f64() - 0.5) * 0.8;
+            x += angle.cos();
+            y += angle.sin() * 0.6;
+            if x < 0.0 || x >= w as f64 || y < 1.0 || y >= map.height as f64 {
+                break;
+            }
+        }
+    }
+}
+
+fn carve_disc(map: &mut TileMap, surface: &[usize], params: &WorldGenParams, cx: i64, cy: i64, radius: i64) {
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (x, y) = (cx + dx, cy + dy);
+            if x < 0 || y < 0 || x as usize >= map.width || y as usize >= map.height {
+                continue;
+            }
+            let (x, y) = (x as usize, y as usize);
+            if in_stone_layer(surface, params, x, y) {
+                map.set_tile(x as i32, y as i32, Tile { tile_type: TileType::Air, water_amount: 0, variant: 0 });
+            }
+        }
+    }
+}
+
+// Flood the bottom of some cave pockets with water. A lake is a level fill
+// from a cave floor tile: it spreads sideways and downwards only, so it stays
+// a basin. Pockets that would leak into a large open area are left dry.
+fn fill_lakes(map: &mut TileMap, surface: &[usize], params: &WorldGenParams, rng: &mut WorldRng) {
+    const MAX_LAKE_TILES: usize = 64;
+    let w = map.width;
+
+    // Cave floor tiles: air inside the stone layer with solid ground below
+    let mut floors = Vec::new();
+    for x in 0..w {
+        for y in 1..map.height {
+            if !in_stone_layer(surface, params, x, y) {
+                continue;
+            }
+            let here = map.tiles[y * w + x].tile_type;
+            let below = map.tiles[(y - 1) * w + x].tile_type;
+            if here == TileType::Air && below != TileType::Air && below != TileType::Water {
+                floors.push((x, y));
+            }
+        }
+    }
+
+    let lakes = floors.len() / 12;
+    for _ in 0..lakes {
+        if floors.is_empty() || rng.next_f64() >= params.lake_chance {
+            continue;
+        }
+        let (sx, sy) = floors[rng.range(0, floors.len())];
+        let level = sy + rng.range(0, 2);
+
+        let mut visited = vec![false; w * map.height];
+        let mut queue = VecDeque::from([(sx, sy)]);
+        let mut basin = Vec::new();
+        visited[sy * w + sx] = true;
+        let mut leaked = false;
+
+        while let Some((x, y)) = queue.pop_front() {
+            basin.push((x, y));
+            if basin.len() > MAX_LAKE_TILES {
+                leaked = true;
+                break;
+            }
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let Some(j) = map.index(x as i32 + dx, y as i32 + dy) else { continue };
+                let (nx, ny) = (j % w, j / w);
+                if ny > level || visited[j] {
+                    continue;
+                }
+                visited[j] = true;
+                if map.tiles[j].tile_type == TileType::Air {
+                    if !in_stone_layer(surface, params, nx, ny) {
+                        leaked = true;
+                    }
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        if !leaked {
+            for (x, y) in basin {
+                map.set_tile(x as i32, y as i32, Tile { tile_type: TileType::Water, water_amount: MAX_WATER_AMOUNT, variant: 0 });
+            }
+        }
+    }
+}
+
+// Flood open air above the terrain up to the sea row
+fn fill_sea(map: &mut TileMap, surface: &[usize], sea_row: usize) {
+    for (x, &top) in surface.iter().enumerate() {
+        for y in top..sea_row.min(map.height) {
+            if map.tiles[y * map.width + x].tile_type == TileType::Air {
+                map.set_tile(x as i32, y as i32, Tile { tile_type: TileType::Water, water_amount: MAX_WATER_AMOUNT, variant: 0 });
+            }
+        }
+    }
+}
+
+// Small blobs of ore replacing stone
+fn place_ore(map: &mut TileMap, surface: &[usize], params: &WorldGenParams, rng: &mut WorldRng) {
+    let w = map.width;
+    let pockets = (params.ore_pockets * w).div_ceil(100);
+    for _ in 0..pockets {
+        let x = rng.range(0, w);
+        let max_y = surface[x].saturating_sub(params.dirt_depth);
+        if max_y <= 1 {
+            continue;
+        }
+        let mut cx = x as i64;
+        let mut cy = rng.range(1, max_y) as i64;
+        let size = rng.range(2, 6);
+
+        for _ in 0..size {
+            if cx >= 0 && cy >= 0 && (cx as usize) < w && (cy as usize) < map.height {
+                let i = cy as usize * w + cx as usize;
+                if map.tiles[i].tile_type == TileType::Stone {
+                    map.tiles[i].tile_type = TileType::Ore;
+                }
+            }
+            match rng.range(0, 4) {
+                0 => cx -= 1,
+                1 => cx += 1,
+                2 => cy -= 1,
+                _ => cy += 1,
+            }
+        }
+    }
+}
+
+// How a structure sits relative to the terrain
+enum Site {
+    Surface,       // Bottom row rests on the ground
+    Sunken(usize), // Bottom rows are dug this many tiles into the ground
+    Span,          // A deck laid across a dip in the surface
+}
+
+struct Structure {
+    name: &'static str,
+    rows: &'static [&'static str],
+    site: Site,
+}
+
+// Embedded schematics (see schematic.rs for the legend)
+const STRUCTURES: &[Structure] = &[
+    Structure {
+        name: "ruin",
+        rows: &[
+            "#     ",
+            "#  #  ",
+            "#..#.#",
+            "######",
+        ],
+        site: Site::Surface,
+    },
+    Structure {
+        name: "hut",
+        rows: &[
+            " www ",
+            "ww.ww",
+            "w...w",
+            "w....",
+            "wwwww",
+        ],
+        site: Site::Surface,
+    },
+    Structure {
+        name: "well",
+        rows: &[
+            "#.#",
+            "#~#",
+            "#~#",
+            "###",
+        ],
+        site: Site::Sunken(3),
+    },
+    Structure {
+        name: "bridge",
+        rows: &[],
+        site: Site::Span,
+    },
+];
+
+fn place_structures(map: &mut TileMap, surface: &[usize], sea_row: usize, params: &WorldGenParams, rng: &mut WorldRng) {
+    let w = map.width;
+    let attempts = (params.structures * w).div_ceil(100);
+    // Columns already claimed by a structure, so they don't overlap
+    let mut claimed = vec![false; w];
+
+    for _ in 0..attempts {
+        let structure = &STRUCTURES[rng.range(0, STRUCTURES.len())];
+        // A few tries to find a suitable site for this structure
+        for _ in 0..8 {
+            let placed = match structure.site {
+                Site::Span => place_bridge(map, surface, &mut claimed, rng),
+                Site::Surface | Site::Sunken(_) => {
+                    let schematic = Schematic::from_rows(structure.rows);
+                    place_on_ground(map, surface, sea_row, &mut claimed, &schematic, &structure.site, rng)
+                }
+            };
+            if let Some(x) = placed {
+                console_log!(Debug, World, "🏛️ Placed {} at x={}", structure.name, x);
+                break;
+            }
+        }
+    }
+}
+
+fn place_on_ground(
+    map: &mut TileMap,
+    surface: &[usize],
+    sea_row: usize,
+    claimed: &mut [bool],
+    schematic: &Schematic,
+    site: &Site,
+    rng: &mut WorldRng,
+) -> Option<usize> {
+    let w = map.width;
+    if schematic.width == 0 || schematic.width > w {
+        return None;
+    }
+    let x = rng.range(0, w - schematic.width + 1);
+    let columns = x..x + schematic.width;
+    if claimed[columns.clone()].iter().any(|&c| c) {
+        return None;
+    }
+
+    // Only build on reasonably flat ground above the sea
+    let lowest = *surface[columns.clone()].iter().min()?;
+    let highest = *surface[columns.clone()].iter().max()?;
+    if highest - lowest > 1 || lowest < sea_row || highest + schematic.height > map.height {
+        return None;
+    }
+
+    let y = match site {
+        Site::Sunken(depth) => highest.checked_sub(*depth)?,
+        _ => highest,
+    };
+    schematic.paste(map, x as i32, y as i32);
+    claimed[columns].iter_mut().for_each(|c| *c = true);
+    Some(x)
+}
+
+// Lay planks across a dip: both ends on ground of equal height, with the
+// surface between them at least two tiles lower
+fn place_bridge(map: &mut TileMap, surface: &[usize], claimed: &mut [bool], rng: &mut WorldRng) -> Option<usize> {
+    const MAX_SPAN: usize = 12;
+    let w = map.width;
+    let start = rng.range(0, w);
+    let top = surface[start];
+
+    let end = (start + 1..(start + MAX_SPAN).min(w)).find(|&x| surface[x] >= top)?;
+    let gap = start + 1..end;
+    if gap.is_empty() || claimed[start..=end].iter().any(|&c| c) {
+        return None;
+    }
+    let deepest = *surface[gap.clone()].iter().min()?;
+    if deepest + 2 > top || top == 0 {
+        return None;
+    }
+
+    let deck = "w".repeat(gap.len());
+    Schematic::from_rows(&[deck.as_str()]).paste(map, gap.start as i32, top as i32 - 1);
+    claimed[start..=end].iter_mut().for_each(|c| *c = true);
+    Some(start)
+}